@@ -0,0 +1,340 @@
+use crate::types::{
+    ClientCapabilities, CreateMessageRequest, CreateMessageResult, JsonRpcRequest, JsonRpcResponse, ListRootsResult, Root,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, watch};
+use tracing::{debug, info, warn};
+
+const CHANNEL_CAPACITY: usize = 64;
+
+/// How long to wait for the client to answer `roots/list`, e.g. an HTTP
+/// client that never opens its event stream
+const ROOTS_TIMEOUT: Duration = Duration::from_secs(30);
+
+tokio::task_local! {
+    /// The client whose tool call is running, whose roots `Sandbox` keeps
+    /// the call's paths inside
+    static CALLER: ClientHandle;
+}
+
+/// Errors from a request the server sent to its client
+#[derive(Debug, thiserror::Error)]
+pub enum ClientRequestError {
+    #[error("The client does not support {0}")]
+    Unsupported(&'static str),
+    #[error("No client is connected to answer {0}")]
+    NotConnected(String),
+    #[error("The client disconnected before answering {0}")]
+    Disconnected(String),
+    #[error("The client refused {method}: {message} (code {code})")]
+    Refused { method: String, code: i32, message: String },
+    #[error("Invalid {method} result from the client: {source}")]
+    InvalidResult { method: String, source: serde_json::Error },
+}
+
+/// A path a file tool was asked to use lies outside every root the client
+/// declared, or can't be checked because the client's roots aren't known
+#[derive(Debug, thiserror::Error)]
+pub enum OutsideRoots {
+    #[error("Path '{0}' is outside the client's roots")]
+    Outside(String),
+    #[error("Path '{0}' can't be checked against the client's roots, which it has not listed")]
+    Unlisted(String),
+}
+
+/// What the server knows of the client's roots
+#[derive(Debug, Clone, Default)]
+enum Roots {
+    /// Not asked for yet, or the first answer is still awaited
+    #[default]
+    Unknown,
+    /// The client's answer to the last `roots/list`
+    Listed(Vec<Root>),
+    /// The last `roots/list` failed or went unanswered
+    Failed,
+}
+
+/// Sends requests from the server to the connected client, such as
+/// `sampling/createMessage`, and routes the client's responses back to
+/// whoever is waiting for them.
+///
+/// The transport serving the session subscribes to the outgoing requests
+/// and passes every response the client sends to `resolve`. Clones share
+/// one session's requests; each session gets a handle of its own.
+#[derive(Debug, Clone, Default)]
+pub struct ClientHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    requests: broadcast::Sender<JsonRpcRequest>,
+    /// Callers waiting for a response, by request id
+    pending: Mutex<HashMap<String, oneshot::Sender<JsonRpcResponse>>>,
+    next_id: AtomicU64,
+    /// What the client declared in `initialize`
+    capabilities: Mutex<Option<ClientCapabilities>>,
+    roots: watch::Sender<Roots>,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            requests: broadcast::channel(CHANNEL_CAPACITY).0,
+            pending: Mutex::default(),
+            next_id: AtomicU64::new(1),
+            capabilities: Mutex::default(),
+            roots: watch::Sender::new(Roots::Unknown),
+        }
+    }
+}
+
+/// Forgets a pending request however its caller stops waiting, e.g. when
+/// a tool call times out
+struct PendingGuard<'a> {
+    handle: &'a ClientHandle,
+    id: String,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.handle.pending().remove(&self.id);
+    }
+}
+
+impl ClientHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests for the transport to write to the client
+    pub fn subscribe(&self) -> broadcast::Receiver<JsonRpcRequest> {
+        self.inner.requests.subscribe()
+    }
+
+    /// Record what the client declared it supports, at `initialize`
+    pub fn set_capabilities(&self, capabilities: ClientCapabilities) {
+        *self.inner.capabilities.lock().unwrap_or_else(|e| e.into_inner()) = Some(capabilities);
+    }
+
+    /// Whether the client declared the `sampling` capability
+    pub fn supports_sampling(&self) -> bool {
+        self.inner
+            .capabilities
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|capabilities| capabilities.sampling.is_some())
+    }
+
+    /// Whether the client declared the `roots` capability
+    pub fn supports_roots(&self) -> bool {
+        self.inner
+            .capabilities
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|capabilities| capabilities.roots.is_some())
+    }
+
+    /// The roots the client last listed, if it has answered `roots/list`
+    pub fn roots(&self) -> Option<Vec<Root>> {
+        match &*self.inner.roots.borrow() {
+            Roots::Listed(roots) => Some(roots.clone()),
+            Roots::Unknown | Roots::Failed => None,
+        }
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<String, oneshot::Sender<JsonRpcResponse>>> {
+        self.inner.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Send `method` to the client and wait for its result
+    pub async fn request(&self, method: &str, params: Option<serde_json::Value>) -> Result<serde_json::Value, ClientRequestError> {
+        let id = format!("server-{}", self.inner.next_id.fetch_add(1, Ordering::Relaxed));
+        let (response_tx, response) = oneshot::channel();
+        self.pending().insert(id.clone(), response_tx);
+        let _pending = PendingGuard { handle: self, id: id.clone() };
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(id)),
+            method: method.to_string(),
+            params,
+        };
+        if self.inner.requests.send(request).is_err() {
+            return Err(ClientRequestError::NotConnected(method.to_string()));
+        }
+        debug!("Sent {} to the client as request {}", method, id);
+
+        let response = response.await.map_err(|_| ClientRequestError::Disconnected(method.to_string()))?;
+        match response.error {
+            Some(error) => Err(ClientRequestError::Refused {
+                method: method.to_string(),
+                code: error.code,
+                message: error.message,
+            }),
+            None => Ok(response.result.unwrap_or_default()),
+        }
+    }
+
+    /// Ask the client's model for a completion with `sampling/createMessage`.
+    /// Fails without a request when the client didn't declare `sampling`.
+    pub async fn create_message(&self, request: CreateMessageRequest) -> Result<CreateMessageResult, ClientRequestError> {
+        const METHOD: &str = "sampling/createMessage";
+        if !self.supports_sampling() {
+            return Err(ClientRequestError::Unsupported("sampling"));
+        }
+        let params = serde_json::to_value(request).map_err(|source| ClientRequestError::InvalidResult {
+            method: METHOD.to_string(),
+            source,
+        })?;
+        let result = self.request(METHOD, Some(params)).await?;
+        serde_json::from_value(result).map_err(|source| ClientRequestError::InvalidResult {
+            method: METHOD.to_string(),
+            source,
+        })
+    }
+
+    /// Ask the client for its roots with `roots/list` and remember them for
+    /// `check_root`. Fails without a request when the client didn't
+    /// declare `roots`; otherwise a failure leaves every path refused until
+    /// the client answers a later `roots/list`.
+    pub async fn list_roots(&self) -> Result<Vec<Root>, ClientRequestError> {
+        const METHOD: &str = "roots/list";
+        if !self.supports_roots() {
+            return Err(ClientRequestError::Unsupported("roots"));
+        }
+        let result = self.request(METHOD, None).await.and_then(|result| {
+            serde_json::from_value::<ListRootsResult>(result).map_err(|source| ClientRequestError::InvalidResult {
+                method: METHOD.to_string(),
+                source,
+            })
+        });
+        match result {
+            Ok(result) => {
+                self.inner.roots.send_replace(Roots::Listed(result.roots.clone()));
+                Ok(result.roots)
+            }
+            Err(e) => {
+                self.inner.roots.send_replace(Roots::Failed);
+                Err(e)
+            }
+        }
+    }
+
+    /// `list_roots` in the background, after `notifications/initialized`
+    /// and whenever the client says its roots changed
+    pub fn refresh_roots(&self) {
+        let client = self.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(ROOTS_TIMEOUT, client.list_roots()).await {
+                Ok(Ok(roots)) => info!("Client declared {} roots", roots.len()),
+                Ok(Err(e)) => warn!("Failed to list the client's roots: {}", e),
+                Err(_) => {
+                    warn!("The client did not answer roots/list within {:?}", ROOTS_TIMEOUT);
+                    client.inner.roots.send_replace(Roots::Failed);
+                }
+            }
+        });
+    }
+
+    /// Refuse `path` unless it lies inside one of the client's `file://`
+    /// roots, after following `..` and symlinks. A client that declared
+    /// `roots` has every path refused until it answers `roots/list`, and
+    /// after that fails; one that didn't declare `roots`, or lists no
+    /// `file://` root, has every path allowed.
+    pub fn check_root(&self, path: &Path) -> Result<(), OutsideRoots> {
+        if !self.supports_roots() {
+            return Ok(());
+        }
+        let Some(roots) = self.roots() else {
+            return Err(OutsideRoots::Unlisted(path.display().to_string()));
+        };
+        let roots: Vec<PathBuf> = roots
+            .iter()
+            .filter_map(|root| url::Url::parse(&root.uri).ok()?.to_file_path().ok())
+            .map(|root| root.canonicalize().unwrap_or(root))
+            .collect();
+        if roots.is_empty() {
+            return Ok(());
+        }
+        match canonical(path) {
+            Some(path) if roots.iter().any(|root| path.starts_with(root)) => Ok(()),
+            _ => Err(OutsideRoots::Outside(path.display().to_string())),
+        }
+    }
+
+    /// Run `call` on behalf of this client: every path resolved through a
+    /// `Sandbox` meanwhile is held to the client's roots by `check_root`.
+    /// A client that declared `roots` but hasn't answered `roots/list` yet
+    /// gets a chance to first, so its early calls aren't refused.
+    pub async fn run_for<F: Future>(&self, call: F) -> F::Output {
+        if self.supports_roots() {
+            let mut roots = self.inner.roots.subscribe();
+            let answered = roots.wait_for(|roots| !matches!(roots, Roots::Unknown));
+            if tokio::time::timeout(ROOTS_TIMEOUT, answered).await.is_err() {
+                debug!("Running a call for a client whose roots are still unknown");
+            }
+        }
+        CALLER.scope(self.clone(), call).await
+    }
+
+    /// Hand a response from the client to the request waiting for it.
+    /// Returns whether one was, so transports can tell answers to their
+    /// own pings apart.
+    pub fn resolve(&self, response: &serde_json::Value) -> bool {
+        let Some(id) = response.get("id").and_then(|id| id.as_str()) else {
+            return false;
+        };
+        let Some(waiting) = self.pending().remove(id) else {
+            return false;
+        };
+        match serde_json::from_value(response.clone()) {
+            Ok(response) => {
+                let _ = waiting.send(response);
+            }
+            // Dropping the sender fails the request as disconnected
+            Err(e) => debug!("Ignoring malformed response to request {}: {}", id, e),
+        }
+        true
+    }
+
+    /// Fail every request still waiting, once the client can no longer answer
+    pub fn disconnect(&self) {
+        let waiting = std::mem::take(&mut *self.pending());
+        if !waiting.is_empty() {
+            debug!("Client gone with {} requests unanswered", waiting.len());
+        }
+    }
+}
+
+/// `check_root` for the client of the call running on this task, as set by
+/// `ClientHandle::run_for`. Tool calls and the client's resource requests
+/// all run for their client, so paths resolved outside one are the
+/// server's own, e.g. for an embedder calling a tool directly, and are not
+/// restricted.
+pub(crate) fn check_caller_root(path: &Path) -> Result<(), OutsideRoots> {
+    CALLER.try_with(|client| client.check_root(path)).unwrap_or(Ok(()))
+}
+
+/// `path` made absolute with its deepest existing ancestor canonicalized,
+/// so paths about to be created can be checked too; `None` if what's left
+/// climbs out with `..`, or if the ancestor is a dangling symlink
+fn canonical(path: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+    let mut resolved = existing.canonicalize().ok()?;
+    resolved.extend(missing.iter().rev());
+    Some(resolved)
+}
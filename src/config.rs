@@ -0,0 +1,242 @@
+use crate::tools::{DEFAULT_ALLOWED_COMMANDS, DEFAULT_BENCHMARK_TOOLS, DEFAULT_TOOL_TIMEOUT};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Setting names containing these are shown redacted by `effective`
+const SECRET_MARKERS: &[&str] = &["secret", "token", "password", "api_key", "credential"];
+
+/// Server-wide settings that decide which tools are registered and how they behave.
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    /// Register tools that open network connections (e.g. `tcp_ping`)
+    pub enable_network: bool,
+    /// Hosts the network tools may connect to; empty means any host
+    pub allowed_hosts: Vec<String>,
+    /// Commands the command-running tools may run; empty means `DEFAULT_ALLOWED_COMMANDS`
+    pub allowed_commands: Vec<String>,
+    /// Register the `process_info` tool, which exposes details of other processes
+    pub enable_process_info: bool,
+    /// Register the `list_cron` tool, which reads crontabs and cron directories
+    pub enable_cron: bool,
+    /// Directory that sandbox-aware file tools are confined to
+    pub root: Option<PathBuf>,
+    /// Directory relative tool paths and commands start from, instead of
+    /// the process's current directory
+    pub working_dir: Option<PathBuf>,
+    /// Refuse tool operations that modify the filesystem
+    pub read_only: bool,
+    /// Replacement descriptions shown in `tools/list`, keyed by tool name
+    pub tool_descriptions: HashMap<String, String>,
+    /// Time limit for each tool call; `None` means `DEFAULT_TOOL_TIMEOUT`
+    pub tool_timeout: Option<Duration>,
+    /// Per-tool time limits that replace `tool_timeout`, keyed by tool name
+    pub tool_timeouts: HashMap<String, Duration>,
+    /// Follow-up tools offered by `tools/suggest_next`, keyed by tool name;
+    /// each entry replaces the built-in suggestions for that tool
+    pub tool_suggestions: HashMap<String, Vec<String>>,
+    /// Extra `server/help` topics in Markdown, keyed by topic name; an
+    /// `overview` entry replaces the generated overview
+    pub help_topics: HashMap<String, String>,
+    /// Directory of prompt templates, reloaded when its files change
+    pub prompts_dir: Option<PathBuf>,
+    /// Built-in tools to leave unregistered, by name
+    pub disabled_tools: Vec<String>,
+    /// Tools `self_benchmark` may call; empty means `DEFAULT_BENCHMARK_TOOLS`
+    pub benchmark_tools: Vec<String>,
+    /// Permission profile granted to TLS clients, keyed by the common name
+    /// on their client certificate
+    pub client_profiles: HashMap<String, String>,
+    /// The only tools a permission profile's clients may list and call,
+    /// keyed by profile; a profile not listed here may use every tool
+    pub profile_tools: HashMap<String, Vec<String>>,
+    /// URI prefixes of the only resources a permission profile's clients may
+    /// list, read and subscribe to, keyed by profile; a profile not listed
+    /// here may use every resource
+    pub profile_resources: HashMap<String, Vec<String>>,
+    /// Which layer set each setting, keyed by field name; unlisted settings
+    /// have their default
+    pub sources: HashMap<String, ConfigSource>,
+}
+
+/// Where a setting's value came from, lowest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    /// A file passed with `--config`
+    Config,
+    Cli,
+}
+
+/// Settings from one source, a config file or the command line. Unset
+/// fields leave the value from lower layers in place; set lists and maps
+/// replace it whole.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub enable_network: Option<bool>,
+    pub allowed_hosts: Option<Vec<String>>,
+    pub allowed_commands: Option<Vec<String>>,
+    pub enable_process_info: Option<bool>,
+    pub enable_cron: Option<bool>,
+    pub root: Option<PathBuf>,
+    pub working_dir: Option<PathBuf>,
+    pub read_only: Option<bool>,
+    pub tool_descriptions: Option<HashMap<String, String>>,
+    /// Seconds
+    pub tool_timeout: Option<u64>,
+    /// Seconds, keyed by tool name
+    pub tool_timeouts: Option<HashMap<String, u64>>,
+    pub tool_suggestions: Option<HashMap<String, Vec<String>>>,
+    pub help_topics: Option<HashMap<String, String>>,
+    pub prompts_dir: Option<PathBuf>,
+    pub disabled_tools: Option<Vec<String>>,
+    pub benchmark_tools: Option<Vec<String>>,
+    pub client_profiles: Option<HashMap<String, String>>,
+    pub profile_tools: Option<HashMap<String, Vec<String>>>,
+    pub profile_resources: Option<HashMap<String, Vec<String>>>,
+}
+
+impl ConfigLayer {
+    /// Read a layer from a TOML file, or from JSON when the file ends in `.json`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+        let layer = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(anyhow::Error::from)
+        } else {
+            toml::from_str(&text).map_err(anyhow::Error::from)
+        };
+        layer.with_context(|| format!("Invalid config in {}", path.display()))
+    }
+}
+
+/// Replace `target` with `value` if the layer sets it, recording the source
+fn apply<T>(
+    sources: &mut HashMap<String, ConfigSource>,
+    name: &str,
+    target: &mut T,
+    value: Option<T>,
+    source: ConfigSource,
+) {
+    if let Some(value) = value {
+        *target = value;
+        sources.insert(name.to_string(), source);
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// Hide a secret setting, or the secret entries of a map-valued one
+fn redact(name: &str, value: Value) -> Value {
+    if is_secret(name) {
+        return json!("[redacted]");
+    }
+    match value {
+        Value::Object(entries) => entries
+            .into_iter()
+            .map(|(key, value)| {
+                let value = if is_secret(&key) { json!("[redacted]") } else { value };
+                (key, value)
+            })
+            .collect(),
+        value => value,
+    }
+}
+
+impl ServerConfig {
+    /// Apply a layer over the current settings. Merge layers in order of
+    /// precedence: defaults first, then the config file, then the CLI.
+    pub fn merge(&mut self, layer: ConfigLayer, source: ConfigSource) {
+        let sources = &mut self.sources;
+        apply(sources, "enable_network", &mut self.enable_network, layer.enable_network, source);
+        apply(sources, "allowed_hosts", &mut self.allowed_hosts, layer.allowed_hosts, source);
+        apply(sources, "allowed_commands", &mut self.allowed_commands, layer.allowed_commands, source);
+        apply(sources, "enable_process_info", &mut self.enable_process_info, layer.enable_process_info, source);
+        apply(sources, "enable_cron", &mut self.enable_cron, layer.enable_cron, source);
+        apply(sources, "root", &mut self.root, layer.root.map(Some), source);
+        apply(sources, "working_dir", &mut self.working_dir, layer.working_dir.map(Some), source);
+        apply(sources, "read_only", &mut self.read_only, layer.read_only, source);
+        apply(sources, "tool_descriptions", &mut self.tool_descriptions, layer.tool_descriptions, source);
+        let tool_timeout = layer.tool_timeout.map(|secs| Some(Duration::from_secs(secs)));
+        apply(sources, "tool_timeout", &mut self.tool_timeout, tool_timeout, source);
+        let tool_timeouts = layer.tool_timeouts.map(|timeouts| {
+            timeouts.into_iter().map(|(name, secs)| (name, Duration::from_secs(secs))).collect()
+        });
+        apply(sources, "tool_timeouts", &mut self.tool_timeouts, tool_timeouts, source);
+        apply(sources, "tool_suggestions", &mut self.tool_suggestions, layer.tool_suggestions, source);
+        apply(sources, "help_topics", &mut self.help_topics, layer.help_topics, source);
+        apply(sources, "prompts_dir", &mut self.prompts_dir, layer.prompts_dir.map(Some), source);
+        apply(sources, "disabled_tools", &mut self.disabled_tools, layer.disabled_tools, source);
+        apply(sources, "benchmark_tools", &mut self.benchmark_tools, layer.benchmark_tools, source);
+        apply(sources, "client_profiles", &mut self.client_profiles, layer.client_profiles, source);
+        apply(sources, "profile_tools", &mut self.profile_tools, layer.profile_tools, source);
+        apply(sources, "profile_resources", &mut self.profile_resources, layer.profile_resources, source);
+    }
+
+    /// The tools `self_benchmark` may call, falling back to `DEFAULT_BENCHMARK_TOOLS`
+    pub fn benchmark_tools(&self) -> Vec<String> {
+        if self.benchmark_tools.is_empty() {
+            DEFAULT_BENCHMARK_TOOLS.iter().map(|tool| tool.to_string()).collect()
+        } else {
+            self.benchmark_tools.clone()
+        }
+    }
+
+    pub fn source(&self, name: &str) -> ConfigSource {
+        self.sources.get(name).copied().unwrap_or(ConfigSource::Default)
+    }
+
+    /// Every setting as `{"value": ..., "source": ...}`, keyed by name.
+    /// Unset settings show the default they fall back to, help topics are
+    /// listed by name only and secrets are redacted.
+    pub fn effective(&self) -> Value {
+        let allowed_commands = if self.allowed_commands.is_empty() {
+            DEFAULT_ALLOWED_COMMANDS.iter().map(|c| c.to_string()).collect()
+        } else {
+            self.allowed_commands.clone()
+        };
+        let tool_timeouts: BTreeMap<&String, f64> = self.tool_timeouts
+            .iter()
+            .map(|(name, timeout)| (name, timeout.as_secs_f64()))
+            .collect();
+        let mut help_topics: Vec<&String> = self.help_topics.keys().collect();
+        help_topics.sort();
+
+        let settings = [
+            ("enable_network", json!(self.enable_network)),
+            ("allowed_hosts", json!(self.allowed_hosts)),
+            ("allowed_commands", json!(allowed_commands)),
+            ("enable_process_info", json!(self.enable_process_info)),
+            ("enable_cron", json!(self.enable_cron)),
+            ("root", json!(self.root)),
+            ("working_dir", json!(self.working_dir)),
+            ("read_only", json!(self.read_only)),
+            ("tool_descriptions", json!(self.tool_descriptions)),
+            ("tool_timeout", json!(self.tool_timeout.unwrap_or(DEFAULT_TOOL_TIMEOUT).as_secs_f64())),
+            ("tool_timeouts", json!(tool_timeouts)),
+            ("tool_suggestions", json!(self.tool_suggestions)),
+            ("help_topics", json!(help_topics)),
+            ("prompts_dir", json!(self.prompts_dir)),
+            ("disabled_tools", json!(self.disabled_tools)),
+            ("benchmark_tools", json!(self.benchmark_tools())),
+            ("client_profiles", json!(self.client_profiles)),
+            ("profile_tools", json!(self.profile_tools)),
+            ("profile_resources", json!(self.profile_resources)),
+        ];
+        settings
+            .into_iter()
+            .map(|(name, value)| {
+                let setting = json!({ "value": redact(name, value), "source": self.source(name) });
+                (name.to_string(), setting)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::types::Tool;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// Topic for a tool's usage, e.g. `tools/read_file`
+const TOOL_TOPIC_PREFIX: &str = "tools/";
+
+/// Errors surfaced to clients by `server/help`
+#[derive(Debug, thiserror::Error)]
+pub enum HelpError {
+    #[error("Unknown help topic '{topic}'")]
+    UnknownTopic { topic: String, topics: Vec<String> },
+}
+
+/// Guidance served by `server/help` one topic at a time, so clients fetch
+/// only what they need instead of receiving it all from `initialize`.
+///
+/// `overview` and one `tools/<name>` topic per tool are generated from the
+/// server itself; configured topics are added alongside them, and a
+/// configured `overview` replaces the generated one.
+pub struct Help<'a> {
+    pub server_name: &'a str,
+    pub server_version: &'a str,
+    /// Registered tools, in any order
+    pub tools: Vec<Tool>,
+    pub topics: &'a HashMap<String, String>,
+}
+
+impl Help<'_> {
+    /// Every topic name, sorted, with `overview` first
+    pub fn topic_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.topics.keys()
+            .filter(|name| name.as_str() != "overview")
+            .cloned()
+            .chain(self.tools.iter().map(|tool| format!("{}{}", TOOL_TOPIC_PREFIX, tool.name)))
+            .collect();
+        names.sort();
+        names.dedup();
+        names.insert(0, "overview".to_string());
+        names
+    }
+
+    /// The Markdown text of a topic. `next` gives a tool's follow-up tools.
+    pub fn topic(&self, topic: &str, next: impl Fn(&str) -> Vec<String>) -> Result<String, HelpError> {
+        if let Some(text) = self.topics.get(topic) {
+            return Ok(text.clone());
+        }
+        if topic == "overview" {
+            return Ok(self.overview());
+        }
+        let tool = topic
+            .strip_prefix(TOOL_TOPIC_PREFIX)
+            .and_then(|name| self.tools.iter().find(|tool| tool.name == name));
+        match tool {
+            Some(tool) => Ok(tool_usage(tool, &next(&tool.name))),
+            None => Err(HelpError::UnknownTopic {
+                topic: topic.to_string(),
+                topics: self.topic_names(),
+            }),
+        }
+    }
+
+    fn overview(&self) -> String {
+        let mut tools: Vec<&Tool> = self.tools.iter().collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut text = format!("# {} v{}\n\n", self.server_name, self.server_version);
+        text.push_str(
+            "Call tools with `tools/call`. Fetch `tools/<name>` from `server/help` for a tool's \
+             parameters, and use `tools/suggest_next` to find tools that commonly follow it.\n\n## Tools\n\n",
+        );
+        for tool in tools {
+            let _ = writeln!(text, "- `{}`: {}", tool.name, tool.description);
+        }
+
+        let extra: Vec<String> = self.topic_names()
+            .into_iter()
+            .filter(|name| name != "overview" && !name.starts_with(TOOL_TOPIC_PREFIX))
+            .collect();
+        if !extra.is_empty() {
+            text.push_str("\n## More topics\n\n");
+            for name in extra {
+                let _ = writeln!(text, "- `{}`", name);
+            }
+        }
+        text
+    }
+}
+
+/// A tool's description, parameters from its input schema and follow-ups
+fn tool_usage(tool: &Tool, next: &[String]) -> String {
+    let mut text = format!("# {}\n\n{}\n", tool.name, tool.description);
+
+    let required: Vec<&str> = tool.input_schema.get("required")
+        .and_then(|v| v.as_array())
+        .map(|names| names.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let properties: BTreeMap<&String, &serde_json::Value> = tool.input_schema.get("properties")
+        .and_then(|v| v.as_object())
+        .map(|properties| properties.iter().collect())
+        .unwrap_or_default();
+
+    if !properties.is_empty() {
+        text.push_str("\n## Parameters\n\n");
+        for (name, schema) in properties {
+            let kind = match schema.get("type") {
+                Some(serde_json::Value::String(kind)) => kind.clone(),
+                Some(serde_json::Value::Array(kinds)) => kinds.iter()
+                    .filter_map(|v| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                _ => "any".to_string(),
+            };
+            let necessity = if required.contains(&name.as_str()) { "required" } else { "optional" };
+            let _ = write!(text, "- `{}` ({}, {})", name, kind, necessity);
+            if let Some(description) = schema.get("description").and_then(|v| v.as_str()) {
+                let _ = write!(text, ": {}", description);
+            }
+            if let Some(default) = schema.get("default") {
+                let _ = write!(text, " (default: {})", default);
+            }
+            text.push('\n');
+        }
+    }
+
+    if !next.is_empty() {
+        let next: Vec<String> = next.iter().map(|name| format!("`{}`", name)).collect();
+        let _ = write!(text, "\nOften followed by {}.\n", next.join(", "));
+    }
+    text
+}
@@ -1,5 +1,14 @@
+pub mod client;
+pub mod config;
+pub mod help;
 pub mod mcp;
+pub mod notifications;
+pub mod prompts;
+pub mod resources;
+pub mod sandbox;
 pub mod server;
+pub mod session;
+pub mod stats;
 pub mod tools;
 pub mod types;
 
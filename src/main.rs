@@ -1,14 +1,33 @@
+use anyhow::Context;
 use clap::Parser;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{info, warn};
+use std::time::Duration;
+use tracing::info;
+use tracing_subscriber::filter::{filter_fn, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
 
+pub mod client;
+pub mod config;
+pub mod help;
 pub mod mcp;
+pub mod notifications;
+pub mod prompts;
+pub mod resources;
+pub mod sandbox;
 pub mod server;
+pub mod session;
+pub mod stats;
 pub mod tools;
 pub mod types;
 
+use config::{ConfigLayer, ConfigSource, ServerConfig};
 use mcp::McpServer;
-use server::StdioServer;
+use notifications::ClientLogLayer;
+use server::{load_tls_acceptor, systemd_listener, CorsPolicy, Framing, HttpServer, HttpSseServer, Keepalive, StdioServer, TcpServer, TransportKind, WireFormat, DEFAULT_SHUTDOWN_TIMEOUT};
 
 #[derive(Parser)]
 #[command(name = "rust-mcp-server")]
@@ -29,42 +48,447 @@ struct Cli {
     /// Server version
     #[arg(short, long, default_value = "0.1.0")]
     version: String,
+    
+    /// Enable tools that open network connections
+    #[arg(long)]
+    enable_network: bool,
+    
+    /// Host that network tools may connect to (repeatable; default: any host)
+    #[arg(long = "allow-host", value_name = "HOST")]
+    allowed_hosts: Vec<String>,
+    
+    /// Command that command-running tools may run, replacing the default list (repeatable)
+    #[arg(long = "allow-command", value_name = "CMD")]
+    allowed_commands: Vec<String>,
+    
+    /// Enable the process_info tool for inspecting running processes
+    #[arg(long)]
+    enable_process_info: bool,
+    
+    /// Enable the list_cron tool for auditing scheduled jobs
+    #[arg(long)]
+    enable_cron: bool,
+    
+    /// Confine file tools to this directory
+    #[arg(long, value_name = "DIR")]
+    root: Option<PathBuf>,
+    
+    /// Resolve relative tool paths and run commands from this directory
+    /// instead of the one the server was started in
+    #[arg(long, value_name = "DIR")]
+    working_dir: Option<PathBuf>,
+    
+    /// Disable tool operations that modify the filesystem
+    #[arg(long)]
+    read_only: bool,
+    
+    /// Transport to serve MCP over
+    #[arg(long, value_enum, default_value_t = TransportKind::Stdio)]
+    transport: TransportKind,
+    
+    /// Port the HTTP, SSE and TCP transports listen on, on 127.0.0.1, unless
+    /// --listen is given
+    #[arg(long, default_value_t = 3000)]
+    port: u16,
+    
+    /// Address the HTTP, SSE and TCP transports listen on, e.g. 0.0.0.0:8080
+    /// (default: 127.0.0.1 and --port)
+    #[arg(long, value_name = "ADDR")]
+    listen: Option<SocketAddr>,
+    
+    /// PEM certificate chain to serve the HTTP, SSE and TCP transports over TLS
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    
+    /// PEM private key for --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    
+    /// PEM bundle of CAs that TLS clients must present a certificate from;
+    /// clients without one are refused during the handshake
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+    
+    /// Grant TLS clients whose certificate has common name CN the permission
+    /// profile PROFILE, as CN=PROFILE (repeatable)
+    #[arg(long = "tls-client-profile", value_name = "CN=PROFILE", value_parser = parse_key_value, requires = "tls_client_ca")]
+    tls_client_profiles: Vec<(String, String)>,
+    
+    /// Limit clients with permission profile PROFILE to the tool TOOL and
+    /// others given the same way, as PROFILE=TOOL (repeatable)
+    #[arg(long = "tls-profile-tool", value_name = "PROFILE=TOOL", value_parser = parse_key_value, requires = "tls_client_ca")]
+    tls_profile_tools: Vec<(String, String)>,
+    
+    /// Limit clients with permission profile PROFILE to the resources whose
+    /// URIs start with PREFIX and others given the same way, as
+    /// PROFILE=PREFIX (repeatable)
+    #[arg(long = "tls-profile-resource", value_name = "PROFILE=PREFIX", value_parser = parse_key_value, requires = "tls_client_ca")]
+    tls_profile_resources: Vec<(String, String)>,
+    
+    /// Browser origin allowed to call the http or sse transport, e.g.
+    /// https://app.example.com, or * for any (repeatable; default: localhost pages only)
+    #[arg(long = "cors-origin", value_name = "ORIGIN")]
+    cors_origins: Vec<String>,
+    
+    /// Request header browsers may send beyond those MCP uses (repeatable)
+    #[arg(long = "cors-header", value_name = "NAME")]
+    cors_headers: Vec<String>,
+    
+    /// HTTP method browsers may use, replacing the default GET, POST and DELETE (repeatable)
+    #[arg(long = "cors-method", value_name = "METHOD")]
+    cors_methods: Vec<String>,
+    
+    /// Named pipe the pipe transport serves
+    #[cfg(windows)]
+    #[arg(long, value_name = "NAME", default_value = server::DEFAULT_PIPE_NAME)]
+    pipe_name: String,
+    
+    /// Message framing on stdin/stdout (default: auto-detected, or content-length with --pretty)
+    #[arg(long, value_enum)]
+    framing: Option<Framing>,
+    
+    /// Pretty-print outgoing JSON for debugging; requires content-length framing
+    #[arg(long)]
+    pretty: bool,
+    
+    /// Override a tool's description as NAME=TEXT (repeatable)
+    #[arg(long = "tool-description", value_name = "NAME=TEXT", value_parser = parse_key_value)]
+    tool_descriptions: Vec<(String, String)>,
+    
+    /// JSON file mapping tool names to follow-up tools for tools/suggest_next,
+    /// e.g. {"search_files": ["read_file"]}; entries replace the built-in ones
+    #[arg(long, value_name = "FILE")]
+    tool_suggestions: Option<PathBuf>,
+    
+    /// Directory of Markdown files served as server/help topics, each named
+    /// by its file stem; overview.md replaces the generated overview
+    #[arg(long, value_name = "DIR")]
+    help_dir: Option<PathBuf>,
+    
+    /// Directory of Markdown or YAML prompt templates, each named by its
+    /// file stem; reloaded when files in it change
+    #[arg(long, value_name = "DIR")]
+    prompts_dir: Option<PathBuf>,
+    
+    /// Seconds a tool call may run before it is cancelled (default: 30)
+    #[arg(long, value_name = "SECS")]
+    tool_timeout: Option<u64>,
+    
+    /// Built-in tool to leave unregistered (repeatable)
+    #[arg(long = "disable-tool", value_name = "NAME")]
+    disabled_tools: Vec<String>,
+    
+    /// Tool that self_benchmark may call, replacing the default of echo (repeatable)
+    #[arg(long = "benchmark-tool", value_name = "NAME")]
+    benchmark_tools: Vec<String>,
+    
+    /// TOML or JSON file of server settings; command line flags override it
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    
+    /// Seconds in-flight requests get to finish after SIGINT or SIGTERM
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_SHUTDOWN_TIMEOUT.as_secs())]
+    shutdown_timeout: u64,
+    
+    /// Requests per second a stdio, TCP or pipe client may send before it is refused
+    /// with a rate limited error (default: unlimited)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit: Option<u32>,
+    
+    /// Path prefix a reverse proxy publishes the http or sse transport under,
+    /// e.g. /ai/mcp; routes and advertised URLs include it
+    #[arg(long, value_name = "PATH", value_parser = parse_base_path)]
+    base_path: Option<String>,
+    
+    /// Don't gzip or deflate http transport responses, even for clients that accept it
+    #[arg(long)]
+    no_compression: bool,
+    
+    /// Seconds a client of a network transport may stay silent before its session
+    /// is closed; the server pings clients so that live ones always have something
+    /// to answer
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    idle_timeout: Option<u64>,
+    
+    /// Seconds between keepalive pings (default: a third of --idle-timeout)
+    #[arg(long, value_name = "SECS", requires = "idle_timeout", value_parser = clap::value_parser!(u64).range(1..))]
+    keepalive_interval: Option<u64>,
+    
+    /// Sessions the http, sse and tcp transports keep open at once; further
+    /// clients are refused until one leaves (default: 1000)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_sessions: Option<u64>,
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", s))
+}
+
+fn parse_base_path(s: &str) -> Result<String, String> {
+    match s.chars().find(|c| matches!(c, '?' | '#') || c.is_whitespace()) {
+        Some(c) => Err(format!("a base path can't contain '{}'", c)),
+        None => Ok(s.to_string()),
+    }
+}
+
+fn load_suggestions(path: &Path) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read tool suggestions from {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("Invalid tool suggestions in {}", path.display()))
+}
+
+fn load_help_topics(dir: &Path) -> anyhow::Result<HashMap<String, String>> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read help topics from {}", dir.display()))?;
+    let mut topics = HashMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "md") {
+            if let Some(topic) = path.file_stem().and_then(|stem| stem.to_str()) {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read help topic {}", path.display()))?;
+                topics.insert(topic.to_string(), text);
+            }
+        }
+    }
+    Ok(topics)
+}
+
+/// The settings given on the command line. Boolean flags can only switch
+/// an option on, so leaving one out keeps the config file's value.
+fn cli_layer(cli: &Cli) -> anyhow::Result<ConfigLayer> {
+    Ok(ConfigLayer {
+        enable_network: cli.enable_network.then_some(true),
+        allowed_hosts: (!cli.allowed_hosts.is_empty()).then(|| cli.allowed_hosts.clone()),
+        allowed_commands: (!cli.allowed_commands.is_empty()).then(|| cli.allowed_commands.clone()),
+        enable_process_info: cli.enable_process_info.then_some(true),
+        enable_cron: cli.enable_cron.then_some(true),
+        root: cli.root.clone(),
+        working_dir: cli.working_dir.clone(),
+        read_only: cli.read_only.then_some(true),
+        tool_descriptions: (!cli.tool_descriptions.is_empty()).then(|| cli.tool_descriptions.iter().cloned().collect()),
+        tool_timeout: cli.tool_timeout,
+        tool_timeouts: None,
+        tool_suggestions: cli.tool_suggestions.as_deref().map(load_suggestions).transpose()?,
+        help_topics: cli.help_dir.as_deref().map(load_help_topics).transpose()?,
+        prompts_dir: cli.prompts_dir.clone(),
+        disabled_tools: (!cli.disabled_tools.is_empty()).then(|| cli.disabled_tools.clone()),
+        benchmark_tools: (!cli.benchmark_tools.is_empty()).then(|| cli.benchmark_tools.clone()),
+        client_profiles: (!cli.tls_client_profiles.is_empty()).then(|| cli.tls_client_profiles.iter().cloned().collect()),
+        profile_tools: (!cli.tls_profile_tools.is_empty()).then(|| {
+            let mut profile_tools: HashMap<String, Vec<String>> = HashMap::new();
+            for (profile, tool) in &cli.tls_profile_tools {
+                profile_tools.entry(profile.clone()).or_default().push(tool.clone());
+            }
+            profile_tools
+        }),
+        profile_resources: (!cli.tls_profile_resources.is_empty()).then(|| {
+            let mut profile_resources: HashMap<String, Vec<String>> = HashMap::new();
+            for (profile, prefix) in &cli.tls_profile_resources {
+                profile_resources.entry(profile.clone()).or_default().push(prefix.clone());
+            }
+            profile_resources
+        }),
+    })
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let wire_format = WireFormat::new(cli.framing, cli.pretty)?;
+    
+    // Log locally unless in quiet mode; events go to the client either way,
+    // once it asks for them with `logging/setLevel`
+    let client_log = ClientLogLayer::new();
+    let local_log = (!cli.quiet).then(|| {
+        tracing_subscriber::fmt::layer().with_filter(if cli.debug {
+            LevelFilter::DEBUG
+        } else {
+            LevelFilter::INFO
+        })
+    });
+    let subscriber = tracing_subscriber::registry()
+        .with(local_log)
+        .with(client_log.clone().with_filter(filter_fn(ClientLogLayer::forwards)));
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("setting default subscriber failed");
     
-    // Initialize tracing only if not in quiet mode
     if !cli.quiet {
-        let subscriber = tracing_subscriber::fmt()
-            .with_max_level(if cli.debug {
-                tracing::Level::DEBUG
-            } else {
-                tracing::Level::INFO
-            })
-            .finish();
-        
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("setting default subscriber failed");
-        
         info!("Starting MCP server: {} v{}", cli.name, cli.version);
     }
     
-    // Create the MCP server
-    let mcp_server = Arc::new(McpServer::new(cli.name, cli.version));
+    let mut config = ServerConfig::default();
+    if let Some(path) = &cli.config {
+        config.merge(ConfigLayer::load(path)?, ConfigSource::Config);
+    }
+    config.merge(cli_layer(&cli)?, ConfigSource::Cli);
+    if let Some(dir) = &config.working_dir {
+        if !dir.is_dir() {
+            anyhow::bail!("Working directory {} is not a directory", dir.display());
+        }
+    }
+    if let Some(dir) = &config.prompts_dir {
+        if !dir.is_dir() {
+            anyhow::bail!("Prompts directory {} is not a directory", dir.display());
+        }
+    }
     
-    // Create and run the stdio server
-    let stdio_server = StdioServer::new(mcp_server, cli.quiet);
+    let tls = match (&cli.tls_cert, &cli.tls_key) {
+        (Some(cert), Some(key)) => {
+            if !matches!(cli.transport, TransportKind::Http | TransportKind::Sse | TransportKind::Tcp) {
+                anyhow::bail!("--tls-cert applies only to the http, sse and tcp transports");
+            }
+            Some(load_tls_acceptor(cert, key, cli.tls_client_ca.as_deref())?)
+        }
+        _ => None,
+    };
     
-    if let Err(e) = stdio_server.run().await {
-        if !cli.quiet {
-            warn!("Server error: {}", e);
+    let cors_given = !(cli.cors_origins.is_empty() && cli.cors_headers.is_empty() && cli.cors_methods.is_empty());
+    if cors_given && !matches!(cli.transport, TransportKind::Http | TransportKind::Sse) {
+        anyhow::bail!("--cors-origin, --cors-header and --cors-method apply only to the http and sse transports");
+    }
+    if cli.base_path.is_some() && !matches!(cli.transport, TransportKind::Http | TransportKind::Sse) {
+        anyhow::bail!("--base-path applies only to the http and sse transports");
+    }
+    let cors = CorsPolicy::new(&cli.cors_origins, &cli.cors_headers, &cli.cors_methods)?;
+    
+    let keepalive = match cli.idle_timeout {
+        Some(_) if cli.transport == TransportKind::Stdio => {
+            anyhow::bail!("--idle-timeout applies only to network transports");
         }
+        Some(idle_timeout) => {
+            let mut keepalive = Keepalive::new(Duration::from_secs(idle_timeout));
+            if let Some(interval) = cli.keepalive_interval {
+                keepalive.interval = Duration::from_secs(interval);
+            }
+            Some(keepalive)
+        }
+        None => None,
+    };
+    
+    if cli.max_sessions.is_some() && !matches!(cli.transport, TransportKind::Http | TransportKind::Sse | TransportKind::Tcp) {
+        anyhow::bail!("--max-sessions applies only to the http, sse and tcp transports");
     }
     
+    if cli.listen.is_some() && !matches!(cli.transport, TransportKind::Http | TransportKind::Sse | TransportKind::Tcp) {
+        anyhow::bail!("--listen applies only to the http, sse and tcp transports");
+    }
+    let listen_addr = cli.listen.unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], cli.port)));
+    
+    // Under socket activation systemd has bound the address already
+    let activated = systemd_listener()?;
+    if activated.is_some() && !matches!(cli.transport, TransportKind::Http | TransportKind::Sse | TransportKind::Tcp) {
+        anyhow::bail!("systemd passed a socket, but only the http, sse and tcp transports can listen on it");
+    }
+    
+    // Create the MCP server
+    let mcp_server = Arc::new(McpServer::with_config(cli.name, cli.version, config));
+    client_log.attach(mcp_server.logger.clone());
+    
+    // Create and run the selected transport
+    let result = match cli.transport {
+        TransportKind::Stdio => {
+            let mut stdio_server = StdioServer::new(mcp_server, cli.quiet)
+                .with_wire_format(wire_format)
+                .with_shutdown_timeout(Duration::from_secs(cli.shutdown_timeout));
+            if let Some(per_second) = cli.rate_limit {
+                stdio_server = stdio_server.with_rate_limit(per_second);
+            }
+            stdio_server.run().await
+        }
+        TransportKind::Http => {
+            let mut http_server = HttpServer::new(mcp_server, cli.quiet)
+                .with_shutdown_timeout(Duration::from_secs(cli.shutdown_timeout))
+                .with_cors(cors)
+                .with_compression(!cli.no_compression);
+            if let Some(base_path) = &cli.base_path {
+                http_server = http_server.with_base_path(base_path);
+            }
+            if let Some(acceptor) = tls {
+                http_server = http_server.with_tls(acceptor);
+            }
+            if let Some(keepalive) = keepalive {
+                http_server = http_server.with_keepalive(keepalive);
+            }
+            if let Some(max_sessions) = cli.max_sessions {
+                http_server = http_server.with_max_sessions(max_sessions as usize);
+            }
+            match activated {
+                Some(listener) => http_server.run_on(listener).await,
+                None => http_server.run(listen_addr).await,
+            }
+        }
+        TransportKind::Sse => {
+            let mut sse_server = HttpSseServer::new(mcp_server, cli.quiet)
+                .with_shutdown_timeout(Duration::from_secs(cli.shutdown_timeout))
+                .with_cors(cors);
+            if let Some(base_path) = &cli.base_path {
+                sse_server = sse_server.with_base_path(base_path);
+            }
+            if let Some(acceptor) = tls {
+                sse_server = sse_server.with_tls(acceptor);
+            }
+            if let Some(keepalive) = keepalive {
+                sse_server = sse_server.with_keepalive(keepalive);
+            }
+            if let Some(max_sessions) = cli.max_sessions {
+                sse_server = sse_server.with_max_sessions(max_sessions as usize);
+            }
+            match activated {
+                Some(listener) => sse_server.run_on(listener).await,
+                None => sse_server.run(listen_addr).await,
+            }
+        }
+        TransportKind::Tcp => {
+            let mut tcp_server = TcpServer::new(mcp_server, cli.quiet)
+                .with_shutdown_timeout(Duration::from_secs(cli.shutdown_timeout));
+            if let Some(per_second) = cli.rate_limit {
+                tcp_server = tcp_server.with_rate_limit(per_second);
+            }
+            if let Some(acceptor) = tls {
+                tcp_server = tcp_server.with_tls(acceptor);
+            }
+            if let Some(keepalive) = keepalive {
+                tcp_server = tcp_server.with_keepalive(keepalive);
+            }
+            if let Some(max_sessions) = cli.max_sessions {
+                tcp_server = tcp_server.with_max_sessions(max_sessions as usize);
+            }
+            match activated {
+                Some(listener) => tcp_server.run_on(listener).await,
+                None => tcp_server.run(listen_addr).await,
+            }
+        }
+        #[cfg(windows)]
+        TransportKind::Pipe => {
+            let mut pipe_server = server::PipeServer::new(mcp_server, cli.quiet)
+                .with_shutdown_timeout(Duration::from_secs(cli.shutdown_timeout));
+            if let Some(per_second) = cli.rate_limit {
+                pipe_server = pipe_server.with_rate_limit(per_second);
+            }
+            if let Some(keepalive) = keepalive {
+                pipe_server = pipe_server.with_keepalive(keepalive);
+            }
+            pipe_server.run(&cli.pipe_name).await
+        }
+    };
+    
+    // Reported even with --quiet, as the reason the exit status is nonzero
+    let code = match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            1
+        }
+    };
+    
     if !cli.quiet {
         info!("MCP server shutting down");
     }
-    Ok(())
+    // After a signal the stdin reader is still blocked in a read, which
+    // the runtime would wait on forever when it is dropped
+    std::process::exit(code)
 }
@@ -1,44 +1,200 @@
-use crate::tools::ToolRegistry;
+use crate::client::ClientHandle;
+use crate::config::ServerConfig;
+use crate::help::{Help, HelpError};
+use crate::notifications::{LogLevel, Logger, Notifier, Progress, UnknownLogLevel};
+use crate::prompts::{PromptError, PromptRegistry};
+use crate::resources::{ResourceError, ResourceRegistry, ResourceSubscriptions};
+use crate::session::Session;
+use crate::stats::Stats;
+use crate::tools::{ToolCallError, ToolContext, ToolRegistry};
 use crate::types::*;
 use anyhow::Result;
-use tracing::{debug, info};
+use base64::Engine;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
 
+/// Protocol versions this server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-03-26", "2024-11-05"];
+
+/// Kept short: detailed guidance is fetched per topic from `server/help`
+const INSTRUCTIONS: &str = "Call `server/help` for usage guidance: topic `overview` (the default) \
+    summarizes the server and lists every topic, and `tools/<name>` explains one tool.";
+
+/// Tools returned per `tools/list` page unless configured otherwise
+pub const DEFAULT_TOOLS_PAGE_SIZE: usize = 100;
+
+/// Most values a `completion/complete` result carries, per the MCP spec
+const MAX_COMPLETIONS: usize = 100;
+
+/// Errors that fail `tools/list`
+#[derive(Debug, thiserror::Error)]
+pub enum ListToolsError {
+    #[error("Invalid cursor '{0}'")]
+    InvalidCursor(String),
+}
+
+/// Request params that are missing or don't have the method's shape
+#[derive(Debug, thiserror::Error)]
+pub enum ParamsError {
+    #[error("{method} requires params")]
+    Missing { method: &'static str },
+    #[error("Invalid params for {method}: {source}")]
+    Malformed { method: &'static str, source: serde_json::Error },
+    #[error("Invalid params for {method}: {message}")]
+    Invalid { method: &'static str, message: String },
+}
+
+impl ParamsError {
+    /// What was wrong, without the method name
+    fn detail(&self) -> String {
+        match self {
+            Self::Missing { .. } => "params are required".to_string(),
+            Self::Malformed { source, .. } => source.to_string(),
+            Self::Invalid { message, .. } => message.clone(),
+        }
+    }
+}
+
+/// Deserialize a method's params, which must be present
+fn parse_params<T: serde::de::DeserializeOwned>(method: &'static str, params: Option<serde_json::Value>) -> Result<T, ParamsError> {
+    let params = params.ok_or(ParamsError::Missing { method })?;
+    serde_json::from_value(params).map_err(|source| ParamsError::Malformed { method, source })
+}
+
+/// Errors that fail `initialize`
+#[derive(Debug, thiserror::Error)]
+pub enum InitializeError {
+    #[error("Unsupported protocol version '{requested}'; supported: {}", .supported.join(", "))]
+    UnsupportedProtocolVersion { requested: String, supported: Vec<String> },
+}
+
+#[derive(Clone)]
 pub struct McpServer {
     pub name: String,
     pub version: String,
+    /// Protocol version in use: the newest supported one until `initialize`
+    /// negotiates one with the client
     pub protocol_version: String,
+    /// Versions `initialize` may agree to, newest first
+    pub supported_protocol_versions: Vec<String>,
+    /// Most tools returned by one `tools/list` call
+    pub tools_page_size: usize,
     pub initialized: bool,
+    pub session: Session,
+    /// Shared by the per-request snapshots of the server
+    pub config: Arc<ServerConfig>,
+    pub notifier: Notifier,
+    /// Log entries for the client, at the level it set with `logging/setLevel`
+    pub logger: Logger,
+    /// Requests to the client, such as `sampling/createMessage`
+    pub client: ClientHandle,
     pub tool_registry: ToolRegistry,
+    pub resource_registry: ResourceRegistry,
+    /// Resources the client asked to hear about changes to
+    pub subscriptions: ResourceSubscriptions,
+    pub prompt_registry: PromptRegistry,
 }
 
 impl McpServer {
     pub fn new(name: String, version: String) -> Self {
+        Self::with_config(name, version, ServerConfig::default())
+    }
+    
+    pub fn with_config(name: String, version: String, config: ServerConfig) -> Self {
+        let notifier = Notifier::new();
         Self {
             name,
             version,
-            protocol_version: "2024-11-05".to_string(),
+            protocol_version: SUPPORTED_PROTOCOL_VERSIONS[0].to_string(),
+            supported_protocol_versions: SUPPORTED_PROTOCOL_VERSIONS.iter().map(|v| v.to_string()).collect(),
+            tools_page_size: DEFAULT_TOOLS_PAGE_SIZE,
             initialized: false,
-            tool_registry: ToolRegistry::new(),
+            session: Session::default(),
+            tool_registry: ToolRegistry::with_config(&config, notifier.clone()),
+            resource_registry: ResourceRegistry::with_config(&config, notifier.clone()),
+            subscriptions: ResourceSubscriptions::new(notifier.clone()),
+            prompt_registry: PromptRegistry::with_config(&config, notifier.clone()),
+            config: Arc::new(config),
+            logger: Logger::new(notifier.clone()),
+            client: ClientHandle::new(),
+            notifier,
         }
     }
     
+    /// Serve the tools of `registry`, e.g. one from `ToolRegistry::with_tools`,
+    /// instead of those built from the config. Its `tools/list_changed` and
+    /// scheduled task results go to this server's clients.
+    pub fn with_tool_registry(mut self, registry: ToolRegistry) -> Self {
+        self.tool_registry = registry.with_notifier(self.notifier.clone());
+        self
+    }
+    
+    /// Request and tool call counters, shared with the tool registry
+    pub fn stats(&self) -> &Stats {
+        self.tool_registry.stats()
+    }
+    
+    /// Whether handling `request` changes server state. Such requests need
+    /// `&mut self` and must finish before the next message is handled; the
+    /// rest only read state, so transports may run them concurrently.
+    pub(crate) fn needs_exclusive(request: &JsonRpcRequest) -> bool {
+        request.id.is_none()
+            || matches!(request.method.as_str(), "initialize" | "initialized" | "notifications/initialized")
+    }
+    
     pub async fn handle_request(&mut self, request: JsonRpcRequest) -> Result<Option<JsonRpcResponse>> {
         debug!("Handling request: {} (id: {:?})", request.method, request.id);
+        // The rest are counted by `handle_shared_request`
+        if Self::needs_exclusive(&request) {
+            self.stats().record_request(&request.method);
+        }
         
         // Handle notifications (no response should be sent)
-        if request.method == "notifications/initialized" {
-            self.handle_initialized().await?;
+        if request.id.is_none() || request.method == "notifications/initialized" {
+            self.handle_notification(&request.method).await?;
             return Ok(None); // No response for notifications
         }
         
         let result = match request.method.as_str() {
             "initialize" => self.handle_initialize(request.params).await,
             "initialized" => self.handle_initialized().await,
-            "tools/list" => self.handle_list_tools().await,
+            _ => return self.handle_shared_request(request).await,
+        };
+        Ok(Some(respond(request.id, result)))
+    }
+    
+    /// Handle a request that leaves server state alone; see `needs_exclusive`.
+    /// Everything but `initialize` and the initialized notification comes
+    /// through here, so this is where requests before `initialize` are refused.
+    pub(crate) async fn handle_shared_request(&self, request: JsonRpcRequest) -> Result<Option<JsonRpcResponse>> {
+        self.stats().record_request(&request.method);
+        if !self.initialized {
+            return Ok(Some(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: None,
+                error: Some(JsonRpcError::server_not_initialized()),
+            }));
+        }
+        
+        let result = match request.method.as_str() {
+            "tools/list" => self.handle_list_tools(request.params).await,
+            "tools/export_openai" => self.handle_export_openai().await,
+            "tools/suggest_next" => self.handle_suggest_next(request.params).await,
             "tools/call" => self.handle_call_tool(request.params).await,
             "resources/list" => self.handle_list_resources().await,
+            "resources/read" => self.handle_read_resource(request.params).await,
+            "resources/subscribe" => self.handle_subscribe(request.params).await,
+            "resources/unsubscribe" => self.handle_unsubscribe(request.params).await,
             "prompts/list" => self.handle_list_prompts().await,
+            "prompts/get" => self.handle_get_prompt(request.params).await,
+            "completion/complete" => self.handle_complete(request.params).await,
             "ping" => self.handle_ping().await,
+            "logging/setLevel" => self.handle_set_level(request.params).await,
+            "session/info" => self.handle_session_info().await,
+            "server/help" => self.handle_help(request.params).await,
+            "server/effective_config" => self.handle_effective_config().await,
+            "self_benchmark" => self.handle_self_benchmark(request.params).await,
             _ => {
                 return Ok(Some(JsonRpcResponse {
                     jsonrpc: "2.0".to_string(),
@@ -48,100 +204,407 @@ impl McpServer {
                 }));
             }
         };
-        
-        match result {
-            Ok(value) => Ok(Some(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(value),
-                error: None,
-            })),
-            Err(e) => {
-                debug!("Request error: {}", e);
-                Ok(Some(JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError::internal_error()),
-                }))
-            }
-        }
+        Ok(Some(respond(request.id, result)))
     }
     
     async fn handle_initialize(&mut self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
-        let request: InitializeRequest = if let Some(params) = params {
-            serde_json::from_value(params)?
-        } else {
-            return Err(anyhow::anyhow!("Initialize request requires parameters"));
-        };
+        let request: InitializeRequest = parse_params("initialize", params)?;
         
         info!("Initializing MCP server for client: {} v{}", 
               request.client_info.name, request.client_info.version);
         
+        let Some(protocol_version) = self.negotiate_protocol_version(&request.protocol_version) else {
+            return Err(InitializeError::UnsupportedProtocolVersion {
+                requested: request.protocol_version,
+                supported: self.supported_protocol_versions.clone(),
+            }
+            .into());
+        };
+        if protocol_version != request.protocol_version {
+            info!("Client requested protocol {}, using {}", request.protocol_version, protocol_version);
+        }
+        
+        self.protocol_version = protocol_version.clone();
         self.initialized = true;
+        self.client.set_capabilities(request.capabilities.clone());
+        self.session = Session {
+            protocol_version: Some(protocol_version),
+            client_info: Some(request.client_info),
+            client_capabilities: Some(request.capabilities),
+            // Granted before `initialize`, by the transport
+            scopes: std::mem::take(&mut self.session.scopes),
+            ..Session::default()
+        };
         
         let response = InitializeResponse {
             protocol_version: self.protocol_version.clone(),
             capabilities: ServerCapabilities {
                 tools: Some(ToolsCapability {
-                    list_changed: Some(false),
+                    list_changed: Some(true),
                 }),
-                resources: None,
-                prompts: None,
-                logging: None,
+                resources: (!self.resource_registry.is_empty()).then_some(ResourcesCapability {
+                    subscribe: Some(true),
+                    list_changed: Some(true),
+                }),
+                prompts: (!self.prompt_registry.is_empty()).then_some(PromptsCapability {
+                    list_changed: self.prompt_registry.watches_directory().then_some(true),
+                }),
+                logging: Some(LoggingCapability {}),
+                completions: Some(CompletionsCapability {}),
             },
             server_info: ServerInfo {
                 name: self.name.clone(),
                 version: self.version.clone(),
             },
+            instructions: Some(INSTRUCTIONS.to_string()),
         };
         
         Ok(serde_json::to_value(response)?)
     }
     
+    /// The requested version if supported, otherwise the newest supported
+    /// version older than it. Versions are dates, so they compare as strings.
+    pub fn negotiate_protocol_version(&self, requested: &str) -> Option<String> {
+        self.supported_protocol_versions
+            .iter()
+            .filter(|version| version.as_str() <= requested)
+            .max()
+            .cloned()
+    }
+    
+    async fn handle_notification(&mut self, method: &str) -> Result<()> {
+        match method {
+            "initialized" | "notifications/initialized" => {
+                self.handle_initialized().await?;
+            }
+            "notifications/roots/list_changed" if self.client.supports_roots() => {
+                debug!("Client roots changed");
+                self.client.refresh_roots();
+            }
+            _ => debug!("Ignoring notification: {}", method),
+        }
+        Ok(())
+    }
+    
     async fn handle_initialized(&mut self) -> Result<serde_json::Value> {
         info!("Client confirmed initialization");
+        if self.client.supports_roots() {
+            self.client.refresh_roots();
+        }
         Ok(serde_json::Value::Null)
     }
     
-    async fn handle_list_tools(&self) -> Result<serde_json::Value> {
-        if !self.initialized {
-            return Err(anyhow::anyhow!("Server not initialized"));
-        }
+    /// One page of tools in name order. The cursor encodes the last name
+    /// returned, so paging stays consistent if tools are added or removed.
+    async fn handle_list_tools(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let after = match params.as_ref().and_then(|p| p.get("cursor")).and_then(|v| v.as_str()) {
+            Some(cursor) => Some(decode_cursor(cursor).ok_or_else(|| ListToolsError::InvalidCursor(cursor.to_string()))?),
+            None => None,
+        };
         
-        let tools = self.tool_registry.list_tools();
-        let response = ListToolsResponse { tools };
+        let mut tools: Vec<Tool> = self.tool_registry
+            .list_tools()
+            .into_iter()
+            .filter(|tool| after.as_ref().is_none_or(|after| tool.name > *after))
+            .collect();
+        let page_size = self.tools_page_size.max(1);
+        let next_cursor = (tools.len() > page_size).then(|| encode_cursor(&tools[page_size - 1].name));
+        tools.truncate(page_size);
+        let response = ListToolsResponse { tools, next_cursor };
         
         debug!("Listing {} tools", response.tools.len());
         Ok(serde_json::to_value(response)?)
     }
     
+    async fn handle_export_openai(&self) -> Result<serde_json::Value> {
+        let (tools, warnings) = self.tool_registry.export_openai();
+        for warning in &warnings {
+            warn!("OpenAI export: {}", warning);
+        }
+        Ok(serde_json::json!({ "tools": tools, "warnings": warnings }))
+    }
+    
+    async fn handle_suggest_next(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let tool = params
+            .as_ref()
+            .and_then(|p| p.get("tool"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ParamsError::Invalid {
+                method: "tools/suggest_next",
+                message: "tool is required".to_string(),
+            })?;
+        
+        let suggestions = self.tool_registry.suggest_next(tool);
+        Ok(serde_json::json!({ "tool": tool, "suggestions": suggestions }))
+    }
+    
+    /// The built-in and configured guidance behind `server/help`
+    pub fn help(&self) -> Help<'_> {
+        Help {
+            server_name: &self.name,
+            server_version: &self.version,
+            tools: self.tool_registry.list_tools(),
+            topics: &self.config.help_topics,
+        }
+    }
+    
+    async fn handle_help(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let topic = params
+            .as_ref()
+            .and_then(|p| p.get("topic"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("overview");
+        
+        let help = self.help();
+        let content = help.topic(topic, |tool| self.tool_registry.suggest_next(tool))?;
+        Ok(serde_json::json!({ "topic": topic, "content": content, "topics": help.topic_names() }))
+    }
+    
+    /// The level is shared by every clone of the server, so the change
+    /// applies to requests already running too
+    async fn handle_set_level(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let level = params
+            .as_ref()
+            .and_then(|p| p.get("level"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ParamsError::Invalid {
+                method: "logging/setLevel",
+                message: "level is required".to_string(),
+            })?;
+        let level: LogLevel = level.parse()?;
+        
+        info!("Client set log level to {:?}", level);
+        self.logger.set_level(level);
+        Ok(serde_json::json!({}))
+    }
+    
+    async fn handle_effective_config(&self) -> Result<serde_json::Value> {
+        let mut tools: Vec<String> = self.tool_registry.list_tools().into_iter().map(|tool| tool.name).collect();
+        tools.sort();
+        Ok(serde_json::json!({ "settings": self.config.effective(), "tools": tools }))
+    }
+    
     async fn handle_call_tool(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
-        if !self.initialized {
-            return Err(anyhow::anyhow!("Server not initialized"));
+        let token = params.as_ref()
+            .and_then(|params| params.get("_meta"))
+            .and_then(|meta| meta.get("progressToken"))
+            .cloned();
+        let request: CallToolRequest = parse_params("tools/call", params)?;
+        if request.arguments.as_ref().is_some_and(|arguments| !arguments.is_object()) {
+            return Err(ParamsError::Invalid {
+                method: "tools/call",
+                message: "arguments must be an object".to_string(),
+            }
+            .into());
         }
         
-        let request: CallToolRequest = if let Some(params) = params {
-            serde_json::from_value(params)?
-        } else {
-            return Err(anyhow::anyhow!("Tool call request requires parameters"));
+        let context = ToolContext {
+            progress: Progress::new(token, self.notifier.clone()),
+            logger: self.logger.clone(),
+            client: self.client.clone(),
         };
-        
-        let response = self.tool_registry.call_tool(request).await?;
+        let response = self.tool_registry.call_tool_with_context(request, context).await?;
         Ok(serde_json::to_value(response)?)
     }
     
+    async fn handle_self_benchmark(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let params = params.unwrap_or_default();
+        let tool = params.get("tool")
+            .and_then(|v| v.as_str())
+            .unwrap_or("echo");
+        let arguments = params.get("arguments")
+            .cloned()
+            .unwrap_or_else(|| if tool == "echo" { serde_json::json!({"text": "benchmark"}) } else { serde_json::json!({}) });
+        let iterations = params.get("iterations")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+        let concurrency = params.get("concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+        
+        // Thousands of calls to a tool with side effects would repeat them
+        let allowed = self.config.benchmark_tools();
+        if !allowed.iter().any(|allowed| allowed == tool) {
+            return Err(ParamsError::Invalid {
+                method: "self_benchmark",
+                message: format!("Tool '{}' may not be benchmarked; allowed: {}", tool, allowed.join(", ")),
+            }
+            .into());
+        }
+        
+        // Made for the client, so file tools stay inside its roots
+        let context = ToolContext {
+            client: self.client.clone(),
+            ..ToolContext::default()
+        };
+        let report = self
+            .tool_registry
+            .benchmark_with_context(tool, arguments, iterations, concurrency, context)
+            .await?;
+        info!("Benchmarked {}: p50 {:.3} ms, {:.0} calls/s", report.tool, report.p50_ms, report.throughput_per_sec);
+        Ok(serde_json::to_value(report)?)
+    }
+    
     async fn handle_ping(&self) -> Result<serde_json::Value> {
         Ok(serde_json::json!({"pong": true}))
     }
     
+    async fn handle_session_info(&self) -> Result<serde_json::Value> {
+        let session = Session {
+            subscriptions: self.subscriptions.uris(),
+            roots: self.client.roots().unwrap_or_default(),
+            ..self.session.clone()
+        };
+        Ok(serde_json::to_value(&session)?)
+    }
+    
     async fn handle_list_resources(&self) -> Result<serde_json::Value> {
-        // Return empty resources list since we don't implement resources yet
-        Ok(serde_json::json!({"resources": []}))
+        // Resources are files too, so they are held to the client's roots
+        let resources = self.client.run_for(self.resource_registry.list_resources()).await?;
+        let response = ListResourcesResponse { resources };
+        
+        debug!("Listing {} resources", response.resources.len());
+        Ok(serde_json::to_value(response)?)
+    }
+    
+    async fn handle_read_resource(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let request: ReadResourceRequest = parse_params("resources/read", params)?;
+        
+        let contents = self.client.run_for(self.resource_registry.read_resource(&request.uri)).await?;
+        Ok(serde_json::to_value(ReadResourceResponse { contents })?)
+    }
+    
+    async fn handle_subscribe(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let request: SubscribeRequest = parse_params("resources/subscribe", params)?;
+        
+        let watched = self.client.run_for(async { self.resource_registry.watch_path(&request.uri) }).await;
+        let Some(path) = watched else {
+            return Err(ResourceError::NotFound(request.uri).into());
+        };
+        self.subscriptions.subscribe(&request.uri, path)?;
+        Ok(serde_json::json!({}))
+    }
+    
+    async fn handle_unsubscribe(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let request: SubscribeRequest = parse_params("resources/unsubscribe", params)?;
+        
+        if !self.subscriptions.unsubscribe(&request.uri) {
+            debug!("Ignoring unsubscribe from {}, which has no subscription", request.uri);
+        }
+        Ok(serde_json::json!({}))
     }
     
     async fn handle_list_prompts(&self) -> Result<serde_json::Value> {
-        // Return empty prompts list since we don't implement prompts yet
-        Ok(serde_json::json!({"prompts": []}))
+        let prompts = self.prompt_registry.list_prompts();
+        let response = ListPromptsResponse { prompts };
+        
+        debug!("Listing {} prompts", response.prompts.len());
+        Ok(serde_json::to_value(response)?)
+    }
+    
+    async fn handle_get_prompt(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let request: GetPromptRequest = parse_params("prompts/get", params)?;
+        
+        let response = self.prompt_registry.get_prompt(request).await?;
+        Ok(serde_json::to_value(response)?)
+    }
+    
+    async fn handle_complete(&self, params: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let request: CompleteRequest = parse_params("completion/complete", params)?;
+        
+        let CompletionArgument { name, value } = &request.argument;
+        let mut values = match &request.reference {
+            CompletionReference::Prompt { name: prompt } => self.prompt_registry.complete(prompt, name, value)?,
+            CompletionReference::Resource { uri } => {
+                self.client.run_for(self.resource_registry.complete(uri, name, value)).await?
+            }
+        };
+        let total = values.len();
+        values.truncate(MAX_COMPLETIONS);
+        let completion = Completion {
+            values,
+            total: Some(total),
+            has_more: Some(total > MAX_COMPLETIONS),
+        };
+        Ok(serde_json::to_value(CompleteResponse { completion })?)
+    }
+}
+
+/// Wrap a handler's result in the response for request `id`
+fn respond(id: Option<serde_json::Value>, result: Result<serde_json::Value>) -> JsonRpcResponse {
+    match result {
+        Ok(value) => JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(value),
+            error: None,
+        },
+        Err(e) => {
+            debug!("Request error: {}", e);
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result: None,
+                error: Some(error_for(&e)),
+            }
+        }
+    }
+}
+
+/// The `tools/list` cursor for a page ending at `last_tool`
+fn encode_cursor(last_tool: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(last_tool)
+}
+
+/// The last tool name of the previous page, or `None` for a cursor this
+/// server didn't hand out
+fn decode_cursor(cursor: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Map a handler error to the JSON-RPC error the client sees
+fn error_for(e: &anyhow::Error) -> JsonRpcError {
+    if let Some(error) = e.downcast_ref::<ParamsError>() {
+        return JsonRpcError {
+            data: Some(serde_json::json!({ "message": error.detail() })),
+            ..JsonRpcError::invalid_params()
+        };
+    }
+    if let Some(tool_error) = e.downcast_ref::<ToolCallError>() {
+        return JsonRpcError::with_data(
+            tool_error.code(),
+            tool_error.to_string(),
+            serde_json::json!({ "tool": tool_error.tool(), "message": tool_error.to_string() }),
+        );
+    }
+    if let Some(error @ InitializeError::UnsupportedProtocolVersion { requested, supported }) = e.downcast_ref() {
+        return JsonRpcError::with_data(
+            error_codes::INVALID_PARAMS,
+            error.to_string(),
+            serde_json::json!({ "requested": requested, "supported": supported }),
+        );
+    }
+    if let Some(error @ HelpError::UnknownTopic { topics, .. }) = e.downcast_ref() {
+        return JsonRpcError::with_data(
+            error_codes::INVALID_PARAMS,
+            error.to_string(),
+            serde_json::json!({ "topics": topics }),
+        );
+    }
+    if let Some(error @ ListToolsError::InvalidCursor(_)) = e.downcast_ref() {
+        return JsonRpcError::new(error_codes::INVALID_PARAMS, error.to_string());
+    }
+    if let Some(error) = e.downcast_ref::<UnknownLogLevel>() {
+        return JsonRpcError::new(error_codes::INVALID_PARAMS, error.to_string());
+    }
+    if let Some(ResourceError::NotFound(uri)) = e.downcast_ref::<ResourceError>() {
+        return JsonRpcError::resource_not_found(uri);
+    }
+    match e.downcast_ref::<PromptError>() {
+        Some(prompt_error @ (PromptError::NotFound(_) | PromptError::MissingArgument { .. })) => {
+            JsonRpcError::new(error_codes::INVALID_PARAMS, prompt_error.to_string())
+        }
+        _ => JsonRpcError::internal_error(),
     }
 }
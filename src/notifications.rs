@@ -0,0 +1,363 @@
+use crate::types::JsonRpcNotification;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::Context;
+
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Fan-out channel for server-to-client notifications.
+///
+/// Tools and the server push notifications here; each transport subscribes
+/// and writes them to its client. Notifications sent while nobody is
+/// subscribed are dropped.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<JsonRpcNotification>,
+    /// The notifiers made by `session`, which also carry what is sent here;
+    /// a session's is forgotten once it is dropped
+    sessions: Arc<Mutex<Vec<broadcast::WeakSender<JsonRpcNotification>>>>,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            sessions: Arc::default(),
+        }
+    }
+
+    /// A notifier for one client's session. What is sent on it, such as
+    /// progress and log entries, reaches that session's subscribers only;
+    /// what is sent on this one, such as `tools/list_changed`, reaches
+    /// every session.
+    pub fn session(&self) -> Self {
+        let session = Self::new();
+        self.lock_sessions().push(session.sender.downgrade());
+        session
+    }
+
+    pub fn notify(&self, method: &str, params: Option<serde_json::Value>) {
+        let notification = JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        };
+        self.lock_sessions().retain(|session| match session.upgrade() {
+            Some(sender) => {
+                let _ = sender.send(notification.clone());
+                true
+            }
+            None => false,
+        });
+        let _ = self.sender.send(notification);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.sender.subscribe()
+    }
+
+    fn lock_sessions(&self) -> std::sync::MutexGuard<'_, Vec<broadcast::WeakSender<JsonRpcNotification>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reports progress on one request to the client as `notifications/progress`.
+///
+/// Clients opt in by sending `_meta.progressToken` with the request; without
+/// a token every report is dropped, so tools can report unconditionally.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    token: Option<serde_json::Value>,
+    notifier: Option<Notifier>,
+}
+
+impl Progress {
+    pub fn new(token: Option<serde_json::Value>, notifier: Notifier) -> Self {
+        Self {
+            token,
+            notifier: Some(notifier),
+        }
+    }
+
+    /// A reporter that drops every report
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Whether the client asked for progress, e.g. to skip expensive estimates
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some() && self.notifier.is_some()
+    }
+
+    /// Report `progress` so far, out of `total` when it is known
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<&str>) {
+        let (Some(token), Some(notifier)) = (&self.token, &self.notifier) else {
+            return;
+        };
+        let mut params = serde_json::json!({
+            "progressToken": token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = serde_json::json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = serde_json::json!(message);
+        }
+        notifier.notify("notifications/progress", Some(params));
+    }
+}
+
+/// Severity of a log message, least severe first. MCP uses the syslog
+/// levels of RFC 5424.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// A `logging/setLevel` level the server doesn't know
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown log level '{0}'; expected one of debug, info, notice, warning, error, critical, alert, emergency")]
+pub struct UnknownLogLevel(pub String);
+
+impl LogLevel {
+    const ALL: [LogLevel; 8] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Notice,
+        LogLevel::Warning,
+        LogLevel::Error,
+        LogLevel::Critical,
+        LogLevel::Alert,
+        LogLevel::Emergency,
+    ];
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = UnknownLogLevel;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::json!(s)).map_err(|_| UnknownLogLevel(s.to_string()))
+    }
+}
+
+/// Sends log entries to the client as `notifications/message`.
+///
+/// Entries below the level the client chose with `logging/setLevel` are
+/// dropped. Clones share that level, so a change reaches every handle.
+#[derive(Debug, Clone)]
+pub struct Logger {
+    state: Arc<LoggerState>,
+    /// Reported as the entry's `logger`, e.g. the tool that wrote it
+    name: Option<String>,
+}
+
+#[derive(Debug)]
+struct LoggerState {
+    notifier: Option<Notifier>,
+    level: AtomicU8,
+    /// Whether the client has chosen a level with `logging/setLevel`
+    level_set: AtomicBool,
+    /// The loggers made by `session`; a session's is forgotten once it is
+    /// dropped
+    sessions: Mutex<Vec<Weak<LoggerState>>>,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::with_notifier(None)
+    }
+}
+
+impl Logger {
+    /// A logger starting at `info`
+    pub fn new(notifier: Notifier) -> Self {
+        Self::with_notifier(Some(notifier))
+    }
+
+    /// A logger that drops every entry
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    fn with_notifier(notifier: Option<Notifier>) -> Self {
+        Self {
+            state: Arc::new(LoggerState {
+                notifier,
+                level: AtomicU8::new(LogLevel::Info as u8),
+                level_set: AtomicBool::new(false),
+                sessions: Mutex::default(),
+            }),
+            name: None,
+        }
+    }
+
+    /// A logger for one client's session, sending on `notifier` at a level
+    /// of its own. A `ClientLogLayer` attached to this logger forwards to
+    /// the session's as well, at the level that session's client chose.
+    pub fn session(&self, notifier: Notifier) -> Self {
+        let session = Self::new(notifier);
+        self.lock_sessions().push(Arc::downgrade(&session.state));
+        session
+    }
+
+    /// This logger and the live ones made by `session`
+    fn with_sessions(&self) -> Vec<Self> {
+        let mut loggers = vec![self.clone()];
+        self.lock_sessions().retain(|session| match session.upgrade() {
+            Some(state) => {
+                loggers.push(Self { state, name: None });
+                true
+            }
+            None => false,
+        });
+        loggers
+    }
+
+    fn lock_sessions(&self) -> std::sync::MutexGuard<'_, Vec<Weak<LoggerState>>> {
+        self.state.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The same logger, reporting entries under `name`
+    pub fn named(&self, name: &str) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            ..self.clone()
+        }
+    }
+
+    pub fn level(&self) -> LogLevel {
+        LogLevel::ALL[usize::from(self.state.level.load(Ordering::Relaxed))]
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        self.state.level.store(level as u8, Ordering::Relaxed);
+        self.state.level_set.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether a level was set, rather than left at the default
+    pub fn is_level_set(&self) -> bool {
+        self.state.level_set.load(Ordering::Relaxed)
+    }
+
+    /// Whether an entry at `level` would be sent, e.g. to skip building costly messages
+    pub fn is_enabled(&self, level: LogLevel) -> bool {
+        self.state.notifier.is_some() && level >= self.level()
+    }
+
+    /// Send `data`, a message or any JSON value, if `level` is at or above the threshold
+    pub fn log(&self, level: LogLevel, data: impl Into<serde_json::Value>) {
+        let Some(notifier) = self.state.notifier.as_ref().filter(|_| self.is_enabled(level)) else {
+            return;
+        };
+        let mut params = serde_json::json!({
+            "level": level,
+            "data": data.into(),
+        });
+        if let Some(name) = &self.name {
+            params["logger"] = serde_json::json!(name);
+        }
+        notifier.notify("notifications/message", Some(params));
+    }
+}
+
+/// A `tracing` layer that forwards the server's own events to the client
+/// through a `Logger`, so clients see tool failures and the like without
+/// reading stderr. Nothing is forwarded until the client sets a level with
+/// `logging/setLevel`; entries are then named after the event's target and
+/// filtered by that level.
+///
+/// Events from other crates and below `debug` are not forwarded, nor are
+/// debug events from the transports, which log every message they send and
+/// would otherwise report their own reports. Clones forward to the same
+/// logger, and to the loggers of the sessions made from it, each filtered
+/// by the level its own client set.
+#[derive(Debug, Clone, Default)]
+pub struct ClientLogLayer {
+    /// Set once the server, and with it the logger, exists; tracing is set
+    /// up before that so startup is logged too
+    logger: Arc<OnceLock<Logger>>,
+}
+
+impl ClientLogLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start forwarding to `logger` and its sessions' loggers. Only the
+    /// first logger attached is used.
+    pub fn attach(&self, logger: Logger) {
+        let _ = self.logger.set(logger);
+    }
+
+    /// Whether events with `metadata` may be forwarded, for use as the
+    /// layer's filter so that stderr logging is unaffected
+    pub fn forwards(metadata: &tracing::Metadata<'_>) -> bool {
+        let target = metadata.target();
+        let ours = target.split("::").next() == Some(env!("CARGO_CRATE_NAME"));
+        let transport = target.starts_with(concat!(env!("CARGO_CRATE_NAME"), "::server"));
+        ours && *metadata.level() <= tracing::Level::DEBUG
+            && !(transport && *metadata.level() == tracing::Level::DEBUG)
+    }
+}
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for ClientLogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let Some(logger) = self.logger.get() else {
+            return;
+        };
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            tracing::Level::ERROR => LogLevel::Error,
+            tracing::Level::WARN => LogLevel::Warning,
+            tracing::Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        };
+        // Recorded once, for the first logger that wants it
+        let mut message: Option<String> = None;
+        for logger in logger.with_sessions() {
+            if !logger.is_level_set() || !logger.is_enabled(level) {
+                continue;
+            }
+            let message = message.get_or_insert_with(|| {
+                let mut message = EventMessage::default();
+                event.record(&mut message);
+                message.0.trim_start().to_string()
+            });
+            logger.named(metadata.target()).log(level, message.as_str());
+        }
+    }
+}
+
+/// An event's message followed by its other fields as `name=value`
+#[derive(Default)]
+struct EventMessage(String);
+
+impl Visit for EventMessage {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.0);
+            let _ = write!(self.0, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
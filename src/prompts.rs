@@ -0,0 +1,259 @@
+use crate::config::ServerConfig;
+use crate::notifications::Notifier;
+use crate::types::{GetPromptRequest, GetPromptResponse, Prompt, PromptArgument, PromptMessage, Role, ToolContent};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+mod directory;
+
+pub use directory::{PromptDirectory, PROMPT_LIST_CHANGED_NOTIFICATION};
+
+/// Errors surfaced to clients by `prompts/get`
+#[derive(Debug, thiserror::Error)]
+pub enum PromptError {
+    #[error("Prompt '{0}' not found")]
+    NotFound(String),
+    #[error("Prompt '{prompt}' requires argument '{argument}'")]
+    MissingArgument { prompt: String, argument: String },
+    #[error("Error rendering prompt '{0}': {1}")]
+    Render(String, anyhow::Error),
+}
+
+#[derive(Clone)]
+pub struct PromptRegistry {
+    prompts: Arc<HashMap<String, Arc<dyn PromptHandler>>>,
+    /// Templates loaded from `prompts_dir`, which take precedence over
+    /// registered prompts of the same name
+    directory: Option<PromptDirectory>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            prompts: Arc::default(),
+            directory: None,
+        };
+
+        // Register built-in prompts
+        registry.register_prompt(
+            "code_review",
+            Box::new(
+                TemplatePrompt::new(
+                    "Ask for a review of a code snippet",
+                    "Please review the following code, focusing on {{focus}}. \
+                     Point out bugs, risky patterns and concrete improvements.\n\n```\n{{code}}\n```",
+                )
+                .argument("code", "Code to review")
+                .optional_argument("focus", "What the review should concentrate on", "correctness, readability and performance")
+                .completions("focus", &["correctness", "error handling", "naming", "performance", "readability", "security", "tests"]),
+            ),
+        );
+        registry.register_prompt(
+            "summarize_text",
+            Box::new(
+                TemplatePrompt::new(
+                    "Ask for a summary of a piece of text",
+                    "Summarize the following text in {{length}}:\n\n{{text}}",
+                )
+                .argument("text", "Text to summarize")
+                .optional_argument("length", "Desired summary length", "a short paragraph")
+                .completions("length", &["one sentence", "a short paragraph", "three bullet points", "a detailed summary"]),
+            ),
+        );
+
+        registry
+    }
+
+    /// The built-in prompts, plus the templates in `config.prompts_dir`
+    /// kept up to date as the directory changes
+    pub fn with_config(config: &ServerConfig, notifier: Notifier) -> Self {
+        let mut registry = Self::new();
+        if let Some(dir) = &config.prompts_dir {
+            match PromptDirectory::watch(dir, notifier) {
+                Ok(directory) => registry.directory = Some(directory),
+                Err(e) => warn!("Not loading prompt templates: {:#}", e),
+            }
+        }
+        registry
+    }
+
+    pub fn register_prompt(&mut self, name: &str, handler: Box<dyn PromptHandler>) {
+        Arc::make_mut(&mut self.prompts).insert(name.to_string(), Arc::from(handler));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty() && self.directory.is_none()
+    }
+
+    /// Whether prompts can change at runtime, as templates in a watched
+    /// directory do
+    pub fn watches_directory(&self) -> bool {
+        self.directory.is_some()
+    }
+
+    fn handler(&self, name: &str) -> Option<Arc<dyn PromptHandler>> {
+        self.directory
+            .as_ref()
+            .and_then(|directory| directory.get(name))
+            .or_else(|| self.prompts.get(name).cloned())
+    }
+
+    pub fn list_prompts(&self) -> Vec<Prompt> {
+        let mut handlers = (*self.prompts).clone();
+        if let Some(directory) = &self.directory {
+            handlers.extend(directory.snapshot());
+        }
+        let mut prompts: Vec<Prompt> = handlers.iter().map(|(name, handler)| {
+            let arguments = handler.arguments();
+            Prompt {
+                name: name.clone(),
+                description: Some(handler.description()),
+                arguments: (!arguments.is_empty()).then_some(arguments),
+            }
+        }).collect();
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        prompts
+    }
+
+    pub async fn get_prompt(&self, request: GetPromptRequest) -> Result<GetPromptResponse, PromptError> {
+        debug!("Getting prompt: {}", request.name);
+
+        let handler = self.handler(&request.name)
+            .ok_or_else(|| PromptError::NotFound(request.name.clone()))?;
+
+        let arguments = request.arguments.unwrap_or_default();
+        for argument in handler.arguments() {
+            if argument.required == Some(true) && !arguments.contains_key(&argument.name) {
+                return Err(PromptError::MissingArgument {
+                    prompt: request.name,
+                    argument: argument.name,
+                });
+            }
+        }
+
+        handler.get(arguments).await.map_err(|e| PromptError::Render(request.name, e))
+    }
+
+    /// Candidate values for one of a prompt's arguments given what has been
+    /// typed so far; empty when the prompt offers none
+    pub fn complete(&self, name: &str, argument: &str, value: &str) -> Result<Vec<String>, PromptError> {
+        let handler = self.handler(name)
+            .ok_or_else(|| PromptError::NotFound(name.to_string()))?;
+        Ok(handler.complete(argument, value))
+    }
+}
+
+#[async_trait::async_trait]
+pub trait PromptHandler: Send + Sync {
+    fn description(&self) -> String;
+    fn arguments(&self) -> Vec<PromptArgument>;
+    /// Render the prompt; required arguments have already been checked
+    async fn get(&self, arguments: HashMap<String, String>) -> Result<GetPromptResponse>;
+
+    /// Values for `argument` that start with `value`. The default offers none.
+    fn complete(&self, _argument: &str, _value: &str) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A single user message built from a template with `{{name}}` placeholders
+pub struct TemplatePrompt {
+    description: String,
+    template: String,
+    arguments: Vec<PromptArgument>,
+    defaults: HashMap<String, String>,
+    completions: HashMap<String, Vec<String>>,
+}
+
+impl TemplatePrompt {
+    pub fn new(description: &str, template: &str) -> Self {
+        Self {
+            description: description.to_string(),
+            template: template.to_string(),
+            arguments: Vec::new(),
+            defaults: HashMap::new(),
+            completions: HashMap::new(),
+        }
+    }
+
+    /// Add a required argument
+    pub fn argument(mut self, name: &str, description: &str) -> Self {
+        self.arguments.push(PromptArgument {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            required: Some(true),
+        });
+        self
+    }
+
+    /// Add an optional argument substituted with `default` when omitted
+    pub fn optional_argument(mut self, name: &str, description: &str, default: &str) -> Self {
+        self.arguments.push(PromptArgument {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            required: Some(false),
+        });
+        self.defaults.insert(name.to_string(), default.to_string());
+        self
+    }
+
+    /// Suggest these values when a client completes `argument`
+    pub fn completions(mut self, argument: &str, values: &[&str]) -> Self {
+        self.completions.insert(argument.to_string(), values.iter().map(|v| v.to_string()).collect());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl PromptHandler for TemplatePrompt {
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn arguments(&self) -> Vec<PromptArgument> {
+        self.arguments.clone()
+    }
+
+    fn complete(&self, argument: &str, value: &str) -> Vec<String> {
+        let typed = value.to_lowercase();
+        self.completions
+            .get(argument)
+            .map(|values| values.iter().filter(|v| v.to_lowercase().starts_with(&typed)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    async fn get(&self, arguments: HashMap<String, String>) -> Result<GetPromptResponse> {
+        // Single pass, so argument values are never themselves expanded
+        let mut text = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let name = &rest[start + 2..start + end];
+            text.push_str(&rest[..start]);
+            match arguments.get(name).or_else(|| self.defaults.get(name)) {
+                Some(value) => text.push_str(value),
+                None => text.push_str(&rest[start..start + end + 2]),
+            }
+            rest = &rest[start + end + 2..];
+        }
+        text.push_str(rest);
+
+        Ok(GetPromptResponse {
+            description: Some(self.description.clone()),
+            messages: vec![PromptMessage {
+                role: Role::User,
+                content: ToolContent::Text { text },
+            }],
+        })
+    }
+}
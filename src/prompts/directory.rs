@@ -0,0 +1,183 @@
+use super::{PromptHandler, TemplatePrompt};
+use crate::notifications::Notifier;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{mpsc, Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Method of the notification sent when the prompt templates change
+pub const PROMPT_LIST_CHANGED_NOTIFICATION: &str = "notifications/prompts/list_changed";
+
+/// Saving several templates at once, or one save arriving as several
+/// events, triggers a single reload
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+type Prompts = HashMap<String, Arc<dyn PromptHandler>>;
+
+/// Prompt templates loaded from the files in a directory, reloaded whenever
+/// a file there is added, changed or removed.
+///
+/// Each `.md` file is one prompt named by its file stem: optional YAML
+/// front matter between `---` lines gives the description and arguments,
+/// and the rest is the template. A `.yaml` or `.yml` file holds the same
+/// keys with the template under `template`. Files that fail to parse are
+/// skipped with a warning. Clones share the loaded prompts, and the watcher
+/// stops with the last of them.
+#[derive(Clone)]
+pub struct PromptDirectory {
+    prompts: Arc<RwLock<Prompts>>,
+    _watcher: Arc<notify::RecommendedWatcher>,
+}
+
+/// The front matter of a Markdown template, or a whole YAML one
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateFile {
+    description: Option<String>,
+    #[serde(default)]
+    arguments: Vec<ArgumentSpec>,
+    /// YAML templates only; a Markdown template's body is its template
+    template: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ArgumentSpec {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    required: bool,
+    /// Substituted when an optional argument is omitted; empty if unset
+    default: Option<String>,
+    /// Values offered by `completion/complete`
+    #[serde(default)]
+    completions: Vec<String>,
+}
+
+impl PromptDirectory {
+    /// Load the templates in `dir` and keep them up to date, sending
+    /// `notifications/prompts/list_changed` after every reload
+    pub fn watch(dir: &Path, notifier: Notifier) -> Result<Self> {
+        let prompts = Arc::new(RwLock::new(load(dir)?));
+        let (changed_tx, changed) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = changed_tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Prompt directory watcher error: {}", e),
+        })?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch prompt directory {}", dir.display()))?;
+
+        // Dropping the watcher closes the channel, which ends the thread
+        let dir = dir.to_path_buf();
+        let shared = prompts.clone();
+        std::thread::spawn(move || {
+            while changed.recv().is_ok() {
+                while changed.recv_timeout(DEBOUNCE).is_ok() {}
+                match load(&dir) {
+                    Ok(loaded) => {
+                        info!("Reloaded {} prompt templates from {}", loaded.len(), dir.display());
+                        *shared.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+                        notifier.notify(PROMPT_LIST_CHANGED_NOTIFICATION, None);
+                    }
+                    Err(e) => warn!("Keeping the previous prompt templates: {:#}", e),
+                }
+            }
+            debug!("No longer watching prompt directory {}", dir.display());
+        });
+
+        Ok(Self {
+            prompts,
+            _watcher: Arc::new(watcher),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn PromptHandler>> {
+        self.prompts.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    /// The prompts as currently loaded
+    pub fn snapshot(&self) -> Prompts {
+        self.prompts.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Every template in `dir`; fails only if the directory can't be read
+fn load(dir: &Path) -> Result<Prompts> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read prompt directory {}", dir.display()))?;
+    let mut prompts = Prompts::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let is_markdown = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => true,
+            Some("yaml" | "yml") => false,
+            _ => continue,
+        };
+        match parse(&path, is_markdown) {
+            Ok(prompt) => {
+                prompts.insert(name.to_string(), Arc::new(prompt) as Arc<dyn PromptHandler>);
+            }
+            Err(e) => warn!("Skipping prompt template {}: {:#}", path.display(), e),
+        }
+    }
+    Ok(prompts)
+}
+
+fn parse(path: &Path, is_markdown: bool) -> Result<TemplatePrompt> {
+    let text = std::fs::read_to_string(path)?;
+    let (file, template) = if is_markdown {
+        let (front_matter, body) = split_front_matter(&text);
+        let file: TemplateFile = match front_matter {
+            Some(yaml) => serde_yaml::from_str(yaml).context("Invalid front matter")?,
+            None => TemplateFile::default(),
+        };
+        anyhow::ensure!(file.template.is_none(), "Markdown templates take their template from the body");
+        (file, body.to_string())
+    } else {
+        let mut file: TemplateFile = serde_yaml::from_str(&text)?;
+        let template = file.template.take().context("Missing template")?;
+        (file, template)
+    };
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let description = file.description.unwrap_or_else(|| format!("Prompt template {}", name));
+    let mut prompt = TemplatePrompt::new(&description, template.trim());
+    for argument in &file.arguments {
+        let description = argument.description.as_deref().unwrap_or_default();
+        prompt = if argument.required {
+            prompt.argument(&argument.name, description)
+        } else {
+            prompt.optional_argument(&argument.name, description, argument.default.as_deref().unwrap_or_default())
+        };
+        if !argument.completions.is_empty() {
+            let values: Vec<&str> = argument.completions.iter().map(String::as_str).collect();
+            prompt = prompt.completions(&argument.name, &values);
+        }
+    }
+    Ok(prompt)
+}
+
+/// The YAML between a leading `---` line and the next one, and the rest
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (None, text);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
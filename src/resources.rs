@@ -0,0 +1,196 @@
+use crate::config::ServerConfig;
+use crate::notifications::Notifier;
+use crate::types::{Resource, ResourceContents};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::debug;
+
+mod files;
+mod subscriptions;
+
+pub use files::FileResourceProvider;
+pub use subscriptions::{ResourceSubscriptions, RESOURCE_UPDATED_NOTIFICATION};
+
+/// Method of the notification sent when the set of resources changes
+pub const RESOURCE_LIST_CHANGED_NOTIFICATION: &str = "notifications/resources/list_changed";
+
+/// Errors surfaced to clients by `resources/read`
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+    #[error("Error reading resource {0}: {1}")]
+    Read(String, anyhow::Error),
+}
+
+/// A source of resources, such as the files below a directory
+#[async_trait::async_trait]
+pub trait ResourceProvider: Send + Sync {
+    async fn list(&self) -> Result<Vec<Resource>>;
+    /// Contents for `uri`, or `None` when this provider does not serve it
+    async fn read(&self, uri: &str) -> Result<Option<Vec<ResourceContents>>>;
+    /// Values for `argument` of the URI template `uri` that start with
+    /// `value`. The default offers none.
+    async fn complete(&self, _uri: &str, _argument: &str, _value: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+    /// The file whose changes are changes to `uri`, for `resources/subscribe`.
+    /// The default, `None`, means this provider's resources can't be watched.
+    fn watch_path(&self, _uri: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Providers are held behind `Arc`, so clones of the registry (and of the
+/// server that owns it) keep every registration.
+#[derive(Clone, Default)]
+pub struct ResourceRegistry {
+    providers: Vec<Arc<dyn ResourceProvider>>,
+    notifier: Notifier,
+    /// URI prefixes the served resources are limited to; `None` serves all
+    allowed: Option<Arc<[String]>>,
+}
+
+impl ResourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: &ServerConfig, notifier: Notifier) -> Self {
+        let mut registry = Self {
+            providers: Vec::new(),
+            notifier,
+            allowed: None,
+        };
+
+        // Files are only listed when confined to a root
+        if let Some(root) = &config.root {
+            registry.register_provider(Box::new(FileResourceProvider::new(root.clone())));
+        }
+
+        registry
+    }
+
+    /// Add a provider and tell clients the resource list changed
+    pub fn register_provider(&mut self, provider: Box<dyn ResourceProvider>) {
+        self.providers.push(Arc::from(provider));
+        self.notify_list_changed();
+    }
+
+    /// Remove every provider, e.g. before registering them again from a
+    /// reloaded configuration, and tell clients the resource list changed
+    pub fn clear(&mut self) {
+        self.providers.clear();
+        self.notify_list_changed();
+    }
+
+    /// Send `notifications/resources/list_changed`. Registering and clearing
+    /// providers does this already; providers whose resources come and go
+    /// on their own call it when theirs do.
+    pub fn notify_list_changed(&self) {
+        self.notifier.notify(RESOURCE_LIST_CHANGED_NOTIFICATION, None);
+    }
+
+    /// Serve only the resources whose URIs start with one of `prefixes`,
+    /// without telling clients, e.g. to narrow a session's resources before
+    /// it starts. Others are left out of listings and can't be read or
+    /// subscribed to, and resource template completions are withheld, as
+    /// the values they offer have no URI to check.
+    pub(crate) fn retain_uris(&mut self, prefixes: &[String]) {
+        self.allowed = Some(prefixes.into());
+    }
+
+    /// Whether `uri` is within the allowed prefixes, once `..` segments and
+    /// the like are normalized away
+    fn allows(&self, uri: &str) -> bool {
+        let Some(allowed) = &self.allowed else {
+            return true;
+        };
+        let Ok(uri) = url::Url::parse(uri) else {
+            return false;
+        };
+        allowed.iter().any(|prefix| uri.as_str().starts_with(prefix.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    pub async fn list_resources(&self) -> Result<Vec<Resource>> {
+        let mut resources = Vec::new();
+        for provider in &self.providers {
+            resources.extend(provider.list().await?);
+        }
+        resources.retain(|resource| self.allows(&resource.uri));
+        Ok(resources)
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>, ResourceError> {
+        debug!("Reading resource: {}", uri);
+
+        if !self.allows(uri) {
+            return Err(ResourceError::NotFound(uri.to_string()));
+        }
+        for provider in &self.providers {
+            match provider.read(uri).await {
+                Ok(Some(contents)) => return Ok(contents),
+                Ok(None) => continue,
+                Err(e) => return Err(ResourceError::Read(uri.to_string(), e)),
+            }
+        }
+        Err(ResourceError::NotFound(uri.to_string()))
+    }
+
+    /// The file to watch for `resources/subscribe` to `uri`, from the first
+    /// provider backing it with one
+    pub fn watch_path(&self, uri: &str) -> Option<PathBuf> {
+        if !self.allows(uri) {
+            return None;
+        }
+        self.providers.iter().find_map(|provider| provider.watch_path(uri))
+    }
+
+    /// Completions for a resource template argument from every provider
+    pub async fn complete(&self, uri: &str, argument: &str, value: &str) -> Result<Vec<String>> {
+        let mut values = Vec::new();
+        if self.allowed.is_some() {
+            return Ok(values);
+        }
+        for provider in &self.providers {
+            values.extend(provider.complete(uri, argument, value).await?);
+        }
+        Ok(values)
+    }
+}
+
+/// Best-effort MIME type from a file extension
+pub(crate) fn mime_type_for(path: &std::path::Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "rs" => "text/x-rust",
+        "py" => "text/x-python",
+        "js" | "mjs" => "text/javascript",
+        "ts" => "text/x-typescript",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "xml" => "application/xml",
+        "sh" => "application/x-sh",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => return None,
+    };
+    Some(mime)
+}
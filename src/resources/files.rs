@@ -0,0 +1,133 @@
+use super::{mime_type_for, ResourceProvider};
+use crate::client::check_caller_root;
+use crate::sandbox::Sandbox;
+use crate::tools::walk::{looks_binary, walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use crate::types::{Resource, ResourceContents};
+use anyhow::Result;
+use base64::Engine;
+use std::path::PathBuf;
+use url::Url;
+
+const MAX_DEPTH: usize = 8;
+const MAX_RESOURCES: usize = 1000;
+const MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Serves the files below a root directory as `file://` resources
+pub struct FileResourceProvider {
+    sandbox: Sandbox,
+}
+
+impl FileResourceProvider {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            sandbox: Sandbox::new(Some(root)),
+        }
+    }
+
+    /// Map a `file://` URI to a regular file inside the root
+    fn resolve(&self, uri: &str) -> Option<PathBuf> {
+        let url = Url::parse(uri).ok()?;
+        if url.scheme() != "file" {
+            return None;
+        }
+        let path = url.to_file_path().ok()?;
+        let resolved = self.sandbox.resolve(path.to_str()?).ok()?;
+        resolved.is_file().then_some(resolved)
+    }
+}
+
+#[async_trait::async_trait]
+impl ResourceProvider for FileResourceProvider {
+    async fn list(&self) -> Result<Vec<Resource>> {
+        let Some(root) = self.sandbox.root() else {
+            return Ok(Vec::new());
+        };
+        let options = WalkOptions {
+            max_depth: MAX_DEPTH,
+            max_entries: MAX_RESOURCES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore: false,
+        };
+
+        let resources = walk(root, &options)
+            .entries
+            .into_iter()
+            .filter(|entry| entry.metadata.is_file() && check_caller_root(&entry.path).is_ok())
+            .filter_map(|entry| {
+                let uri = Url::from_file_path(&entry.path).ok()?;
+                Some(Resource {
+                    uri: uri.to_string(),
+                    name: entry.relative,
+                    description: None,
+                    mime_type: mime_type_for(&entry.path).map(str::to_string),
+                })
+            })
+            .collect();
+        Ok(resources)
+    }
+
+    async fn read(&self, uri: &str) -> Result<Option<Vec<ResourceContents>>> {
+        let Some(path) = self.resolve(uri) else {
+            return Ok(None);
+        };
+
+        let size = std::fs::metadata(&path)?.len();
+        if size > MAX_READ_BYTES {
+            anyhow::bail!("File is too large ({} bytes, max: {} bytes)", size, MAX_READ_BYTES);
+        }
+        let bytes = std::fs::read(&path)?;
+        let mime_type = mime_type_for(&path).map(str::to_string);
+
+        let contents = match String::from_utf8(bytes) {
+            Ok(text) if !looks_binary(text.as_bytes()) => ResourceContents::Text {
+                uri: uri.to_string(),
+                mime_type,
+                text,
+            },
+            Ok(text) => ResourceContents::Blob {
+                uri: uri.to_string(),
+                mime_type,
+                blob: base64::engine::general_purpose::STANDARD.encode(text.as_bytes()),
+            },
+            Err(e) => ResourceContents::Blob {
+                uri: uri.to_string(),
+                mime_type,
+                blob: base64::engine::general_purpose::STANDARD.encode(e.as_bytes()),
+            },
+        };
+        Ok(Some(vec![contents]))
+    }
+
+    fn watch_path(&self, uri: &str) -> Option<PathBuf> {
+        self.resolve(uri)
+    }
+
+    /// Completes a `path` argument of a `file:` URI template, one segment at
+    /// a time, with paths relative to the root; directories end in `/`
+    async fn complete(&self, uri: &str, argument: &str, value: &str) -> Result<Vec<String>> {
+        if argument != "path" || !uri.starts_with("file:") || self.sandbox.root().is_none() {
+            return Ok(Vec::new());
+        }
+        let (dir, prefix) = match value.rsplit_once('/') {
+            Some((dir, prefix)) => (format!("{}/", dir), prefix),
+            None => (String::new(), value),
+        };
+        let Some(read_dir) = self.sandbox.resolve(&dir).ok().and_then(|path| std::fs::read_dir(path).ok()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut values: Vec<String> = read_dir
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let is_dir = entry.file_type().ok()?.is_dir();
+                if !name.starts_with(prefix) || (is_dir && DEFAULT_SKIP_DIRS.contains(&name.as_str())) {
+                    return None;
+                }
+                Some(format!("{}{}{}", dir, name, if is_dir { "/" } else { "" }))
+            })
+            .collect();
+        values.sort();
+        Ok(values)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::notifications::Notifier;
+use anyhow::Result;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Method of the notification sent when a subscribed resource changes
+pub const RESOURCE_UPDATED_NOTIFICATION: &str = "notifications/resources/updated";
+
+/// One save often arrives as several events (truncate, write, rename...);
+/// events this close together are reported as a single update
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Resources the client subscribed to with `resources/subscribe`, and the
+/// filesystem watchers that report their changes.
+///
+/// Each watched file has one watcher, shared by every URI that maps to it
+/// and dropped with the last of them. Clones share the same subscriptions.
+#[derive(Clone)]
+pub struct ResourceSubscriptions {
+    notifier: Notifier,
+    watches: Arc<Mutex<HashMap<PathBuf, Watch>>>,
+}
+
+struct Watch {
+    uris: BTreeSet<String>,
+    // Dropping the watcher closes its event channel, which ends the task
+    // sending the notifications
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl ResourceSubscriptions {
+    pub fn new(notifier: Notifier) -> Self {
+        Self {
+            notifier,
+            watches: Arc::default(),
+        }
+    }
+
+    /// Send `notifications/resources/updated` for `uri` whenever the file at
+    /// `path` changes. Subscribing twice is the same as once.
+    pub fn subscribe(&self, uri: &str, path: PathBuf) -> Result<()> {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        if watches.get(&path).is_some_and(|watch| watch.uris.contains(uri)) {
+            return Ok(());
+        }
+        self.unsubscribe_locked(&mut watches, uri);
+        if let Some(watch) = watches.get_mut(&path) {
+            watch.uris.insert(uri.to_string());
+            return Ok(());
+        }
+
+        let watcher = self.watch(&path)?;
+        debug!("Watching {} for resource updates", path.display());
+        watches.insert(path, Watch {
+            uris: BTreeSet::from([uri.to_string()]),
+            _watcher: watcher,
+        });
+        Ok(())
+    }
+
+    /// Stop notifying about `uri`. Returns whether it was subscribed.
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        let mut watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        self.unsubscribe_locked(&mut watches, uri)
+    }
+
+    /// Every subscribed URI, sorted
+    pub fn uris(&self) -> Vec<String> {
+        let watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        let mut uris: Vec<String> = watches.values().flat_map(|watch| watch.uris.iter().cloned()).collect();
+        uris.sort();
+        uris
+    }
+
+    /// Files currently being watched, sorted
+    pub fn watched_paths(&self) -> Vec<PathBuf> {
+        let watches = self.watches.lock().unwrap_or_else(|e| e.into_inner());
+        let mut paths: Vec<PathBuf> = watches.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    fn unsubscribe_locked(&self, watches: &mut HashMap<PathBuf, Watch>, uri: &str) -> bool {
+        let Some(path) = watches.iter().find(|(_, watch)| watch.uris.contains(uri)).map(|(path, _)| path.clone()) else {
+            return false;
+        };
+        if let Some(watch) = watches.get_mut(&path) {
+            watch.uris.remove(uri);
+            if watch.uris.is_empty() {
+                debug!("No subscribers left; no longer watching {}", path.display());
+                watches.remove(&path);
+            }
+        }
+        true
+    }
+
+    /// Watch the directory holding `path`, so that a file replaced by a
+    /// rename, as editors save, is still seen
+    fn watch(&self, path: &Path) -> Result<notify::RecommendedWatcher> {
+        let parent = path.parent().ok_or_else(|| anyhow::anyhow!("{} has no parent directory", path.display()))?;
+        let (changed_tx, mut changed) = mpsc::unbounded_channel();
+        let target = path.to_path_buf();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) && event.paths.contains(&target) => {
+                let _ = changed_tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("File watcher error: {}", e),
+        })?;
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+
+        let notifier = self.notifier.clone();
+        let watches = self.watches.clone();
+        let path = path.to_path_buf();
+        tokio::spawn(async move {
+            while changed.recv().await.is_some() {
+                tokio::time::sleep(DEBOUNCE).await;
+                while changed.try_recv().is_ok() {}
+
+                let uris = watches
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .get(&path)
+                    .map(|watch| watch.uris.clone())
+                    .unwrap_or_default();
+                for uri in uris {
+                    notifier.notify(RESOURCE_UPDATED_NOTIFICATION, Some(serde_json::json!({ "uri": uri })));
+                }
+            }
+        });
+        Ok(watcher)
+    }
+}
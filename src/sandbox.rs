@@ -0,0 +1,140 @@
+use crate::client::{check_caller_root, OutsideRoots};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+/// Errors produced when a requested path cannot be used inside the sandbox
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Path '{0}' is outside the allowed root")]
+    OutsideRoot(String),
+    #[error("Invalid path '{0}': {1}")]
+    Io(String, std::io::Error),
+    #[error(transparent)]
+    OutsideClientRoots(#[from] OutsideRoots),
+}
+
+/// Confines file tools to an optional root directory.
+///
+/// Relative paths are resolved against the working directory when one is
+/// set, then the root, then the process's current directory. Without a
+/// root every path is otherwise passed through unchanged. With a root,
+/// the canonical result (after following `..` and symlinks) must stay
+/// inside it. During a client's tool call the result must also lie inside
+/// the client's roots, see `ClientHandle::run_for`.
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    root: Option<PathBuf>,
+    working_dir: Option<PathBuf>,
+}
+
+impl Sandbox {
+    pub fn new(root: Option<PathBuf>) -> Self {
+        // Canonicalize up front so prefix checks compare like with like
+        let root = root.map(|r| r.canonicalize().unwrap_or(r));
+        Self { root, working_dir: None }
+    }
+
+    /// Resolve relative paths against `dir` instead of the root or the
+    /// process's current directory
+    pub fn with_working_dir(mut self, dir: PathBuf) -> Self {
+        self.working_dir = Some(dir.canonicalize().unwrap_or(dir));
+        self
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    pub fn working_dir(&self) -> Option<&Path> {
+        self.working_dir.as_deref()
+    }
+
+    /// Resolve an existing path, rejecting anything that escapes the root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let resolved = self.resolve_in_root(path)?;
+        check_caller_root(&resolved)?;
+        Ok(resolved)
+    }
+
+    /// Resolve a path that may not exist yet, such as a file about to be created.
+    ///
+    /// The deepest existing ancestor is canonicalized and checked against the
+    /// root; the remaining components may not contain `..`. A symlink counts
+    /// as existing even when its target doesn't, so a dangling link fails to
+    /// canonicalize rather than passing for a file about to be created.
+    pub fn resolve_new(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let resolved = self.resolve_new_in_root(path)?;
+        check_caller_root(&resolved)?;
+        Ok(resolved)
+    }
+
+    fn resolve_in_root(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let Some(root) = &self.root else {
+            return Ok(self.join(path));
+        };
+
+        let canonical = self
+            .join(path)
+            .canonicalize()
+            .map_err(|e| SandboxError::Io(path.to_string(), e))?;
+
+        if canonical.starts_with(root) {
+            Ok(canonical)
+        } else {
+            Err(SandboxError::OutsideRoot(path.to_string()))
+        }
+    }
+
+    fn resolve_new_in_root(&self, path: &str) -> Result<PathBuf, SandboxError> {
+        let Some(root) = &self.root else {
+            return Ok(self.join(path));
+        };
+
+        let joined = self.join(path);
+        let mut existing = joined.as_path();
+        let mut missing = Vec::new();
+        while existing.symlink_metadata().is_err() {
+            match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    missing.push(name.to_os_string());
+                    existing = parent;
+                }
+                _ => return Err(SandboxError::OutsideRoot(path.to_string())),
+            }
+        }
+
+        let mut resolved = existing
+            .canonicalize()
+            .map_err(|e| SandboxError::Io(path.to_string(), e))?;
+        if !resolved.starts_with(root) {
+            return Err(SandboxError::OutsideRoot(path.to_string()));
+        }
+        for name in missing.into_iter().rev() {
+            if name == ".." {
+                return Err(SandboxError::OutsideRoot(path.to_string()));
+            }
+            resolved.push(name);
+        }
+        Ok(resolved)
+    }
+
+    /// `path` against the working directory or root; absolute paths are kept
+    fn join(&self, path: &str) -> PathBuf {
+        match self.working_dir.as_ref().or(self.root.as_ref()) {
+            Some(base) => base.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+}
+
+/// Make `options` refuse to open a symlink, so a path checked by
+/// `Sandbox::resolve_new` can't be swapped for a link before it is written.
+/// Only the final component is covered; a no-op off Unix.
+pub fn no_follow(options: &mut OpenOptions) -> &mut OpenOptions {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    options
+}
@@ -1,135 +1,697 @@
+use crate::client::ClientHandle;
 use crate::mcp::McpServer;
-use crate::tools::ToolRegistry;
 use crate::types::{JsonRpcRequest, JsonRpcResponse, JsonRpcError};
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, Mutex, OwnedMutexGuard};
+use tokio::task::AbortHandle;
+use tokio::time::{sleep_until, Instant};
 use tracing::{debug, error, info, warn};
 
+mod cors;
+mod event_log;
+mod http;
+mod in_process;
+#[cfg(windows)]
+mod pipe;
+mod rate_limit;
+mod sse;
+mod systemd;
+mod tcp;
+mod tls;
+mod transport;
+
+pub use cors::CorsPolicy;
+pub use http::HttpServer;
+pub use in_process::{InProcessClient, InProcessSink, InProcessSource, InProcessTransport};
+#[cfg(windows)]
+pub use pipe::{PipeServer, DEFAULT_PIPE_NAME};
+pub use sse::HttpSseServer;
+pub use systemd::systemd_listener;
+pub use tcp::TcpServer;
+pub use tls::{load_tls_acceptor, TLS_HANDSHAKE_TIMEOUT};
+pub use transport::{MessageSink, MessageSource, StreamSink, StreamSource, StreamTransport, Transport};
+pub use rate_limit::RateLimiter;
+
+/// Which transport the server speaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TransportKind {
+    /// JSON-RPC over stdin/stdout
+    #[default]
+    Stdio,
+    /// JSON-RPC POSTed to `/mcp`, with notifications streamed from `/sse`
+    Http,
+    /// The MCP HTTP+SSE transport: replies and notifications stream from
+    /// `/sse`, and messages are POSTed to the endpoint it announces
+    Sse,
+    /// Newline-delimited JSON-RPC over TCP, one session per connection
+    Tcp,
+    /// Newline-delimited JSON-RPC over a Windows named pipe, one session
+    /// per client
+    #[cfg(windows)]
+    Pipe,
+}
+
+/// How messages are delimited on stdin/stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Framing {
+    /// Detect from the first message received, and answer in kind; until
+    /// then, output is ndjson
+    #[default]
+    Auto,
+    /// One JSON message per line
+    Ndjson,
+    /// LSP-style `Content-Length: N` header, a blank line, then N bytes of JSON
+    ContentLength,
+}
+
+/// Framing and formatting of messages on the wire
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WireFormat {
+    pub framing: Framing,
+    /// Pretty-print outgoing JSON, for humans reading the stream
+    pub pretty: bool,
+}
+
+impl WireFormat {
+    /// Pretty output spans several lines, so it needs length-prefixed framing:
+    /// `pretty` switches an unspecified framing to `ContentLength` and is
+    /// rejected together with an explicit `Ndjson`.
+    pub fn new(framing: Option<Framing>, pretty: bool) -> Result<Self> {
+        let framing = match (framing, pretty) {
+            (Some(Framing::Ndjson | Framing::Auto), true) => {
+                anyhow::bail!("Pretty output breaks ndjson framing; use content-length framing instead")
+            }
+            (None, true) => Framing::ContentLength,
+            (framing, _) => framing.unwrap_or_default(),
+        };
+        Ok(Self { framing, pretty })
+    }
+    
+    /// Serialize and frame one outgoing message
+    pub fn encode<T: Serialize>(&self, message: &T) -> Result<Vec<u8>> {
+        let json = if self.pretty {
+            serde_json::to_string_pretty(message)?
+        } else {
+            serde_json::to_string(message)?
+        };
+        
+        debug!("Sending: {}", json);
+        
+        Ok(match self.framing {
+            Framing::Auto | Framing::Ndjson => format!("{}\n", json).into_bytes(),
+            Framing::ContentLength => format!("Content-Length: {}\r\n\r\n{}", json.len(), json).into_bytes(),
+        })
+    }
+}
+
+/// How long in-flight requests get to finish after a shutdown signal
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an HTTP transport session may go unused before it is closed,
+/// unless the server is given a `Keepalive` of its own
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Server-initiated `ping` requests that find clients which went away
+/// without closing their connection, e.g. behind a proxy that dropped it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// How often the client is pinged
+    pub interval: Duration,
+    /// How long the client may send nothing, not even a ping response,
+    /// before its session is closed; checked at each ping
+    pub idle_timeout: Duration,
+}
+
+impl Keepalive {
+    /// Ping at a third of `idle_timeout`, so a client gets a few chances to
+    /// answer before it is dropped
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            interval: idle_timeout / 3,
+            idle_timeout,
+        }
+    }
+}
+
 pub struct StdioServer {
+    /// A tokio `Mutex`: it never poisons and its guard is released while
+    /// unwinding, so a panic during a request cannot leave the server locked.
+    /// Tool panics are additionally caught by `ToolRegistry::call_tool` and
+    /// answered with an internal error; std mutexes inside tools recover
+    /// their guard with `PoisonError::into_inner`.
     mcp_server: Arc<Mutex<McpServer>>,
     quiet: bool,
+    format: WireFormat,
+    shutdown_timeout: Duration,
+    /// Requests per second each client may send; `None` is unlimited
+    rate_limit: Option<u32>,
+    keepalive: Option<Keepalive>,
 }
 
 impl StdioServer {
+    /// Serve a copy of `mcp_server`, with its own handle for requests to
+    /// the client
     pub fn new(mcp_server: Arc<McpServer>, quiet: bool) -> Self {
+        let session = McpServer {
+            client: ClientHandle::new(),
+            ..(*mcp_server).clone()
+        };
+        Self::from_session(Arc::new(Mutex::new(session)), quiet)
+    }
+    
+    /// Serve an existing session, e.g. one registered with a `SessionManager`
+    pub fn from_session(mcp_server: Arc<Mutex<McpServer>>, quiet: bool) -> Self {
         Self {
-            mcp_server: Arc::new(Mutex::new((*mcp_server).clone())),
+            mcp_server,
             quiet,
+            format: WireFormat::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            rate_limit: None,
+            keepalive: None,
         }
     }
     
+    pub fn with_wire_format(mut self, format: WireFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// How long requests still running at shutdown get to finish before
+    /// they are abandoned
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+    
+    /// Refuse requests beyond `per_second` a second, after an initial burst
+    /// of as many, with a rate limited error. `initialize`, the initialized
+    /// notification and `ping` are never limited.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+    
+    /// Ping the client every `keepalive.interval`, and end the session
+    /// once it has been silent for `keepalive.idle_timeout`. Requests still
+    /// running then are abandoned, as at the shutdown timeout.
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+    
+    /// Serve stdin/stdout until end of input or SIGINT/SIGTERM
     pub async fn run(&self) -> Result<()> {
+        self.run_with_streams(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+    
+    /// What `run` does, over any byte streams instead of stdin/stdout, for
+    /// applications that embed the server behind a pipe, a PTY or a test
+    /// fixture: serve until `reader` reaches EOF or the process gets
+    /// SIGINT/SIGTERM. Use `serve_until` to stop on something else.
+    pub async fn run_with_streams<R, W>(&self, reader: R, writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send,
+    {
         if !self.quiet {
             info!("Starting stdio server");
         }
         
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
-        let mut line = String::new();
+        self.serve_until(reader, writer, shutdown_signal()).await?;
+        
+        if !self.quiet {
+            info!("Stdio server stopped");
+        }
+        Ok(())
+    }
+    
+    /// Serve one client over any byte streams until `reader` reaches EOF.
+    ///
+    /// Each message runs on its own task, so a slow tool call does not hold
+    /// up a ping behind it; responses are written as they complete, which
+    /// may differ from the order requests arrived in. A
+    /// `notifications/cancelled` naming an in-flight request aborts its task,
+    /// and the cancelled request gets no response.
+    pub async fn serve<R, W>(&self, reader: R, writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send,
+    {
+        self.serve_until(reader, writer, std::future::pending()).await
+    }
+    
+    /// Like `serve`, but also stop when `shutdown` completes: no more
+    /// messages are read, and requests already running get the shutdown
+    /// timeout to send their replies before they are aborted.
+    pub async fn serve_until<R, W, S>(&self, reader: R, writer: W, shutdown: S) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send,
+        S: Future<Output = ()>,
+    {
+        self.serve_transport(StreamTransport::new(reader, writer, self.format), shutdown).await
+    }
+    
+    /// Run the loop `serve_until` runs over any transport, until its source
+    /// runs dry or `shutdown` completes
+    pub async fn serve_transport<T, S>(&self, transport: T, shutdown: S) -> Result<()>
+    where
+        T: Transport,
+        S: Future<Output = ()>,
+    {
+        let (mut source, mut sink) = transport.split();
+        tokio::pin!(shutdown);
+        let mut deadline = None;
+        let (mut notifications, client) = {
+            let server = self.mcp_server.lock().await;
+            (server.notifier.subscribe(), server.client.clone())
+        };
+        let mut notifications_open = true;
+        let mut client_requests = client.subscribe();
+        let mut client_requests_open = true;
+        
+        // Messages are read on their own task so that a half-read frame is
+        // never dropped when a notification wins the select below
+        let (incoming_tx, mut incoming) = mpsc::channel(16);
+        let reading = tokio::spawn(async move {
+            loop {
+                let message = source.receive().await;
+                let done = !matches!(message, Ok(Some(_)));
+                if incoming_tx.send(message).await.is_err() || done {
+                    break;
+                }
+            }
+        });
+        // Stop reading when serving ends, so input left open after a
+        // shutdown doesn't keep the reader alive
+        let _reading = AbortOnDrop(reading.abort_handle());
+        
+        // Dropped at end of input; the loop then ends once every in-flight
+        // request has sent its reply
+        let (reply_tx, mut replies) = mpsc::channel(16);
+        let mut reply_tx = Some(reply_tx);
+        let mut in_flight = InFlight::default();
+        let mut limiter = self.rate_limit.map(RateLimiter::new);
+        let mut last_heard = Instant::now();
+        let mut pings = 0u64;
+        let mut next_ping = self.keepalive.map(|keepalive| Instant::now() + keepalive.interval);
         
         loop {
-            line.clear();
-            
-            match reader.read_line(&mut line).await {
-                Ok(0) => {
-                    // EOF reached
+            // Biased so that notifications a request sent before finishing,
+            // such as its progress, are written ahead of its reply
+            tokio::select! {
+                biased;
+                
+                notification = notifications.recv(), if notifications_open => match notification {
+                    Ok(notification) => send_message(&mut sink, &notification).await?,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} notifications for a slow client", skipped);
+                    }
+                    Err(RecvError::Closed) => notifications_open = false,
+                },
+                request = client_requests.recv(), if client_requests_open => match request {
+                    Ok(request) => send_message(&mut sink, &request).await?,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} requests to a slow client", skipped);
+                    }
+                    Err(RecvError::Closed) => client_requests_open = false,
+                },
+                reply = replies.recv() => match reply {
+                    Some(reply) => send_message(&mut sink, &reply).await?,
+                    None => break,
+                },
+                () = &mut shutdown, if deadline.is_none() => {
                     if !self.quiet {
-                        info!("Client disconnected");
+                        info!("Shutting down; waiting up to {:?} for in-flight requests", self.shutdown_timeout);
                     }
+                    reply_tx = None;
+                    deadline = Some(Instant::now() + self.shutdown_timeout);
+                }
+                () = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    warn!("Abandoning requests still running after the shutdown timeout");
+                    in_flight.abort_all();
                     break;
                 }
-                Ok(_) => {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        continue;
+                () = sleep_until(next_ping.unwrap_or_else(Instant::now)), if next_ping.is_some() && reply_tx.is_some() => {
+                    let keepalive = self.keepalive.expect("pings are only scheduled with a keepalive");
+                    if last_heard.elapsed() >= keepalive.idle_timeout {
+                        if !self.quiet {
+                            info!("Closing session idle for {:?}", last_heard.elapsed());
+                        }
+                        in_flight.abort_all();
+                        break;
                     }
-                    
-                    debug!("Received: {}", trimmed);
-                    
-                    let response = self.process_message(trimmed).await;
-                    
-                    // Only send response if it's not None (notifications return None)
-                    if let Some(actual_response) = response {
-                        let response_json = serde_json::to_string(&actual_response)?;
+                    pings += 1;
+                    send_message(&mut sink, &ping_request(pings)).await?;
+                    next_ping = Some(Instant::now() + keepalive.interval);
+                }
+                message = incoming.recv(), if reply_tx.is_some() => match message {
+                    Some(Ok(Some(message))) => {
+                        debug!("Received: {}", message);
+                        last_heard = Instant::now();
                         
-                        debug!("Sending: {}", response_json);
+                        // Parse the JSON-RPC message
+                        let value: serde_json::Value = match serde_json::from_str(&message) {
+                            Ok(value) => value,
+                            Err(e) => {
+                                warn!("Failed to parse JSON-RPC message: {}", e);
+                                let reply = Reply::Single(error_response(None, JsonRpcError::parse_error()));
+                                send_message(&mut sink, &reply).await?;
+                                continue;
+                            }
+                        };
                         
-                        stdout.write_all(response_json.as_bytes()).await?;
-                        stdout.write_all(b"\n").await?;
-                        stdout.flush().await?;
+                        // Answers to our requests and pings; a response is
+                        // never answered
+                        if is_response(&value) {
+                            if !client.resolve(&value) {
+                                debug!("Client answered request {}", value["id"]);
+                            }
+                            continue;
+                        }
+                        
+                        // Cancellation is handled here rather than queued
+                        // behind the lock, which the target may be holding
+                        if let Some(request_id) = cancelled_request_id(&value) {
+                            in_flight.cancel(request_id);
+                            continue;
+                        }
+                        let refused = limiter.as_mut().is_some_and(|limiter| !rate_limit::admit(limiter, &value));
+                        if let Some(limiter) = limiter.as_ref().filter(|_| refused) {
+                            debug!("Rate limited: {}", message);
+                            if let Some(reply) = rate_limit::refusal(limiter, &value) {
+                                send_message(&mut sink, &reply).await?;
+                            }
+                            continue;
+                        }
+                        let request_id = cancellable_request_id(&value).cloned();
+                        
+                        // Locking here, in arrival order, means each message
+                        // sees the state left by the ones before it
+                        let server = self.mcp_server.clone().lock_owned().await;
+                        let reply_tx = reply_tx.clone().expect("messages are only read while replies are open");
+                        let task = tokio::spawn(async move {
+                            // Only send response if it's not None (notifications return None)
+                            if let Some(reply) = process_value(server, value).await {
+                                let _ = reply_tx.send(reply).await;
+                            }
+                        });
+                        if let Some(request_id) = request_id {
+                            in_flight.insert(&request_id, task.abort_handle());
+                        }
                     }
-                }
-                Err(e) => {
-                    error!("Error reading from stdin: {}", e);
-                    break;
-                }
+                    Some(Ok(None)) | None => {
+                        // EOF reached
+                        if !self.quiet {
+                            info!("Client disconnected");
+                        }
+                        reply_tx = None;
+                        client.disconnect();
+                    }
+                    Some(Err(e)) => {
+                        error!("Error reading from client: {}", e);
+                        reply_tx = None;
+                        client.disconnect();
+                    }
+                },
             }
         }
         
-        if !self.quiet {
-            info!("Stdio server stopped");
-        }
         Ok(())
     }
-    
-    async fn process_message(&self, message: &str) -> Option<JsonRpcResponse> {
-        // Parse the JSON-RPC request
-        let request: JsonRpcRequest = match serde_json::from_str(message) {
-            Ok(req) => req,
+}
+
+/// A path prefix in canonical form: one leading slash and no trailing one,
+/// so `ai/mcp/` becomes `/ai/mcp`, and the root becomes empty
+fn normalize_base_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// Serve `router` under `base_path` as well as at the root, so it works
+/// behind proxies that forward the prefix and ones that strip it
+fn mount(router: axum::Router, base_path: &str) -> axum::Router {
+    if base_path.is_empty() {
+        router
+    } else {
+        router.clone().nest(base_path, router)
+    }
+}
+
+/// How long a listener waits after a failed `accept`: 10ms, doubling with
+/// each failure in a row up to a second. Failures are usually transient,
+/// e.g. running out of file descriptors, and retrying at once would spin
+/// while they last.
+#[derive(Default)]
+struct AcceptBackoff {
+    delay: Option<Duration>,
+}
+
+impl AcceptBackoff {
+    const INITIAL: Duration = Duration::from_millis(10);
+    const MAX: Duration = Duration::from_secs(1);
+
+    async fn failed(&mut self) {
+        let delay = self.delay.map_or(Self::INITIAL, |delay| (delay * 2).min(Self::MAX));
+        self.delay = Some(delay);
+        tokio::time::sleep(delay).await;
+    }
+
+    fn succeeded(&mut self) {
+        self.delay = None;
+    }
+}
+
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run a server until `shutdown`, then flag `stop` so that it stops
+/// accepting and winds down, and give what is still open `timeout` to
+/// finish before it is dropped
+async fn drain<F>(serving: F, shutdown: impl Future<Output = ()>, stop: &watch::Sender<bool>, timeout: Duration, quiet: bool) -> Result<()>
+where
+    F: Future<Output = std::io::Result<()>>,
+{
+    tokio::pin!(serving);
+    tokio::select! {
+        result = &mut serving => return Ok(result?),
+        () = shutdown => {}
+    }
+    if !quiet {
+        info!("Shutting down; waiting up to {:?} for open requests", timeout);
+    }
+    stop.send_replace(true);
+    match tokio::time::timeout(timeout, serving).await {
+        Ok(result) => result?,
+        Err(_) => warn!("Abandoning requests still open after the shutdown timeout"),
+    }
+    Ok(())
+}
+
+/// Resolves once `stop` is flagged; never, if its sender is gone
+async fn stopped(mut stop: watch::Receiver<bool>) {
+    if stop.wait_for(|stopped| *stopped).await.is_err() {
+        std::future::pending().await
+    }
+}
+
+/// Resolves on SIGINT, or on SIGTERM on Unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            },
             Err(e) => {
-                warn!("Failed to parse JSON-RPC request: {}", e);
-                return Some(JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: None,
-                    result: None,
-                    error: Some(JsonRpcError::parse_error()),
-                });
+                warn!("Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
             }
-        };
-        
-        // Validate JSON-RPC version
-        if request.jsonrpc != "2.0" {
-            return Some(JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: None,
-                error: Some(JsonRpcError::invalid_request()),
-            });
         }
-        
-        // Handle the request
-        let mut server = self.mcp_server.lock().await;
-        match server.handle_request(request).await {
-            Ok(Some(response)) => Some(response),
-            Ok(None) => {
-                // No response needed (notification)
-                None
-            },
-            Err(e) => {
-                error!("Error handling request: {}", e);
-                Some(JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: None,
-                    result: None,
-                    error: Some(JsonRpcError::internal_error()),
-                })
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Tasks for requests that have not been answered yet, keyed by the JSON
+/// text of their id so that `1` and `"1"` stay distinct
+#[derive(Default)]
+struct InFlight {
+    tasks: HashMap<String, AbortHandle>,
+}
+
+impl InFlight {
+    fn insert(&mut self, id: &serde_json::Value, task: AbortHandle) {
+        // Forget finished requests so the map stays small and ids can be reused
+        self.tasks.retain(|_, task| !task.is_finished());
+        self.tasks.insert(id.to_string(), task);
+    }
+    
+    fn abort_all(&mut self) {
+        for (_, task) in self.tasks.drain() {
+            task.abort();
+        }
+    }
+    
+    /// Abort the request with this id; unknown or finished ids are ignored
+    fn cancel(&mut self, id: &serde_json::Value) {
+        match self.tasks.remove(&id.to_string()) {
+            Some(task) if !task.is_finished() => {
+                info!("Cancelling request {}", id);
+                task.abort();
             }
+            _ => debug!("Ignoring cancellation of unknown request {}", id),
         }
     }
 }
 
-// We need to implement Clone for McpServer to use it with Arc<Mutex<>>
-impl Clone for McpServer {
-    fn clone(&self) -> Self {
-        Self {
-            name: self.name.clone(),
-            version: self.version.clone(),
-            protocol_version: self.protocol_version.clone(),
-            initialized: self.initialized,
-            tool_registry: ToolRegistry::new(), // Create new registry for cloned instance
+/// A keepalive `ping`; ids are prefixed so they can't be mistaken for the
+/// client's own request ids in logs
+fn ping_request(n: u64) -> serde_json::Value {
+    serde_json::json!({"jsonrpc": "2.0", "id": format!("keepalive-{}", n), "method": "ping"})
+}
+
+/// Whether a message is a response from the client rather than a request
+fn is_response(value: &serde_json::Value) -> bool {
+    value.get("method").is_none() && (value.get("result").is_some() || value.get("error").is_some())
+}
+
+/// The `requestId` of a `notifications/cancelled` message
+fn cancelled_request_id(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    if value.get("method")?.as_str()? != "notifications/cancelled" || value.get("id").is_some() {
+        return None;
+    }
+    value.get("params")?.get("requestId").filter(|id| !id.is_null())
+}
+
+/// The id of a single request that a client may cancel. The spec forbids
+/// cancelling `initialize`, and requests inside a batch are not tracked.
+fn cancellable_request_id(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    if value.get("method")?.as_str()? == "initialize" {
+        return None;
+    }
+    value.get("id").filter(|id| !id.is_null())
+}
+
+/// Route a parsed message, which is either a single request or a batch.
+/// Every transport goes through here so they behave alike.
+///
+/// The caller locks the server, fixing the order in which messages see its
+/// state. Requests that only read state release the lock once they have a
+/// snapshot of the server, so their tool calls run alongside later messages.
+pub(crate) async fn process_value(server: OwnedMutexGuard<McpServer>, value: serde_json::Value) -> Option<Reply> {
+    let serde_json::Value::Array(batch) = value else {
+        return process_request(server, value, JsonRpcError::parse_error()).await.map(Reply::Single);
+    };
+    
+    // An empty batch is answered with a single error, not an empty array
+    if batch.is_empty() {
+        return Some(Reply::Single(error_response(None, JsonRpcError::invalid_request())));
+    }
+    
+    // Entries are handled in order, each waiting its turn for the lock
+    let mcp_server = OwnedMutexGuard::mutex(&server).clone();
+    let mut server = Some(server);
+    let mut responses = Vec::new();
+    for entry in batch {
+        let guard = match server.take() {
+            Some(guard) => guard,
+            None => mcp_server.clone().lock_owned().await,
+        };
+        if let Some(response) = process_request(guard, entry, JsonRpcError::invalid_request()).await {
+            responses.push(response);
+        }
+    }
+    
+    // Nothing is written for a batch made up only of notifications
+    (!responses.is_empty()).then_some(Reply::Batch(responses))
+}
+
+/// Handle one request; `malformed` is the error for a value that is not a request
+async fn process_request(
+    mut server: OwnedMutexGuard<McpServer>,
+    value: serde_json::Value,
+    malformed: JsonRpcError,
+) -> Option<JsonRpcResponse> {
+    let request: JsonRpcRequest = match serde_json::from_value(value) {
+        Ok(req) => req,
+        Err(e) => {
+            warn!("Failed to parse JSON-RPC request: {}", e);
+            return Some(error_response(None, malformed));
+        }
+    };
+    
+    // Validate JSON-RPC version
+    if request.jsonrpc != "2.0" {
+        return Some(error_response(request.id, JsonRpcError::invalid_request()));
+    }
+    
+    // Handle the request; notifications carry no id and are never answered
+    let is_notification = request.id.is_none();
+    let result = if McpServer::needs_exclusive(&request) {
+        server.handle_request(request).await
+    } else {
+        // The config and the registries' maps are shared behind `Arc`, so the
+        // snapshot costs a few reference counts rather than a copy of every
+        // tool, and tool calls on it still reach the same tools and stores
+        let snapshot = server.clone();
+        drop(server);
+        snapshot.handle_shared_request(request).await
+    };
+    match result {
+        Ok(Some(response)) => Some(response),
+        Ok(None) => {
+            // No response needed (notification)
+            None
+        },
+        Err(e) => {
+            error!("Error handling request: {}", e);
+            (!is_notification).then(|| error_response(None, JsonRpcError::internal_error()))
         }
     }
 }
+
+/// What is written back for one incoming message
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Reply {
+    Single(JsonRpcResponse),
+    /// Responses to a batch, with notifications left out
+    Batch(Vec<JsonRpcResponse>),
+}
+
+pub(crate) fn error_response(id: Option<serde_json::Value>, error: JsonRpcError) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(error),
+    }
+}
+
+/// Hand one outgoing message to the client's sink
+async fn send_message<K, T>(sink: &mut K, message: &T) -> Result<()>
+where
+    K: MessageSink,
+    T: Serialize,
+{
+    sink.send(serde_json::to_value(message)?).await
+}
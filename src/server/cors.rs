@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::debug;
+
+/// Request headers the MCP HTTP transports read, always allowed
+const MCP_HEADERS: &[&str] = &["content-type", "accept", "mcp-session-id", "mcp-protocol-version", "last-event-id"];
+
+/// Which browser origins may call the HTTP and SSE transports, and with
+/// which extra headers and methods.
+///
+/// The default allows only pages served from `localhost`, `127.0.0.1` or
+/// `[::1]`, on any port, so that a page on some other site can't drive a
+/// server running on the user's machine. Besides answering browsers with
+/// the matching CORS headers, the transports refuse any request whose
+/// `Origin` the policy doesn't allow, see `check_origin`.
+#[derive(Debug, Clone, Default)]
+pub struct CorsPolicy {
+    /// `None` allows the localhost origins only
+    origins: Option<AllowedOrigins>,
+    headers: Vec<HeaderName>,
+    methods: Vec<Method>,
+}
+
+#[derive(Debug, Clone)]
+enum AllowedOrigins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+impl CorsPolicy {
+    /// Allow `origins`, each a scheme, host and optional port such as
+    /// `https://app.example.com`, or `*` for any origin; no origins keeps
+    /// the localhost default. `headers` are allowed on top of the ones MCP
+    /// uses, and `methods`, when given, replace the methods the transports
+    /// serve.
+    pub fn new(origins: &[String], headers: &[String], methods: &[String]) -> Result<Self> {
+        let origins = if origins.is_empty() {
+            None
+        } else if origins.iter().any(|origin| origin == "*") {
+            Some(AllowedOrigins::Any)
+        } else {
+            let origins = origins
+                .iter()
+                .map(|origin| {
+                    HeaderValue::from_str(origin.trim_end_matches('/'))
+                        .with_context(|| format!("Invalid CORS origin '{}'", origin))
+                })
+                .collect::<Result<_>>()?;
+            Some(AllowedOrigins::List(origins))
+        };
+        let headers = headers
+            .iter()
+            .map(|name| HeaderName::try_from(name.as_str()).with_context(|| format!("Invalid CORS header '{}'", name)))
+            .collect::<Result<_>>()?;
+        let methods = methods
+            .iter()
+            .map(|method| {
+                Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                    .with_context(|| format!("Invalid CORS method '{}'", method))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { origins, headers, methods })
+    }
+
+    /// Whether a page from `origin` may call the transports
+    pub(super) fn allows(&self, origin: &HeaderValue) -> bool {
+        match &self.origins {
+            None => is_localhost(origin),
+            Some(AllowedOrigins::Any) => true,
+            Some(AllowedOrigins::List(origins)) => origins.contains(origin),
+        }
+    }
+
+    pub(super) fn layer(&self) -> CorsLayer {
+        let allow_origin = match &self.origins {
+            None => AllowOrigin::predicate(|origin, _| is_localhost(origin)),
+            Some(AllowedOrigins::Any) => AllowOrigin::any(),
+            Some(AllowedOrigins::List(origins)) => AllowOrigin::list(origins.iter().cloned()),
+        };
+        let headers = MCP_HEADERS
+            .iter()
+            .map(|name| HeaderName::from_static(name))
+            .chain(self.headers.iter().cloned())
+            .collect::<Vec<_>>();
+        let methods = if self.methods.is_empty() {
+            vec![Method::GET, Method::POST, Method::DELETE]
+        } else {
+            self.methods.clone()
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_headers(headers)
+            .allow_methods(methods)
+            // Browsers hide response headers from scripts unless told otherwise
+            .expose_headers([HeaderName::from_static("mcp-session-id"), header::CONTENT_TYPE])
+    }
+}
+
+/// Middleware answering requests from a browser page the policy doesn't
+/// allow with `403 Forbidden`. CORS headers only stop such a page reading
+/// responses, and a page whose host name was rebound to this machine
+/// isn't even cross-origin, but browsers still send its `Origin`. Requests
+/// without one, such as those from non-browser clients, go through.
+pub(super) async fn check_origin(State(policy): State<CorsPolicy>, request: Request, next: Next) -> Response {
+    match request.headers().get(header::ORIGIN) {
+        Some(origin) if !policy.allows(origin) => {
+            debug!("Refused a request from origin {:?}", origin);
+            (StatusCode::FORBIDDEN, "Origin not allowed").into_response()
+        }
+        _ => next.run(request).await,
+    }
+}
+
+/// Whether an `Origin` is a page served from this machine
+fn is_localhost(origin: &HeaderValue) -> bool {
+    let Some(url) = origin.to_str().ok().and_then(|origin| url::Url::parse(origin).ok()) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https")
+        && matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+}
@@ -0,0 +1,178 @@
+use axum::response::sse::Event;
+use futures::Stream;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Events a session keeps for clients resuming a stream with `Last-Event-ID`
+const EVENT_BUFFER: usize = 256;
+
+/// Which of a session's streams an event goes out on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum StreamKey {
+    /// The stream a client opens with `GET`
+    Standalone,
+    /// The stream answering one `POST`, which ends with its reply
+    Request(u64),
+}
+
+#[derive(Clone)]
+struct LoggedEvent {
+    id: u64,
+    stream: StreamKey,
+    /// `None` ends a request stream that has no reply to send
+    data: Option<String>,
+    /// Whether this is the last event of its stream
+    last: bool,
+}
+
+#[derive(Default)]
+struct LogState {
+    /// Event ids are unique within the session and start at 1
+    last_id: u64,
+    last_request: u64,
+    /// Request streams handed out that haven't ended yet. The rest have,
+    /// even once their last event has dropped out of the buffer.
+    open_requests: HashSet<u64>,
+    events: VecDeque<LoggedEvent>,
+    /// Set when the session ends, which ends every stream
+    closed: bool,
+}
+
+/// The most recent server-to-client messages of one Streamable HTTP
+/// session, numbered so that a client whose connection dropped can pick up
+/// a stream where it left off. Messages are logged whether or not anyone
+/// is connected to their stream; the oldest are dropped past `EVENT_BUFFER`.
+pub(super) struct EventLog {
+    state: std::sync::Mutex<LogState>,
+    /// The id of the latest event, to wake streams waiting for more
+    appended: watch::Sender<u64>,
+}
+
+impl EventLog {
+    pub(super) fn new() -> Self {
+        Self {
+            state: std::sync::Mutex::default(),
+            appended: watch::Sender::new(0),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LogState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A stream for the reply to a new request
+    pub(super) fn request_stream(&self) -> StreamKey {
+        let mut state = self.lock();
+        state.last_request += 1;
+        let request = state.last_request;
+        state.open_requests.insert(request);
+        StreamKey::Request(request)
+    }
+
+    pub(super) fn last_id(&self) -> u64 {
+        self.lock().last_id
+    }
+
+    /// Log a message on `stream`; `last` ends the stream after it
+    pub(super) fn append<T: Serialize>(&self, stream: StreamKey, message: &T, last: bool) {
+        let data = match serde_json::to_string(message) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!("Dropped message that failed to serialize: {}", e);
+                if !last {
+                    return;
+                }
+                None
+            }
+        };
+        self.push(stream, data, last);
+    }
+
+    /// End a request stream without a reply
+    pub(super) fn end(&self, stream: StreamKey) {
+        self.push(stream, None, true);
+    }
+
+    fn push(&self, stream: StreamKey, data: Option<String>, last: bool) {
+        let id = {
+            let mut state = self.lock();
+            state.last_id += 1;
+            let id = state.last_id;
+            if let (StreamKey::Request(request), true) = (stream, last) {
+                state.open_requests.remove(&request);
+            }
+            state.events.push_back(LoggedEvent { id, stream, data, last });
+            if state.events.len() > EVENT_BUFFER {
+                state.events.pop_front();
+            }
+            id
+        };
+        self.appended.send_replace(id);
+    }
+
+    /// End every stream, once the session is gone
+    pub(super) fn close(&self) {
+        self.lock().closed = true;
+        self.appended.send_modify(|_| {});
+    }
+
+    /// The stream that event `id` went out on, while it is still buffered
+    pub(super) fn stream_of(&self, id: u64) -> Option<StreamKey> {
+        self.lock().events.iter().find(|event| event.id == id).map(|event| event.stream)
+    }
+
+    /// Whether `stream` has nothing after event `after` left to send: it
+    /// ended and none of its buffered events are newer, even if its last
+    /// event has dropped out of the buffer. Everything ends with the log.
+    fn ended_after(&self, stream: StreamKey, after: u64) -> bool {
+        let state = self.lock();
+        let ended = match stream {
+            StreamKey::Standalone => false,
+            StreamKey::Request(request) => !state.open_requests.contains(&request),
+        };
+        state.closed || (ended && !state.events.iter().any(|event| event.stream == stream && event.id > after))
+    }
+
+    fn events_after(&self, stream: StreamKey, after: u64) -> VecDeque<LoggedEvent> {
+        self.lock()
+            .events
+            .iter()
+            .filter(|event| event.stream == stream && event.id > after)
+            .cloned()
+            .collect()
+    }
+
+    /// Server-sent events for `stream` from just after event `after`: those
+    /// already logged, then new ones as they come. A request stream ends
+    /// after its reply.
+    pub(super) fn replay(
+        self: &Arc<Self>,
+        stream: StreamKey,
+        after: u64,
+    ) -> impl Stream<Item = Result<Event, Infallible>> + Send + 'static {
+        let appended = self.appended.subscribe();
+        let state = (self.clone(), appended, after, VecDeque::new());
+        futures::stream::unfold(Some(state), move |state| async move {
+            let (log, mut appended, mut cursor, mut pending) = state?;
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    let LoggedEvent { id, data, last, .. } = event;
+                    cursor = id;
+                    let data = data?;
+                    let event = Event::default().event("message").id(id.to_string()).data(data);
+                    let next = (!last).then_some((log, appended, cursor, pending));
+                    return Some((Ok(event), next));
+                }
+                // Marked seen first, so nothing logged after the read is missed
+                appended.borrow_and_update();
+                pending = log.events_after(stream, cursor);
+                if pending.is_empty() && (log.ended_after(stream, cursor) || appended.changed().await.is_err()) {
+                    return None;
+                }
+            }
+        })
+    }
+}
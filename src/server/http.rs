@@ -0,0 +1,584 @@
+use super::event_log::{EventLog, StreamKey};
+use super::sse::message_event;
+use super::cors::{check_origin, CorsPolicy};
+use super::tls::{self, TlsListener, TlsPeer};
+use super::{cancellable_request_id, cancelled_request_id, drain, error_response, is_response, mount, normalize_base_path, shutdown_signal, stopped, DEFAULT_IDLE_TIMEOUT, DEFAULT_SHUTDOWN_TIMEOUT, ping_request, process_value, AbortOnDrop, InFlight, Keepalive, Reply};
+use crate::client::ClientHandle;
+use crate::mcp::McpServer;
+use crate::notifications::Notifier;
+use crate::session::SessionManager;
+use crate::types::{error_codes, JsonRpcError, JsonRpcNotification, JsonRpcRequest};
+use anyhow::Result;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::time::Instant;
+use tokio_rustls::TlsAcceptor;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tracing::{debug, info, warn};
+
+/// Header carrying the session a Streamable HTTP request belongs to
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Responses smaller than this aren't worth compressing
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+/// Header naming the last event a client received on a stream it resumes
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Serves the Streamable HTTP transport of the 2025-03-26 MCP spec on one
+/// `/mcp` endpoint:
+///
+/// - `POST` sends a JSON-RPC message or batch. An `initialize` starts a
+///   session, whose id comes back in the `Mcp-Session-Id` header and must
+///   accompany every later request. Responses are JSON, except that a
+///   `tools/call` from a client accepting `text/event-stream` is answered
+///   with an event stream carrying the call's notifications, then its
+///   response.
+/// - `GET` streams server-to-client notifications as server-sent events,
+///   along with requests to the client such as `sampling/createMessage`,
+///   whose responses the client `POST`s back.
+/// - `DELETE` ends the session.
+///
+/// Events on a session's streams carry ids, and each session buffers its
+/// recent ones whether or not a client is connected. A `GET` with
+/// `Last-Event-ID` resumes the stream that event was sent on, replaying
+/// what came after it, so the reply to a tool call whose `POST` dropped is
+/// not lost.
+///
+/// `/sse` streams the notifications every client gets, such as
+/// `tools/list_changed`, without needing a session. Requests go through
+/// the same routing as the stdio transport, and a slow tool call holds up
+/// no other request. Sessions are kept by a `SessionManager`.
+pub struct HttpServer {
+    state: AppState,
+    quiet: bool,
+    tls: Option<TlsAcceptor>,
+    keepalive: Option<Keepalive>,
+    cors: CorsPolicy,
+    /// Compress responses for clients that accept gzip or deflate
+    compression: bool,
+    base_path: String,
+    shutdown_timeout: Duration,
+    /// Flagged at shutdown, to stop accepting and end notification streams
+    stop: watch::Sender<bool>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    sessions: SessionManager,
+    /// Each session's buffered events, by session id
+    events: Arc<std::sync::Mutex<HashMap<String, SessionEvents>>>,
+    /// The template's notifier, for notifications to every client
+    notifier: Notifier,
+    stopping: watch::Receiver<bool>,
+}
+
+/// A session's event log and the task feeding notifications and requests
+/// to the client into its standalone stream, which stops when the session
+/// ends
+struct SessionEvents {
+    log: Arc<EventLog>,
+    client: ClientHandle,
+    /// The session's requests still running, for `notifications/cancelled`
+    in_flight: InFlight,
+    _feed: AbortOnDrop,
+}
+
+impl Drop for SessionEvents {
+    fn drop(&mut self) {
+        self.log.close();
+        self.client.disconnect();
+    }
+}
+
+impl HttpServer {
+    pub fn new(mcp_server: Arc<McpServer>, quiet: bool) -> Self {
+        let notifier = mcp_server.notifier.clone();
+        let (stop, stopping) = watch::channel(false);
+        Self {
+            state: AppState {
+                sessions: SessionManager::new(mcp_server),
+                events: Arc::default(),
+                notifier,
+                stopping,
+            },
+            quiet,
+            tls: None,
+            keepalive: Some(Keepalive::new(DEFAULT_IDLE_TIMEOUT)),
+            cors: CorsPolicy::default(),
+            compression: true,
+            base_path: String::new(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            stop,
+        }
+    }
+
+    /// Serve HTTPS instead of plain HTTP
+    pub fn with_tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.tls = Some(acceptor);
+        self
+    }
+
+    /// How long open requests get to finish at shutdown before they are
+    /// abandoned
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serve under `base_path` too, e.g. `/ai/mcp` for a reverse proxy
+    /// that publishes the server there and forwards the prefix; the routes
+    /// stay available at the root for proxies that strip it.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Answer browsers by `policy` instead of allowing localhost pages only
+    pub fn with_cors(mut self, policy: CorsPolicy) -> Self {
+        self.cors = policy;
+        self
+    }
+
+    /// Whether JSON responses are gzipped or deflated for clients that send
+    /// `Accept-Encoding`, which they are by default. Event streams are
+    /// never compressed.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Ping each session's client on its `GET` stream every
+    /// `keepalive.interval`, and end sessions that have sent no request,
+    /// ping response included, for `keepalive.idle_timeout`. Without it
+    /// sessions are kept for `DEFAULT_IDLE_TIMEOUT` after their last request.
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// How many sessions may be open at once; `initialize` beyond that is
+    /// answered with 503 until one closes or expires
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.state.sessions.set_max_sessions(max_sessions);
+        self
+    }
+
+    /// The sessions started by `initialize` and not yet closed
+    pub fn sessions(&self) -> &SessionManager {
+        &self.state.sessions
+    }
+
+    pub fn router(&self) -> Router {
+        let router = Router::new()
+            .route("/mcp", get(open_stream).post(handle_message).delete(close_session))
+            .route("/sse", get(handle_events))
+            .with_state(self.state.clone());
+        // Event streams are left alone, so each event still goes out as
+        // soon as it is sent
+        let router = if self.compression {
+            let predicate = SizeAbove::new(COMPRESSION_MIN_BYTES)
+                .and(NotForContentType::GRPC)
+                .and(NotForContentType::IMAGES)
+                .and(NotForContentType::SSE);
+            router.layer(CompressionLayer::new().compress_when(predicate))
+        } else {
+            router
+        };
+        mount(router, &self.base_path)
+            .layer(axum::middleware::from_fn_with_state(self.cors.clone(), check_origin))
+            .layer(self.cors.layer())
+    }
+
+    /// Listen on `addr` until SIGINT/SIGTERM
+    pub async fn run(&self, addr: SocketAddr) -> Result<()> {
+        self.run_on(TcpListener::bind(addr).await?).await
+    }
+
+    /// Serve an already bound listener, such as one passed in by systemd,
+    /// until SIGINT/SIGTERM
+    pub async fn run_on(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, shutdown_signal()).await?;
+        if !self.quiet {
+            info!("HTTP server stopped");
+        }
+        Ok(())
+    }
+
+    /// Serve on an already bound listener, e.g. one on port 0 in tests
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, std::future::pending()).await
+    }
+
+    /// Like `serve`, but when `shutdown` completes stop accepting
+    /// connections and end notification streams, then return once open
+    /// requests have been answered or the shutdown timeout has passed.
+    /// Tool call streams run until their reply.
+    pub async fn serve_until<S>(&self, listener: TcpListener, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()>,
+    {
+        if !self.quiet {
+            let scheme = if self.tls.is_some() { "https" } else { "http" };
+            info!("Starting HTTP server on {}://{}{}/mcp", scheme, listener.local_addr()?, self.base_path);
+        }
+        let _keepalive = self
+            .keepalive
+            .map(|keepalive| AbortOnDrop(tokio::spawn(keep_alive(self.state.clone(), keepalive)).abort_handle()));
+        let stopping = stopped(self.stop.subscribe());
+        let serving = async {
+            match &self.tls {
+                Some(acceptor) => {
+                    let router = self.router().into_make_service_with_connect_info::<TlsPeer>();
+                    let listener = TlsListener::new(listener, acceptor.clone())?;
+                    axum::serve(listener, router).with_graceful_shutdown(stopping).await
+                }
+                None => axum::serve(listener, self.router()).with_graceful_shutdown(stopping).await,
+            }
+        };
+        drain(serving, shutdown, &self.stop, self.shutdown_timeout, self.quiet).await
+    }
+}
+
+/// Why a request couldn't be matched to a session
+enum SessionError {
+    Missing,
+    Unknown,
+    TooMany,
+}
+
+impl IntoResponse for SessionError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            SessionError::Missing => (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"),
+            SessionError::Unknown => (StatusCode::NOT_FOUND, "Unknown or expired session"),
+            SessionError::TooMany => (StatusCode::SERVICE_UNAVAILABLE, "Too many open sessions"),
+        };
+        let response = error_response(None, JsonRpcError::new(error_codes::INVALID_REQUEST, message.to_string()));
+        (status, Json(response)).into_response()
+    }
+}
+
+/// The session named by the request's `Mcp-Session-Id` header
+fn find_session(state: &AppState, headers: &HeaderMap) -> Result<(String, Arc<Mutex<McpServer>>), SessionError> {
+    let id = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(SessionError::Missing)?;
+    state.sessions.get(id).map(|server| (id.to_string(), server)).ok_or(SessionError::Unknown)
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Whether a message is, or a batch contains, a `tools/call` request
+fn has_tool_call(value: &serde_json::Value) -> bool {
+    let is_call = |entry: &serde_json::Value| {
+        entry.get("method").and_then(|v| v.as_str()) == Some("tools/call")
+            && entry.get("id").is_some_and(|id| !id.is_null())
+    };
+    match value {
+        serde_json::Value::Array(batch) => batch.iter().any(is_call),
+        single => is_call(single),
+    }
+}
+
+/// The `_meta.progressToken`s of a message's, or a batch's, `tools/call`
+/// requests
+fn progress_tokens(value: &serde_json::Value) -> Vec<serde_json::Value> {
+    let token = |entry: &serde_json::Value| entry.pointer("/params/_meta/progressToken").cloned();
+    match value {
+        serde_json::Value::Array(batch) => batch.iter().filter_map(token).collect(),
+        single => token(single).into_iter().collect(),
+    }
+}
+
+/// Whether a notification belongs on the event stream of a request with
+/// these progress tokens: the request's progress, and log entries sent
+/// while it runs. Everything else goes on the session's `GET` stream only.
+fn for_request(notification: &JsonRpcNotification, tokens: &[serde_json::Value]) -> bool {
+    match notification.method.as_str() {
+        "notifications/message" => true,
+        "notifications/progress" => notification
+            .params
+            .as_ref()
+            .and_then(|params| params.get("progressToken"))
+            .is_some_and(|token| tokens.contains(token)),
+        _ => false,
+    }
+}
+
+async fn handle_message(
+    State(state): State<AppState>,
+    peer: Option<Extension<ConnectInfo<TlsPeer>>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let value: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Failed to parse JSON-RPC message: {}", e);
+            let response = error_response(None, JsonRpcError::parse_error());
+            return (StatusCode::BAD_REQUEST, Json(response)).into_response();
+        }
+    };
+
+    if value.get("method").and_then(|v| v.as_str()) == Some("initialize") {
+        let peer = peer.map(|Extension(ConnectInfo(peer))| peer);
+        return initialize_session(&state, peer, value).await;
+    }
+    let (id, mcp_server) = match find_session(&state, &headers) {
+        Ok(session) => session,
+        Err(e) => return e.into_response(),
+    };
+    // Looking the session up was all a ping response needed to do; other
+    // responses answer the session's requests to the client
+    if is_response(&value) {
+        if let Some(client) = session_client(&state, &id) {
+            client.resolve(&value);
+        }
+        return StatusCode::ACCEPTED.into_response();
+    }
+    // Handled here rather than queued behind the lock, which the target
+    // may be holding
+    if let Some(request_id) = cancelled_request_id(&value) {
+        if let Some(events) = state.events.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&id) {
+            events.in_flight.cancel(request_id);
+        }
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let request_id = cancellable_request_id(&value).cloned();
+    let server = mcp_server.lock_owned().await;
+    // Subscribed before the call starts, so none of its notifications are missed
+    let notifications = (accepts_event_stream(&headers) && has_tool_call(&value)).then(|| server.notifier.subscribe());
+    let tokens = progress_tokens(&value);
+    let task = tokio::spawn(process_value(server, value));
+    if let Some(request_id) = request_id {
+        if let Some(events) = state.events.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&id) {
+            events.in_flight.insert(&request_id, task.abort_handle());
+        }
+    }
+    // A cancelled request has no reply
+    let reply = async move { task.await.ok().flatten() };
+    match notifications.zip(session_log(&state, &id)) {
+        Some((notifications, log)) => {
+            let stream = log.request_stream();
+            tokio::spawn(log_reply(log.clone(), stream, notifications, tokens, reply));
+            Sse::new(log.replay(stream, 0)).into_response()
+        }
+        None => match reply.await {
+            Some(reply) => Json(reply).into_response(),
+            // Notifications and cancelled requests have nothing to answer
+            None => StatusCode::ACCEPTED.into_response(),
+        },
+    }
+}
+
+/// Answer `initialize` on a fresh copy of the server, which becomes a new
+/// session if it succeeds. Over TLS the session gets the scopes of the
+/// client's certificate.
+async fn initialize_session(state: &AppState, peer: Option<TlsPeer>, value: serde_json::Value) -> Response {
+    let common_name = peer.as_ref().and_then(|peer| peer.common_name.as_deref());
+    let mcp_server = Arc::new(Mutex::new(tls::client_session(state.sessions.fresh(), common_name)));
+    let reply = process_value(mcp_server.clone().lock_owned().await, value).await;
+    let Some(Reply::Single(response)) = reply else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+    if response.error.is_some() {
+        return Json(response).into_response();
+    }
+
+    let (notifications, client) = {
+        let server = mcp_server.lock().await;
+        (server.notifier.subscribe(), server.client.clone())
+    };
+    let Some(id) = state.sessions.insert(mcp_server) else {
+        warn!("Refusing an HTTP session: too many are open");
+        return SessionError::TooMany.into_response();
+    };
+    let log = Arc::new(EventLog::new());
+    let feed = tokio::spawn(log_notifications(log.clone(), notifications, client.subscribe()));
+    let events = SessionEvents {
+        log,
+        client,
+        in_flight: InFlight::default(),
+        _feed: AbortOnDrop(feed.abort_handle()),
+    };
+    state.events.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), events);
+    match &peer {
+        Some(peer) => debug!("HTTP session {} started for {}", id, peer.addr),
+        None => debug!("HTTP session {} started", id),
+    }
+    ([(SESSION_HEADER, id)], Json(response)).into_response()
+}
+
+fn session_log(state: &AppState, id: &str) -> Option<Arc<EventLog>> {
+    state.events.lock().unwrap_or_else(|e| e.into_inner()).get(id).map(|events| events.log.clone())
+}
+
+fn session_client(state: &AppState, id: &str) -> Option<ClientHandle> {
+    state.events.lock().unwrap_or_else(|e| e.into_inner()).get(id).map(|events| events.client.clone())
+}
+
+/// Log server notifications and the session's requests to its client on
+/// the session's standalone stream
+async fn log_notifications(
+    log: Arc<EventLog>,
+    mut notifications: broadcast::Receiver<JsonRpcNotification>,
+    mut requests: broadcast::Receiver<JsonRpcRequest>,
+) {
+    loop {
+        tokio::select! {
+            notification = notifications.recv() => match notification {
+                Ok(notification) => log.append(StreamKey::Standalone, &notification, false),
+                Err(RecvError::Lagged(skipped)) => warn!("Dropped {} notifications for an HTTP session", skipped),
+                Err(RecvError::Closed) => return,
+            },
+            request = requests.recv() => match request {
+                Ok(request) => log.append(StreamKey::Standalone, &request, false),
+                Err(RecvError::Lagged(skipped)) => warn!("Dropped {} requests to an HTTP session's client", skipped),
+                Err(RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+/// Log the request's notifications sent while it runs on its stream, then
+/// its reply. This carries on if the client disconnects, so a client
+/// resuming the stream still gets the reply.
+async fn log_reply(
+    log: Arc<EventLog>,
+    stream: StreamKey,
+    mut notifications: broadcast::Receiver<JsonRpcNotification>,
+    progress_tokens: Vec<serde_json::Value>,
+    reply: impl Future<Output = Option<Reply>>,
+) {
+    tokio::pin!(reply);
+    let reply = loop {
+        // Biased so that notifications sent before the reply go first
+        tokio::select! {
+            biased;
+
+            notification = notifications.recv() => match notification {
+                Ok(notification) if for_request(&notification, &progress_tokens) => {
+                    log.append(stream, &notification, false)
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Dropped {} notifications for a slow HTTP client", skipped);
+                }
+                Err(RecvError::Closed) => break reply.await,
+            },
+            reply = &mut reply => break reply,
+        }
+    };
+    match reply {
+        Some(reply) => log.append(stream, &reply, true),
+        None => log.end(stream),
+    }
+}
+
+/// `GET /mcp`: the session's stream of server-to-client notifications. With
+/// `Last-Event-ID`, the stream that event was sent on, resumed after it.
+async fn open_stream(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let log = match find_session(&state, &headers) {
+        Ok((id, _)) => session_log(&state, &id),
+        Err(e) => return e.into_response(),
+    };
+    let Some(log) = log else {
+        return SessionError::Unknown.into_response();
+    };
+    let last_event_id = headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let resumed = last_event_id.and_then(|id| Some((log.stream_of(id)?, id)));
+    match resumed {
+        Some((StreamKey::Standalone, after)) => {
+            debug!("Resuming the standalone stream after event {}", after);
+            let events = log.replay(StreamKey::Standalone, after).take_until(stopped(state.stopping.clone()));
+            Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+        }
+        Some((stream, after)) => {
+            debug!("Resuming {:?} after event {}", stream, after);
+            Sse::new(log.replay(stream, after)).keep_alive(KeepAlive::default()).into_response()
+        }
+        None => {
+            if let Some(id) = last_event_id {
+                debug!("Event {} is no longer buffered; starting a new stream", id);
+            }
+            let events = log.replay(StreamKey::Standalone, log.last_id()).take_until(stopped(state.stopping.clone()));
+            Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+        }
+    }
+}
+
+/// Ping every session on its standalone stream, and end those whose clients
+/// have gone quiet
+async fn keep_alive(state: AppState, keepalive: Keepalive) {
+    let mut ticks = tokio::time::interval_at(Instant::now() + keepalive.interval, keepalive.interval);
+    let mut pings = 0u64;
+    loop {
+        ticks.tick().await;
+        let idle = state.sessions.remove_idle(keepalive.idle_timeout);
+        let mut events = state.events.lock().unwrap_or_else(|e| e.into_inner());
+        for id in idle {
+            events.remove(&id);
+            debug!("HTTP session {} closed after {:?} idle", id, keepalive.idle_timeout);
+        }
+        pings += 1;
+        let ping = ping_request(pings);
+        for session in events.values() {
+            session.log.append(StreamKey::Standalone, &ping, false);
+        }
+    }
+}
+
+/// `DELETE /mcp`: end the session; later requests naming it get 404
+async fn close_session(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let id = match find_session(&state, &headers) {
+        Ok((id, _)) => id,
+        Err(e) => return e.into_response(),
+    };
+    state.sessions.remove(&id);
+    state.events.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    debug!("HTTP session {} closed by the client", id);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn handle_events(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stopping = stopped(state.stopping.clone());
+    let receiver = state.notifier.subscribe();
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(notification) => return Some((Ok(message_event(&notification)), receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Dropped {} notifications for a slow SSE client", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events.take_until(stopping)).keep_alive(KeepAlive::default())
+}
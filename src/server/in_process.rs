@@ -0,0 +1,167 @@
+use super::{MessageSink, MessageSource, StdioServer, Transport};
+use crate::mcp::SUPPORTED_PROTOCOL_VERSIONS;
+use crate::types::{ClientInfo, JsonRpcResponse};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+
+/// Messages either side may have queued before the other catches up
+const CHANNEL_CAPACITY: usize = 64;
+
+/// A transport over in-memory channels, connecting the request loop to an
+/// `InProcessClient` in the same process: for tests that want the whole
+/// loop, notifications and errors included, and for hosts that embed the
+/// server without a pipe in between.
+///
+/// The client sends JSON text, so malformed input can be tried too, and
+/// receives each message as a parsed value.
+pub struct InProcessTransport {
+    incoming: mpsc::Receiver<String>,
+    outgoing: mpsc::Sender<Value>,
+}
+
+impl InProcessTransport {
+    /// A transport and the client at its other end
+    pub fn pair() -> (Self, InProcessClient) {
+        let (to_server, incoming) = mpsc::channel(CHANNEL_CAPACITY);
+        let (outgoing, from_server) = mpsc::channel(CHANNEL_CAPACITY);
+        let client = InProcessClient {
+            outgoing: to_server,
+            incoming: from_server,
+            unclaimed: VecDeque::new(),
+            next_id: 1,
+        };
+        (Self { incoming, outgoing }, client)
+    }
+}
+
+impl Transport for InProcessTransport {
+    type Source = InProcessSource;
+    type Sink = InProcessSink;
+
+    fn split(self) -> (Self::Source, Self::Sink) {
+        (InProcessSource(self.incoming), InProcessSink(self.outgoing))
+    }
+}
+
+pub struct InProcessSource(mpsc::Receiver<String>);
+
+#[async_trait::async_trait]
+impl MessageSource for InProcessSource {
+    async fn receive(&mut self) -> std::io::Result<Option<String>> {
+        Ok(self.0.recv().await)
+    }
+}
+
+pub struct InProcessSink(mpsc::Sender<Value>);
+
+#[async_trait::async_trait]
+impl MessageSink for InProcessSink {
+    async fn send(&mut self, message: Value) -> Result<()> {
+        self.0
+            .send(message)
+            .await
+            .map_err(|_| anyhow::anyhow!("In-process client disconnected"))
+    }
+}
+
+/// The client end of an `InProcessTransport`.
+///
+/// Dropping it is the client going away: the server answers what it has
+/// already received, then its loop ends.
+pub struct InProcessClient {
+    outgoing: mpsc::Sender<String>,
+    incoming: mpsc::Receiver<Value>,
+    /// Messages that arrived while `request` waited for its response
+    unclaimed: VecDeque<Value>,
+    next_id: u64,
+}
+
+impl InProcessClient {
+    /// Send one message as raw text, exactly as a client would write it
+    pub async fn send_raw(&self, text: impl Into<String>) -> Result<()> {
+        self.outgoing
+            .send(text.into())
+            .await
+            .map_err(|_| anyhow::anyhow!("In-process server stopped"))
+    }
+
+    /// Send one message, or a batch when given an array
+    pub async fn send<T: Serialize>(&self, message: &T) -> Result<()> {
+        self.send_raw(serde_json::to_string(message)?).await
+    }
+
+    /// Send a notification, which gets no response
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        let mut notification = json!({"jsonrpc": "2.0", "method": method});
+        if let Some(params) = params {
+            notification["params"] = params;
+        }
+        self.send(&notification).await
+    }
+
+    /// The next message from the server: a response, a notification or a
+    /// request such as a keepalive `ping`. `None` once the server stopped.
+    pub async fn receive(&mut self) -> Option<Value> {
+        match self.unclaimed.pop_front() {
+            Some(message) => Some(message),
+            None => self.incoming.recv().await,
+        }
+    }
+
+    /// Send a request and wait for its response. Messages that arrive in
+    /// the meantime, such as notifications the request caused, are kept
+    /// for `receive`.
+    pub async fn request(&mut self, method: &str, params: Option<Value>) -> Result<JsonRpcResponse> {
+        let id = json!(self.next_id);
+        self.next_id += 1;
+        let mut request = json!({"jsonrpc": "2.0", "id": id, "method": method});
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        self.send(&request).await?;
+
+        loop {
+            let message = self
+                .incoming
+                .recv()
+                .await
+                .with_context(|| format!("Server stopped before answering {}", method))?;
+            if message.get("method").is_none() && message.get("id") == Some(&id) {
+                return Ok(serde_json::from_value(message)?);
+            }
+            self.unclaimed.push_back(message);
+        }
+    }
+
+    /// `initialize` as `client_info` and confirm with
+    /// `notifications/initialized`, so that tools can be called
+    pub async fn initialize(&mut self, client_info: ClientInfo) -> Result<JsonRpcResponse> {
+        let params = json!({
+            "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+            "capabilities": {},
+            "clientInfo": client_info,
+        });
+        let response = self.request("initialize", Some(params)).await?;
+        if response.error.is_none() {
+            self.notify("notifications/initialized", None).await?;
+        }
+        Ok(response)
+    }
+}
+
+impl StdioServer {
+    /// Run the request loop on a task of its own over an
+    /// `InProcessTransport`, and return the client connected to it
+    pub fn connect_in_process(self) -> InProcessClient {
+        let (transport, client) = InProcessTransport::pair();
+        tokio::spawn(async move {
+            if let Err(e) = self.serve_transport(transport, std::future::pending()).await {
+                tracing::debug!("In-process session ended: {}", e);
+            }
+        });
+        client
+    }
+}
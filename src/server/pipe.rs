@@ -0,0 +1,137 @@
+use super::{shutdown_signal, Keepalive, StdioServer, DEFAULT_SHUTDOWN_TIMEOUT};
+use crate::mcp::McpServer;
+use anyhow::Result;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+/// Pipe the server listens on unless told otherwise
+pub const DEFAULT_PIPE_NAME: &str = r"\\.\pipe\rust-mcp-server";
+
+/// Serves newline-delimited JSON-RPC over a Windows named pipe, so that
+/// local processes can share one server where Unix sockets aren't
+/// available.
+///
+/// Like the TCP transport, each client connection runs the stdio loop and
+/// is its own session with its own copy of the server.
+pub struct PipeServer {
+    mcp_server: Arc<McpServer>,
+    quiet: bool,
+    shutdown_timeout: Duration,
+    /// Requests per second each client may send; `None` is unlimited
+    rate_limit: Option<u32>,
+    keepalive: Option<Keepalive>,
+}
+
+impl PipeServer {
+    pub fn new(mcp_server: Arc<McpServer>, quiet: bool) -> Self {
+        Self {
+            mcp_server,
+            quiet,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            rate_limit: None,
+            keepalive: None,
+        }
+    }
+
+    /// How long requests still running for a client get to finish at
+    /// shutdown before they are abandoned
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Refuse requests beyond `per_second` a second from any one client,
+    /// as `StdioServer::with_rate_limit` does
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    /// Ping every client and drop those that go quiet, as
+    /// `StdioServer::with_keepalive` does
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Serve the pipe `name` until SIGINT/SIGTERM
+    pub async fn run(&self, name: &str) -> Result<()> {
+        self.serve_until(name, shutdown_signal()).await?;
+        if !self.quiet {
+            info!("Named pipe server stopped");
+        }
+        Ok(())
+    }
+
+    /// Create the pipe `name` and serve its clients until `shutdown`
+    /// completes, then return once every open connection has wound down.
+    /// Fails if another process already serves a pipe of that name.
+    pub async fn serve_until<S>(&self, name: &str, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()>,
+    {
+        // Claiming the first instance keeps a second server from silently
+        // sharing the name
+        let mut listening = ServerOptions::new().first_pipe_instance(true).create(name)?;
+        if !self.quiet {
+            info!("Starting named pipe server on {}", name);
+        }
+        tokio::pin!(shutdown);
+        let (stop_tx, stop) = watch::channel(false);
+        let mut connections = JoinSet::new();
+        loop {
+            let connected = tokio::select! {
+                _ = &mut shutdown => break,
+                connected = listening.connect() => connected,
+                // Reap finished connections as we go
+                Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            };
+            if let Err(e) = connected {
+                warn!("Failed to accept named pipe client: {}", e);
+                listening = ServerOptions::new().create(name)?;
+                continue;
+            }
+
+            // A new instance has to exist before the next client can connect
+            let pipe = std::mem::replace(&mut listening, ServerOptions::new().create(name)?);
+            debug!("Named pipe client connected");
+            let connection = self.connection();
+            let mut stop = stop.clone();
+            connections.spawn(async move {
+                let stopped = async move {
+                    let _ = stop.wait_for(|stopped| *stopped).await;
+                };
+                if let Err(e) = serve_connection(connection, pipe, stopped).await {
+                    warn!("Named pipe connection failed: {}", e);
+                }
+                debug!("Named pipe client disconnected");
+            });
+        }
+
+        let _ = stop_tx.send(true);
+        while connections.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /// A fresh session for one client
+    fn connection(&self) -> StdioServer {
+        let mut server = StdioServer::new(self.mcp_server.clone(), true).with_shutdown_timeout(self.shutdown_timeout);
+        if let Some(per_second) = self.rate_limit {
+            server = server.with_rate_limit(per_second);
+        }
+        if let Some(keepalive) = self.keepalive {
+            server = server.with_keepalive(keepalive);
+        }
+        server
+    }
+}
+
+async fn serve_connection(server: StdioServer, pipe: NamedPipeServer, shutdown: impl Future<Output = ()>) -> Result<()> {
+    let (reader, writer) = tokio::io::split(pipe);
+    server.serve_until(reader, writer, shutdown).await
+}
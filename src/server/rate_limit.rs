@@ -0,0 +1,88 @@
+use super::{error_response, Reply};
+use crate::types::JsonRpcError;
+use tokio::time::Instant;
+
+/// Methods that are never limited, so a throttled client can still keep
+/// its session alive and finish the handshake
+const EXEMPT_METHODS: &[&str] = &["initialize", "notifications/initialized", "ping"];
+
+/// Token bucket holding up to one second's worth of requests. Each request
+/// takes a token, and tokens come back continuously at `per_second`, so a
+/// client may burst up to the limit and then keep to the steady rate.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: u32,
+    per_second: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// A full bucket for `per_second` requests per second, at least one
+    pub fn new(per_second: u32) -> Self {
+        let limit = per_second.max(1);
+        let per_second = f64::from(limit);
+        Self {
+            limit,
+            per_second,
+            tokens: per_second,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Take a token for one request, or return false if the bucket is empty
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// `try_acquire` as of `now`
+    pub fn try_acquire_at(&mut self, now: Instant) -> bool {
+        self.try_acquire_many_at(1, now)
+    }
+
+    /// Take `count` tokens at once, or none if there aren't that many
+    pub(crate) fn try_acquire_many_at(&mut self, count: usize, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.per_second);
+        self.refilled = self.refilled.max(now);
+        if self.tokens < count as f64 {
+            return false;
+        }
+        self.tokens -= count as f64;
+        true
+    }
+}
+
+/// Take a token for each request in a message, single or batch, that
+/// isn't exempt. A message over the limit is refused as a whole.
+pub(crate) fn admit(limiter: &mut RateLimiter, value: &serde_json::Value) -> bool {
+    let limited = entries(value)
+        .iter()
+        .filter(|entry| {
+            let method = entry.get("method").and_then(|v| v.as_str()).unwrap_or_default();
+            !EXEMPT_METHODS.contains(&method)
+        })
+        .count();
+    limited == 0 || limiter.try_acquire_many_at(limited, Instant::now())
+}
+
+/// What to send back for a refused message: a rate limited error for each
+/// of its requests that expects a response
+pub(crate) fn refusal(limiter: &RateLimiter, value: &serde_json::Value) -> Option<Reply> {
+    let mut responses: Vec<_> = entries(value)
+        .iter()
+        .filter_map(|entry| entry.get("id").filter(|id| !id.is_null()))
+        .map(|id| error_response(Some(id.clone()), JsonRpcError::rate_limited(limiter.limit)))
+        .collect();
+    match value {
+        serde_json::Value::Array(_) => (!responses.is_empty()).then_some(Reply::Batch(responses)),
+        _ => responses.pop().map(Reply::Single),
+    }
+}
+
+fn entries(value: &serde_json::Value) -> &[serde_json::Value] {
+    match value {
+        serde_json::Value::Array(batch) => batch,
+        single => std::slice::from_ref(single),
+    }
+}
@@ -0,0 +1,351 @@
+use super::cors::{check_origin, CorsPolicy};
+use super::tls::{self, TlsListener, TlsPeer};
+use super::{cancellable_request_id, cancelled_request_id, drain, error_response, is_response, mount, normalize_base_path, shutdown_signal, stopped, DEFAULT_SHUTDOWN_TIMEOUT, ping_request, process_value, AbortOnDrop, InFlight, Keepalive};
+use crate::client::ClientHandle;
+use crate::mcp::McpServer;
+use crate::session::SessionManager;
+use crate::types::JsonRpcError;
+use anyhow::Result;
+use axum::extract::{ConnectInfo, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::time::Instant;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+
+/// Messages waiting to be streamed to one client
+const REPLY_BUFFER: usize = 64;
+
+/// Serves the MCP HTTP+SSE transport: a client opens an event stream with
+/// `GET /sse`, whose first `endpoint` event names the URL to POST its
+/// JSON-RPC messages to, and every reply arrives on that stream as a
+/// `message` event, as do server notifications and requests to the client.
+///
+/// Each stream is its own session, kept by a `SessionManager`, so one
+/// client's `initialize` doesn't initialize another. Messages go through
+/// the same routing as the other transports.
+pub struct HttpSseServer {
+    state: SseState,
+    quiet: bool,
+    tls: Option<TlsAcceptor>,
+    keepalive: Option<Keepalive>,
+    cors: CorsPolicy,
+    shutdown_timeout: Duration,
+    /// Flagged at shutdown, to stop accepting connections
+    stop: watch::Sender<bool>,
+}
+
+#[derive(Clone)]
+struct SseState {
+    sessions: SessionManager,
+    /// Where each session's replies and pings are streamed from, by session id
+    replies: Arc<std::sync::Mutex<HashMap<String, mpsc::Sender<Event>>>>,
+    /// Each session's requests still running, for `notifications/cancelled`
+    in_flight: Arc<std::sync::Mutex<HashMap<String, InFlight>>>,
+    /// Prefix of the URLs clients are sent to, e.g. `/ai/mcp` behind a proxy
+    base_path: String,
+}
+
+#[derive(Deserialize)]
+struct SessionQuery {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+}
+
+impl HttpSseServer {
+    pub fn new(mcp_server: Arc<McpServer>, quiet: bool) -> Self {
+        Self {
+            state: SseState {
+                sessions: SessionManager::new(mcp_server),
+                replies: Arc::default(),
+                in_flight: Arc::default(),
+                base_path: String::new(),
+            },
+            quiet,
+            tls: None,
+            keepalive: None,
+            cors: CorsPolicy::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            stop: watch::Sender::new(false),
+        }
+    }
+
+    /// Serve HTTPS instead of plain HTTP
+    pub fn with_tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.tls = Some(acceptor);
+        self
+    }
+
+    /// How long requests still running at shutdown get to send their
+    /// replies before the event streams are cut
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serve under `base_path`, e.g. `/ai/mcp` for a reverse proxy that
+    /// publishes the server there, and advertise message URLs under it.
+    /// The routes stay available at the root for proxies that strip it.
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        self.state.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Answer browsers by `policy` instead of allowing localhost pages only
+    pub fn with_cors(mut self, policy: CorsPolicy) -> Self {
+        self.cors = policy;
+        self
+    }
+
+    /// Ping each client on its event stream every `keepalive.interval`, and
+    /// end sessions that have POSTed nothing, ping response included, for
+    /// `keepalive.idle_timeout`
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// How many event streams may be open at once; more are answered with
+    /// 503 until one closes
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.state.sessions.set_max_sessions(max_sessions);
+        self
+    }
+
+    /// The sessions of the open event streams
+    pub fn sessions(&self) -> &SessionManager {
+        &self.state.sessions
+    }
+
+    pub fn router(&self) -> Router {
+        let router = Router::new()
+            .route("/sse", get(open_session))
+            .route("/messages", post(handle_message))
+            .with_state(self.state.clone());
+        mount(router, &self.state.base_path)
+            .layer(axum::middleware::from_fn_with_state(self.cors.clone(), check_origin))
+            .layer(self.cors.layer())
+    }
+
+    /// Listen on `addr` until SIGINT/SIGTERM
+    pub async fn run(&self, addr: SocketAddr) -> Result<()> {
+        self.run_on(TcpListener::bind(addr).await?).await
+    }
+
+    /// Serve an already bound listener, such as one passed in by systemd,
+    /// until SIGINT/SIGTERM
+    pub async fn run_on(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, shutdown_signal()).await?;
+        if !self.quiet {
+            info!("SSE server stopped");
+        }
+        Ok(())
+    }
+
+    /// Serve on an already bound listener, e.g. one on port 0 in tests
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, std::future::pending()).await
+    }
+
+    /// Like `serve`, but when `shutdown` completes stop accepting
+    /// connections and messages, and end each event stream once the
+    /// requests its session has running have replied, or when the shutdown
+    /// timeout passes
+    pub async fn serve_until<S>(&self, listener: TcpListener, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()>,
+    {
+        if !self.quiet {
+            let scheme = if self.tls.is_some() { "https" } else { "http" };
+            info!("Starting SSE server on {}://{}{}/sse", scheme, listener.local_addr()?, self.state.base_path);
+        }
+        let _keepalive = self
+            .keepalive
+            .map(|keepalive| AbortOnDrop(tokio::spawn(keep_alive(self.state.clone(), keepalive)).abort_handle()));
+        let stopping = stopped(self.stop.subscribe());
+        let serving = async {
+            match &self.tls {
+                Some(acceptor) => {
+                    let router = self.router().into_make_service_with_connect_info::<TlsPeer>();
+                    let listener = TlsListener::new(listener, acceptor.clone())?;
+                    axum::serve(listener, router).with_graceful_shutdown(stopping).await
+                }
+                None => axum::serve(listener, self.router()).with_graceful_shutdown(stopping).await,
+            }
+        };
+        // A stream ends when the last sender of its replies is gone: the
+        // map's now, and each running request's once it has replied
+        let shutdown = async {
+            shutdown.await;
+            self.state.replies.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        };
+        drain(serving, shutdown, &self.stop, self.shutdown_timeout, self.quiet).await
+    }
+}
+
+/// Ends a session when its event stream is dropped, i.e. when the client
+/// disconnects
+struct SessionGuard {
+    id: String,
+    state: SseState,
+    client: ClientHandle,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        debug!("SSE session {} closed", self.id);
+        self.client.disconnect();
+        self.state.sessions.remove(&self.id);
+        self.state.replies.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+        self.state.in_flight.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+/// Over TLS the session gets the scopes of the client's certificate
+async fn open_session(
+    State(state): State<SseState>,
+    peer: Option<Extension<ConnectInfo<TlsPeer>>>,
+) -> Response {
+    let common_name = peer.as_ref().and_then(|Extension(ConnectInfo(peer))| peer.common_name.as_deref());
+    let mcp_server = tls::client_session(state.sessions.fresh(), common_name);
+    let notifications = mcp_server.notifier.subscribe();
+    let client = mcp_server.client.clone();
+    let requests = client.subscribe();
+    let (replies_tx, replies) = mpsc::channel(REPLY_BUFFER);
+    let Some(id) = state.sessions.insert(Arc::new(Mutex::new(mcp_server))) else {
+        warn!("Refusing an SSE session: too many are open");
+        return (StatusCode::SERVICE_UNAVAILABLE, "Too many open sessions").into_response();
+    };
+    state.replies.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), replies_tx);
+    state.in_flight.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), InFlight::default());
+    match &peer {
+        Some(Extension(ConnectInfo(peer))) => debug!("SSE session {} opened for {}", id, peer.addr),
+        None => debug!("SSE session {} opened", id),
+    }
+
+    let endpoint = format!("{}/messages?sessionId={}", state.base_path, id);
+    let endpoint = Event::default().event("endpoint").data(endpoint);
+    let guard = SessionGuard { id, state, client };
+    let channels = (replies, notifications, requests, guard);
+    let messages = futures::stream::unfold(channels, |(mut replies, mut notifications, mut requests, guard)| async move {
+        loop {
+            let event = tokio::select! {
+                reply = replies.recv() => match reply {
+                    Some(event) => event,
+                    None => return None,
+                },
+                notification = notifications.recv() => match notification {
+                    Ok(notification) => message_event(&notification),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} notifications for a slow SSE client", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+                request = requests.recv() => match request {
+                    Ok(request) => message_event(&request),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} requests to a slow SSE client", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+            return Some((Ok::<_, Infallible>(event), (replies, notifications, requests, guard)));
+        }
+    });
+    let events = futures::stream::once(async { Ok(endpoint) }).chain(messages);
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+pub(super) fn message_event<T: serde::Serialize>(message: &T) -> Event {
+    Event::default()
+        .event("message")
+        .json_data(message)
+        .unwrap_or_else(|e| Event::default().comment(format!("dropped message: {}", e)))
+}
+
+/// Accept one message for a session; its reply, if any, goes out on the
+/// session's event stream
+async fn handle_message(State(state): State<SseState>, Query(query): Query<SessionQuery>, body: String) -> Response {
+    let replies = state.replies.lock().unwrap_or_else(|e| e.into_inner()).get(&query.session_id).cloned();
+    let (Some(mcp_server), Some(replies)) = (state.sessions.get(&query.session_id), replies) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown session '{}'", query.session_id)).into_response();
+    };
+
+    let value: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Failed to parse JSON-RPC message: {}", e);
+            let response = error_response(None, JsonRpcError::parse_error());
+            return (StatusCode::BAD_REQUEST, Json(response)).into_response();
+        }
+    };
+
+    // Looking the session up was all a ping response needed to do; other
+    // responses answer the session's requests to the client
+    if is_response(&value) {
+        mcp_server.lock().await.client.resolve(&value);
+        return StatusCode::ACCEPTED.into_response();
+    }
+    // Handled here rather than queued behind the lock, which the target
+    // may be holding
+    if let Some(request_id) = cancelled_request_id(&value) {
+        if let Some(in_flight) = state.in_flight.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&query.session_id) {
+            in_flight.cancel(request_id);
+        }
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    // Locked before returning, so messages see each other's state in the
+    // order they were POSTed
+    let request_id = cancellable_request_id(&value).cloned();
+    let server = mcp_server.lock_owned().await;
+    let task = tokio::spawn(async move {
+        if let Some(reply) = process_value(server, value).await {
+            let _ = replies.send(message_event(&reply)).await;
+        }
+    });
+    if let Some(request_id) = request_id {
+        if let Some(in_flight) = state.in_flight.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&query.session_id) {
+            in_flight.insert(&request_id, task.abort_handle());
+        }
+    }
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// Ping every session on its event stream, and end those whose clients
+/// have gone quiet; dropping a session's sender ends its stream
+async fn keep_alive(state: SseState, keepalive: Keepalive) {
+    let mut ticks = tokio::time::interval_at(Instant::now() + keepalive.interval, keepalive.interval);
+    let mut pings = 0u64;
+    loop {
+        ticks.tick().await;
+        let idle = state.sessions.remove_idle(keepalive.idle_timeout);
+        let mut replies = state.replies.lock().unwrap_or_else(|e| e.into_inner());
+        for id in idle {
+            replies.remove(&id);
+            debug!("SSE session {} closed after {:?} idle", id, keepalive.idle_timeout);
+        }
+        pings += 1;
+        let ping = message_event(&ping_request(pings));
+        for sender in replies.values() {
+            // A client too slow to take a ping gets the next one
+            let _ = sender.try_send(ping.clone());
+        }
+    }
+}
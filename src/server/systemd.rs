@@ -0,0 +1,50 @@
+use anyhow::Result;
+use tokio::net::TcpListener;
+
+/// The first file descriptor systemd passes, `SD_LISTEN_FDS_START`
+#[cfg(unix)]
+const LISTEN_FDS_START: std::os::fd::RawFd = 3;
+
+/// The listening socket systemd passed in under socket activation, if the
+/// process was started that way.
+///
+/// Follows `sd_listen_fds(3)`: the socket is taken only when `LISTEN_PID`
+/// names this process, and `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES`
+/// are removed so that child processes don't take it too. Only the first
+/// socket is used, and it has to be a TCP socket already listening; the
+/// unit's `.socket` file decides the address. Always `None` off Unix.
+pub fn systemd_listener() -> Result<Option<TcpListener>> {
+    #[cfg(unix)]
+    {
+        use std::os::fd::FromRawFd;
+
+        let pid = std::env::var("LISTEN_PID").ok();
+        let fds = std::env::var("LISTEN_FDS").ok();
+        for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+            std::env::remove_var(name);
+        }
+        if pid.and_then(|pid| pid.parse::<u32>().ok()) != Some(std::process::id()) {
+            return Ok(None);
+        }
+        let count: i32 = match fds.as_deref().map(str::parse) {
+            Some(Ok(count)) if count > 0 => count,
+            _ => anyhow::bail!("LISTEN_PID is set but LISTEN_FDS names no sockets"),
+        };
+        if count > 1 {
+            tracing::warn!("systemd passed {} sockets; only the first is used", count);
+        }
+
+        // Safety: under socket activation systemd hands this process the
+        // descriptors from LISTEN_FDS_START on, and nothing else owns them
+        let listener = unsafe { std::net::TcpListener::from_raw_fd(LISTEN_FDS_START) };
+        // Fails for sockets that aren't TCP, e.g. a Unix socket
+        let addr = listener
+            .local_addr()
+            .map_err(|e| anyhow::anyhow!("Socket passed by systemd is not a TCP socket: {}", e))?;
+        listener.set_nonblocking(true)?;
+        tracing::info!("Using the socket passed by systemd on {}", addr);
+        Ok(Some(TcpListener::from_std(listener)?))
+    }
+    #[cfg(not(unix))]
+    Ok(None)
+}
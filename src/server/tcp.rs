@@ -0,0 +1,226 @@
+use super::{shutdown_signal, tls, AcceptBackoff, Keepalive, StdioServer, DEFAULT_SHUTDOWN_TIMEOUT};
+use crate::mcp::McpServer;
+use crate::session::SessionManager;
+use anyhow::Result;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+
+/// Serves newline-delimited JSON-RPC over raw TCP, for clients that can
+/// reach the server over the network but not spawn it.
+///
+/// Each connection runs the same loop as the stdio transport and is its own
+/// session, so it must `initialize` on its own; sessions are registered
+/// with a `SessionManager` while their connection is open. Connections are
+/// served concurrently.
+pub struct TcpServer {
+    sessions: SessionManager,
+    quiet: bool,
+    shutdown_timeout: Duration,
+    /// Requests per second each connection may send; `None` is unlimited
+    rate_limit: Option<u32>,
+    /// Wraps each connection in TLS when set
+    tls: Option<TlsAcceptor>,
+    keepalive: Option<Keepalive>,
+}
+
+impl TcpServer {
+    pub fn new(mcp_server: Arc<McpServer>, quiet: bool) -> Self {
+        Self {
+            sessions: SessionManager::new(mcp_server),
+            quiet,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            rate_limit: None,
+            tls: None,
+            keepalive: None,
+        }
+    }
+
+    /// How long requests still running on a connection get to finish at
+    /// shutdown before they are abandoned
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Refuse requests beyond `per_second` a second on any one connection,
+    /// as `StdioServer::with_rate_limit` does
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    /// Ping every connection's client and close connections that go quiet,
+    /// as `StdioServer::with_keepalive` does
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// How many connections may have a session at once; later ones are
+    /// closed until one ends
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.sessions.set_max_sessions(max_sessions);
+        self
+    }
+
+    /// Speak TLS on every connection; clients that fail the handshake are
+    /// dropped before any JSON-RPC is read
+    pub fn with_tls(mut self, acceptor: TlsAcceptor) -> Self {
+        self.tls = Some(acceptor);
+        self
+    }
+
+    /// The sessions of the open connections
+    pub fn sessions(&self) -> &SessionManager {
+        &self.sessions
+    }
+
+    /// Listen on `addr` until SIGINT/SIGTERM
+    pub async fn run(&self, addr: SocketAddr) -> Result<()> {
+        self.run_on(TcpListener::bind(addr).await?).await
+    }
+
+    /// Serve an already bound listener, such as one passed in by systemd,
+    /// until SIGINT/SIGTERM
+    pub async fn run_on(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, shutdown_signal()).await?;
+        if !self.quiet {
+            info!("TCP server stopped");
+        }
+        Ok(())
+    }
+
+    /// Accept connections on an already bound listener, e.g. one on port 0
+    /// in tests
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        self.serve_until(listener, std::future::pending()).await
+    }
+
+    /// Like `serve`, but when `shutdown` completes stop accepting, and
+    /// return once every open connection has wound down as the stdio
+    /// transport does at shutdown
+    pub async fn serve_until<S>(&self, listener: TcpListener, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()>,
+    {
+        if !self.quiet {
+            let scheme = if self.tls.is_some() { "TLS" } else { "TCP" };
+            info!("Starting {} server on {}", scheme, listener.local_addr()?);
+        }
+        tokio::pin!(shutdown);
+        let (stop_tx, stop) = watch::channel(false);
+        let mut connections = JoinSet::new();
+        let mut backoff = AcceptBackoff::default();
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = &mut shutdown => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => {
+                        backoff.succeeded();
+                        accepted
+                    }
+                    Err(e) => {
+                        warn!("Failed to accept TCP connection: {}", e);
+                        backoff.failed().await;
+                        continue;
+                    }
+                },
+                // Reap finished connections as we go
+                Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            };
+            debug!("TCP connection from {}", peer);
+            let connection = Connection {
+                sessions: self.sessions.clone(),
+                shutdown_timeout: self.shutdown_timeout,
+                rate_limit: self.rate_limit,
+                tls: self.tls.clone(),
+                keepalive: self.keepalive,
+            };
+            let mut stop = stop.clone();
+            connections.spawn(async move {
+                let stopped = async move {
+                    let _ = stop.wait_for(|stopped| *stopped).await;
+                };
+                if let Err(e) = connection.serve(stream, stopped).await {
+                    warn!("TCP connection from {} failed: {}", peer, e);
+                }
+                debug!("TCP connection from {} closed", peer);
+            });
+        }
+
+        let _ = stop_tx.send(true);
+        while connections.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+/// What a connection needs to set up its own session
+struct Connection {
+    sessions: SessionManager,
+    shutdown_timeout: Duration,
+    rate_limit: Option<u32>,
+    tls: Option<TlsAcceptor>,
+    keepalive: Option<Keepalive>,
+}
+
+impl Connection {
+    /// The stdio loop, serving one session
+    fn session(&self, session: Arc<Mutex<McpServer>>) -> StdioServer {
+        let mut server = StdioServer::from_session(session, true).with_shutdown_timeout(self.shutdown_timeout);
+        if let Some(per_second) = self.rate_limit {
+            server = server.with_rate_limit(per_second);
+        }
+        if let Some(keepalive) = self.keepalive {
+            server = server.with_keepalive(keepalive);
+        }
+        server
+    }
+
+    async fn serve(self, stream: TcpStream, shutdown: impl Future<Output = ()>) -> Result<()> {
+        stream.set_nodelay(true)?;
+        match &self.tls {
+            Some(acceptor) => {
+                let stream = tls::handshake(acceptor, stream).await?;
+                let common_name = tls::client_common_name(&stream);
+                let (reader, writer) = tokio::io::split(stream);
+                self.serve_session(common_name.as_deref(), reader, writer, shutdown).await
+            }
+            None => {
+                let (reader, writer) = stream.into_split();
+                self.serve_session(None, reader, writer, shutdown).await
+            }
+        }
+    }
+
+    /// Open a session for the connection, with the scopes of the client's
+    /// certificate under TLS, and close it when the connection ends
+    async fn serve_session<R, W>(
+        &self,
+        common_name: Option<&str>,
+        reader: R,
+        writer: W,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send,
+    {
+        let session = Arc::new(Mutex::new(tls::client_session(self.sessions.fresh(), common_name)));
+        let Some(id) = self.sessions.insert(session.clone()) else {
+            anyhow::bail!("too many open sessions");
+        };
+        debug!("TCP session {} opened", id);
+        let served = self.session(session).serve_until(reader, writer, shutdown).await;
+        self.sessions.remove(&id);
+        debug!("TCP session {} closed", id);
+        served
+    }
+}
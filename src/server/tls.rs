@@ -0,0 +1,187 @@
+use super::{AbortOnDrop, AcceptBackoff};
+use crate::mcp::McpServer;
+use anyhow::{Context, Result};
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// How long a client gets to complete the TLS handshake before it is dropped
+pub const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build an acceptor from a PEM certificate chain, leaf first, and the PEM
+/// private key that goes with it. With `client_ca`, a PEM bundle of CA
+/// certificates, every client must present a certificate issued by one of
+/// them, and the handshake fails for clients that don't.
+pub fn load_tls_acceptor(cert: &Path, key: &Path, client_ca: Option<&Path>) -> Result<TlsAcceptor> {
+    let certs = load_certs(cert)?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .with_context(|| format!("Failed to read TLS private key from {}", key.display()))?;
+
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+    let builder = match client_ca {
+        Some(client_ca) => {
+            let mut roots = RootCertStore::empty();
+            for ca in load_certs(client_ca)? {
+                roots
+                    .add(ca)
+                    .with_context(|| format!("Invalid CA certificate in {}", client_ca.display()))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(certs, key)
+        .context("TLS private key does not match the certificate")?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read TLS certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in {}", path.display());
+    }
+    Ok(certs)
+}
+
+/// The common name on the certificate a client authenticated with, if any
+pub(super) fn client_common_name(stream: &TlsStream<TcpStream>) -> Option<String> {
+    let leaf = stream.get_ref().1.peer_certificates()?.first()?;
+    let (_, cert) = X509Certificate::from_der(leaf.as_ref()).ok()?;
+    let common_name = cert.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(common_name.to_string())
+}
+
+/// Grant a new session for a client that presented a certificate for
+/// `common_name` the scope of the permission profile that `client_profiles`
+/// maps the name to, only the tools `profile_tools` lists for it, and only
+/// the resources under the prefixes `profile_resources` lists for it
+pub(super) fn client_session(mut server: McpServer, common_name: Option<&str>) -> McpServer {
+    if let Some(common_name) = common_name {
+        match server.config.client_profiles.get(common_name) {
+            Some(profile) => {
+                info!("Client {} authenticated with profile {}", common_name, profile);
+                if let Some(tools) = server.config.profile_tools.get(profile) {
+                    server.tool_registry.retain_tools(|name| tools.iter().any(|tool| tool == name));
+                }
+                if let Some(prefixes) = server.config.profile_resources.get(profile) {
+                    server.resource_registry.retain_uris(prefixes);
+                }
+                server.session.scopes = vec![profile.clone()];
+            }
+            None => info!("Client {} authenticated without a profile", common_name),
+        }
+    }
+    server
+}
+
+/// The peer of a connection accepted by `TlsListener`, available to
+/// handlers as `ConnectInfo<TlsPeer>`
+#[derive(Debug, Clone)]
+pub(super) struct TlsPeer {
+    pub addr: SocketAddr,
+    /// Common name of the client's certificate, under client authentication
+    pub common_name: Option<String>,
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for TlsPeer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+/// Complete the server side of a handshake on a freshly accepted connection
+pub(super) async fn handshake(acceptor: &TlsAcceptor, stream: TcpStream) -> std::io::Result<TlsStream<TcpStream>> {
+    tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+}
+
+/// A listener for `axum::serve` that hands over connections once their TLS
+/// handshake is done.
+///
+/// Handshakes run on their own tasks, so a client that connects and stalls
+/// holds up no one else; connections whose handshake fails are dropped
+/// before any HTTP is read.
+pub(super) struct TlsListener {
+    local_addr: SocketAddr,
+    handshaken: mpsc::Receiver<(TlsStream<TcpStream>, TlsPeer)>,
+    _accepting: AbortOnDrop,
+}
+
+impl TlsListener {
+    pub(super) fn new(listener: TcpListener, acceptor: TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (handshaken_tx, handshaken) = mpsc::channel(16);
+        let accepting = tokio::spawn(async move {
+            let mut backoff = AcceptBackoff::default();
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => {
+                        backoff.succeeded();
+                        accepted
+                    }
+                    Err(e) => {
+                        warn!("Failed to accept TCP connection: {}", e);
+                        backoff.failed().await;
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let handshaken_tx = handshaken_tx.clone();
+                tokio::spawn(async move {
+                    match handshake(&acceptor, stream).await {
+                        Ok(stream) => {
+                            let common_name = client_common_name(&stream);
+                            let _ = handshaken_tx.send((stream, TlsPeer { addr: peer, common_name })).await;
+                        }
+                        Err(e) => debug!("TLS handshake with {} failed: {}", peer, e),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            local_addr,
+            handshaken,
+            _accepting: AbortOnDrop(accepting.abort_handle()),
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = TlsPeer;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.handshaken.recv().await {
+            Some(accepted) => accepted,
+            // The accepting task only ends when the listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(TlsPeer {
+            addr: self.local_addr,
+            common_name: None,
+        })
+    }
+}
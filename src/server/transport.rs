@@ -0,0 +1,218 @@
+use super::{Framing, WireFormat};
+use anyhow::Result;
+use std::sync::{Arc, OnceLock};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::debug;
+
+/// A connection to one client that `StdioServer::serve_transport` can run
+/// the request loop over, e.g. a message queue or an in-memory channel.
+///
+/// It splits into a source of incoming messages, read on a task of its
+/// own, and a sink that replies and notifications are sent to.
+pub trait Transport {
+    type Source: MessageSource;
+    type Sink: MessageSink;
+
+    fn split(self) -> (Self::Source, Self::Sink);
+}
+
+/// Where a client's messages come from
+#[async_trait::async_trait]
+pub trait MessageSource: Send + 'static {
+    /// The next message as JSON text, or `None` once the client has gone.
+    /// Text that isn't JSON is answered with a parse error.
+    async fn receive(&mut self) -> std::io::Result<Option<String>>;
+}
+
+/// Where replies and notifications for a client go
+#[async_trait::async_trait]
+pub trait MessageSink: Send {
+    /// Deliver one message: a response, a batch of responses or a notification
+    async fn send(&mut self, message: serde_json::Value) -> Result<()>;
+}
+
+/// Framed JSON-RPC over a pair of byte streams, as spoken on stdin/stdout
+/// and over TCP
+pub struct StreamTransport<R, W> {
+    reader: R,
+    writer: W,
+    format: WireFormat,
+}
+
+impl<R, W> StreamTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send,
+{
+    pub fn new(reader: R, writer: W, format: WireFormat) -> Self {
+        Self { reader, writer, format }
+    }
+}
+
+impl<R, W> Transport for StreamTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send,
+{
+    type Source = StreamSource<R>;
+    type Sink = StreamSink<W>;
+
+    fn split(self) -> (Self::Source, Self::Sink) {
+        // Settled by the first message when the framing is auto-detected,
+        // and answered in kind
+        let detected = Arc::new(OnceLock::new());
+        let source = StreamSource {
+            reader: BufReader::new(self.reader),
+            framing: self.format.framing,
+            detected: detected.clone(),
+        };
+        let sink = StreamSink {
+            writer: self.writer,
+            format: self.format,
+            detected,
+        };
+        (source, sink)
+    }
+}
+
+pub struct StreamSource<R> {
+    reader: BufReader<R>,
+    framing: Framing,
+    detected: Arc<OnceLock<Framing>>,
+}
+
+#[async_trait::async_trait]
+impl<R: AsyncRead + Unpin + Send + 'static> MessageSource for StreamSource<R> {
+    async fn receive(&mut self) -> std::io::Result<Option<String>> {
+        let message = read_message(&mut self.reader, &mut self.framing).await;
+        if self.framing != Framing::Auto {
+            let _ = self.detected.set(self.framing);
+        }
+        message
+    }
+}
+
+pub struct StreamSink<W> {
+    writer: W,
+    format: WireFormat,
+    detected: Arc<OnceLock<Framing>>,
+}
+
+#[async_trait::async_trait]
+impl<W: AsyncWrite + Unpin + Send> MessageSink for StreamSink<W> {
+    async fn send(&mut self, message: serde_json::Value) -> Result<()> {
+        let format = WireFormat {
+            framing: self.detected.get().copied().unwrap_or(self.format.framing),
+            ..self.format
+        };
+        self.writer.write_all(&format.encode(&message)?).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+/// Upper bound on a single message, whether a `Content-Length` frame or a
+/// line, and on each header line before a frame
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+fn too_large(what: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{} exceeds the {} byte limit", what, MAX_FRAME_BYTES),
+    )
+}
+
+/// The next line, or `None` at end of input. Unlike `read_line` this stops
+/// buffering at `MAX_FRAME_BYTES`, so a peer that never sends a newline
+/// can't exhaust memory.
+async fn read_bounded_line<R>(reader: &mut R) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    let read = (&mut *reader).take(MAX_FRAME_BYTES as u64 + 1).read_until(b'\n', &mut line).await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read > MAX_FRAME_BYTES {
+        return Err(too_large("Line"));
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Read the next message body, or `None` at end of input. `Auto` framing
+/// is replaced by the one detected from this first message.
+async fn read_message<R>(reader: &mut R, framing: &mut Framing) -> std::io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    if *framing == Framing::Auto {
+        *framing = detect_framing(reader).await?;
+        debug!("Detected {:?} framing", framing);
+    }
+    match framing {
+        // Only left undetected at end of input
+        Framing::Auto | Framing::Ndjson => loop {
+            let Some(line) = read_bounded_line(reader).await? else {
+                return Ok(None);
+            };
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                return Ok(Some(trimmed.to_string()));
+            }
+        },
+        Framing::ContentLength => {
+            let mut content_length = None;
+            loop {
+                let Some(header) = read_bounded_line(reader).await? else {
+                    return Ok(None);
+                };
+                let header = header.trim();
+                if header.is_empty() {
+                    // Tolerate blank lines between frames
+                    if content_length.is_some() {
+                        break;
+                    }
+                    continue;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse::<usize>().ok();
+                    }
+                }
+            }
+            
+            let length = content_length.unwrap_or_default();
+            if length > MAX_FRAME_BYTES {
+                return Err(too_large(&format!("Frame of {} bytes", length)));
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await?;
+            String::from_utf8(body)
+                .map(Some)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }
+    }
+}
+
+/// Peek past leading whitespace: a message that opens with JSON is
+/// newline-delimited, and anything else is taken for a `Content-Length`
+/// header
+async fn detect_framing<R>(reader: &mut R) -> std::io::Result<Framing>
+where
+    R: AsyncBufRead + Unpin,
+{
+    loop {
+        let buffer = reader.fill_buf().await?;
+        if buffer.is_empty() {
+            return Ok(Framing::Auto);
+        }
+        if let Some(&first) = buffer.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            return Ok(if matches!(first, b'{' | b'[') { Framing::Ndjson } else { Framing::ContentLength });
+        }
+        let whitespace = buffer.len();
+        reader.consume(whitespace);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::client::ClientHandle;
+use crate::mcp::McpServer;
+use crate::resources::ResourceSubscriptions;
+use crate::types::{ClientCapabilities, ClientInfo, Root};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// What the connected client negotiated during `initialize`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// Protocol version agreed with the client, which may be older than
+    /// the one it requested
+    pub protocol_version: Option<String>,
+    pub client_info: Option<ClientInfo>,
+    pub client_capabilities: Option<ClientCapabilities>,
+    /// Permission scopes granted to the client by its transport, such as
+    /// the profile mapped from a TLS client certificate
+    pub scopes: Vec<String>,
+    /// Resource URIs the client is subscribed to
+    pub subscriptions: Vec<String>,
+    /// The roots the client last listed, which file tools stay inside
+    pub roots: Vec<Root>,
+}
+
+/// Sessions a `SessionManager` holds unless configured otherwise
+pub const DEFAULT_MAX_SESSIONS: usize = 1000;
+
+/// The sessions of clients connected over a network transport, by id.
+///
+/// Each session is its own copy of the template server, with its own
+/// initialized flag, negotiated protocol version, capabilities and client
+/// info. The copies share the template's tools: registries hold their
+/// handlers, stores and request counters behind `Arc`, so a session is cheap
+/// to open and every client sees the same tool state. What a session sends
+/// its client is its own, though: progress, log entries at the level it
+/// set, scheduled tasks and resource subscriptions go to that client only.
+#[derive(Clone)]
+pub struct SessionManager {
+    template: Arc<McpServer>,
+    sessions: Arc<std::sync::Mutex<HashMap<String, Entry>>>,
+    max_sessions: usize,
+}
+
+struct Entry {
+    session: Arc<Mutex<McpServer>>,
+    /// When the session was opened or last looked up
+    last_active: Instant,
+}
+
+impl SessionManager {
+    pub fn new(template: Arc<McpServer>) -> Self {
+        Self {
+            template,
+            sessions: Arc::default(),
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+
+    /// How many sessions may be open at once; `insert` refuses more
+    pub fn set_max_sessions(&mut self, max_sessions: usize) {
+        self.max_sessions = max_sessions;
+    }
+
+    /// The server every session starts as a copy of
+    pub fn template(&self) -> &McpServer {
+        &self.template
+    }
+
+    /// A fresh copy of the template, not yet registered, with its own
+    /// notifier, logger, task scheduler, resource subscriptions and handle
+    /// for requests to its client
+    pub fn fresh(&self) -> McpServer {
+        let notifier = self.template.notifier.session();
+        McpServer {
+            tool_registry: self.template.tool_registry.clone().with_notifier(notifier.clone()),
+            subscriptions: ResourceSubscriptions::new(notifier.clone()),
+            logger: self.template.logger.session(notifier.clone()),
+            client: ClientHandle::new(),
+            notifier,
+            ..(*self.template).clone()
+        }
+    }
+
+    /// Register a session and return the id it is known by, or `None` if
+    /// the maximum number of sessions are open already
+    pub fn insert(&self, session: Arc<Mutex<McpServer>>) -> Option<String> {
+        let mut sessions = self.lock();
+        if sessions.len() >= self.max_sessions {
+            return None;
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        let entry = Entry {
+            session,
+            last_active: Instant::now(),
+        };
+        sessions.insert(id.clone(), entry);
+        Some(id)
+    }
+
+    /// Look up a session, which counts as activity on it
+    pub fn get(&self, id: &str) -> Option<Arc<Mutex<McpServer>>> {
+        let mut sessions = self.lock();
+        let entry = sessions.get_mut(id)?;
+        entry.last_active = Instant::now();
+        Some(entry.session.clone())
+    }
+
+    /// End a session; returns whether it was open
+    pub fn remove(&self, id: &str) -> bool {
+        self.lock().remove(id).is_some()
+    }
+
+    /// End the sessions not looked up for `idle_timeout`, returning their ids
+    pub fn remove_idle(&self, idle_timeout: Duration) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.lock().retain(|id, entry| {
+            let idle = entry.last_active.elapsed() >= idle_timeout;
+            if idle {
+                removed.push(id.clone());
+            }
+            !idle
+        });
+        removed
+    }
+
+    /// Ids of the open sessions, in no particular order
+    pub fn ids(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
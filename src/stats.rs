@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Distinct method or tool names counted one by one. Both come from
+/// clients, so names past the limit are lumped together under `OTHER`.
+const MAX_NAMES: usize = 256;
+const OTHER: &str = "(other)";
+
+/// Request and tool call counters for the whole server.
+///
+/// Clones share the same counters, so the server, its registry and the
+/// `get_server_stats` tool all see one set of numbers.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug)]
+struct Counters {
+    started: Instant,
+    requests: AtomicU64,
+    tool_calls: AtomicU64,
+    tool_errors: AtomicU64,
+    by_method: Mutex<BTreeMap<String, u64>>,
+    by_tool: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+/// Calls to one tool, and how many of them failed
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct ToolStats {
+    pub calls: u64,
+    pub errors: u64,
+}
+
+/// The counters at one moment, as returned by `get_server_stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsSnapshot {
+    pub uptime_seconds: u64,
+    pub requests: u64,
+    pub requests_by_method: BTreeMap<String, u64>,
+    pub tool_calls: u64,
+    pub tool_errors: u64,
+    pub tools: BTreeMap<String, ToolStats>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Counters {
+                started: Instant::now(),
+                requests: AtomicU64::new(0),
+                tool_calls: AtomicU64::new(0),
+                tool_errors: AtomicU64::new(0),
+                by_method: Mutex::new(BTreeMap::new()),
+                by_tool: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// Count one request or notification
+    pub fn record_request(&self, method: &str) {
+        self.inner.requests.fetch_add(1, Ordering::Relaxed);
+        let mut by_method = self.inner.by_method.lock().unwrap_or_else(|e| e.into_inner());
+        *entry(&mut by_method, method) += 1;
+    }
+
+    /// Count one finished tool call. `failed` covers error results as well
+    /// as calls rejected or abandoned by the registry.
+    pub fn record_tool_call(&self, tool: &str, failed: bool) {
+        self.inner.tool_calls.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.inner.tool_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut by_tool = self.inner.by_tool.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = entry(&mut by_tool, tool);
+        stats.calls += 1;
+        if failed {
+            stats.errors += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            uptime_seconds: self.inner.started.elapsed().as_secs(),
+            requests: self.inner.requests.load(Ordering::Relaxed),
+            requests_by_method: self.inner.by_method.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            tool_calls: self.inner.tool_calls.load(Ordering::Relaxed),
+            tool_errors: self.inner.tool_errors.load(Ordering::Relaxed),
+            tools: self.inner.by_tool.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        }
+    }
+}
+
+/// The counter for `name`, or the shared `OTHER` counter once `MAX_NAMES`
+/// names are taken
+fn entry<'a, T: Default>(counters: &'a mut BTreeMap<String, T>, name: &str) -> &'a mut T {
+    let name = if counters.contains_key(name) || counters.len() < MAX_NAMES { name } else { OTHER };
+    counters.entry(name.to_string()).or_default()
+}
@@ -1,65 +1,608 @@
-use crate::types::{CallToolRequest, CallToolResponse, Tool, ToolContent};
+use crate::client::ClientHandle;
+use crate::config::ServerConfig;
+use crate::notifications::{Logger, Notifier, Progress};
+use crate::sandbox::Sandbox;
+use crate::stats::Stats;
+use crate::types::{error_codes, CallToolRequest, CallToolResponse, ResourceContents, Tool, ToolContent};
 use anyhow::Result;
+use base64::Engine;
+use futures::FutureExt;
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::process::Command;
-use tracing::debug;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, warn};
 
+mod benchmark;
+mod cache_key;
+mod chart;
+mod chunk_text;
+mod extract_strings;
+mod fetch_url;
+mod find_duplicates;
+mod find_todos;
+mod humanize_time;
+mod limited_command;
+mod list_cron;
+mod openai;
+mod output_page;
+mod ownership_audit;
+mod parse_compose;
+mod parse_dockerfile;
+mod parse_url;
+mod process_info;
+mod project_overview;
+mod query_jsonl;
+mod read_file_smart;
+mod recently_modified;
+mod resource_limits;
+mod result_store;
+mod scaffold;
+mod schedule;
+mod schema;
+mod semver;
+mod server_stats;
+mod suggest;
+mod summarize_file;
+mod tcp_ping;
+mod tls_inspect;
+mod transform_text;
+pub(crate) mod walk;
+mod write_file;
+mod xattr;
+
+use self::semver::SemverTool;
+use self::xattr::XattrTool;
+use cache_key::CacheKeyTool;
+use chart::ChartTool;
+use chunk_text::ChunkTextTool;
+use extract_strings::ExtractStringsTool;
+use find_duplicates::FindDuplicatesTool;
+use find_todos::FindTodosTool;
+use humanize_time::HumanizeTimeTool;
+use limited_command::LimitedCommandTool;
+use list_cron::ListCronTool;
+use output_page::OutputPageTool;
+use ownership_audit::OwnershipAuditTool;
+use parse_compose::ParseComposeTool;
+use parse_dockerfile::ParseDockerfileTool;
+use parse_url::ParseUrlTool;
+use process_info::ProcessInfoTool;
+use project_overview::ProjectOverviewTool;
+use query_jsonl::QueryJsonlTool;
+use read_file_smart::ReadFileSmartTool;
+use recently_modified::RecentlyModifiedTool;
+use resource_limits::ResourceLimitsTool;
+use result_store::{paged_response, ResultStore, DEFAULT_PAGE_SIZE};
+use scaffold::ScaffoldTool;
+use server_stats::ServerStatsTool;
+use summarize_file::SummarizeFileTool;
+use schedule::{CancelTaskTool, ListTasksTool, ScheduleTaskTool, TaskScheduler};
+
+pub use benchmark::BenchmarkReport;
+pub use fetch_url::FetchUrlTool;
+pub use schedule::TASK_RESULT_NOTIFICATION;
+use tcp_ping::TcpPingTool;
+use tls_inspect::TlsInspectTool;
+use transform_text::TransformTextTool;
+use walk::{walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use write_file::WriteFileTool;
+
+/// Calls that fail at the protocol level, surfaced to clients as JSON-RPC
+/// errors. Failures a tool reports itself with `CallToolResponse::error`
+/// stay in the result so the caller can recover from them.
+#[derive(Debug, thiserror::Error)]
+pub enum ToolCallError {
+    #[error("Tool '{0}' not found")]
+    NotFound(String),
+    #[error("Invalid arguments for tool '{tool}': {message}")]
+    InvalidArguments { tool: String, message: String },
+    #[error("Tool '{tool}' failed: {message}")]
+    Failed { tool: String, message: String },
+    #[error("Tool '{tool}' panicked: {message}")]
+    Panicked { tool: String, message: String },
+}
+
+impl ToolCallError {
+    /// The JSON-RPC error code clients see
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => error_codes::TOOL_NOT_FOUND,
+            Self::InvalidArguments { .. } => error_codes::INVALID_PARAMS,
+            Self::Failed { .. } | Self::Panicked { .. } => error_codes::INTERNAL_ERROR,
+        }
+    }
+    
+    pub fn tool(&self) -> &str {
+        match self {
+            Self::NotFound(tool)
+            | Self::InvalidArguments { tool, .. }
+            | Self::Failed { tool, .. }
+            | Self::Panicked { tool, .. } => tool,
+        }
+    }
+}
+
+/// How long a tool call may run before it is abandoned, unless overridden
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Tools `self_benchmark` may call unless configured otherwise. Benchmarks
+/// call a tool thousands of times, so only side-effect-free tools belong here.
+pub const DEFAULT_BENCHMARK_TOOLS: &[&str] = &["echo"];
+
+/// Method of the notification sent when tools are registered or removed
+pub const TOOL_LIST_CHANGED_NOTIFICATION: &str = "notifications/tools/list_changed";
+
+/// Tools are held behind `Arc`, so clones of the registry (and of the
+/// server that owns it) keep every registration, including custom tools.
+/// The maps are shared until a clone changes them, so cloning is cheap.
+#[derive(Clone)]
 pub struct ToolRegistry {
-    tools: HashMap<String, Box<dyn ToolHandler>>,
+    tools: Arc<HashMap<String, Arc<dyn ToolHandler>>>,
+    description_overrides: Arc<HashMap<String, String>>,
+    default_timeout: Duration,
+    timeouts: Arc<HashMap<String, Duration>>,
+    suggestions: Arc<HashMap<String, Vec<String>>>,
+    stats: Stats,
+    notifier: Notifier,
+    /// The tools `schedule_task` may run
+    schedulable: Arc<HashMap<String, Arc<dyn ToolHandler>>>,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
+        Self::with_config(&ServerConfig::default(), Notifier::new())
+    }
+    
+    pub fn with_config(config: &ServerConfig, notifier: Notifier) -> Self {
+        Self::build(config, notifier, |name| !config.disabled_tools.iter().any(|disabled| disabled == name))
+    }
+    
+    /// Only the named built-in tools, with default settings; names that
+    /// aren't built in are ignored. Embedders can start from a short list,
+    /// e.g. without `execute_command` and `write_file` for a read-only server,
+    /// and register their own tools on top. Hand the registry to
+    /// `McpServer::with_tool_registry` so its notifications reach clients.
+    pub fn with_tools(enabled: &[&str]) -> Self {
+        Self::build(&ServerConfig::default(), Notifier::new(), |name| enabled.contains(&name))
+    }
+    
+    /// The built-in tools `config` allows for which `enabled` returns true
+    fn build(config: &ServerConfig, notifier: Notifier, enabled: impl Fn(&str) -> bool) -> Self {
         let mut registry = Self {
-            tools: HashMap::new(),
+            tools: Arc::default(),
+            description_overrides: Arc::default(),
+            default_timeout: config.tool_timeout.unwrap_or(DEFAULT_TOOL_TIMEOUT),
+            timeouts: Arc::default(),
+            suggestions: Arc::new(suggest::suggestions(&config.tool_suggestions)),
+            stats: Stats::new(),
+            notifier,
+            schedulable: Arc::default(),
         };
+        let mut sandbox = Sandbox::new(config.root.clone());
+        if let Some(dir) = &config.working_dir {
+            sandbox = sandbox.with_working_dir(dir.clone());
+        }
+        let results = ResultStore::default();
+        let allowed = allowed_commands(config);
         
         // Register built-in tools
         registry.register_tool("echo", Box::new(EchoTool));
         registry.register_tool("get_system_info", Box::new(SystemInfoTool));
-        registry.register_tool("list_files", Box::new(ListFilesTool));
-        registry.register_tool("read_file", Box::new(ReadFileTool));
-        registry.register_tool("execute_command", Box::new(ExecuteCommandTool));
+        registry.register_tool("get_server_stats", Box::new(ServerStatsTool::new(registry.stats.clone())));
+        registry.register_tool("list_files", Box::new(ListFilesTool { sandbox: sandbox.clone() }));
+        registry.register_tool("read_file", Box::new(ReadFileTool { sandbox: sandbox.clone() }));
+        registry.register_tool("read_file_smart", Box::new(ReadFileSmartTool::new(sandbox.clone())));
+        registry.register_tool("summarize_file", Box::new(SummarizeFileTool::new(sandbox.clone())));
+        let execute_command = || {
+            let tool = ExecuteCommandTool::with_allowed(allowed.clone()).with_results(results.clone());
+            match sandbox.working_dir() {
+                Some(dir) => tool.with_working_dir(dir.to_path_buf()),
+                None => tool,
+            }
+        };
+        registry.register_tool("execute_command", Box::new(execute_command()));
+        registry.register_tool(
+            "execute_command_limited",
+            Box::new(LimitedCommandTool::new(allowed.clone(), sandbox.working_dir().map(Path::to_path_buf))),
+        );
+        registry.register_tool("output_page", Box::new(OutputPageTool::new(results.clone())));
+        registry.register_tool("transform_text", Box::new(TransformTextTool));
+        registry.register_tool("chunk_text", Box::new(ChunkTextTool));
+        registry.register_tool("chart", Box::new(ChartTool));
+        registry.register_tool("parse_url", Box::new(ParseUrlTool));
+        registry.register_tool("semver", Box::new(SemverTool));
+        registry.register_tool("humanize_time", Box::new(HumanizeTimeTool));
+        registry.register_tool("cache_key", Box::new(CacheKeyTool::new(sandbox.clone())));
+        registry.register_tool("extract_strings", Box::new(ExtractStringsTool::new(sandbox.clone())));
+        registry.register_tool("find_duplicates", Box::new(FindDuplicatesTool::new(sandbox.clone())));
+        registry.register_tool("find_todos", Box::new(FindTodosTool::new(sandbox.clone())));
+        registry.register_tool("ownership_audit", Box::new(OwnershipAuditTool::new(sandbox.clone())));
+        registry.register_tool("parse_compose", Box::new(ParseComposeTool::new(sandbox.clone())));
+        registry.register_tool("parse_dockerfile", Box::new(ParseDockerfileTool::new(sandbox.clone())));
+        registry.register_tool("project_overview", Box::new(ProjectOverviewTool::new(sandbox.clone())));
+        registry.register_tool("query_jsonl", Box::new(QueryJsonlTool::new(sandbox.clone())));
+        registry.register_tool("recently_modified", Box::new(RecentlyModifiedTool::new(sandbox.clone())));
+        registry.register_tool("resource_limits", Box::new(ResourceLimitsTool::new(sandbox.clone())));
+        registry.register_tool("xattr", Box::new(XattrTool::new(sandbox.clone(), config.read_only)));
+        
+        // Tools that create files are withheld in read-only mode
+        if !config.read_only {
+            registry.register_tool("scaffold", Box::new(ScaffoldTool::new(sandbox.clone())));
+            registry.register_tool("write_file", Box::new(WriteFileTool::new(sandbox.clone())));
+        }
+        
+        // Scheduled tasks may re-run commands or re-read files
+        let mut schedulable: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+        schedulable.insert("execute_command".to_string(), Arc::new(execute_command()));
+        schedulable.insert("read_file".to_string(), Arc::new(ReadFileTool { sandbox: sandbox.clone() }));
+        // A disabled tool can't be reached through the scheduler either
+        schedulable.retain(|name, _| enabled(name));
+        registry.schedulable = Arc::new(schedulable);
+        for (name, tool) in registry.scheduler_tools() {
+            Arc::make_mut(&mut registry.tools).insert(name.to_string(), tool);
+        }
+        
+        // Network tools are opt-in
+        if config.enable_network {
+            registry.register_tool("fetch_url", Box::new(FetchUrlTool::new(config.allowed_hosts.clone())));
+            registry.register_tool("tcp_ping", Box::new(TcpPingTool::new(config.allowed_hosts.clone())));
+            registry.register_tool("tls_inspect", Box::new(TlsInspectTool::new(config.allowed_hosts.clone())));
+        }
+        
+        if config.enable_process_info {
+            registry.register_tool("process_info", Box::new(ProcessInfoTool));
+        }
+        
+        // Crontabs reveal what runs on the machine and as whom
+        if config.enable_cron {
+            registry.register_tool("list_cron", Box::new(ListCronTool::new(sandbox.clone())));
+        }
+        
+        for name in &config.disabled_tools {
+            if !registry.tools.contains_key(name) {
+                warn!("Tool '{}' is not registered; nothing to disable", name);
+            }
+        }
+        Arc::make_mut(&mut registry.tools).retain(|name, _| enabled(name));
+        
+        for (name, description) in &config.tool_descriptions {
+            registry.set_description_override(name, description);
+        }
+        for (name, timeout) in &config.tool_timeouts {
+            registry.set_timeout(name, *timeout);
+        }
+        // Scheduled runs are dispatched with the timeouts as they stand now
+        registry.replace_scheduler();
         
         registry
     }
     
+    /// The same tools, sending their notifications on `notifier` and with a
+    /// task scheduler of their own, e.g. for one client's session, whose
+    /// scheduled results must not reach other clients
+    pub(crate) fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = notifier;
+        self.replace_scheduler();
+        self
+    }
+    
+    /// Drop the tools for which `keep` returns false, without telling
+    /// clients, e.g. to narrow a session's tools before it starts. The
+    /// dropped tools can't be reached through `schedule_task` either.
+    pub(crate) fn retain_tools(&mut self, keep: impl Fn(&str) -> bool) {
+        Arc::make_mut(&mut self.tools).retain(|name, _| keep(name));
+        Arc::make_mut(&mut self.schedulable).retain(|name, _| keep(name));
+        self.replace_scheduler();
+    }
+    
+    /// Swap the registered `schedule_task`, `list_tasks` and `cancel_task`
+    /// for ones on a new scheduler
+    fn replace_scheduler(&mut self) {
+        for (name, tool) in self.scheduler_tools() {
+            if let Some(registered) = Arc::make_mut(&mut self.tools).get_mut(name) {
+                *registered = tool;
+            }
+        }
+    }
+    
+    /// `schedule_task`, `list_tasks` and `cancel_task` on a new scheduler
+    /// whose results go out on the registry's notifier
+    fn scheduler_tools(&self) -> [(&'static str, Arc<dyn ToolHandler>); 3] {
+        let runnable = Self {
+            tools: self.schedulable.clone(),
+            ..self.clone()
+        };
+        let scheduler = Arc::new(TaskScheduler::new(runnable, self.notifier.clone()));
+        [
+            ("schedule_task", Arc::new(ScheduleTaskTool::new(scheduler.clone()))),
+            ("list_tasks", Arc::new(ListTasksTool::new(scheduler.clone()))),
+            ("cancel_task", Arc::new(CancelTaskTool::new(scheduler))),
+        ]
+    }
+    
+    /// Replace the description a tool reports in `tools/list` without touching its handler.
+    /// Overrides for tools that aren't registered are ignored.
+    pub fn set_description_override(&mut self, name: &str, description: &str) {
+        if self.tools.contains_key(name) {
+            Arc::make_mut(&mut self.description_overrides).insert(name.to_string(), description.to_string());
+        } else {
+            warn!("Ignoring description override for unknown tool '{}'", name);
+        }
+    }
+    
+    /// Time limit for tools without their own override
+    pub fn set_default_timeout(&mut self, timeout: Duration) {
+        self.default_timeout = timeout;
+    }
+    
+    /// Give one tool a time limit other than the default, e.g. for a known slow tool.
+    /// Timeouts for tools that aren't registered are ignored.
+    pub fn set_timeout(&mut self, name: &str, timeout: Duration) {
+        if self.tools.contains_key(name) {
+            Arc::make_mut(&mut self.timeouts).insert(name.to_string(), timeout);
+        } else {
+            warn!("Ignoring timeout for unknown tool '{}'", name);
+        }
+    }
+    
+    /// Add a tool, replacing any of the same name, and tell clients the
+    /// tool list changed
     pub fn register_tool(&mut self, name: &str, handler: Box<dyn ToolHandler>) {
-        self.tools.insert(name.to_string(), handler);
+        Arc::make_mut(&mut self.tools).insert(name.to_string(), Arc::from(handler));
+        self.notify_list_changed();
+    }
+    
+    /// Register a tool backed by an async closure instead of a `ToolHandler`
+    /// implementation, e.g. `|args| async move { CallToolResponse::text(..) }`
+    pub fn register_tool_fn<F, Fut>(&mut self, name: &str, description: &str, input_schema: Value, call: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CallToolResponse> + Send + 'static,
+    {
+        self.register_tool(name, Box::new(FnTool {
+            description: description.to_string(),
+            input_schema,
+            call,
+        }));
     }
     
+    /// Remove a tool along with its description override and timeout.
+    /// Returns whether the tool was registered.
+    pub fn unregister_tool(&mut self, name: &str) -> bool {
+        Arc::make_mut(&mut self.description_overrides).remove(name);
+        Arc::make_mut(&mut self.timeouts).remove(name);
+        let removed = Arc::make_mut(&mut self.tools).remove(name).is_some();
+        if removed {
+            self.notify_list_changed();
+        }
+        removed
+    }
+    
+    /// Send `notifications/tools/list_changed`. Registering and unregistering
+    /// tools does this already; embedders that change what tools do some
+    /// other way, e.g. reloading a plugin in place, call it when they do.
+    pub fn notify_list_changed(&self) {
+        self.notifier.notify(TOOL_LIST_CHANGED_NOTIFICATION, None);
+    }
+    
+    /// Counters for the calls made through this registry, shared by its clones
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    
+    /// Every registered tool, sorted by name
     pub fn list_tools(&self) -> Vec<Tool> {
-        self.tools.iter().map(|(name, handler)| {
+        let mut tools: Vec<Tool> = self.tools.iter().map(|(name, handler)| {
             Tool {
                 name: name.clone(),
-                description: handler.description(),
+                description: self.description_overrides.get(name)
+                    .cloned()
+                    .unwrap_or_else(|| handler.description()),
                 input_schema: handler.input_schema(),
             }
-        }).collect()
+        }).collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+    
+    /// The registered tools as OpenAI function-calling definitions, sorted by
+    /// name, plus warnings for schema features that had to be dropped
+    pub fn export_openai(&self) -> (Vec<Value>, Vec<String>) {
+        openai::export(&self.list_tools())
+    }
+    
+    /// Tools commonly useful after `name`, limited to registered ones.
+    /// Tools without suggestions, including unknown ones, get none.
+    pub fn suggest_next(&self, name: &str) -> Vec<String> {
+        self.suggestions
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|next| self.tools.contains_key(next.as_str()))
+            .cloned()
+            .collect()
+    }
+    
+    /// Call a tool `iterations` times (default 100), at most `concurrency`
+    /// at once (default 1), and summarize the latencies. Both are clamped.
+    pub async fn benchmark(
+        &self,
+        name: &str,
+        arguments: Value,
+        iterations: Option<usize>,
+        concurrency: Option<usize>,
+    ) -> Result<BenchmarkReport> {
+        self.benchmark_with_context(name, arguments, iterations, concurrency, ToolContext::default()).await
+    }
+    
+    /// `benchmark` with every call made with `context`, e.g. on behalf of
+    /// the client that asked for it
+    pub async fn benchmark_with_context(
+        &self,
+        name: &str,
+        arguments: Value,
+        iterations: Option<usize>,
+        concurrency: Option<usize>,
+        context: ToolContext,
+    ) -> Result<BenchmarkReport> {
+        if !self.tools.contains_key(name) {
+            anyhow::bail!("Tool '{}' not found", name);
+        }
+        Ok(benchmark::run(self, name, arguments, iterations, concurrency, context).await)
     }
     
     pub async fn call_tool(&self, request: CallToolRequest) -> Result<CallToolResponse> {
+        self.call_tool_with_context(request, ToolContext::default()).await
+    }
+    
+    /// Call a tool with handles for reporting progress and logging to the
+    /// client; log entries are tagged with the tool's name
+    pub async fn call_tool_with_context(&self, request: CallToolRequest, context: ToolContext) -> Result<CallToolResponse> {
+        let name = request.name.clone();
+        let result = self.dispatch(request, context).await;
+        let failed = result.as_ref().map_or(true, |response| response.is_error == Some(true));
+        self.stats.record_tool_call(&name, failed);
+        result
+    }
+    
+    async fn dispatch(&self, request: CallToolRequest, context: ToolContext) -> Result<CallToolResponse> {
         debug!("Calling tool: {}", request.name);
         
         if let Some(handler) = self.tools.get(&request.name) {
-            handler.call(request.arguments.unwrap_or(json!({}))).await
+            // Reject malformed arguments before any handler sees them
+            let arguments = request.arguments.unwrap_or(json!({}));
+            if let Err(e) = schema::validate(&handler.input_schema(), &arguments) {
+                debug!("Rejected arguments for tool '{}': {}", request.name, e);
+                return Err(ToolCallError::InvalidArguments {
+                    tool: request.name,
+                    message: e.to_string(),
+                }
+                .into());
+            }
+            
+            // A panicking handler fails its own call instead of unwinding
+            // through the server loop
+            let context = ToolContext {
+                logger: context.logger.named(&request.name),
+                ..context
+            };
+            let client = context.client.clone();
+            let call = AssertUnwindSafe(client.run_for(handler.call_with_context(arguments, context)));
+            let timeout = self.timeouts.get(&request.name).copied().unwrap_or(self.default_timeout);
+            match tokio::time::timeout(timeout, call.catch_unwind()).await {
+                // Dropping the handler's future cancels it; child processes
+                // are spawned with kill_on_drop so they die with it
+                Err(_) => {
+                    warn!("Tool '{}' timed out after {:?}", request.name, timeout);
+                    Ok(CallToolResponse::error(format!(
+                        "Tool '{}' timed out after {} seconds",
+                        request.name,
+                        timeout.as_secs_f64()
+                    )))
+                }
+                Ok(Ok(Ok(response))) => Ok(response),
+                Ok(Ok(Err(e))) => {
+                    warn!("Tool '{}' failed: {:#}", request.name, e);
+                    Err(ToolCallError::Failed {
+                        tool: request.name,
+                        message: format!("{:#}", e),
+                    }
+                    .into())
+                }
+                Ok(Err(payload)) => {
+                    let message = panic_message(payload.as_ref());
+                    error!("Tool '{}' panicked: {}", request.name, message);
+                    Err(ToolCallError::Panicked {
+                        tool: request.name,
+                        message,
+                    }
+                    .into())
+                }
+            }
         } else {
-            Ok(CallToolResponse {
-                content: vec![ToolContent::Text {
-                    text: format!("Tool '{}' not found", request.name),
-                }],
-                is_error: Some(true),
-            })
+            Err(ToolCallError::NotFound(request.name).into())
         }
     }
 }
 
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+/// Whether network tools may connect to `host`; an empty allowlist permits any host
+fn is_host_allowed(allowed_hosts: &[String], host: &str) -> bool {
+    allowed_hosts.is_empty()
+        || allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
 #[async_trait::async_trait]
 pub trait ToolHandler: Send + Sync {
     fn description(&self) -> String;
     fn input_schema(&self) -> Value;
     async fn call(&self, args: Value) -> Result<CallToolResponse>;
+    
+    /// Like `call`, with a reporter for progress updates on long-running
+    /// work. Tools that report progress override this; the default ignores
+    /// the reporter.
+    async fn call_with_progress(&self, args: Value, _progress: Progress) -> Result<CallToolResponse> {
+        self.call(args).await
+    }
+    
+    /// Like `call_with_progress`, with a logger and a handle for requests
+    /// to the client as well. Tools that log to or query the client
+    /// override this; the default passes progress on.
+    async fn call_with_context(&self, args: Value, context: ToolContext) -> Result<CallToolResponse> {
+        self.call_with_progress(args, context.progress).await
+    }
+}
+
+/// Handles a tool gets for talking to the client while it runs
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    pub progress: Progress,
+    pub logger: Logger,
+    /// For requests to the client, such as asking its model to summarize
+    pub client: ClientHandle,
+}
+
+/// A tool registered with `ToolRegistry::register_tool_fn`
+struct FnTool<F> {
+    description: String,
+    input_schema: Value,
+    call: F,
+}
+
+#[async_trait::async_trait]
+impl<F, Fut> ToolHandler for FnTool<F>
+where
+    F: Fn(Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = CallToolResponse> + Send + 'static,
+{
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+    
+    fn input_schema(&self) -> Value {
+        self.input_schema.clone()
+    }
+    
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        Ok((self.call)(args).await)
+    }
 }
 
 // Echo tool - simple example
@@ -135,7 +678,13 @@ impl ToolHandler for SystemInfoTool {
 }
 
 // List files tool
-struct ListFilesTool;
+struct ListFilesTool {
+    sandbox: Sandbox,
+}
+
+/// Depth a recursive listing descends to unless told otherwise
+const LIST_FILES_DEFAULT_MAX_DEPTH: usize = 10;
+const LIST_FILES_MAX_ENTRIES: usize = 10_000;
 
 #[async_trait::async_trait]
 impl ToolHandler for ListFilesTool {
@@ -151,6 +700,26 @@ impl ToolHandler for ListFilesTool {
                     "type": "string",
                     "description": "Directory path to list",
                     "default": "."
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "description": "Also list the contents of subdirectories",
+                    "default": false
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "How many directory levels a recursive listing descends; 1 lists only the directory itself",
+                    "minimum": 1,
+                    "default": LIST_FILES_DEFAULT_MAX_DEPTH
+                },
+                "pattern": {
+                    "type": "string",
+                    "description": "Only list entries whose relative path matches this glob (e.g. '*.rs')"
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip paths matched by .gitignore files",
+                    "default": true
                 }
             }
         })
@@ -160,47 +729,108 @@ impl ToolHandler for ListFilesTool {
         let path = args.get("path")
             .and_then(|v| v.as_str())
             .unwrap_or(".");
+        
+        let recursive = args.get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        
+        let max_depth = if recursive {
+            args.get("max_depth")
+                .and_then(|v| v.as_u64())
+                .map(|d| d as usize)
+                .unwrap_or(LIST_FILES_DEFAULT_MAX_DEPTH)
+        } else {
+            1
+        };
+        
+        let respect_gitignore = args.get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        
+        let matcher = match args.get("pattern").and_then(|v| v.as_str()) {
+            Some(pattern) => match globset::Glob::new(pattern) {
+                Ok(glob) => Some(glob.compile_matcher()),
+                Err(e) => return Ok(CallToolResponse::error(format!("Invalid glob: {}", e))),
+            },
+            None => None,
+        };
             
-        match std::fs::read_dir(path) {
-            Ok(entries) => {
-                let mut files = Vec::new();
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let name = entry.file_name().to_string_lossy().to_string();
-                        let file_type = if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-                            "directory"
-                        } else {
-                            "file"
-                        };
-                        files.push(format!("{} ({})", name, file_type));
-                    }
-                }
-                
-                let result = if files.is_empty() {
-                    "Directory is empty".to_string()
-                } else {
-                    format!("Files in {}:\n{}", path, files.join("\n"))
-                };
-                
-                Ok(CallToolResponse {
-                    content: vec![ToolContent::Text { text: result }],
-                    is_error: None,
-                })
-            }
-            Err(e) => {
-                Ok(CallToolResponse {
-                    content: vec![ToolContent::Text {
-                        text: format!("Error listing directory: {}", e),
-                    }],
-                    is_error: Some(true),
-                })
-            }
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        
+        // Unreadable subdirectories are skipped, but the listed one must open
+        if let Err(e) = std::fs::read_dir(&resolved) {
+            return Ok(CallToolResponse::error(format!("Error listing directory: {}", e)));
         }
+        
+        let options = WalkOptions {
+            max_depth,
+            max_entries: LIST_FILES_MAX_ENTRIES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore,
+        };
+        let walked = walk(&resolved, &options);
+        
+        let entries: Vec<Value> = walked
+            .entries
+            .iter()
+            .filter(|entry| matcher.as_ref().is_none_or(|m| m.is_match(&entry.relative)))
+            .map(|entry| {
+                let file_type = entry.metadata.file_type();
+                let mut listed = json!({
+                    "path": entry.relative,
+                    "type": if file_type.is_symlink() {
+                        "symlink"
+                    } else if file_type.is_dir() {
+                        "directory"
+                    } else {
+                        "file"
+                    },
+                });
+                if file_type.is_file() {
+                    listed["size"] = json!(entry.metadata.len());
+                }
+                listed
+            })
+            .collect();
+        
+        let result = json!({
+            "path": path,
+            "total": entries.len(),
+            "entries": entries,
+            "truncated": walked.truncated,
+        });
+        
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
     }
 }
 
 // Read file tool
-struct ReadFileTool;
+struct ReadFileTool {
+    sandbox: Sandbox,
+}
+
+/// Content for a file that isn't text: images inline, anything else as an
+/// embedded resource, both base64-encoded
+fn binary_content(path: &std::path::Path, bytes: &[u8]) -> ToolContent {
+    let mime_type = crate::resources::mime_type_for(path).unwrap_or("application/octet-stream");
+    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+    if mime_type.starts_with("image/") {
+        return ToolContent::Image {
+            data,
+            mime_type: mime_type.to_string(),
+        };
+    }
+    ToolContent::Resource {
+        resource: ResourceContents::Blob {
+            uri: url::Url::from_file_path(path).map(String::from).unwrap_or_else(|_| path.display().to_string()),
+            mime_type: Some(mime_type.to_string()),
+            blob: data,
+        },
+    }
+}
 
 #[async_trait::async_trait]
 impl ToolHandler for ReadFileTool {
@@ -220,6 +850,21 @@ impl ToolHandler for ReadFileTool {
                     "type": "integer",
                     "description": "Maximum file size to read in bytes",
                     "default": 1048576
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["text", "base64"],
+                    "description": "Force text or base64 output; by default files that aren't UTF-8 text are returned as base64"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Read a slice starting at this byte instead of the whole file, so files larger than max_size can be paged through",
+                    "minimum": 0
+                },
+                "length": {
+                    "type": "integer",
+                    "description": "Bytes to read from offset, at most max_size (default: max_size)",
+                    "minimum": 0
                 }
             },
             "required": ["path"]
@@ -227,6 +872,10 @@ impl ToolHandler for ReadFileTool {
     }
     
     async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        self.call_with_context(args, ToolContext::default()).await
+    }
+    
+    async fn call_with_context(&self, args: Value, context: ToolContext) -> Result<CallToolResponse> {
         let path = args.get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
@@ -234,8 +883,23 @@ impl ToolHandler for ReadFileTool {
         let max_size = args.get("max_size")
             .and_then(|v| v.as_u64())
             .unwrap_or(1048576); // 1MB default
+        
+        let encoding = args.get("encoding").and_then(|v| v.as_str());
+        
+        let offset = args.get("offset").and_then(|v| v.as_u64());
+        let length = args.get("length").and_then(|v| v.as_u64());
             
-        match std::fs::metadata(path) {
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        
+        if offset.is_some() || length.is_some() {
+            let length = length.unwrap_or(max_size).min(max_size);
+            return read_file_slice(path, &resolved, offset.unwrap_or(0), length, encoding);
+        }
+        
+        match std::fs::metadata(&resolved) {
             Ok(metadata) => {
                 if metadata.len() > max_size {
                     return Ok(CallToolResponse {
@@ -246,7 +910,23 @@ impl ToolHandler for ReadFileTool {
                     });
                 }
                 
-                match std::fs::read_to_string(path) {
+                let bytes = match read_with_progress(&resolved, metadata.len(), &context.progress) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return Ok(CallToolResponse::error(format!("Error reading file: {}", e))),
+                };
+                
+                // Text unless forced to base64, or detected as binary without being forced to text
+                let text = match String::from_utf8(bytes) {
+                    Ok(text) if encoding == Some("text") => Ok(text),
+                    Ok(text) if encoding != Some("base64") && !walk::looks_binary(text.as_bytes()) => Ok(text),
+                    Ok(text) => Err(text.into_bytes()),
+                    Err(e) if encoding == Some("text") => {
+                        return Ok(CallToolResponse::error(format!("Error reading file: {}", e)));
+                    }
+                    Err(e) => Err(e.into_bytes()),
+                };
+                
+                match text {
                     Ok(content) => {
                         Ok(CallToolResponse {
                             content: vec![ToolContent::Text {
@@ -255,12 +935,15 @@ impl ToolHandler for ReadFileTool {
                             is_error: None,
                         })
                     }
-                    Err(e) => {
+                    Err(bytes) => {
                         Ok(CallToolResponse {
-                            content: vec![ToolContent::Text {
-                                text: format!("Error reading file: {}", e),
-                            }],
-                            is_error: Some(true),
+                            content: vec![
+                                ToolContent::Text {
+                                    text: format!("Contents of {} ({} bytes, base64-encoded):", path, bytes.len()),
+                                },
+                                binary_content(&resolved, &bytes),
+                            ],
+                            is_error: None,
                         })
                     }
                 }
@@ -277,8 +960,160 @@ impl ToolHandler for ReadFileTool {
     }
 }
 
+/// Bytes between progress reports while reading a large file or download
+const PROGRESS_STEP_BYTES: usize = 256 * 1024;
+
+/// `std::fs::read`, reporting progress every `PROGRESS_STEP_BYTES` of the
+/// `len` expected when the client asked for it
+fn read_with_progress(path: &Path, len: u64, progress: &Progress) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    
+    if !progress.is_enabled() {
+        return std::fs::read(path);
+    }
+    let mut file = std::fs::File::open(path)?;
+    let mut bytes = Vec::with_capacity(len as usize);
+    let mut chunk = vec![0; PROGRESS_STEP_BYTES];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        progress.report(bytes.len() as f64, Some(len.max(bytes.len() as u64) as f64), None);
+    }
+}
+
+/// Bytes `offset..offset + length` of a file, with a JSON block describing
+/// where the slice sits in the file followed by the slice itself. Text
+/// slices are narrowed to whole UTF-8 characters, so `next_offset` is where
+/// the following read should start.
+fn read_file_slice(
+    path: &str,
+    resolved: &std::path::Path,
+    offset: u64,
+    length: u64,
+    encoding: Option<&str>,
+) -> Result<CallToolResponse> {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut bytes = Vec::new();
+    let read = std::fs::File::open(resolved).and_then(|mut file| {
+        let total_size = file.metadata()?.len();
+        file.seek(SeekFrom::Start(offset))?;
+        file.take(length).read_to_end(&mut bytes)?;
+        Ok(total_size)
+    });
+    let total_size = match read {
+        Ok(total_size) => total_size,
+        Err(e) => return Ok(CallToolResponse::error(format!("Error reading file: {}", e))),
+    };
+    
+    // Skip a character cut by the start of the slice and drop one cut by its end
+    let lead = bytes.iter().take(3).take_while(|b| (**b & 0xC0) == 0x80).count();
+    let text = match std::str::from_utf8(&bytes[lead..]) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[lead..lead + e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+    let text = match text {
+        Some(text) if encoding == Some("text") => Some(text),
+        Some(text) if encoding != Some("base64") && !walk::looks_binary(text.as_bytes()) => Some(text),
+        _ if encoding == Some("text") => {
+            return Ok(CallToolResponse::error(format!("Bytes {}..{} of {} are not UTF-8 text", offset, offset + bytes.len() as u64, path)));
+        }
+        _ => None,
+    };
+    
+    let (start, len) = match text {
+        Some(text) => (offset + lead as u64, text.len() as u64),
+        None => (offset, bytes.len() as u64),
+    };
+    let next_offset = start + len;
+    let metadata = json!({
+        "path": path,
+        "offset": start,
+        "length": len,
+        "total_size": total_size,
+        "next_offset": next_offset,
+        "has_more": next_offset < total_size,
+        "encoding": if text.is_some() { "text" } else { "base64" },
+    });
+    let slice = match text {
+        Some(text) => ToolContent::Text { text: text.to_string() },
+        None => binary_content(resolved, &bytes),
+    };
+    
+    Ok(CallToolResponse {
+        content: vec![
+            ToolContent::Text {
+                text: serde_json::to_string_pretty(&metadata)?,
+            },
+            slice,
+        ],
+        is_error: None,
+    })
+}
+
+/// Commands that command-running tools may execute unless configured otherwise
+pub const DEFAULT_ALLOWED_COMMANDS: &[&str] = &["echo", "date", "whoami", "pwd", "ls", "cat", "head", "tail", "wc"];
+
+/// The configured allowlist, falling back to `DEFAULT_ALLOWED_COMMANDS`
+fn allowed_commands(config: &ServerConfig) -> Vec<String> {
+    if config.allowed_commands.is_empty() {
+        DEFAULT_ALLOWED_COMMANDS.iter().map(|c| c.to_string()).collect()
+    } else {
+        config.allowed_commands.clone()
+    }
+}
+
+/// The friendly refusal for a command outside the allowlist
+fn command_not_allowed(command: &str, allowed: &[String]) -> CallToolResponse {
+    CallToolResponse::error(format!(
+        "Command '{}' is not allowed. Allowed commands: {}",
+        command,
+        allowed.join(", ")
+    ))
+}
+
+/// How often a running command reports progress
+const COMMAND_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 // Execute command tool (with safety restrictions)
-struct ExecuteCommandTool;
+pub struct ExecuteCommandTool {
+    allowed: Vec<String>,
+    results: ResultStore,
+    working_dir: Option<PathBuf>,
+}
+
+impl Default for ExecuteCommandTool {
+    fn default() -> Self {
+        Self::with_allowed(DEFAULT_ALLOWED_COMMANDS.iter().map(|c| c.to_string()).collect())
+    }
+}
+
+impl ExecuteCommandTool {
+    /// Only run the commands in `allowed`
+    pub fn with_allowed(allowed: Vec<String>) -> Self {
+        Self {
+            allowed,
+            results: ResultStore::default(),
+            working_dir: None,
+        }
+    }
+    
+    /// Run commands in `dir` instead of the server's current directory
+    pub fn with_working_dir(mut self, dir: PathBuf) -> Self {
+        self.working_dir = Some(dir);
+        self
+    }
+    
+    /// Keep paginated output where `output_page` can find it
+    fn with_results(mut self, results: ResultStore) -> Self {
+        self.results = results;
+        self
+    }
+}
 
 #[async_trait::async_trait]
 impl ToolHandler for ExecuteCommandTool {
@@ -300,6 +1135,16 @@ impl ToolHandler for ExecuteCommandTool {
                         "type": "string"
                     },
                     "description": "Command arguments"
+                },
+                "paginate": {
+                    "type": "boolean",
+                    "description": "Return large output in pages fetched with output_page",
+                    "default": false
+                },
+                "page_size": {
+                    "type": "integer",
+                    "description": "Maximum page size in bytes when paginating",
+                    "default": DEFAULT_PAGE_SIZE
                 }
             },
             "required": ["command"]
@@ -307,21 +1152,17 @@ impl ToolHandler for ExecuteCommandTool {
     }
     
     async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        self.call_with_progress(args, Progress::none()).await
+    }
+    
+    async fn call_with_progress(&self, args: Value, progress: Progress) -> Result<CallToolResponse> {
         let command = args.get("command")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Command is required"))?;
             
         // Safety: Only allow specific safe commands
-        let allowed_commands = vec!["echo", "date", "whoami", "pwd", "ls", "cat", "head", "tail", "wc"];
-        
-        if !allowed_commands.contains(&command) {
-            return Ok(CallToolResponse {
-                content: vec![ToolContent::Text {
-                    text: format!("Command '{}' is not allowed. Allowed commands: {}", 
-                        command, allowed_commands.join(", ")),
-                }],
-                is_error: Some(true),
-            });
+        if !self.allowed.iter().any(|allowed| allowed == command) {
+            return Ok(command_not_allowed(command, &self.allowed));
         }
         
         let cmd_args: Vec<String> = args.get("args")
@@ -329,7 +1170,28 @@ impl ToolHandler for ExecuteCommandTool {
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
             .unwrap_or_default();
             
-        match Command::new(command).args(&cmd_args).output() {
+        let mut cmd = tokio::process::Command::new(command);
+        cmd.args(&cmd_args).kill_on_drop(true);
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        
+        // The output isn't known until the command exits, so report how
+        // long it has been running instead
+        let output = cmd.output();
+        tokio::pin!(output);
+        let started = std::time::Instant::now();
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + COMMAND_PROGRESS_INTERVAL, COMMAND_PROGRESS_INTERVAL);
+        let output = loop {
+            tokio::select! {
+                output = &mut output => break output,
+                _ = ticks.tick() => {
+                    let seconds = started.elapsed().as_secs();
+                    progress.report(seconds as f64, None, Some(&format!("{} running for {}s", command, seconds)));
+                }
+            }
+        };
+        match output {
             Ok(output) => {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 let stderr = String::from_utf8_lossy(&output.stderr);
@@ -342,6 +1204,20 @@ impl ToolHandler for ExecuteCommandTool {
                         command, cmd_args.join(" "), stdout)
                 };
                 
+                let paginate = args.get("paginate")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if paginate {
+                    let page_size = args.get("page_size")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(DEFAULT_PAGE_SIZE as u64) as usize;
+                    let mut response = paged_response(&self.results, None, &result, 0, page_size);
+                    if !output.status.success() {
+                        response.is_error = Some(true);
+                    }
+                    return Ok(response);
+                }
+                
                 Ok(CallToolResponse {
                     content: vec![ToolContent::Text { text: result }],
                     is_error: if output.status.success() { None } else { Some(true) },
@@ -0,0 +1,93 @@
+use super::{ToolContext, ToolRegistry};
+use crate::types::CallToolRequest;
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 100;
+const MAX_ITERATIONS: usize = 10_000;
+const MAX_CONCURRENCY: usize = 64;
+
+/// Latency summary for repeated calls of one tool
+#[derive(Debug, Serialize)]
+pub struct BenchmarkReport {
+    pub tool: String,
+    pub iterations: usize,
+    pub concurrency: usize,
+    /// Calls that failed or returned `is_error`
+    pub errors: usize,
+    pub total_ms: f64,
+    pub throughput_per_sec: f64,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+    millis(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub(crate) async fn run(
+    registry: &ToolRegistry,
+    tool: &str,
+    arguments: Value,
+    iterations: Option<usize>,
+    concurrency: Option<usize>,
+    context: ToolContext,
+) -> BenchmarkReport {
+    let iterations = iterations.unwrap_or(DEFAULT_ITERATIONS).clamp(1, MAX_ITERATIONS);
+    let concurrency = concurrency.unwrap_or(1).clamp(1, MAX_CONCURRENCY);
+
+    let started = Instant::now();
+    let results: Vec<(Duration, bool)> = futures::stream::iter(0..iterations)
+        .map(|_| {
+            let request = CallToolRequest {
+                name: tool.to_string(),
+                arguments: Some(arguments.clone()),
+            };
+            let context = context.clone();
+            async move {
+                let call_started = Instant::now();
+                let result = registry.call_tool_with_context(request, context).await;
+                let ok = matches!(result, Ok(response) if response.is_error != Some(true));
+                (call_started.elapsed(), ok)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    let total = started.elapsed();
+
+    let errors = results.iter().filter(|(_, ok)| !ok).count();
+    let mut latencies: Vec<Duration> = results.into_iter().map(|(latency, _)| latency).collect();
+    latencies.sort();
+    let sum: Duration = latencies.iter().sum();
+
+    BenchmarkReport {
+        tool: tool.to_string(),
+        iterations,
+        concurrency,
+        errors,
+        total_ms: millis(total),
+        throughput_per_sec: iterations as f64 / total.as_secs_f64().max(f64::EPSILON),
+        min_ms: latencies.first().copied().map(millis).unwrap_or_default(),
+        mean_ms: millis(sum) / iterations as f64,
+        p50_ms: percentile(&latencies, 50.0),
+        p95_ms: percentile(&latencies, 95.0),
+        p99_ms: percentile(&latencies, 99.0),
+        max_ms: latencies.last().copied().map(millis).unwrap_or_default(),
+    }
+}
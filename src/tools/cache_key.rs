@@ -0,0 +1,139 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+const MAX_INPUTS: usize = 1000;
+
+// Content-addressed cache key tool
+pub(crate) struct CacheKeyTool {
+    sandbox: Sandbox,
+}
+
+impl CacheKeyTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// SHA-256 of a file's contents, read in fixed-size blocks
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn string_list<'a>(args: &'a Value, key: &str) -> Vec<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default()
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for CacheKeyTool {
+    fn description(&self) -> String {
+        "Compute a stable SHA-256 cache key from file contents and literal strings".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "files": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files whose contents contribute to the key"
+                },
+                "values": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Literal strings that contribute to the key"
+                },
+                "ordered": {
+                    "type": "boolean",
+                    "description": "Whether input order affects the key",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let files = string_list(&args, "files");
+        let values = string_list(&args, "values");
+        let ordered = args.get("ordered")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if files.is_empty() && values.is_empty() {
+            return Ok(CallToolResponse::error("Provide at least one file or value"));
+        }
+        if files.len() + values.len() > MAX_INPUTS {
+            return Ok(CallToolResponse::error(format!(
+                "Too many inputs (max: {})",
+                MAX_INPUTS
+            )));
+        }
+
+        let mut inputs = Vec::new();
+        for file in files {
+            let path = match self.sandbox.resolve(file) {
+                Ok(path) => path,
+                Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+            };
+            match hash_file(&path) {
+                Ok(hash) => inputs.push(json!({ "kind": "file", "input": file, "hash": hash })),
+                Err(e) => {
+                    return Ok(CallToolResponse::error(format!(
+                        "Error reading '{}': {}",
+                        file, e
+                    )));
+                }
+            }
+        }
+        for value in values {
+            let hash = hex::encode(Sha256::digest(value.as_bytes()));
+            inputs.push(json!({ "kind": "value", "input": value, "hash": hash }));
+        }
+
+        // Each input contributes "kind:hash"; unordered keys sort these first
+        let mut parts: Vec<String> = inputs
+            .iter()
+            .map(|input| {
+                format!(
+                    "{}:{}",
+                    input["kind"].as_str().unwrap_or_default(),
+                    input["hash"].as_str().unwrap_or_default()
+                )
+            })
+            .collect();
+        if !ordered {
+            parts.sort();
+        }
+        let mut hasher = Sha256::new();
+        for part in &parts {
+            hasher.update(part.as_bytes());
+            hasher.update(b"\n");
+        }
+
+        let result = json!({
+            "key": hex::encode(hasher.finalize()),
+            "ordered": ordered,
+            "inputs": inputs,
+        });
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
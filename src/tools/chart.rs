@@ -0,0 +1,191 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const DEFAULT_WIDTH: usize = 40;
+const MAX_WIDTH: usize = 200;
+const LINE_HEIGHT: usize = 10;
+const MAX_POINTS: usize = 10_000;
+
+// ASCII chart tool
+pub(crate) struct ChartTool;
+
+/// Average `values` into at most `width` buckets
+fn resample(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    (0..width)
+        .map(|i| {
+            let start = i * values.len() / width;
+            let end = ((i + 1) * values.len() / width).max(start + 1);
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect()
+}
+
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+        (lo.min(*v), hi.max(*v))
+    })
+}
+
+/// Position of `value` within `[min, max]` scaled to `0..=steps`
+fn scale(value: f64, min: f64, max: f64, steps: usize) -> usize {
+    if max > min {
+        (((value - min) / (max - min)) * steps as f64).round() as usize
+    } else {
+        steps / 2
+    }
+}
+
+fn sparkline(values: &[f64], width: usize) -> String {
+    let values = resample(values, width);
+    let (min, max) = bounds(&values);
+    values
+        .iter()
+        .map(|v| SPARK_CHARS[scale(*v, min, max, SPARK_CHARS.len() - 1)])
+        .collect()
+}
+
+fn bar_chart(values: &[f64], labels: &[String], width: usize) -> String {
+    let max = values.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let label = labels.get(i).cloned().unwrap_or_default();
+            let length = if max > 0.0 {
+                ((value.abs() / max) * width as f64).round() as usize
+            } else {
+                0
+            };
+            let bar = if *value < 0.0 { "░" } else { "█" }.repeat(length);
+            format!("{:<label_width$} | {} {}", label, bar, value)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn line_chart(values: &[f64], width: usize) -> String {
+    let values = resample(values, width);
+    let (min, max) = bounds(&values);
+    let rows: Vec<usize> = values
+        .iter()
+        .map(|v| scale(*v, min, max, LINE_HEIGHT - 1))
+        .collect();
+
+    let axis_width = format!("{}", max).len().max(format!("{}", min).len());
+    let mut lines = Vec::with_capacity(LINE_HEIGHT + 1);
+    for row in (0..LINE_HEIGHT).rev() {
+        let label = if row == LINE_HEIGHT - 1 {
+            format!("{}", max)
+        } else if row == 0 {
+            format!("{}", min)
+        } else {
+            String::new()
+        };
+        let cells: String = rows
+            .iter()
+            .map(|r| if *r == row { '*' } else { ' ' })
+            .collect();
+        lines.push(format!("{:>axis_width$} |{}", label, cells.trim_end()));
+    }
+    lines.push(format!("{:>axis_width$} +{}", "", "-".repeat(rows.len())));
+    lines.join("\n")
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ChartTool {
+    fn description(&self) -> String {
+        "Render a numeric series as an ASCII bar chart, line chart or sparkline".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "values": {
+                    "type": "array",
+                    "items": { "type": "number" },
+                    "description": "Numeric series to plot"
+                },
+                "type": {
+                    "type": "string",
+                    "enum": ["bar", "line", "sparkline"],
+                    "description": "Chart type",
+                    "default": "bar"
+                },
+                "labels": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Labels for each value (bar charts)"
+                },
+                "width": {
+                    "type": "integer",
+                    "description": "Maximum chart width in characters",
+                    "default": DEFAULT_WIDTH
+                }
+            },
+            "required": ["values"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let raw = args.get("values")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Values are required"))?;
+
+        let values: Vec<f64> = raw.iter().filter_map(|v| v.as_f64()).collect();
+        if values.is_empty() {
+            return Ok(CallToolResponse::error("Cannot chart an empty series"));
+        }
+        if values.len() != raw.len() || values.iter().any(|v| !v.is_finite()) {
+            return Ok(CallToolResponse::error("All values must be finite numbers"));
+        }
+        if values.len() > MAX_POINTS {
+            return Ok(CallToolResponse::error(format!(
+                "Too many values ({}, max: {})",
+                values.len(),
+                MAX_POINTS
+            )));
+        }
+
+        let chart_type = args.get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("bar");
+
+        let width = args.get("width")
+            .and_then(|v| v.as_u64())
+            .map(|w| w as usize)
+            .unwrap_or(DEFAULT_WIDTH)
+            .clamp(1, MAX_WIDTH);
+
+        let labels: Vec<String> = args.get("labels")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let chart = match chart_type {
+            "sparkline" => sparkline(&values, width),
+            "bar" => bar_chart(&values, &labels, width),
+            "line" => line_chart(&values, width),
+            other => {
+                return Ok(CallToolResponse::error(format!(
+                    "Unknown chart type '{}'. Expected bar, line or sparkline",
+                    other
+                )));
+            }
+        };
+
+        Ok(CallToolResponse::text(chart))
+    }
+}
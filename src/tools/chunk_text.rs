@@ -0,0 +1,151 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const MAX_CHUNKS: usize = 1000;
+const DEFAULT_CHUNK_SIZE: u64 = 1000;
+const DEFAULT_OVERLAP: u64 = 100;
+
+// Document chunking tool for embedding pipelines
+pub(crate) struct ChunkTextTool;
+
+/// Byte offsets where a chunk may start or end, in ascending order from 0 to `text.len()`.
+///
+/// In token mode a token that ends inside a multi-byte character is merged
+/// with the following one so every boundary is a valid char boundary.
+fn unit_boundaries(text: &str, mode: &str) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    match mode {
+        "tokens" => {
+            let bpe = tiktoken_rs::cl100k_base_singleton();
+            let mut offset = 0;
+            for token in bpe.encode_ordinary(text) {
+                offset += bpe.decode_bytes(&[token]).map(|b| b.len()).unwrap_or(0);
+                if offset < text.len() && text.is_char_boundary(offset) {
+                    boundaries.push(offset);
+                }
+            }
+        }
+        _ => {
+            boundaries.extend(text.char_indices().skip(1).map(|(i, _)| i));
+        }
+    }
+    if !text.is_empty() {
+        boundaries.push(text.len());
+    }
+    boundaries
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ChunkTextTool {
+    fn description(&self) -> String {
+        "Split text into overlapping chunks (by characters or tokens) for embedding".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Text to split"
+                },
+                "chunk_size": {
+                    "type": "integer",
+                    "description": "Chunk size in units of the selected mode",
+                    "minimum": 1,
+                    "default": DEFAULT_CHUNK_SIZE
+                },
+                "overlap": {
+                    "type": "integer",
+                    "description": "Units shared between consecutive chunks; must be smaller than chunk_size",
+                    "minimum": 0,
+                    "default": DEFAULT_OVERLAP
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["chars", "tokens"],
+                    "description": "Count characters, or cl100k_base tokens",
+                    "default": "chars"
+                }
+            },
+            "required": ["text"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let text = args.get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Text is required"))?;
+
+        let chunk_size = args.get("chunk_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_CHUNK_SIZE) as usize;
+
+        let overlap = args.get("overlap")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_OVERLAP.min(chunk_size as u64 / 2)) as usize;
+
+        let mode = args.get("mode")
+            .and_then(|v| v.as_str())
+            .unwrap_or("chars");
+
+        if mode != "chars" && mode != "tokens" {
+            return Ok(CallToolResponse::error(format!(
+                "Unknown mode '{}'. Expected chars or tokens",
+                mode
+            )));
+        }
+        if chunk_size == 0 || overlap >= chunk_size {
+            return Ok(CallToolResponse::error(
+                "chunk_size must be positive and larger than overlap",
+            ));
+        }
+
+        let boundaries = unit_boundaries(text, mode);
+        let units = boundaries.len().saturating_sub(1);
+
+        // Character offset of each boundary, for reporting positions in the original text
+        let mut char_offsets = vec![0];
+        for pair in boundaries.windows(2) {
+            let previous = char_offsets[char_offsets.len() - 1];
+            char_offsets.push(previous + text[pair[0]..pair[1]].chars().count());
+        }
+
+        let mut chunks = Vec::new();
+        let mut truncated = false;
+        let mut start = 0;
+        while start < units {
+            if chunks.len() == MAX_CHUNKS {
+                truncated = true;
+                break;
+            }
+            let end = (start + chunk_size).min(units);
+            let (from, to) = (boundaries[start], boundaries[end]);
+            chunks.push(json!({
+                "index": chunks.len(),
+                "start": char_offsets[start],
+                "end": char_offsets[end],
+                "units": end - start,
+                "text": &text[from..to],
+            }));
+            if end == units {
+                break;
+            }
+            start = end - overlap;
+        }
+
+        let result = json!({
+            "mode": mode,
+            "chunk_size": chunk_size,
+            "overlap": overlap,
+            "total_units": units,
+            "chunk_count": chunks.len(),
+            "truncated": truncated,
+            "chunks": chunks,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,220 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::Read;
+
+const DEFAULT_MIN_LENGTH: usize = 4;
+const DEFAULT_MAX_STRINGS: usize = 1000;
+const MAX_STRINGS: usize = 10_000;
+const DEFAULT_MAX_SCAN_BYTES: u64 = 50 * 1024 * 1024;
+/// Longer runs are reported with their full length but shortened text
+const MAX_STRING_CHARS: usize = 1024;
+
+// Printable string extraction tool, like `strings`
+pub(crate) struct ExtractStringsTool {
+    sandbox: Sandbox,
+}
+
+impl ExtractStringsTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Ascii,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    fn unit_size(self) -> usize {
+        match self {
+            Encoding::Ascii => 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        }
+    }
+
+    /// The printable ASCII character a code unit encodes, if any
+    fn printable(self, unit: &[u8]) -> Option<char> {
+        let code = match self {
+            Encoding::Ascii => u16::from(unit[0]),
+            Encoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+            Encoding::Utf16Be => u16::from_be_bytes([unit[0], unit[1]]),
+        };
+        match code {
+            0x09 | 0x20..=0x7e => Some(code as u8 as char),
+            _ => None,
+        }
+    }
+}
+
+/// Collects runs of printable characters as they stream past
+struct Runs {
+    min_length: usize,
+    max_strings: usize,
+    start: u64,
+    length: usize,
+    text: String,
+    strings: Vec<Value>,
+}
+
+impl Runs {
+    fn push(&mut self, offset: u64, c: Option<char>) {
+        match c {
+            Some(c) => {
+                if self.length == 0 {
+                    self.start = offset;
+                }
+                self.length += 1;
+                if self.text.len() < MAX_STRING_CHARS {
+                    self.text.push(c);
+                }
+            }
+            None => self.end(),
+        }
+    }
+
+    fn end(&mut self) {
+        if self.length >= self.min_length && !self.is_full() {
+            self.strings.push(json!({
+                "offset": self.start,
+                "length": self.length,
+                "text": self.text,
+            }));
+        }
+        self.length = 0;
+        self.text.clear();
+    }
+
+    fn is_full(&self) -> bool {
+        self.strings.len() >= self.max_strings
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ExtractStringsTool {
+    fn description(&self) -> String {
+        "Extract runs of printable characters from a binary file, like the strings command".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File to scan"
+                },
+                "min_length": {
+                    "type": "integer",
+                    "description": "Shortest run of printable characters to report",
+                    "minimum": 1,
+                    "default": DEFAULT_MIN_LENGTH
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["ascii", "utf-16le", "utf-16be"],
+                    "description": "Character encoding to look for; UTF-16 finds wide strings common in Windows binaries",
+                    "default": "ascii"
+                },
+                "max_strings": {
+                    "type": "integer",
+                    "description": "Maximum strings to return",
+                    "default": DEFAULT_MAX_STRINGS
+                },
+                "max_scan_bytes": {
+                    "type": "integer",
+                    "description": "Bytes to scan from the start of the file",
+                    "default": DEFAULT_MAX_SCAN_BYTES
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+
+        let min_length = args.get("min_length")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).max(1))
+            .unwrap_or(DEFAULT_MIN_LENGTH);
+
+        let encoding = match args.get("encoding").and_then(|v| v.as_str()).unwrap_or("ascii") {
+            "utf-16le" => Encoding::Utf16Le,
+            "utf-16be" => Encoding::Utf16Be,
+            _ => Encoding::Ascii,
+        };
+
+        let max_strings = args.get("max_strings")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_STRINGS))
+            .unwrap_or(DEFAULT_MAX_STRINGS);
+
+        let max_scan_bytes = args.get("max_scan_bytes")
+            .and_then(|v| v.as_u64())
+            .map(|n| n.min(DEFAULT_MAX_SCAN_BYTES))
+            .unwrap_or(DEFAULT_MAX_SCAN_BYTES);
+
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !resolved.is_file() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a file", path)));
+        }
+        let file = match std::fs::File::open(&resolved) {
+            Ok(file) => file,
+            Err(e) => return Ok(CallToolResponse::error(format!("Error opening file: {}", e))),
+        };
+        let size = file.metadata()?.len();
+
+        let unit_size = encoding.unit_size();
+        let mut reader = file.take(max_scan_bytes);
+        let mut runs = Runs {
+            min_length,
+            max_strings,
+            start: 0,
+            length: 0,
+            text: String::new(),
+            strings: Vec::new(),
+        };
+        let mut buffer = vec![0; 64 * 1024];
+        // A UTF-16 code unit may straddle two reads
+        let mut pending = Vec::with_capacity(unit_size);
+        let mut offset = 0u64;
+        while !runs.is_full() {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            for &byte in &buffer[..read] {
+                pending.push(byte);
+                if pending.len() == unit_size {
+                    runs.push(offset, encoding.printable(&pending));
+                    offset += unit_size as u64;
+                    pending.clear();
+                }
+            }
+        }
+        runs.end();
+
+        let result = json!({
+            "path": path,
+            "encoding": args.get("encoding").and_then(|v| v.as_str()).unwrap_or("ascii"),
+            "strings": runs.strings,
+            "returned": runs.strings.len(),
+            "bytes_scanned": offset + pending.len() as u64,
+            // The string limit was hit or the scan stopped before the end of the file
+            "truncated": runs.is_full() || offset + (pending.len() as u64) < size,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
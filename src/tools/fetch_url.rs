@@ -0,0 +1,243 @@
+use super::walk::looks_binary;
+use super::{is_host_allowed, ToolHandler, PROGRESS_STEP_BYTES};
+use crate::notifications::Progress;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Ports fetched from unless the tool is built with others
+const DEFAULT_ALLOWED_PORTS: &[u16] = &[80, 443];
+const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+const MAX_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+/// HTTP(S) GET tool. Redirects are reported rather than followed, so every
+/// request goes to a URL that passed the scheme, host and port checks.
+/// Hosts that resolve to loopback, private or link-local addresses, such as
+/// cloud metadata endpoints, are refused unless they are allowlisted by name.
+pub struct FetchUrlTool {
+    allowed_hosts: Vec<String>,
+    allowed_ports: Vec<u16>,
+}
+
+impl FetchUrlTool {
+    /// An empty `allowed_hosts` permits any host with public addresses
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self {
+            allowed_hosts,
+            allowed_ports: DEFAULT_ALLOWED_PORTS.to_vec(),
+        }
+    }
+
+    /// Replace the ports that may be fetched from (default: 80 and 443)
+    pub fn with_allowed_ports(mut self, ports: Vec<u16>) -> Self {
+        self.allowed_ports = ports;
+        self
+    }
+}
+
+/// Whether `ip` is only reachable from this machine or its own network:
+/// loopback, private, shared (carrier-grade NAT), link-local and unspecified
+/// addresses, and their IPv6 counterparts
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
+/// The body as text if it is UTF-8 that doesn't look binary. A character
+/// cut in half by truncation is dropped.
+fn body_text(body: &[u8], truncated: bool) -> Option<&str> {
+    if looks_binary(body) {
+        return None;
+    }
+    match std::str::from_utf8(body) {
+        Ok(text) => Some(text),
+        Err(e) if truncated && e.error_len().is_none() => std::str::from_utf8(&body[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FetchUrlTool {
+    fn description(&self) -> String {
+        "Fetch a URL with HTTP GET and return its status, content type and body".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "http or https URL to fetch"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Truncate the body after this many bytes",
+                    "default": DEFAULT_MAX_BYTES,
+                    "maximum": MAX_MAX_BYTES
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "description": "Timeout for the whole request in milliseconds",
+                    "default": DEFAULT_TIMEOUT_MS,
+                    "maximum": MAX_TIMEOUT_MS
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        self.call_with_progress(args, Progress::none()).await
+    }
+
+    async fn call_with_progress(&self, args: Value, progress: Progress) -> Result<CallToolResponse> {
+        let raw_url = args.get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("URL is required"))?;
+
+        let max_bytes = args.get("max_bytes")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_BYTES)
+            .clamp(1, MAX_MAX_BYTES);
+
+        let timeout_ms = args.get("timeout_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_MS)
+            .clamp(1, MAX_TIMEOUT_MS);
+
+        let url = match url::Url::parse(raw_url) {
+            Ok(url) => url,
+            Err(e) => return Ok(CallToolResponse::error(format!("Invalid URL: {}", e))),
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return Ok(CallToolResponse::error(format!(
+                "Scheme '{}' is not allowed; use http or https",
+                url.scheme()
+            )));
+        }
+        let Some(host) = url.host_str() else {
+            return Ok(CallToolResponse::error("URL has no host"));
+        };
+        // IPv6 hosts keep their brackets in the URL but not in the allowlist
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if !is_host_allowed(&self.allowed_hosts, host) {
+            return Ok(CallToolResponse::error(format!(
+                "Host '{}' is not in the allowed host list",
+                host
+            )));
+        }
+        let port = url.port_or_known_default().unwrap_or_default();
+        if !self.allowed_ports.contains(&port) {
+            return Ok(CallToolResponse::error(format!(
+                "Port {} is not allowed. Allowed ports: {}",
+                port,
+                self.allowed_ports.iter().map(u16::to_string).collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        let mut client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_millis(timeout_ms));
+        // Hosts allowlisted by name may be internal; any other must resolve
+        // to public addresses only, and the request is pinned to those
+        // addresses so a second lookup can't swap in an internal one
+        if !self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)) {
+            let addrs: Vec<SocketAddr> = match tokio::net::lookup_host((host, port)).await {
+                Ok(addrs) => addrs.collect(),
+                Err(e) => return Ok(CallToolResponse::error(format!("Could not resolve host '{}': {}", host, e))),
+            };
+            if let Some(addr) = addrs.iter().find(|addr| is_internal(addr.ip())) {
+                return Ok(CallToolResponse::error(format!(
+                    "Host '{}' resolves to the internal address {}; allow it with --allow-host to fetch from it",
+                    host,
+                    addr.ip()
+                )));
+            }
+            client = client.resolve_to_addrs(host, &addrs);
+        }
+        let client = client.build()?;
+        let mut response = match client.get(url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => return Ok(CallToolResponse::error(format!("Request failed: {}", e))),
+        };
+
+        let status = response.status();
+        let header = |name: reqwest::header::HeaderName| {
+            response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+        };
+        let content_type = header(reqwest::header::CONTENT_TYPE);
+        let location = header(reqwest::header::LOCATION);
+
+        // Only as much of the body as will be read
+        let expected = response.content_length().map(|length| length.min(max_bytes) as f64);
+        let mut body = Vec::new();
+        let mut truncated = false;
+        let mut reported = 0;
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => return Ok(CallToolResponse::error(format!("Error reading response body: {}", e))),
+            };
+            let room = max_bytes as usize - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+            if body.len() - reported >= PROGRESS_STEP_BYTES {
+                reported = body.len();
+                progress.report(reported as f64, expected, None);
+            }
+        }
+
+        let (encoding, content) = match body_text(&body, truncated) {
+            Some(text) => ("text", text.to_string()),
+            None => ("base64", base64::engine::general_purpose::STANDARD.encode(&body)),
+        };
+
+        let mut result = json!({
+            "url": url.as_str(),
+            "status": status.as_u16(),
+            "content_type": content_type,
+            "bytes": body.len(),
+            "truncated": truncated,
+            "encoding": encoding,
+            "body": content,
+        });
+        if truncated {
+            result["note"] = json!(format!("Body truncated at {} bytes; raise max_bytes to read more", max_bytes));
+        }
+        if let Some(location) = location.filter(|_| status.is_redirection()) {
+            result["location"] = json!(location);
+        }
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,140 @@
+use super::cache_key::hash_file;
+use super::walk::{walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const DEFAULT_MAX_DEPTH: usize = 10;
+const MAX_ENTRIES: usize = 50_000;
+const DEFAULT_MIN_SIZE: u64 = 1;
+const MAX_GROUPS: usize = 500;
+
+// Duplicate file detection tool
+pub(crate) struct FindDuplicatesTool {
+    sandbox: Sandbox,
+}
+
+impl FindDuplicatesTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FindDuplicatesTool {
+    fn description(&self) -> String {
+        "Find files with identical content below a directory, grouped by SHA-256".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to scan",
+                    "default": "."
+                },
+                "min_size": {
+                    "type": "integer",
+                    "description": "Ignore files smaller than this many bytes",
+                    "default": DEFAULT_MIN_SIZE
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Maximum directory depth to descend",
+                    "default": DEFAULT_MAX_DEPTH
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let min_size = args.get("min_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MIN_SIZE);
+
+        let max_depth = args.get("max_depth")
+            .and_then(|v| v.as_u64())
+            .map(|d| d as usize)
+            .unwrap_or(DEFAULT_MAX_DEPTH);
+
+        let root = match self.sandbox.resolve(path) {
+            Ok(root) => root,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !root.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a directory", path)));
+        }
+
+        let options = WalkOptions {
+            max_depth,
+            max_entries: MAX_ENTRIES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore: false,
+        };
+        let walked = walk(&root, &options);
+
+        // Only files sharing a size can share content, so hash nothing else
+        let mut by_size: HashMap<u64, Vec<_>> = HashMap::new();
+        let mut files_scanned = 0;
+        for entry in walked.entries {
+            if entry.metadata.is_file() && entry.metadata.len() >= min_size {
+                files_scanned += 1;
+                by_size.entry(entry.metadata.len()).or_default().push(entry);
+            }
+        }
+
+        let mut files_hashed = 0;
+        let mut groups: Vec<(u64, String, Vec<String>)> = Vec::new();
+        for (size, entries) in by_size {
+            if entries.len() < 2 {
+                continue;
+            }
+            let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+            for entry in entries {
+                if let Ok(hash) = hash_file(&entry.path) {
+                    files_hashed += 1;
+                    by_hash.entry(hash).or_default().push(entry.relative);
+                }
+            }
+            for (hash, mut paths) in by_hash {
+                if paths.len() > 1 {
+                    paths.sort();
+                    groups.push((size, hash, paths));
+                }
+            }
+        }
+
+        // Largest reclaimable space first
+        let wasted = |(size, _, paths): &(u64, String, Vec<String>)| size * (paths.len() as u64 - 1);
+        groups.sort_by(|a, b| wasted(b).cmp(&wasted(a)).then_with(|| a.2.cmp(&b.2)));
+        let wasted_bytes: u64 = groups.iter().map(wasted).sum();
+        let total_groups = groups.len();
+
+        let groups: Vec<Value> = groups
+            .into_iter()
+            .take(MAX_GROUPS)
+            .map(|(size, hash, paths)| json!({ "size": size, "sha256": hash, "paths": paths }))
+            .collect();
+
+        let result = json!({
+            "root": root,
+            "groups": groups,
+            "total_groups": total_groups,
+            "wasted_bytes": wasted_bytes,
+            "files_scanned": files_scanned,
+            "files_hashed": files_hashed,
+            "truncated": walked.truncated || total_groups > MAX_GROUPS,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,240 @@
+use super::walk::{looks_binary, walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use globset::Glob;
+use serde_json::{json, Map, Value};
+
+const DEFAULT_TAGS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+const DEFAULT_MAX_DEPTH: usize = 10;
+const MAX_ENTRIES: usize = 50_000;
+const DEFAULT_LIMIT: usize = 500;
+const MAX_LIMIT: usize = 5000;
+/// Larger files are generated or data, not code worth reviewing
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_TEXT_CHARS: usize = 500;
+
+/// Markers that start a comment in common languages
+const COMMENT_MARKERS: &[&str] = &["//", "/*", "#", "--", ";", "<!--"];
+
+// TODO/FIXME comment finder
+pub(crate) struct FindTodosTool {
+    sandbox: Sandbox,
+}
+
+impl FindTodosTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Where the comment on a line starts, if it has one. Lines starting with
+/// `*` are taken to continue a block comment.
+fn comment_start(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start().len();
+    if line.trim_start().starts_with('*') {
+        return Some(indent);
+    }
+    COMMENT_MARKERS.iter().filter_map(|marker| line.find(marker)).min()
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// The first tag appearing as a whole word inside the line's comment, with
+/// an optional `(author)` and the text after it
+fn find_tag<'a>(line: &'a str, tags: &'a [String]) -> Option<(&'a str, Option<&'a str>, &'a str)> {
+    let comment = &line[comment_start(line)?..];
+    let bytes = comment.as_bytes();
+    let (position, tag) = tags
+        .iter()
+        .filter_map(|tag| {
+            comment.match_indices(tag.as_str()).find(|(i, _)| {
+                let before = i.checked_sub(1).map(|b| bytes[b]);
+                let after = bytes.get(i + tag.len()).copied();
+                !before.is_some_and(is_word_byte) && !after.is_some_and(is_word_byte)
+            })
+            .map(|(i, _)| (i, tag.as_str()))
+        })
+        .min_by_key(|(i, _)| *i)?;
+
+    let mut rest = &comment[position + tag.len()..];
+    let mut author = None;
+    if let Some(inner) = rest.strip_prefix('(') {
+        if let Some(end) = inner.find(')') {
+            author = Some(inner[..end].trim());
+            rest = &inner[end + 1..];
+        }
+    }
+    let text = rest
+        .trim_start_matches([':', '-', ' ', '\t'])
+        .trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+    Some((tag, author, text))
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FindTodosTool {
+    fn description(&self) -> String {
+        "Find TODO, FIXME, HACK and XXX comments below a directory, grouped by tag".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to scan",
+                    "default": "."
+                },
+                "tags": {
+                    "type": "array",
+                    "items": {
+                        "type": "string"
+                    },
+                    "description": "Tags to look for",
+                    "default": DEFAULT_TAGS
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only scan files whose relative path matches this glob (e.g. '**/*.rs')"
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Maximum directory depth to descend",
+                    "default": DEFAULT_MAX_DEPTH
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of comments to return",
+                    "default": DEFAULT_LIMIT
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip paths matched by .gitignore files",
+                    "default": true
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let tags: Vec<String> = args.get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str()).filter(|t| !t.is_empty()).map(str::to_string).collect())
+            .unwrap_or_else(|| DEFAULT_TAGS.iter().map(|t| t.to_string()).collect());
+        if tags.is_empty() {
+            return Ok(CallToolResponse::error("At least one tag is required"));
+        }
+
+        let max_depth = args.get("max_depth")
+            .and_then(|v| v.as_u64())
+            .map(|d| d as usize)
+            .unwrap_or(DEFAULT_MAX_DEPTH);
+
+        let limit = args.get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|l| l as usize)
+            .unwrap_or(DEFAULT_LIMIT)
+            .clamp(1, MAX_LIMIT);
+
+        let respect_gitignore = args.get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let matcher = match args.get("glob").and_then(|v| v.as_str()) {
+            Some(pattern) => match Glob::new(pattern) {
+                Ok(glob) => Some(glob.compile_matcher()),
+                Err(e) => return Ok(CallToolResponse::error(format!("Invalid glob: {}", e))),
+            },
+            None => None,
+        };
+
+        let root = match self.sandbox.resolve(path) {
+            Ok(root) => root,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !root.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a directory", path)));
+        }
+
+        let options = WalkOptions {
+            max_depth,
+            max_entries: MAX_ENTRIES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore,
+        };
+        let walked = walk(&root, &options);
+
+        let mut by_tag: Map<String, Value> = tags.iter().map(|t| (t.clone(), json!([]))).collect();
+        let mut found = 0;
+        let mut files_scanned = 0;
+        let mut skipped_binary = 0;
+        let mut hit_limit = false;
+        let files = walked
+            .entries
+            .iter()
+            .filter(|entry| entry.metadata.is_file() && entry.metadata.len() <= MAX_FILE_BYTES)
+            .filter(|entry| matcher.as_ref().is_none_or(|m| m.is_match(&entry.relative)));
+        'files: for entry in files {
+            let Ok(bytes) = std::fs::read(&entry.path) else {
+                continue;
+            };
+            if looks_binary(&bytes) {
+                skipped_binary += 1;
+                continue;
+            }
+            files_scanned += 1;
+
+            let text = String::from_utf8_lossy(&bytes);
+            for (index, line) in text.lines().enumerate() {
+                let Some((tag, author, comment)) = find_tag(line, &tags) else {
+                    continue;
+                };
+                if found == limit {
+                    hit_limit = true;
+                    break 'files;
+                }
+                found += 1;
+                let mut todo = json!({
+                    "path": entry.relative,
+                    "line": index + 1,
+                    "tag": tag,
+                    "text": comment.chars().take(MAX_TEXT_CHARS).collect::<String>(),
+                });
+                if let Some(author) = author {
+                    todo["author"] = json!(author);
+                }
+                if let Some(Value::Array(entries)) = by_tag.get_mut(tag) {
+                    entries.push(todo);
+                }
+            }
+        }
+
+        let counts: Map<String, Value> = by_tag
+            .iter()
+            .map(|(tag, entries)| (tag.clone(), json!(entries.as_array().map_or(0, Vec::len))))
+            .collect();
+
+        let result = json!({
+            "root": root,
+            "total": found,
+            "counts": counts,
+            "by_tag": by_tag,
+            "files_scanned": files_scanned,
+            "skipped_binary": skipped_binary,
+            "truncated": walked.truncated || hit_limit,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,124 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 9999-12-31T23:59:59Z, the last time RFC 3339 can write
+const MAX_UNIX_SECONDS: u64 = 253_402_300_799;
+
+const UNITS: &[(&str, u64)] = &[
+    ("year", 365 * 24 * 60 * 60),
+    ("month", 30 * 24 * 60 * 60),
+    ("week", 7 * 24 * 60 * 60),
+    ("day", 24 * 60 * 60),
+    ("hour", 60 * 60),
+    ("minute", 60),
+    ("second", 1),
+];
+
+// Relative time description tool
+pub(crate) struct HumanizeTimeTool;
+
+/// An RFC 3339 timestamp or a Unix time in seconds
+fn parse_timestamp(value: &Value) -> Result<SystemTime, String> {
+    let time = match value {
+        Value::String(text) => humantime::parse_rfc3339_weak(text.trim())
+            .or_else(|_| text.trim().parse::<u64>().map(|secs| UNIX_EPOCH + Duration::from_secs(secs)))
+            .map_err(|_| format!("'{}' is not an RFC 3339 timestamp or Unix seconds", text))?,
+        _ => match value.as_u64() {
+            Some(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+            None => return Err("Unix timestamps must be whole seconds after 1970".to_string()),
+        },
+    };
+    check_range(time)
+}
+
+fn check_range(time: SystemTime) -> Result<SystemTime, String> {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) if since_epoch.as_secs() <= MAX_UNIX_SECONDS => Ok(time),
+        _ => Err("Time must be between 1970 and the end of 9999".to_string()),
+    }
+}
+
+/// "3 hours ago" or "in 2 days", counting whole units of the largest unit that fits
+fn describe(delta: Duration, future: bool) -> String {
+    let seconds = delta.as_secs();
+    let Some((unit, size)) = UNITS.iter().find(|(_, size)| seconds >= *size) else {
+        return "just now".to_string();
+    };
+    let count = seconds / size;
+    let plural = if count == 1 { "" } else { "s" };
+    if future {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for HumanizeTimeTool {
+    fn description(&self) -> String {
+        "Describe a timestamp or an offset in seconds relative to now, e.g. '3 hours ago' or 'in 2 days'".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "timestamp": {
+                    "type": ["string", "integer"],
+                    "description": "RFC 3339 timestamp or Unix seconds to describe"
+                },
+                "seconds": {
+                    "type": "number",
+                    "description": "Offset from now instead of a timestamp; negative is in the past"
+                },
+                "relative_to": {
+                    "type": ["string", "integer"],
+                    "description": "Reference time as an RFC 3339 timestamp or Unix seconds (default: now)"
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let now = match args.get("relative_to") {
+            Some(reference) => match parse_timestamp(reference) {
+                Ok(time) => time,
+                Err(e) => return Ok(CallToolResponse::error(format!("Invalid relative_to: {}", e))),
+            },
+            None => SystemTime::now(),
+        };
+
+        let time = match (args.get("timestamp"), args.get("seconds").and_then(|v| v.as_f64())) {
+            (Some(timestamp), None) => parse_timestamp(timestamp),
+            (None, Some(seconds)) => Duration::try_from_secs_f64(seconds.abs())
+                .ok()
+                .and_then(|offset| if seconds < 0.0 { now.checked_sub(offset) } else { now.checked_add(offset) })
+                .ok_or_else(|| format!("{} seconds is out of range", seconds))
+                .and_then(check_range),
+            _ => Err("Provide exactly one of timestamp or seconds".to_string()),
+        };
+        let time = match time {
+            Ok(time) => time,
+            Err(e) => return Ok(CallToolResponse::error(e)),
+        };
+
+        let (delta, future) = match time.duration_since(now) {
+            Ok(ahead) => (ahead, true),
+            Err(e) => (e.duration(), false),
+        };
+        let delta_seconds = delta.as_secs() as i64;
+
+        let result = json!({
+            "relative": describe(delta, future),
+            "absolute": humantime::format_rfc3339_seconds(time).to_string(),
+            "unix_seconds": time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            "delta_seconds": if future { delta_seconds } else { -delta_seconds },
+            "duration": humantime::format_duration(Duration::from_secs(delta.as_secs())).to_string(),
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
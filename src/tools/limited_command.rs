@@ -0,0 +1,226 @@
+use super::{command_not_allowed, ToolHandler};
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_MAX_CPU_SECONDS: u64 = 10;
+const DEFAULT_MAX_MEMORY_MB: u64 = 512;
+const DEFAULT_MAX_OUTPUT_BYTES: u64 = 1024 * 1024;
+
+/// The highest caps a client may ask for; larger requests are clamped
+const MAX_CPU_SECONDS: u64 = 300;
+const MAX_MEMORY_MB: u64 = 4096;
+const MAX_OUTPUT_BYTES: u64 = 16 * 1024 * 1024;
+
+// Resource-capped command execution (Unix only)
+pub(crate) struct LimitedCommandTool {
+    allowed: Vec<String>,
+    /// Where commands run; the server's current directory when unset
+    working_dir: Option<PathBuf>,
+}
+
+impl LimitedCommandTool {
+    pub(crate) fn new(allowed: Vec<String>, working_dir: Option<PathBuf>) -> Self {
+        Self { allowed, working_dir }
+    }
+}
+
+/// Caps applied to the child process
+#[cfg_attr(not(unix), allow(dead_code))]
+struct Limits {
+    cpu_seconds: u64,
+    memory_mb: u64,
+    output_bytes: u64,
+}
+
+/// Messages that allocation failures print under a memory cap
+#[cfg(unix)]
+const OUT_OF_MEMORY_MARKERS: &[&str] = &["memory exhausted", "cannot allocate memory", "out of memory"];
+
+/// glibc types the resource argument of setrlimit differently from other libcs
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+#[cfg(unix)]
+fn set_limit(resource: RlimitResource, value: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    // SAFETY: setrlimit only reads the struct we pass
+    if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Read at most `limit + 1` bytes so an overrun can be detected without
+/// buffering it; on overrun the whole process is killed.
+#[cfg(unix)]
+async fn read_capped<R>(reader: R, limit: u64, pid: Option<u32>) -> std::io::Result<(Vec<u8>, bool)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut buffer = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut buffer).await?;
+    let exceeded = buffer.len() as u64 > limit;
+    if exceeded {
+        buffer.truncate(limit as usize);
+        if let Some(pid) = pid {
+            // SAFETY: plain signal delivery to our own child
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+    Ok((buffer, exceeded))
+}
+
+#[cfg(unix)]
+async fn run(command: &str, args: &[String], working_dir: Option<&Path>, limits: &Limits) -> Result<CallToolResponse> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let cpu_seconds = limits.cpu_seconds;
+    let memory_bytes = limits.memory_mb.saturating_mul(1024 * 1024);
+    let file_bytes = limits.output_bytes;
+
+    let mut cmd = tokio::process::Command::new(command);
+    cmd.args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+    // SAFETY: the closure only calls setrlimit, which is async-signal-safe
+    unsafe {
+        cmd.pre_exec(move || {
+            set_limit(libc::RLIMIT_CPU, cpu_seconds)?;
+            set_limit(libc::RLIMIT_AS, memory_bytes)?;
+            set_limit(libc::RLIMIT_FSIZE, file_bytes)?;
+            Ok(())
+        });
+    }
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return Ok(CallToolResponse::error(format!("Error executing command: {}", e))),
+    };
+    let pid = child.id();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let (stdout, stderr) = tokio::join!(
+        read_capped(stdout, limits.output_bytes, pid),
+        read_capped(stderr, limits.output_bytes, pid),
+    );
+    let (stdout, stdout_exceeded) = stdout?;
+    let (stderr, stderr_exceeded) = stderr?;
+    let status = child.wait().await?;
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    let stderr = String::from_utf8_lossy(&stderr);
+    let lower_stderr = stderr.to_lowercase();
+
+    let limit_hit = if stdout_exceeded || stderr_exceeded {
+        Some(format!("output limit ({} bytes)", limits.output_bytes))
+    } else if status.signal() == Some(libc::SIGXCPU) || status.signal() == Some(libc::SIGKILL) {
+        Some(format!("CPU time limit ({} s)", limits.cpu_seconds))
+    } else if status.signal() == Some(libc::SIGXFSZ) {
+        Some(format!("file size limit ({} bytes)", limits.output_bytes))
+    } else if !status.success() && OUT_OF_MEMORY_MARKERS.iter().any(|m| lower_stderr.contains(m)) {
+        Some(format!("memory limit ({} MB)", limits.memory_mb))
+    } else {
+        None
+    };
+
+    let mut text = format!("Command: {} {}\nSTDOUT:\n{}", command, args.join(" "), stdout);
+    if !stderr.is_empty() {
+        text.push_str(&format!("\nSTDERR:\n{}", stderr));
+    }
+    if let Some(limit) = limit_hit {
+        return Ok(CallToolResponse::error(format!("Command stopped by the {}\n{}", limit, text)));
+    }
+    if !status.success() {
+        return Ok(CallToolResponse::error(format!("Command failed ({})\n{}", status, text)));
+    }
+    Ok(CallToolResponse::text(text))
+}
+
+#[cfg(not(unix))]
+async fn run(_command: &str, _args: &[String], _working_dir: Option<&Path>, _limits: &Limits) -> Result<CallToolResponse> {
+    Ok(CallToolResponse::error("Resource-limited execution is only supported on Unix"))
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for LimitedCommandTool {
+    fn description(&self) -> String {
+        "Execute a safe system command under CPU time, memory and output size limits".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "Command to execute"
+                },
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Command arguments"
+                },
+                "max_cpu_seconds": {
+                    "type": "integer",
+                    "description": "CPU time limit in seconds",
+                    "default": DEFAULT_MAX_CPU_SECONDS
+                },
+                "max_memory_mb": {
+                    "type": "integer",
+                    "description": "Address space limit in megabytes",
+                    "default": DEFAULT_MAX_MEMORY_MB
+                },
+                "max_output_bytes": {
+                    "type": "integer",
+                    "description": "Limit on stdout, stderr and any file the command writes",
+                    "default": DEFAULT_MAX_OUTPUT_BYTES
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let command = args.get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Command is required"))?;
+
+        if !self.allowed.iter().any(|allowed| allowed == command) {
+            return Ok(command_not_allowed(command, &self.allowed));
+        }
+
+        let cmd_args: Vec<String> = args.get("args")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+
+        let limit = |key: &str, default: u64, max: u64| args.get(key)
+            .and_then(|v| v.as_u64())
+            .unwrap_or(default)
+            .clamp(1, max);
+        let limits = Limits {
+            cpu_seconds: limit("max_cpu_seconds", DEFAULT_MAX_CPU_SECONDS, MAX_CPU_SECONDS),
+            memory_mb: limit("max_memory_mb", DEFAULT_MAX_MEMORY_MB, MAX_MEMORY_MB),
+            output_bytes: limit("max_output_bytes", DEFAULT_MAX_OUTPUT_BYTES, MAX_OUTPUT_BYTES),
+        };
+
+        run(command, &cmd_args, self.working_dir.as_deref(), &limits).await
+    }
+}
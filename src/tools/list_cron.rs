@@ -0,0 +1,266 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// System crontabs, which carry a user field before the command
+const SYSTEM_CRONTAB: &str = "/etc/crontab";
+const SYSTEM_CRON_DIR: &str = "/etc/cron.d";
+
+/// Directories whose scripts run-parts executes on a fixed schedule
+const PERIODIC_DIRS: &[(&str, &str)] = &[
+    ("/etc/cron.hourly", "@hourly"),
+    ("/etc/cron.daily", "@daily"),
+    ("/etc/cron.weekly", "@weekly"),
+    ("/etc/cron.monthly", "@monthly"),
+];
+
+const SPECIAL_SCHEDULES: &[&str] = &[
+    "@reboot", "@yearly", "@annually", "@monthly", "@weekly", "@daily", "@midnight", "@hourly",
+];
+
+// Scheduled task audit tool (read-only)
+pub(crate) struct ListCronTool {
+    sandbox: Sandbox,
+}
+
+impl ListCronTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[derive(Serialize)]
+struct CronEntry {
+    source: String,
+    line: Option<usize>,
+    schedule: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    command: String,
+}
+
+#[derive(Serialize)]
+struct CronError {
+    source: String,
+    line: usize,
+    text: String,
+    error: String,
+}
+
+#[derive(Default)]
+struct Crontab {
+    entries: Vec<CronEntry>,
+    variables: Vec<String>,
+    errors: Vec<CronError>,
+}
+
+/// Split off `count` whitespace-separated fields, returning them with the rest of the line
+fn split_fields(line: &str, count: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(count);
+    let mut rest = line.trim_start();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    Some((fields, rest))
+}
+
+fn is_time_field(field: &str) -> bool {
+    !field.is_empty()
+        && field
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | ',' | '-' | '/'))
+}
+
+/// Parse one crontab; `system` files name the user to run as before the command.
+/// Lines that cannot be parsed are collected as errors rather than failing the file.
+fn parse_crontab(text: &str, source: &str, system: bool, crontab: &mut Crontab) {
+    let user_fields = usize::from(system);
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if is_variable(line) {
+            crontab.variables.push(line.to_string());
+            continue;
+        }
+
+        let error = |error: &str| CronError {
+            source: source.to_string(),
+            line: index + 1,
+            text: raw.to_string(),
+            error: error.to_string(),
+        };
+
+        let parsed = if line.starts_with('@') {
+            split_fields(line, 1 + user_fields).map(|(fields, command)| {
+                if !SPECIAL_SCHEDULES.contains(&fields[0]) {
+                    return Err(error(&format!("Unknown schedule '{}'", fields[0])));
+                }
+                Ok((fields[0].to_string(), fields.get(1).map(|u| u.to_string()), command))
+            })
+        } else {
+            split_fields(line, 5 + user_fields).map(|(fields, command)| {
+                if !fields[..5].iter().all(|f| is_time_field(f)) {
+                    return Err(error("Invalid time field"));
+                }
+                Ok((fields[..5].join(" "), fields.get(5).map(|u| u.to_string()), command))
+            })
+        };
+
+        match parsed {
+            Some(Ok((schedule, user, command))) => {
+                crontab.entries.push(CronEntry {
+                    source: source.to_string(),
+                    line: Some(index + 1),
+                    schedule,
+                    user,
+                    command: command.to_string(),
+                });
+            }
+            Some(Err(e)) => crontab.errors.push(e),
+            None => crontab.errors.push(error(if system {
+                "Expected a schedule, a user and a command"
+            } else {
+                "Expected a schedule and a command"
+            })),
+        }
+    }
+}
+
+/// `NAME=value` environment assignments, e.g. `MAILTO=ops@example.com`
+fn is_variable(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(name, _)| {
+        let name = name.trim();
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Output of `crontab -l` for the current user, or why there is none
+fn user_crontab() -> Result<String, String> {
+    let output = std::process::Command::new("crontab")
+        .arg("-l")
+        .output()
+        .map_err(|e| format!("crontab command unavailable: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn read_system_crontabs(crontab: &mut Crontab, notes: &mut Vec<String>) {
+    let mut files = vec![Path::new(SYSTEM_CRONTAB).to_path_buf()];
+    if let Ok(dir) = std::fs::read_dir(SYSTEM_CRON_DIR) {
+        let mut paths: Vec<_> = dir.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        files.extend(paths.into_iter().filter(|p| p.is_file()));
+    }
+    for file in files {
+        match std::fs::read_to_string(&file) {
+            Ok(text) => parse_crontab(&text, &file.display().to_string(), true, crontab),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => notes.push(format!("{}: {}", file.display(), e)),
+        }
+    }
+
+    for (dir, schedule) in PERIODIC_DIRS {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut scripts: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|p| p.is_file())
+            .collect();
+        scripts.sort();
+        for script in scripts {
+            crontab.entries.push(CronEntry {
+                source: dir.to_string(),
+                line: None,
+                schedule: schedule.to_string(),
+                user: Some("root".to_string()),
+                command: script.display().to_string(),
+            });
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ListCronTool {
+    fn description(&self) -> String {
+        "List scheduled cron jobs from the user's crontab and the system cron directories".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Parse this crontab file instead of the installed ones"
+                },
+                "system_format": {
+                    "type": "boolean",
+                    "description": "Whether `path` is a system crontab with a user column",
+                    "default": false
+                },
+                "include_user": {
+                    "type": "boolean",
+                    "description": "Read the current user's crontab",
+                    "default": true
+                },
+                "include_system": {
+                    "type": "boolean",
+                    "description": "Read /etc/crontab, /etc/cron.d and the periodic cron directories",
+                    "default": true
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let flag = |key: &str, default: bool| args.get(key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(default);
+
+        let mut crontab = Crontab::default();
+        let mut notes = Vec::new();
+
+        if let Some(path) = args.get("path").and_then(|v| v.as_str()) {
+            let resolved = match self.sandbox.resolve(path) {
+                Ok(resolved) => resolved,
+                Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+            };
+            let text = match std::fs::read_to_string(&resolved) {
+                Ok(text) => text,
+                Err(e) => return Ok(CallToolResponse::error(format!("Error reading crontab: {}", e))),
+            };
+            parse_crontab(&text, path, flag("system_format", false), &mut crontab);
+        } else {
+            if flag("include_user", true) {
+                match user_crontab() {
+                    Ok(text) => parse_crontab(&text, "crontab -l", false, &mut crontab),
+                    Err(note) => notes.push(note),
+                }
+            }
+            if flag("include_system", true) {
+                read_system_crontabs(&mut crontab, &mut notes);
+            }
+        }
+
+        let result = json!({
+            "entries": crontab.entries,
+            "variables": crontab.variables,
+            "errors": crontab.errors,
+            "notes": notes,
+        });
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
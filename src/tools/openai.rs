@@ -0,0 +1,102 @@
+use crate::types::Tool;
+use serde_json::{json, Value};
+
+/// Schema keywords OpenAI function calling rejects in strict mode. They are
+/// removed, and a `default` is kept as a hint in the description instead.
+const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "$schema",
+    "default",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "format",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+];
+
+/// Convert MCP tools into OpenAI `tools` entries, returning them with a
+/// warning for every keyword stripped or name rewritten along the way.
+pub(crate) fn export(tools: &[Tool]) -> (Vec<Value>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let functions = tools
+        .iter()
+        .map(|tool| {
+            let name = function_name(&tool.name);
+            if name != tool.name {
+                warnings.push(format!("{}: renamed to '{}' to fit OpenAI function names", tool.name, name));
+            }
+            let mut parameters = tool.input_schema.clone();
+            strip(&mut parameters, &tool.name, "", &mut warnings);
+            json!({
+                "type": "function",
+                "function": {
+                    "name": name,
+                    "description": tool.description,
+                    "parameters": parameters,
+                }
+            })
+        })
+        .collect();
+    (functions, warnings)
+}
+
+/// OpenAI names allow `[A-Za-z0-9_-]{1,64}`
+fn function_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .take(64)
+        .collect();
+    if sanitized.is_empty() {
+        sanitized.push('_');
+    }
+    sanitized
+}
+
+fn strip(schema: &mut Value, tool: &str, path: &str, warnings: &mut Vec<String>) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+
+    let location = if path.is_empty() { tool.to_string() } else { format!("{}.{}", tool, path) };
+    for keyword in UNSUPPORTED_KEYWORDS {
+        let Some(value) = object.remove(*keyword) else {
+            continue;
+        };
+        if *keyword == "default" {
+            let hint = format!("(default: {})", value);
+            let description = match object.get("description").and_then(|d| d.as_str()) {
+                Some(description) => format!("{} {}", description, hint),
+                None => hint,
+            };
+            object.insert("description".to_string(), Value::String(description));
+        } else {
+            warnings.push(format!("{}: removed unsupported keyword '{}'", location, keyword));
+        }
+    }
+
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            strip(property, tool, &join(path, name), warnings);
+        }
+    }
+    for keyword in ["items", "additionalProperties"] {
+        if let Some(nested) = object.get_mut(keyword) {
+            strip(nested, tool, &join(path, keyword), warnings);
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
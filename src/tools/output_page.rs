@@ -0,0 +1,66 @@
+use super::result_store::{paged_response, ResultStore, DEFAULT_PAGE_SIZE};
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+// Fetch further pages of output stored by other tools
+pub(crate) struct OutputPageTool {
+    results: ResultStore,
+}
+
+impl OutputPageTool {
+    pub(crate) fn new(results: ResultStore) -> Self {
+        Self { results }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for OutputPageTool {
+    fn description(&self) -> String {
+        "Fetch the next page of a paginated tool result".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "handle": {
+                    "type": "string",
+                    "description": "The 'next' handle returned with the previous page"
+                },
+                "page_size": {
+                    "type": "integer",
+                    "description": "Maximum page size in bytes",
+                    "default": DEFAULT_PAGE_SIZE
+                }
+            },
+            "required": ["handle"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let handle = args.get("handle")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Handle is required"))?;
+
+        let page_size = args.get("page_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_PAGE_SIZE as u64) as usize;
+
+        let Some((id, offset)) = handle
+            .rsplit_once(':')
+            .and_then(|(id, offset)| Some((id, offset.parse::<usize>().ok()?)))
+        else {
+            return Ok(CallToolResponse::error(format!("Malformed handle '{}'", handle)));
+        };
+
+        match self.results.get(id) {
+            Some(text) => Ok(paged_response(&self.results, Some(id), &text, offset, page_size)),
+            None => Ok(CallToolResponse::error(format!(
+                "Handle '{}' is unknown or has expired",
+                handle
+            ))),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use super::walk::{walk, WalkEntry, WalkOptions, DEFAULT_SKIP_DIRS};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const DEFAULT_MAX_DEPTH: usize = 10;
+const DEFAULT_MAX_ENTRIES: usize = 50_000;
+const MAX_FINDINGS: usize = 1000;
+
+// File ownership and permission audit tool
+pub(crate) struct OwnershipAuditTool {
+    sandbox: Sandbox,
+}
+
+impl OwnershipAuditTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Look up a user or group name through NSS, like `ls -l` does
+#[cfg(unix)]
+fn lookup_name(id: u32, group: bool) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: the out-pointers reference locals and `buffer` outlives every read
+    // of the returned strings, which are copied before returning
+    unsafe {
+        if group {
+            let mut entry: libc::group = std::mem::zeroed();
+            let mut found: *mut libc::group = std::ptr::null_mut();
+            let rc = libc::getgrgid_r(id, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut found);
+            (rc == 0 && !found.is_null()).then(|| CStr::from_ptr(entry.gr_name).to_string_lossy().into_owned())
+        } else {
+            let mut entry: libc::passwd = std::mem::zeroed();
+            let mut found: *mut libc::passwd = std::ptr::null_mut();
+            let rc = libc::getpwuid_r(id, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut found);
+            (rc == 0 && !found.is_null()).then(|| CStr::from_ptr(entry.pw_name).to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Per-owner tallies, keyed by uid or gid
+#[cfg(unix)]
+#[derive(Default)]
+struct Tally {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+}
+
+#[cfg(unix)]
+fn audit(entries: &[WalkEntry]) -> Value {
+    use std::collections::BTreeMap;
+    use std::os::unix::fs::MetadataExt;
+
+    let mut owners: BTreeMap<u32, Tally> = BTreeMap::new();
+    let mut groups: BTreeMap<u32, Tally> = BTreeMap::new();
+    let mut findings = Vec::new();
+    let mut total_findings = 0;
+    let mut symlinks = 0;
+
+    for entry in entries {
+        let metadata = &entry.metadata;
+        // Link permissions are meaningless and targets may lie outside the root
+        if metadata.file_type().is_symlink() {
+            symlinks += 1;
+            continue;
+        }
+
+        for tally in [
+            owners.entry(metadata.uid()).or_default(),
+            groups.entry(metadata.gid()).or_default(),
+        ] {
+            if metadata.is_dir() {
+                tally.dirs += 1;
+            } else {
+                tally.files += 1;
+                tally.bytes += metadata.len();
+            }
+        }
+
+        let mode = metadata.mode() & 0o7777;
+        let mut issues = Vec::new();
+        if mode & 0o002 != 0 {
+            // Sticky directories like /tmp are world-writable by design
+            if metadata.is_dir() && mode & 0o1000 != 0 {
+                issues.push("world_writable_sticky_dir");
+            } else {
+                issues.push("world_writable");
+            }
+        }
+        if metadata.is_file() && mode & 0o4000 != 0 {
+            issues.push("setuid");
+        }
+        if metadata.is_file() && mode & 0o2000 != 0 {
+            issues.push("setgid");
+        }
+        if issues.is_empty() {
+            continue;
+        }
+
+        total_findings += 1;
+        if findings.len() < MAX_FINDINGS {
+            findings.push(json!({
+                "path": entry.relative,
+                "type": if metadata.is_dir() { "dir" } else { "file" },
+                "mode": format!("{:04o}", mode),
+                "uid": metadata.uid(),
+                "gid": metadata.gid(),
+                "issues": issues,
+            }));
+        }
+    }
+
+    let summarize = |tallies: BTreeMap<u32, Tally>, group: bool| -> Vec<Value> {
+        tallies
+            .into_iter()
+            .map(|(id, tally)| {
+                json!({
+                    if group { "gid" } else { "uid" }: id,
+                    "name": lookup_name(id, group),
+                    "files": tally.files,
+                    "dirs": tally.dirs,
+                    "bytes": tally.bytes,
+                })
+            })
+            .collect()
+    };
+
+    json!({
+        "owners": summarize(owners, false),
+        "groups": summarize(groups, true),
+        "findings": findings,
+        "total_findings": total_findings,
+        "symlinks_skipped": symlinks,
+    })
+}
+
+/// Windows has no uid/gid or mode bits; only the read-only attribute is reported
+#[cfg(not(unix))]
+fn audit(entries: &[WalkEntry]) -> Value {
+    let files = entries.iter().filter(|e| e.metadata.is_file()).count();
+    let dirs = entries.iter().filter(|e| e.metadata.is_dir()).count();
+    let read_only: Vec<&str> = entries
+        .iter()
+        .filter(|e| e.metadata.permissions().readonly())
+        .take(MAX_FINDINGS)
+        .map(|e| e.relative.as_str())
+        .collect();
+
+    json!({
+        "files": files,
+        "dirs": dirs,
+        "read_only": read_only,
+        "note": "Ownership and permission bits are only audited on Unix",
+    })
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for OwnershipAuditTool {
+    fn description(&self) -> String {
+        "Group files below a directory by owner and group, and flag world-writable, setuid and setgid entries".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to audit",
+                    "default": "."
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Maximum directory depth to descend",
+                    "default": DEFAULT_MAX_DEPTH
+                },
+                "max_entries": {
+                    "type": "integer",
+                    "description": "Stop after this many files and directories",
+                    "default": DEFAULT_MAX_ENTRIES
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let max_depth = args.get("max_depth")
+            .and_then(|v| v.as_u64())
+            .map(|d| d as usize)
+            .unwrap_or(DEFAULT_MAX_DEPTH);
+
+        let max_entries = args.get("max_entries")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).min(DEFAULT_MAX_ENTRIES))
+            .unwrap_or(DEFAULT_MAX_ENTRIES);
+
+        let root = match self.sandbox.resolve(path) {
+            Ok(root) => root,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !root.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a directory", path)));
+        }
+
+        let options = WalkOptions {
+            max_depth,
+            max_entries,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore: false,
+        };
+        let walked = walk(&root, &options);
+
+        let mut result = audit(&walked.entries);
+        result["root"] = json!(root);
+        result["entries_scanned"] = json!(walked.entries.len());
+        result["truncated"] = json!(walked.truncated);
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
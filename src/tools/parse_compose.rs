@@ -0,0 +1,187 @@
+use super::parse_dockerfile::{parse_image, read_config_file, Warning};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+// Docker Compose file parser and linter
+pub(crate) struct ParseComposeTool {
+    sandbox: Sandbox,
+}
+
+impl ParseComposeTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Names from a field that compose accepts either as a list or as a map keyed by name
+fn names(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            // List-form environment entries are `NAME=value`
+            .map(|item| item.split('=').next().unwrap_or(item).to_string())
+            .collect(),
+        Some(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_service(name: &str, service: &Value, warnings: &mut Vec<Warning>) -> Result<Value, String> {
+    let Some(service) = service.as_object() else {
+        return Err(format!("services.{}: expected a mapping", name));
+    };
+    let image = service.get("image").and_then(|i| i.as_str());
+    let build = match service.get("build") {
+        None => None,
+        Some(Value::String(context)) => Some(json!({"context": context})),
+        Some(Value::Object(build)) => Some(json!({
+            "context": build.get("context").cloned().unwrap_or(json!(".")),
+            "dockerfile": build.get("dockerfile"),
+            "target": build.get("target"),
+        })),
+        Some(_) => return Err(format!("services.{}.build: expected a path or a mapping", name)),
+    };
+    if image.is_none() && build.is_none() {
+        return Err(format!("services.{}: needs an image or a build", name));
+    }
+
+    // With a build section the image is the tag to produce, not one to pull
+    if let (Some(image), None) = (image, &build) {
+        if !image.contains('$') && parse_image(image).is_unpinned() {
+            warnings.push(Warning {
+                line: None,
+                rule: "unpinned_image",
+                message: format!("services.{}: image '{}' has no pinned tag or digest", name, image),
+            });
+        }
+    }
+    if service.get("privileged").and_then(|p| p.as_bool()) == Some(true) {
+        warnings.push(Warning {
+            line: None,
+            rule: "privileged",
+            message: format!("services.{}: runs privileged, with full access to the host", name),
+        });
+    }
+    if service.get("network_mode").and_then(|n| n.as_str()) == Some("host") {
+        warnings.push(Warning {
+            line: None,
+            rule: "host_network",
+            message: format!("services.{}: shares the host's network namespace", name),
+        });
+    }
+
+    Ok(json!({
+        "name": name,
+        "image": image,
+        "build": build,
+        "command": service.get("command"),
+        "entrypoint": service.get("entrypoint"),
+        "ports": service.get("ports").cloned().unwrap_or(json!([])),
+        "volumes": service.get("volumes").cloned().unwrap_or(json!([])),
+        // Names only, since values are often credentials
+        "environment": names(service.get("environment")),
+        "depends_on": names(service.get("depends_on")),
+        "networks": names(service.get("networks")),
+        "restart": service.get("restart"),
+    }))
+}
+
+/// Services, top-level resource names and warnings for a compose document
+fn parse_compose(document: &Value) -> Result<Map<String, Value>, String> {
+    let Some(document) = document.as_object() else {
+        return Err("expected a mapping at the top level".to_string());
+    };
+    let Some(services) = document.get("services").and_then(|s| s.as_object()) else {
+        return Err("expected a 'services' mapping".to_string());
+    };
+
+    let mut warnings = Vec::new();
+    if document.contains_key("version") {
+        warnings.push(Warning {
+            line: None,
+            rule: "obsolete_version",
+            message: "The top-level 'version' field is obsolete and ignored".to_string(),
+        });
+    }
+
+    let parsed = services
+        .iter()
+        .map(|(name, service)| parse_service(name, service, &mut warnings))
+        .collect::<Result<Vec<_>, _>>()?;
+    for service in &parsed {
+        for dependency in service["depends_on"].as_array().into_iter().flatten().filter_map(|d| d.as_str()) {
+            if !services.contains_key(dependency) {
+                let name = service["name"].as_str().unwrap_or_default();
+                return Err(format!("services.{}.depends_on: unknown service '{}'", name, dependency));
+            }
+        }
+    }
+
+    let mut result = Map::new();
+    result.insert("name".to_string(), json!(document.get("name")));
+    result.insert("services".to_string(), json!(parsed));
+    for key in ["networks", "volumes", "secrets", "configs"] {
+        result.insert(key.to_string(), json!(names(document.get(key))));
+    }
+    result.insert("warnings".to_string(), json!(warnings));
+    Ok(result)
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ParseComposeTool {
+    fn description(&self) -> String {
+        "Parse a Docker Compose file into its services, with lint warnings such as unpinned images".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Compose file to parse",
+                    "default": "docker-compose.yml"
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or("docker-compose.yml");
+
+        let text = match read_config_file(&self.sandbox, path) {
+            Ok(text) => text,
+            Err(response) => return Ok(response),
+        };
+
+        let yaml = serde_yaml::from_str::<serde_yaml::Value>(&text).and_then(|mut yaml| {
+            // Resolve `<<: *defaults` merge keys, which compose files use for shared settings
+            yaml.apply_merge()?;
+            Ok(yaml)
+        });
+        let document = match yaml {
+            Ok(yaml) => serde_json::to_value(yaml)?,
+            Err(e) => {
+                let message = match e.location() {
+                    Some(location) => format!("{}:{}:{}: {}", path, location.line(), location.column(), e),
+                    None => format!("{}: {}", path, e),
+                };
+                return Ok(CallToolResponse::error(message));
+            }
+        };
+
+        let mut result = match parse_compose(&document) {
+            Ok(result) => result,
+            Err(e) => return Ok(CallToolResponse::error(format!("{}: {}", path, e))),
+        };
+        result.insert("path".to_string(), json!(path));
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,571 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+pub(super) const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+const INSTRUCTIONS: &[&str] = &[
+    "ADD", "ARG", "CMD", "COPY", "ENTRYPOINT", "ENV", "EXPOSE", "FROM", "HEALTHCHECK", "LABEL",
+    "MAINTAINER", "ONBUILD", "RUN", "SHELL", "STOPSIGNAL", "USER", "VOLUME", "WORKDIR",
+];
+
+/// Instructions that take leading `--name=value` flags
+const FLAGGED_INSTRUCTIONS: &[&str] = &["ADD", "COPY", "FROM", "HEALTHCHECK", "RUN"];
+
+const ARCHIVE_SUFFIXES: &[&str] = &[".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz"];
+
+// Dockerfile parser and linter
+pub(crate) struct ParseDockerfileTool {
+    sandbox: Sandbox,
+}
+
+impl ParseDockerfileTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Why a file could not be parsed, and the 1-based line where it went wrong
+struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct Warning {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// An image reference split into its parts, e.g. `ghcr.io/org/app:1.2@sha256:...`
+pub(super) struct ImageRef<'a> {
+    pub name: &'a str,
+    pub tag: Option<&'a str>,
+    pub digest: Option<&'a str>,
+}
+
+impl ImageRef<'_> {
+    /// Neither a digest nor a tag other than `latest` fixes what gets pulled
+    pub(super) fn is_unpinned(&self) -> bool {
+        self.digest.is_none() && self.tag.is_none_or(|tag| tag == "latest")
+    }
+}
+
+pub(super) fn parse_image(reference: &str) -> ImageRef<'_> {
+    let (rest, digest) = match reference.split_once('@') {
+        Some((rest, digest)) => (rest, Some(digest)),
+        None => (reference, None),
+    };
+    // A colon before the last slash separates a registry port, not a tag
+    let name_start = rest.rfind('/').map_or(0, |i| i + 1);
+    match rest[name_start..].rfind(':') {
+        Some(i) => ImageRef {
+            name: &rest[..name_start + i],
+            tag: Some(&rest[name_start + i + 1..]),
+            digest,
+        },
+        None => ImageRef { name: rest, tag: None, digest },
+    }
+}
+
+/// One instruction with its continuation lines joined and heredoc bodies collected
+struct LogicalLine {
+    line: usize,
+    keyword: String,
+    arguments: String,
+    heredocs: Vec<Value>,
+}
+
+/// Leading `# name=value` comments, which must come before anything else
+fn parse_directives(lines: &[&str]) -> Result<(Map<String, Value>, usize), ParseError> {
+    let mut directives = Map::new();
+    for (index, line) in lines.iter().enumerate() {
+        let directive = line
+            .trim()
+            .strip_prefix('#')
+            .and_then(|comment| comment.split_once('='))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+            .filter(|(name, _)| matches!(name.as_str(), "syntax" | "escape" | "check"));
+        let Some((name, value)) = directive else {
+            return Ok((directives, index));
+        };
+        if name == "escape" && value != "\\" && value != "`" {
+            return Err(ParseError::new(index + 1, format!("Invalid escape directive '{}'; use \\ or `", value)));
+        }
+        directives.insert(name, json!(value));
+    }
+    Ok((directives, lines.len()))
+}
+
+/// `<<EOF`, `<<-EOF` and `<<"EOF"` markers in a RUN, COPY or ADD line
+fn heredoc_markers(arguments: &str) -> Vec<(String, bool)> {
+    let mut markers = Vec::new();
+    let mut rest = arguments;
+    while let Some(start) = rest.find("<<") {
+        rest = &rest[start + 2..];
+        let strip_tabs = rest.starts_with('-');
+        let word = rest.trim_start_matches('-').trim_start_matches(['"', '\'']);
+        let end = word
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(word.len());
+        if end > 0 {
+            markers.push((word[..end].to_string(), strip_tabs));
+        }
+    }
+    markers
+}
+
+fn logical_lines(lines: &[&str], start: usize, escape: char) -> Result<Vec<LogicalLine>, ParseError> {
+    let mut logical = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let first = i;
+        i += 1;
+        let trimmed = lines[first].trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut current = lines[first];
+        loop {
+            let current_trimmed = current.trim_end();
+            let Some(body) = current_trimmed.strip_suffix(escape) else {
+                text.push_str(current_trimmed);
+                break;
+            };
+            text.push_str(body);
+            // Comments inside a continuation are dropped, as docker does
+            while i < lines.len() && lines[i].trim_start().starts_with('#') {
+                i += 1;
+            }
+            if i == lines.len() {
+                break;
+            }
+            current = lines[i];
+            i += 1;
+        }
+
+        let text = text.trim();
+        let (keyword, arguments) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let keyword = keyword.to_ascii_uppercase();
+
+        let mut heredocs = Vec::new();
+        if matches!(keyword.as_str(), "RUN" | "COPY" | "ADD") {
+            for (word, strip_tabs) in heredoc_markers(arguments) {
+                let mut body = Vec::new();
+                loop {
+                    let Some(line) = lines.get(i) else {
+                        return Err(ParseError::new(first + 1, format!("Unterminated heredoc '<<{}'", word)));
+                    };
+                    i += 1;
+                    let line = if strip_tabs { line.trim_start_matches('\t') } else { line };
+                    if line == word {
+                        break;
+                    }
+                    body.push(line);
+                }
+                heredocs.push(json!({"name": word, "body": body.join("\n")}));
+            }
+        }
+
+        logical.push(LogicalLine {
+            line: first + 1,
+            keyword,
+            arguments: arguments.trim().to_string(),
+            heredocs,
+        });
+    }
+    Ok(logical)
+}
+
+/// Split on whitespace, keeping quoted strings together without their quotes
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                continue;
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), c) => word.push(c),
+            (_, '\\') => word.extend(chars.next()),
+            (_, c) => word.push(c),
+        }
+        in_word = true;
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Strip leading `--name=value` flags
+fn take_flags(mut rest: &str) -> (Map<String, Value>, &str) {
+    let mut flags = Map::new();
+    while let Some(flag) = rest.strip_prefix("--") {
+        let end = flag.find(char::is_whitespace).unwrap_or(flag.len());
+        match flag[..end].split_once('=') {
+            Some((name, value)) => flags.insert(name.to_string(), json!(value)),
+            None => flags.insert(flag[..end].to_string(), json!(true)),
+        };
+        rest = flag[end..].trim_start();
+    }
+    (flags, rest)
+}
+
+/// `["executable", "arg"]` exec form, if the arguments are a JSON array of strings
+fn exec_form(arguments: &str) -> Option<Vec<String>> {
+    arguments
+        .starts_with('[')
+        .then(|| serde_json::from_str(arguments).ok())
+        .flatten()
+}
+
+/// `KEY=value` pairs, or the legacy `KEY value` form when `legacy` is allowed
+fn parse_variables(arguments: &str, legacy: bool) -> Map<String, Value> {
+    let words = split_words(arguments);
+    let mut variables = Map::new();
+    if legacy && words.first().is_some_and(|w| !w.contains('=')) {
+        variables.insert(words[0].clone(), json!(words[1..].join(" ")));
+        return variables;
+    }
+    for word in words {
+        match word.split_once('=') {
+            Some((name, value)) => variables.insert(name.to_string(), json!(value)),
+            None => variables.insert(word, Value::Null),
+        };
+    }
+    variables
+}
+
+fn parse_instruction(logical: &LogicalLine) -> Result<Map<String, Value>, ParseError> {
+    let keyword = logical.keyword.as_str();
+    if !INSTRUCTIONS.contains(&keyword) {
+        return Err(ParseError::new(logical.line, format!("Unknown instruction '{}'", logical.keyword)));
+    }
+    if logical.arguments.is_empty() {
+        return Err(ParseError::new(logical.line, format!("{} requires arguments", keyword)));
+    }
+
+    let mut instruction = Map::new();
+    instruction.insert("line".to_string(), json!(logical.line));
+    instruction.insert("instruction".to_string(), json!(keyword));
+    instruction.insert("arguments".to_string(), json!(logical.arguments));
+
+    let rest = if FLAGGED_INSTRUCTIONS.contains(&keyword) {
+        let (flags, rest) = take_flags(&logical.arguments);
+        if !flags.is_empty() {
+            instruction.insert("flags".to_string(), Value::Object(flags));
+        }
+        rest
+    } else {
+        logical.arguments.as_str()
+    };
+
+    match keyword {
+        "FROM" => {
+            let words = split_words(rest);
+            let stage = match words.as_slice() {
+                [_] => None,
+                [_, r#as, name] if r#as.eq_ignore_ascii_case("as") => Some(name.clone()),
+                _ => return Err(ParseError::new(logical.line, "Expected 'FROM <image> [AS <name>]'")),
+            };
+            let image = parse_image(&words[0]);
+            instruction.insert("image".to_string(), json!(image.name));
+            instruction.insert("tag".to_string(), json!(image.tag));
+            instruction.insert("digest".to_string(), json!(image.digest));
+            instruction.insert("stage".to_string(), json!(stage));
+        }
+        "RUN" | "CMD" | "ENTRYPOINT" | "SHELL" => match exec_form(rest) {
+            Some(command) => {
+                instruction.insert("form".to_string(), json!("exec"));
+                instruction.insert("command".to_string(), json!(command));
+            }
+            None if keyword == "SHELL" => {
+                return Err(ParseError::new(logical.line, "SHELL requires the JSON array form"));
+            }
+            None => {
+                instruction.insert("form".to_string(), json!("shell"));
+                instruction.insert("command".to_string(), json!(rest));
+            }
+        },
+        "COPY" | "ADD" => {
+            let mut paths = exec_form(rest).unwrap_or_else(|| split_words(rest));
+            if paths.len() < 2 {
+                return Err(ParseError::new(logical.line, format!("{} requires a source and a destination", keyword)));
+            }
+            let destination = paths.pop();
+            instruction.insert("sources".to_string(), json!(paths));
+            instruction.insert("destination".to_string(), json!(destination));
+        }
+        "ENV" | "LABEL" | "ARG" => {
+            instruction.insert("variables".to_string(), Value::Object(parse_variables(rest, keyword == "ENV")));
+        }
+        "EXPOSE" => {
+            instruction.insert("ports".to_string(), json!(split_words(rest)));
+        }
+        _ => {}
+    }
+
+    if !logical.heredocs.is_empty() {
+        instruction.insert("heredocs".to_string(), json!(logical.heredocs));
+    }
+    Ok(instruction)
+}
+
+/// Per-stage state for lints that look at a whole stage
+#[derive(Default)]
+struct Stage {
+    from_line: usize,
+    cmd: Vec<usize>,
+    entrypoint: Vec<usize>,
+    user: Option<String>,
+}
+
+fn finish_stage(stage: &Stage, warnings: &mut Vec<Warning>) {
+    for (lines, name) in [(&stage.cmd, "CMD"), (&stage.entrypoint, "ENTRYPOINT")] {
+        if let [.., last] = lines.as_slice() {
+            if lines.len() > 1 {
+                warnings.push(Warning {
+                    line: Some(*last),
+                    rule: "multiple_instructions",
+                    message: format!("Only the last {} in a stage takes effect", name),
+                });
+            }
+        }
+    }
+}
+
+struct Dockerfile {
+    directives: Map<String, Value>,
+    instructions: Vec<Map<String, Value>>,
+    stages: Vec<Value>,
+    warnings: Vec<Warning>,
+}
+
+fn parse_dockerfile(text: &str) -> Result<Dockerfile, ParseError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (directives, start) = parse_directives(&lines)?;
+    let escape = match directives.get("escape").and_then(|e| e.as_str()) {
+        Some("`") => '`',
+        _ => '\\',
+    };
+
+    let instructions = logical_lines(&lines, start, escape)?
+        .iter()
+        .map(parse_instruction)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stages: Vec<Value> = Vec::new();
+    let mut stage_names: Vec<String> = Vec::new();
+    let mut current: Option<Stage> = None;
+    let mut warnings = Vec::new();
+
+    for instruction in &instructions {
+        let line = instruction["line"].as_u64().unwrap_or_default() as usize;
+        let keyword = instruction["instruction"].as_str().unwrap_or_default();
+        let text = |key: &str| instruction.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+
+        if keyword == "FROM" {
+            if let Some(stage) = &current {
+                finish_stage(stage, &mut warnings);
+            }
+            let image = text("image");
+            let words = split_words(take_flags(text("arguments")).1);
+            let reference = words[0].as_str();
+            // Earlier stages, scratch and ARG-templated images have nothing to pin
+            let builds_on_stage = stage_names.iter().any(|name| name.eq_ignore_ascii_case(reference));
+            if !builds_on_stage && image != "scratch" && !image.contains('$') && parse_image(reference).is_unpinned() {
+                warnings.push(Warning {
+                    line: Some(line),
+                    rule: "unpinned_base_image",
+                    message: format!("Base image '{}' has no pinned tag or digest", reference),
+                });
+            }
+
+            let name = instruction.get("stage").and_then(|s| s.as_str());
+            if let Some(name) = name {
+                if stage_names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                    return Err(ParseError::new(line, format!("Duplicate stage name '{}'", name)));
+                }
+                stage_names.push(name.to_string());
+            }
+            stages.push(json!({
+                "index": stages.len(),
+                "name": name,
+                "base": reference,
+                "line": line,
+            }));
+            current = Some(Stage {
+                from_line: line,
+                ..Stage::default()
+            });
+            continue;
+        }
+
+        let Some(stage) = current.as_mut() else {
+            if keyword == "ARG" {
+                continue;
+            }
+            return Err(ParseError::new(line, format!("Expected FROM before {}", keyword)));
+        };
+        match keyword {
+            "CMD" | "ENTRYPOINT" => {
+                if keyword == "CMD" {
+                    stage.cmd.push(line);
+                } else {
+                    stage.entrypoint.push(line);
+                }
+                if text("form") == "shell" {
+                    warnings.push(Warning {
+                        line: Some(line),
+                        rule: "json_args_recommended",
+                        message: format!("Shell-form {} runs under /bin/sh -c, which does not forward signals", keyword),
+                    });
+                }
+            }
+            "USER" => stage.user = Some(text("arguments").to_string()),
+            "WORKDIR" => {
+                let dir = text("arguments");
+                if !dir.starts_with('/') && !dir.starts_with('$') {
+                    warnings.push(Warning {
+                        line: Some(line),
+                        rule: "relative_workdir",
+                        message: format!("WORKDIR '{}' is relative to whatever the previous one was", dir),
+                    });
+                }
+            }
+            "ADD" => {
+                let local_files = instruction.get("heredocs").is_none()
+                    && instruction["sources"].as_array().is_some_and(|sources| {
+                        sources.iter().filter_map(|s| s.as_str()).all(|source| {
+                            !source.contains("://")
+                                && !source.starts_with("git@")
+                                && !ARCHIVE_SUFFIXES.iter().any(|suffix| source.ends_with(suffix))
+                        })
+                    });
+                if local_files {
+                    warnings.push(Warning {
+                        line: Some(line),
+                        rule: "add_instead_of_copy",
+                        message: "Use COPY for local files; ADD also fetches URLs and unpacks archives".to_string(),
+                    });
+                }
+            }
+            "MAINTAINER" => warnings.push(Warning {
+                line: Some(line),
+                rule: "maintainer_deprecated",
+                message: "MAINTAINER is deprecated; use LABEL maintainer=...".to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    let Some(last) = current else {
+        return Err(ParseError::new(lines.len().max(1), "No FROM instruction"));
+    };
+    finish_stage(&last, &mut warnings);
+    let user = last.user.as_deref().map(|u| u.split(':').next().unwrap_or(u));
+    if matches!(user, None | Some("root") | Some("0")) {
+        warnings.push(Warning {
+            line: Some(last.from_line),
+            rule: "runs_as_root",
+            message: "The final stage runs as root; add a USER instruction".to_string(),
+        });
+    }
+
+    Ok(Dockerfile {
+        directives,
+        instructions,
+        stages,
+        warnings,
+    })
+}
+
+/// Read a small text file through the sandbox, or the error result to return instead
+pub(super) fn read_config_file(sandbox: &Sandbox, path: &str) -> Result<String, CallToolResponse> {
+    let resolved = sandbox.resolve(path).map_err(|e| CallToolResponse::error(e.to_string()))?;
+    let metadata = std::fs::metadata(&resolved)
+        .map_err(|e| CallToolResponse::error(format!("Error reading file: {}", e)))?;
+    if !metadata.is_file() {
+        return Err(CallToolResponse::error(format!("'{}' is not a file", path)));
+    }
+    if metadata.len() > MAX_FILE_SIZE {
+        return Err(CallToolResponse::error(format!(
+            "File is too large ({} bytes, max: {} bytes)",
+            metadata.len(),
+            MAX_FILE_SIZE
+        )));
+    }
+    std::fs::read_to_string(&resolved).map_err(|e| CallToolResponse::error(format!("Error reading file: {}", e)))
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ParseDockerfileTool {
+    fn description(&self) -> String {
+        "Parse a Dockerfile into structured instructions and build stages, with lint warnings such as unpinned base images".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Dockerfile to parse",
+                    "default": "Dockerfile"
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Dockerfile");
+
+        let text = match read_config_file(&self.sandbox, path) {
+            Ok(text) => text,
+            Err(response) => return Ok(response),
+        };
+
+        let dockerfile = match parse_dockerfile(&text) {
+            Ok(dockerfile) => dockerfile,
+            Err(e) => return Ok(CallToolResponse::error(format!("{}:{}: {}", path, e.line, e.message))),
+        };
+
+        let result = json!({
+            "path": path,
+            "directives": dockerfile.directives,
+            "stages": dockerfile.stages,
+            "instructions": dockerfile.instructions,
+            "warnings": dockerfile.warnings,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,100 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use url::Url;
+
+// URL parsing tool
+pub(crate) struct ParseUrlTool;
+
+/// Query parameters as a map; repeated keys collect into an array
+fn query_map(url: &Url) -> Map<String, Value> {
+    let mut params = Map::new();
+    for (key, value) in url.query_pairs() {
+        let value = Value::String(value.into_owned());
+        match params.get_mut(key.as_ref()) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                params.insert(key.into_owned(), value);
+            }
+        }
+    }
+    params
+}
+
+/// Canonical form: the parser already lowercases the scheme and host, drops
+/// default ports and resolves dot segments; on top of that query parameters
+/// are sorted and empty query strings and fragments are removed.
+fn normalize(url: &Url) -> String {
+    let mut url = url.clone();
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        pairs.sort();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    if url.fragment() == Some("") {
+        url.set_fragment(None);
+    }
+    url.to_string()
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ParseUrlTool {
+    fn description(&self) -> String {
+        "Parse and validate a URL into scheme, host, port, path, query parameters and fragment".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "Absolute URL to parse"
+                },
+                "normalize": {
+                    "type": "boolean",
+                    "description": "Also return the canonical form of the URL",
+                    "default": false
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let input = args.get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("URL is required"))?;
+
+        let normalize_flag = args.get("normalize")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let url = match Url::parse(input.trim()) {
+            Ok(url) => url,
+            Err(e) => return Ok(CallToolResponse::error(format!("Invalid URL '{}': {}", input, e))),
+        };
+
+        let mut result = json!({
+            "scheme": url.scheme(),
+            "username": (!url.username().is_empty()).then(|| url.username()),
+            "has_password": url.password().is_some(),
+            "host": url.host_str(),
+            "port": url.port(),
+            "effective_port": url.port_or_known_default(),
+            "path": url.path(),
+            "query": url.query(),
+            "query_params": query_map(&url),
+            "fragment": url.fragment(),
+        });
+        if normalize_flag {
+            result["normalized"] = json!(normalize(&url));
+        }
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
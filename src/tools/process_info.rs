@@ -0,0 +1,137 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Argument fragments that suggest the following value is a credential
+#[cfg(target_os = "linux")]
+const SENSITIVE_MARKERS: &[&str] = &["pass", "secret", "token", "key", "auth", "credential"];
+#[cfg(target_os = "linux")]
+const REDACTED: &str = "<redacted>";
+
+/// Fields copied from `/proc/<pid>/status`
+#[cfg(target_os = "linux")]
+const STATUS_FIELDS: &[&str] = &["Name", "State", "PPid", "Uid", "Gid", "Threads", "VmRSS", "VmSize"];
+
+// Process diagnostics tool (Linux only)
+pub(crate) struct ProcessInfoTool;
+
+#[cfg(target_os = "linux")]
+fn is_sensitive(arg: &str) -> bool {
+    let lower = arg.to_ascii_lowercase();
+    SENSITIVE_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Redact values of credential-looking arguments: `--token=abc` and `--token abc`
+#[cfg(target_os = "linux")]
+fn redact_args(args: Vec<String>) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut redact_next = false;
+    for arg in args {
+        if redact_next && !arg.starts_with('-') {
+            redacted.push(REDACTED.to_string());
+            redact_next = false;
+            continue;
+        }
+        redact_next = false;
+        match arg.split_once('=') {
+            Some((key, _)) if is_sensitive(key) => redacted.push(format!("{}={}", key, REDACTED)),
+            None if arg.starts_with('-') && is_sensitive(&arg) => {
+                redact_next = true;
+                redacted.push(arg);
+            }
+            _ => redacted.push(arg),
+        }
+    }
+    redacted
+}
+
+#[cfg(target_os = "linux")]
+fn read_process(pid: u32, redact: bool) -> Result<Value, String> {
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+    if !proc_dir.exists() {
+        return Err(format!("No process with pid {}", pid));
+    }
+
+    let status_text = std::fs::read_to_string(proc_dir.join("status"))
+        .map_err(|e| format!("Error reading process status: {}", e))?;
+    let mut status = serde_json::Map::new();
+    for line in status_text.lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            if STATUS_FIELDS.contains(&key) {
+                let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                status.insert(key.to_string(), Value::String(value));
+            }
+        }
+    }
+
+    // Processes owned by other users may hide these; report what is readable
+    let cmdline = std::fs::read(proc_dir.join("cmdline")).ok().map(|raw| {
+        let args: Vec<String> = raw
+            .split(|b| *b == 0)
+            .filter(|part| !part.is_empty())
+            .map(|part| String::from_utf8_lossy(part).to_string())
+            .collect();
+        if redact { redact_args(args) } else { args }
+    });
+    let cwd = std::fs::read_link(proc_dir.join("cwd"))
+        .ok()
+        .map(|p| p.to_string_lossy().to_string());
+    let open_files = std::fs::read_dir(proc_dir.join("fd")).ok().map(|d| d.count());
+
+    Ok(json!({
+        "pid": pid,
+        "cmdline": cmdline,
+        "cwd": cwd,
+        "open_files": open_files,
+        "status": status,
+        "redacted": redact,
+    }))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_process(_pid: u32, _redact: bool) -> Result<Value, String> {
+    Err("process_info is only supported on Linux".to_string())
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ProcessInfoTool {
+    fn description(&self) -> String {
+        "Show a process's command line, working directory, open file count and status (Linux)"
+            .to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pid": {
+                    "type": "integer",
+                    "description": "Process id to inspect"
+                },
+                "redact": {
+                    "type": "boolean",
+                    "description": "Mask credential-looking command line arguments",
+                    "default": true
+                }
+            },
+            "required": ["pid"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let pid = args.get("pid")
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow::anyhow!("Pid is required"))?;
+
+        let redact = args.get("redact")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        match read_process(pid, redact) {
+            Ok(info) => Ok(CallToolResponse::text(serde_json::to_string_pretty(&info)?)),
+            Err(e) => Ok(CallToolResponse::error(e)),
+        }
+    }
+}
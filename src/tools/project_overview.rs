@@ -0,0 +1,206 @@
+use super::walk::{looks_binary, walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const MAX_DEPTH: usize = 8;
+const MAX_ENTRIES: usize = 20_000;
+const MAX_LOC_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Marker files identifying a project type, in priority order
+const PROJECT_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "cargo"),
+    ("package.json", "npm"),
+    ("pyproject.toml", "python"),
+    ("setup.py", "python"),
+    ("requirements.txt", "python"),
+    ("go.mod", "go"),
+    ("pom.xml", "maven"),
+    ("build.gradle", "gradle"),
+    ("build.gradle.kts", "gradle"),
+    ("Gemfile", "ruby"),
+    ("composer.json", "composer"),
+    ("CMakeLists.txt", "cmake"),
+    ("Makefile", "make"),
+];
+
+/// Top-level files worth pointing an agent at
+const KEY_FILES: &[&str] = &[
+    "README.md", "README", "LICENSE", "Cargo.toml", "Cargo.lock", "package.json",
+    "package-lock.json", "tsconfig.json", "pyproject.toml", "setup.py", "requirements.txt",
+    "go.mod", "pom.xml", "build.gradle", "Makefile", "Dockerfile", "docker-compose.yml",
+    ".gitignore", ".editorconfig", "rust-toolchain.toml", ".github",
+];
+
+// Project orientation tool
+pub(crate) struct ProjectOverviewTool {
+    sandbox: Sandbox,
+}
+
+impl ProjectOverviewTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Count declared dependencies for the manifests we know how to read
+fn dependency_counts(root: &Path) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+
+    if let Ok(text) = std::fs::read_to_string(root.join("Cargo.toml")) {
+        if let Ok(manifest) = text.parse::<toml::Table>() {
+            for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+                if let Some(table) = manifest.get(section).and_then(|v| v.as_table()) {
+                    counts.insert(format!("cargo.{}", section), table.len());
+                }
+            }
+        }
+    }
+
+    if let Ok(text) = std::fs::read_to_string(root.join("package.json")) {
+        if let Ok(manifest) = serde_json::from_str::<Value>(&text) {
+            for section in ["dependencies", "devDependencies"] {
+                if let Some(map) = manifest.get(section).and_then(|v| v.as_object()) {
+                    counts.insert(format!("npm.{}", section), map.len());
+                }
+            }
+        }
+    }
+
+    if let Ok(text) = std::fs::read_to_string(root.join("requirements.txt")) {
+        let count = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('-'))
+            .count();
+        counts.insert("python.requirements".to_string(), count);
+    }
+
+    counts
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ProjectOverviewTool {
+    fn description(&self) -> String {
+        "Summarize a project: type, key files, directory layout, dependency counts and lines of code"
+            .to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project root directory",
+                    "default": "."
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip paths matched by .gitignore files",
+                    "default": true
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let respect_gitignore = args.get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let root = match self.sandbox.resolve(path) {
+            Ok(root) => root,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !root.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a directory", path)));
+        }
+
+        let project_types: Vec<&str> = PROJECT_MARKERS
+            .iter()
+            .filter(|(marker, _)| root.join(marker).exists())
+            .map(|(_, kind)| *kind)
+            .fold(Vec::new(), |mut kinds, kind| {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+                kinds
+            });
+        let key_files: Vec<&str> = KEY_FILES
+            .iter()
+            .copied()
+            .filter(|name| root.join(name).exists())
+            .collect();
+
+        let options = WalkOptions {
+            max_depth: MAX_DEPTH,
+            max_entries: MAX_ENTRIES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore,
+        };
+        let walked = walk(&root, &options);
+
+        // Per top-level entry: number of files beneath it
+        let mut layout: BTreeMap<String, usize> = BTreeMap::new();
+        let mut lines_by_extension: BTreeMap<String, u64> = BTreeMap::new();
+        let mut total_files = 0;
+        let mut total_lines = 0;
+
+        for entry in &walked.entries {
+            let top = entry.relative.split('/').next().unwrap_or_default().to_string();
+            if entry.metadata.is_dir() {
+                layout.entry(top).or_insert(0);
+                continue;
+            }
+            if !entry.metadata.is_file() {
+                continue;
+            }
+            total_files += 1;
+            *layout.entry(top).or_insert(0) += 1;
+
+            if entry.metadata.len() > MAX_LOC_FILE_BYTES {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(&entry.path) else {
+                continue;
+            };
+            if looks_binary(&bytes) {
+                continue;
+            }
+            let lines = bytes.split(|b| *b == b'\n').filter(|line| !line.is_empty()).count() as u64;
+            let extension = entry
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_else(|| "(none)".to_string());
+            *lines_by_extension.entry(extension).or_insert(0) += lines;
+            total_lines += lines;
+        }
+
+        let result = json!({
+            "root": root,
+            "project_type": project_types.first().copied().unwrap_or("unknown"),
+            "project_types": project_types,
+            "key_files": key_files,
+            "layout": layout,
+            "dependencies": dependency_counts(&root),
+            "total_files": total_files,
+            "lines_of_code": {
+                "total": total_lines,
+                "by_extension": lines_by_extension,
+            },
+            "truncated": walked.truncated,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
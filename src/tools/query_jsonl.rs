@@ -0,0 +1,276 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 10_000;
+const DEFAULT_MAX_SCAN_BYTES: u64 = 50 * 1024 * 1024;
+
+// JSON-lines log query tool
+pub(crate) struct QueryJsonlTool {
+    sandbox: Sandbox,
+}
+
+impl QueryJsonlTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+/// One `field op value` comparison; `field` is a dotted path into the object
+#[derive(Debug)]
+struct Condition {
+    field: Vec<String>,
+    operator: Operator,
+    value: Value,
+}
+
+/// Parse `level == "error" && status >= 500`. Values are JSON literals or bare words.
+fn parse_filter(filter: &str) -> Result<Vec<Condition>, String> {
+    let mut conditions = Vec::new();
+    let mut rest = filter.trim();
+    while !rest.is_empty() {
+        let field_end = rest
+            .find(|c: char| c.is_whitespace() || "=!<>~".contains(c))
+            .ok_or_else(|| format!("Expected an operator after '{}'", rest))?;
+        let field = &rest[..field_end];
+        if field.is_empty() {
+            return Err(format!("Expected a field name at '{}'", rest));
+        }
+        rest = rest[field_end..].trim_start();
+
+        let (operator, len) = [
+            ("==", Operator::Eq),
+            ("!=", Operator::Ne),
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("~=", Operator::Contains),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+        .map(|(symbol, operator)| (operator, symbol.len()))
+        .ok_or_else(|| format!("Unknown operator at '{}'; use ==, !=, <, <=, >, >= or ~=", rest))?;
+        rest = rest[len..].trim_start();
+
+        let mut values = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+        let value = match values.next() {
+            Some(Ok(value)) if !value.is_object() && !value.is_array() => {
+                let consumed = values.byte_offset();
+                rest = &rest[consumed..];
+                value
+            }
+            _ => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(format!("Expected a value after the operator for '{}'", field));
+                }
+                let word = Value::String(rest[..end].to_string());
+                rest = &rest[end..];
+                word
+            }
+        };
+
+        conditions.push(Condition {
+            field: field.split('.').map(str::to_string).collect(),
+            operator,
+            value,
+        });
+
+        rest = rest.trim_start();
+        if let Some(next) = rest.strip_prefix("&&") {
+            rest = next.trim_start();
+            if rest.is_empty() {
+                return Err("Expected a condition after '&&'".to_string());
+            }
+        } else if !rest.is_empty() {
+            return Err(format!("Expected '&&' between conditions at '{}'", rest));
+        }
+    }
+    Ok(conditions)
+}
+
+impl Condition {
+    fn matches(&self, object: &Value) -> bool {
+        let found = self.field.iter().try_fold(object, |value, key| value.get(key));
+        let Some(found) = found else {
+            return self.operator == Operator::Ne;
+        };
+
+        let ordering = match (found, &self.value) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match self.operator {
+            Operator::Eq => ordering.map_or(found == &self.value, |o| o.is_eq()),
+            Operator::Ne => ordering.map_or(found != &self.value, |o| o.is_ne()),
+            Operator::Lt => ordering.is_some_and(|o| o.is_lt()),
+            Operator::Le => ordering.is_some_and(|o| o.is_le()),
+            Operator::Gt => ordering.is_some_and(|o| o.is_gt()),
+            Operator::Ge => ordering.is_some_and(|o| o.is_ge()),
+            Operator::Contains => match (found, &self.value) {
+                (Value::String(haystack), Value::String(needle)) => haystack.contains(needle.as_str()),
+                (Value::Array(items), needle) => items.contains(needle),
+                _ => false,
+            },
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for QueryJsonlTool {
+    fn description(&self) -> String {
+        "Stream a JSON-lines file and return the objects matching a field filter".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "JSON-lines file to query"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Conditions such as level == \"error\" && http.status >= 500; operators: ==, !=, <, <=, >, >=, ~= (contains)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum matches to return",
+                    "default": DEFAULT_LIMIT
+                },
+                "tail": {
+                    "type": "boolean",
+                    "description": "Return the last matches in the file instead of the first",
+                    "default": false
+                },
+                "max_scan_bytes": {
+                    "type": "integer",
+                    "description": "Bytes to scan; with tail, the end of the file is scanned",
+                    "default": DEFAULT_MAX_SCAN_BYTES
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+
+        let conditions = match parse_filter(args.get("filter").and_then(|v| v.as_str()).unwrap_or("")) {
+            Ok(conditions) => conditions,
+            Err(e) => return Ok(CallToolResponse::error(format!("Invalid filter: {}", e))),
+        };
+
+        let limit = args.get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, MAX_LIMIT))
+            .unwrap_or(DEFAULT_LIMIT);
+
+        let tail = args.get("tail")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let max_scan_bytes = args.get("max_scan_bytes")
+            .and_then(|v| v.as_u64())
+            .map(|n| n.min(DEFAULT_MAX_SCAN_BYTES))
+            .unwrap_or(DEFAULT_MAX_SCAN_BYTES);
+
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !resolved.is_file() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a file", path)));
+        }
+        let mut file = match std::fs::File::open(&resolved) {
+            Ok(file) => file,
+            Err(e) => return Ok(CallToolResponse::error(format!("Error opening file: {}", e))),
+        };
+        let size = file.metadata()?.len();
+
+        // In tail mode start near the end, dropping the partial first line
+        let start = if tail { size.saturating_sub(max_scan_bytes) } else { 0 };
+        file.seek(SeekFrom::Start(start))?;
+        let mut reader = BufReader::new(file.take(max_scan_bytes));
+        let mut bytes_read = 0;
+        if start > 0 {
+            bytes_read += reader.read_until(b'\n', &mut Vec::new())? as u64;
+        }
+
+        let mut matches = VecDeque::new();
+        let mut matched = 0;
+        let mut lines_scanned = 0;
+        let mut malformed_lines = 0;
+        let mut stopped_early = false;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            bytes_read += read as u64;
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            lines_scanned += 1;
+
+            let object: Value = match serde_json::from_str(text) {
+                Ok(value @ Value::Object(_)) => value,
+                _ => {
+                    malformed_lines += 1;
+                    continue;
+                }
+            };
+            if !conditions.iter().all(|c| c.matches(&object)) {
+                continue;
+            }
+
+            matched += 1;
+            matches.push_back(object);
+            if matches.len() > limit {
+                matches.pop_front();
+            }
+            if !tail && matches.len() == limit {
+                stopped_early = true;
+                break;
+            }
+        }
+
+        let result = json!({
+            "path": path,
+            "matches": matches,
+            "returned": matches.len(),
+            "matched": matched,
+            "lines_scanned": lines_scanned,
+            "malformed_lines": malformed_lines,
+            // More matches may exist than were returned or scanned
+            "truncated": stopped_early || matched > matches.len() || start > 0 || start + bytes_read < size,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
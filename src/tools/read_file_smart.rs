@@ -0,0 +1,137 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde_json::{json, Value};
+
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+// Encoding-aware file reader
+pub(crate) struct ReadFileSmartTool {
+    sandbox: Sandbox,
+}
+
+impl ReadFileSmartTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// BOM-less UTF-16 shows up as NUL bytes in every other position for
+/// ASCII-heavy text. Returns the matching endianness when that pattern holds.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let even_nuls = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_nuls = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    if odd_nuls * 10 >= pairs * 3 && even_nuls == 0 {
+        Some(UTF_16LE)
+    } else if even_nuls * 10 >= pairs * 3 && odd_nuls == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Pick the source encoding: BOM, then valid UTF-8, then UTF-16 patterns,
+/// then statistical detection. `None` means the bytes look binary.
+fn detect(bytes: &[u8]) -> Option<(&'static Encoding, &'static str)> {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return Some((encoding, "bom"));
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Some((UTF_8, "valid_utf8"));
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return Some((encoding, "utf16_pattern"));
+    }
+    if bytes.contains(&0) {
+        return None;
+    }
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Allow);
+    detector.feed(bytes, true);
+    Some((detector.guess(None, Utf8Detection::Allow), "detected"))
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ReadFileSmartTool {
+    fn description(&self) -> String {
+        "Read a text file in any common encoding (UTF-8, UTF-16, Latin-1, ...) and return it as UTF-8".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file to read"
+                },
+                "max_size": {
+                    "type": "integer",
+                    "description": "Maximum file size to read in bytes",
+                    "default": DEFAULT_MAX_SIZE
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+
+        let max_size = args.get("max_size")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_SIZE);
+
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        let metadata = match std::fs::metadata(&resolved) {
+            Ok(metadata) => metadata,
+            Err(e) => return Ok(CallToolResponse::error(format!("Error accessing file: {}", e))),
+        };
+        if metadata.len() > max_size {
+            return Ok(CallToolResponse::error(format!(
+                "File is too large ({} bytes, max: {} bytes)",
+                metadata.len(),
+                max_size
+            )));
+        }
+        let bytes = match std::fs::read(&resolved) {
+            Ok(bytes) => bytes,
+            Err(e) => return Ok(CallToolResponse::error(format!("Error reading file: {}", e))),
+        };
+
+        let Some((encoding, method)) = detect(&bytes) else {
+            let result = json!({
+                "path": path,
+                "size": bytes.len(),
+                "binary": true,
+                "note": "File appears to be binary; no text encoding applies",
+            });
+            return Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?));
+        };
+
+        // `decode` strips a BOM matching any Unicode encoding
+        let (content, encoding, had_errors) = encoding.decode(&bytes);
+
+        let result = json!({
+            "path": path,
+            "size": bytes.len(),
+            "encoding": encoding.name(),
+            "detection": method,
+            "had_invalid_sequences": had_errors,
+            "content": content,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
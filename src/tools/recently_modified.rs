@@ -0,0 +1,191 @@
+use super::walk::{walk, WalkOptions, DEFAULT_SKIP_DIRS};
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use globset::Glob;
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_DEPTH: usize = 10;
+const MAX_ENTRIES: usize = 50_000;
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+// Recently modified files tool
+pub(crate) struct RecentlyModifiedTool {
+    sandbox: Sandbox,
+}
+
+impl RecentlyModifiedTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Last second `humantime` can format, the end of the year 9999
+const LATEST_CUTOFF_SECS: u64 = 253_402_300_799;
+
+/// A `since` the tool can't turn into a cutoff
+#[derive(Debug, thiserror::Error)]
+enum SinceError {
+    #[error("Invalid since '{0}'. Use a duration like '2h', an RFC 3339 timestamp or Unix seconds")]
+    Invalid(String),
+    #[error("Since '{0}' is out of range; the cutoff must fall between 1970 and 9999")]
+    OutOfRange(String),
+}
+
+/// Parse `since` as a duration ago ("15m", "2h 30m"), an RFC 3339 timestamp
+/// or seconds since the Unix epoch
+fn parse_since(since: &str, now: SystemTime) -> Result<SystemTime, SinceError> {
+    let cutoff = if let Ok(duration) = humantime::parse_duration(since) {
+        now.checked_sub(duration)
+    } else if let Ok(time) = humantime::parse_rfc3339_weak(since) {
+        Some(time)
+    } else {
+        let secs = since.parse::<u64>().map_err(|_| SinceError::Invalid(since.to_string()))?;
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+    };
+    cutoff
+        .filter(|cutoff| format_time(*cutoff).is_some())
+        .ok_or_else(|| SinceError::OutOfRange(since.to_string()))
+}
+
+/// `time` as an RFC 3339 timestamp, or `None` outside 1970 to 9999
+fn format_time(time: SystemTime) -> Option<String> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    (secs <= LATEST_CUTOFF_SECS).then(|| humantime::format_rfc3339_seconds(time).to_string())
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for RecentlyModifiedTool {
+    fn description(&self) -> String {
+        "List files modified within a time window, newest first".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to search",
+                    "default": "."
+                },
+                "since": {
+                    "type": "string",
+                    "description": "Duration ago (e.g. '15m', '2h', '7d'), RFC 3339 timestamp or Unix seconds"
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only include files whose relative path matches this glob (e.g. '*.rs')"
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Maximum directory depth to descend",
+                    "default": DEFAULT_MAX_DEPTH
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of files to return",
+                    "default": DEFAULT_LIMIT
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip paths matched by .gitignore files",
+                    "default": true
+                }
+            },
+            "required": ["since"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let since = args.get("since")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Since is required"))?;
+
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let max_depth = args.get("max_depth")
+            .and_then(|v| v.as_u64())
+            .map(|d| d as usize)
+            .unwrap_or(DEFAULT_MAX_DEPTH);
+
+        let limit = args.get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|l| l as usize)
+            .unwrap_or(DEFAULT_LIMIT)
+            .clamp(1, MAX_LIMIT);
+
+        let respect_gitignore = args.get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let cutoff = match parse_since(since, SystemTime::now()) {
+            Ok(cutoff) => cutoff,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+
+        let matcher = match args.get("glob").and_then(|v| v.as_str()) {
+            Some(pattern) => match Glob::new(pattern) {
+                Ok(glob) => Some(glob.compile_matcher()),
+                Err(e) => return Ok(CallToolResponse::error(format!("Invalid glob: {}", e))),
+            },
+            None => None,
+        };
+
+        let root = match self.sandbox.resolve(path) {
+            Ok(root) => root,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !root.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is not a directory", path)));
+        }
+
+        let options = WalkOptions {
+            max_depth,
+            max_entries: MAX_ENTRIES,
+            skip_dirs: DEFAULT_SKIP_DIRS,
+            respect_gitignore,
+        };
+        let walked = walk(&root, &options);
+
+        let mut files: Vec<(SystemTime, String, u64)> = walked
+            .entries
+            .into_iter()
+            .filter(|entry| entry.metadata.is_file())
+            .filter(|entry| matcher.as_ref().is_none_or(|m| m.is_match(&entry.relative)))
+            .filter_map(|entry| {
+                let modified = entry.metadata.modified().ok()?;
+                (modified >= cutoff).then_some((modified, entry.relative, entry.metadata.len()))
+            })
+            .collect();
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let total_matches = files.len();
+        let entries: Vec<Value> = files
+            .into_iter()
+            .take(limit)
+            .map(|(modified, path, size)| {
+                json!({
+                    "path": path,
+                    "mtime": format_time(modified),
+                    "size": size,
+                })
+            })
+            .collect();
+
+        let result = json!({
+            "root": root,
+            "since": format_time(cutoff),
+            "files": entries,
+            "total_matches": total_matches,
+            "truncated": walked.truncated || total_matches > limit,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,151 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+// File descriptor and inode limits tool
+pub(crate) struct ResourceLimitsTool {
+    sandbox: Sandbox,
+}
+
+impl ResourceLimitsTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[cfg(unix)]
+fn rlimit_value(value: libc::rlim_t) -> Value {
+    if value == libc::RLIM_INFINITY {
+        json!("unlimited")
+    } else {
+        json!(value)
+    }
+}
+
+/// Open descriptor count and `RLIMIT_NOFILE` for this process
+#[cfg(unix)]
+fn fd_limits(notes: &mut Vec<String>) -> Option<Value> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes into the struct we pass
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        notes.push(format!("getrlimit failed: {}", std::io::Error::last_os_error()));
+        return None;
+    }
+
+    // Reading the directory opens one descriptor of its own
+    let open = match std::fs::read_dir("/dev/fd") {
+        Ok(entries) => Some(entries.count().saturating_sub(1)),
+        Err(e) => {
+            notes.push(format!("Open descriptor count unavailable: {}", e));
+            None
+        }
+    };
+
+    Some(json!({
+        "open": open,
+        "soft_limit": rlimit_value(limit.rlim_cur),
+        "hard_limit": rlimit_value(limit.rlim_max),
+    }))
+}
+
+#[cfg(not(unix))]
+fn fd_limits(notes: &mut Vec<String>) -> Option<Value> {
+    notes.push("File descriptor limits are only available on Unix".to_string());
+    None
+}
+
+/// Inode usage of the filesystem holding `path`
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs field widths differ between platforms
+fn inode_usage(path: &Path, notes: &mut Vec<String>) -> Option<Value> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        notes.push("Path contains a NUL byte".to_string());
+        return None;
+    };
+    // SAFETY: statvfs is a plain C struct for which all-zero bytes are valid
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is NUL-terminated and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        notes.push(format!("statvfs failed: {}", std::io::Error::last_os_error()));
+        return None;
+    }
+
+    let total = stat.f_files as u64;
+    let free = stat.f_ffree as u64;
+    if total == 0 {
+        notes.push("Filesystem does not report inode counts".to_string());
+        return None;
+    }
+    let used = total.saturating_sub(free);
+
+    Some(json!({
+        "total": total,
+        "used": used,
+        "free": free,
+        "available": stat.f_favail as u64,
+        "used_percent": (used as f64 / total as f64 * 1000.0).round() / 10.0,
+    }))
+}
+
+#[cfg(not(unix))]
+fn inode_usage(_path: &Path, notes: &mut Vec<String>) -> Option<Value> {
+    notes.push("Inode usage is only available on Unix".to_string());
+    None
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ResourceLimitsTool {
+    fn description(&self) -> String {
+        "Report this process's open file descriptor limits and inode usage for a filesystem".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path on the filesystem whose inode usage to report",
+                    "default": "."
+                }
+            }
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if !resolved.exists() {
+            return Ok(CallToolResponse::error(format!("Path '{}' does not exist", path)));
+        }
+
+        let mut notes = Vec::new();
+        let mut result = serde_json::Map::new();
+        if let Some(fds) = fd_limits(&mut notes) {
+            result.insert("file_descriptors".to_string(), fds);
+        }
+        if let Some(inodes) = inode_usage(&resolved, &mut notes) {
+            result.insert("path".to_string(), json!(resolved));
+            result.insert("inodes".to_string(), inodes);
+        }
+        if !notes.is_empty() {
+            result.insert("notes".to_string(), json!(notes));
+        }
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
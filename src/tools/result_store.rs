@@ -0,0 +1,109 @@
+use crate::types::{CallToolResponse, ToolContent};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+pub(crate) const DEFAULT_PAGE_SIZE: usize = 10_000;
+
+/// Short-lived storage for tool output too large to return in one response.
+///
+/// Entries are keyed by a random handle and dropped once their TTL passes.
+#[derive(Clone)]
+pub(crate) struct ResultStore {
+    entries: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+    ttl: Duration,
+}
+
+impl Default for ResultStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl ResultStore {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    /// Store `text` and return the handle it can be fetched with.
+    pub(crate) fn insert(&self, text: String) -> String {
+        let handle = uuid::Uuid::new_v4().to_string();
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        entries.retain(|_, (_, expires)| *expires > now);
+        entries.insert(handle.clone(), (text, now + self.ttl));
+        handle
+    }
+
+    pub(crate) fn get(&self, handle: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .get(handle)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(text, _)| text.clone())
+    }
+}
+
+/// A page of stored output starting at `offset` bytes
+struct Page<'a> {
+    text: &'a str,
+    next_offset: Option<usize>,
+}
+
+/// Slice up to `size` bytes of `text` starting at `offset`, snapping to char boundaries.
+fn page(text: &str, offset: usize, size: usize) -> Page<'_> {
+    let mut start = offset.min(text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = start.saturating_add(size.max(1)).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == start && start < text.len() {
+        // Always make progress, even if a single char is wider than the page
+        end = start + text[start..].chars().next().map_or(1, char::len_utf8);
+    }
+    Page {
+        text: &text[start..end],
+        next_offset: (end < text.len()).then_some(end),
+    }
+}
+
+/// Build a response holding one page of `text`, plus a `next` handle when more remains.
+pub(crate) fn paged_response(
+    store: &ResultStore,
+    handle: Option<&str>,
+    text: &str,
+    offset: usize,
+    size: usize,
+) -> CallToolResponse {
+    let page = page(text, offset, size);
+    let mut content = vec![ToolContent::Text {
+        text: page.text.to_string(),
+    }];
+
+    if let Some(next_offset) = page.next_offset {
+        let handle = match handle {
+            Some(handle) => handle.to_string(),
+            None => store.insert(text.to_string()),
+        };
+        content.push(ToolContent::Text {
+            text: json!({
+                "next": format!("{}:{}", handle, next_offset),
+                "total_bytes": text.len(),
+            })
+            .to_string(),
+        });
+    }
+
+    CallToolResponse {
+        content,
+        is_error: None,
+    }
+}
@@ -0,0 +1,253 @@
+use super::ToolHandler;
+use crate::sandbox::{no_follow, Sandbox};
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+const MAX_FILES: usize = 500;
+const MAX_TOTAL_BYTES: usize = 10 * 1024 * 1024;
+
+// Templated multi-file scaffold tool
+pub(crate) struct ScaffoldTool {
+    sandbox: Sandbox,
+}
+
+impl ScaffoldTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+/// Replace `{{name}}` placeholders, recording any name without a value
+fn render(template: &str, variables: &Map<String, Value>, missing: &mut BTreeSet<String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        match variables.get(name) {
+            Some(Value::String(value)) => output.push_str(value),
+            Some(value) => output.push_str(&value.to_string()),
+            None => {
+                missing.insert(name.to_string());
+            }
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Template paths must stay below the destination
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// `std::fs::write`, refusing to write through a symlink
+fn write_no_follow(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    no_follow(OpenOptions::new().write(true).create(true).truncate(true))
+        .open(path)?
+        .write_all(content)
+}
+
+/// Everything written so far, so a failed scaffold can be undone
+#[derive(Default)]
+struct Written {
+    dirs: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    /// Original contents of files replaced under `force`
+    replaced: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Written {
+    fn create_parents(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut missing: Vec<&Path> = path.ancestors().skip(1).take_while(|p| !p.exists()).collect();
+        missing.reverse();
+        for dir in missing {
+            std::fs::create_dir(dir)?;
+            self.dirs.push(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> std::io::Result<()> {
+        self.create_parents(path)?;
+        if path.exists() {
+            let original = std::fs::read(path)?;
+            self.replaced.push((path.to_path_buf(), original));
+            write_no_follow(path, content.as_bytes())?;
+        } else {
+            self.files.push(path.to_path_buf());
+            write_no_follow(path, content.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Best-effort undo, newest changes first
+    fn rollback(self) {
+        for path in self.files.iter().rev() {
+            let _ = std::fs::remove_file(path);
+        }
+        for (path, original) in self.replaced.iter().rev() {
+            let _ = write_no_follow(path, original);
+        }
+        for dir in self.dirs.iter().rev() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ScaffoldTool {
+    fn description(&self) -> String {
+        "Create several files from templates with {{var}} placeholders, rolling back on failure".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "destination": {
+                    "type": "string",
+                    "description": "Directory to create the files under",
+                    "default": "."
+                },
+                "files": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Map of relative path to file content; both may contain {{var}} placeholders"
+                },
+                "variables": {
+                    "type": "object",
+                    "description": "Values substituted for {{var}} placeholders"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Overwrite files that already exist",
+                    "default": false
+                }
+            },
+            "required": ["files"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let files = args.get("files")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| anyhow::anyhow!("Files are required"))?;
+
+        let destination = args.get("destination")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let force = args.get("force")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let empty = Map::new();
+        let variables = args.get("variables")
+            .and_then(|v| v.as_object())
+            .unwrap_or(&empty);
+
+        if files.is_empty() {
+            return Ok(CallToolResponse::error("No files to scaffold"));
+        }
+        if files.len() > MAX_FILES {
+            return Ok(CallToolResponse::error(format!(
+                "Too many files ({}, max: {})",
+                files.len(),
+                MAX_FILES
+            )));
+        }
+
+        // Render everything before touching the filesystem
+        let mut missing = BTreeSet::new();
+        let mut rendered = Vec::with_capacity(files.len());
+        let mut total_bytes = 0;
+        for (path, content) in files {
+            let Some(content) = content.as_str() else {
+                return Ok(CallToolResponse::error(format!("Content for '{}' must be a string", path)));
+            };
+            let path = render(path, variables, &mut missing);
+            let content = render(content, variables, &mut missing);
+            if !is_safe_relative(&path) {
+                return Ok(CallToolResponse::error(format!(
+                    "Template path '{}' must be relative and may not contain '..'",
+                    path
+                )));
+            }
+            total_bytes += content.len();
+            rendered.push((path, content));
+        }
+        if !missing.is_empty() {
+            let names: Vec<String> = missing.into_iter().collect();
+            return Ok(CallToolResponse::error(format!("Missing variables: {}", names.join(", "))));
+        }
+        if total_bytes > MAX_TOTAL_BYTES {
+            return Ok(CallToolResponse::error(format!(
+                "Scaffold too large ({} bytes, max: {} bytes)",
+                total_bytes,
+                MAX_TOTAL_BYTES
+            )));
+        }
+
+        let mut targets = Vec::with_capacity(rendered.len());
+        let mut conflicts = Vec::new();
+        for (relative, content) in rendered {
+            let target = match self.sandbox.resolve_new(&format!("{}/{}", destination, relative)) {
+                Ok(target) => target,
+                Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+            };
+            if target.is_dir() {
+                return Ok(CallToolResponse::error(format!("'{}' is an existing directory", relative)));
+            }
+            if target.exists() && !force {
+                conflicts.push(relative.clone());
+            }
+            targets.push((relative, target, content));
+        }
+        if !conflicts.is_empty() {
+            return Ok(CallToolResponse::error(format!(
+                "Refusing to overwrite existing files (set force to replace them): {}",
+                conflicts.join(", ")
+            )));
+        }
+
+        let mut written = Written::default();
+        let mut created = Vec::new();
+        let mut overwritten = Vec::new();
+        for (relative, target, content) in &targets {
+            let existed = target.exists();
+            if let Err(e) = written.write(target, content) {
+                written.rollback();
+                return Ok(CallToolResponse::error(format!(
+                    "Error writing '{}': {}. All changes were rolled back",
+                    relative, e
+                )));
+            }
+            if existed {
+                overwritten.push(relative);
+            } else {
+                created.push(relative);
+            }
+        }
+
+        let result = json!({
+            "destination": destination,
+            "created": created,
+            "overwritten": overwritten,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,369 @@
+use super::{ToolContext, ToolHandler, ToolRegistry};
+use crate::client::ClientHandle;
+use crate::notifications::Notifier;
+use crate::types::{CallToolRequest, CallToolResponse};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const MAX_TASKS: usize = 16;
+const MIN_INTERVAL_SECONDS: f64 = 1.0;
+/// Longest delay or interval a task may ask for, 30 days
+const MAX_SECONDS: f64 = 30.0 * 24.0 * 3600.0;
+
+/// Notification emitted after every run of a scheduled task
+pub const TASK_RESULT_NOTIFICATION: &str = "notifications/tasks/result";
+
+struct ScheduledTask {
+    tool: String,
+    arguments: Value,
+    delay: Duration,
+    interval: Option<Duration>,
+    handle: JoinHandle<()>,
+}
+
+type TaskMap = Arc<Mutex<HashMap<String, ScheduledTask>>>;
+
+/// Forgets a task however its future ends, including by panicking or being
+/// aborted, so it can't hold a slot forever
+struct TaskGuard {
+    tasks: TaskMap,
+    id: String,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner).remove(&self.id);
+    }
+}
+
+/// Runs tool calls after a delay or on an interval and reports each result
+/// as a notification. Pending tasks are aborted when the scheduler is dropped.
+pub(crate) struct TaskScheduler {
+    tasks: TaskMap,
+    /// The tools tasks may invoke, with the timeouts and stats of the
+    /// registry they came from
+    runnable: ToolRegistry,
+    notifier: Notifier,
+}
+
+impl TaskScheduler {
+    /// `runnable` holds the tools that tasks are allowed to invoke; runs are
+    /// dispatched through it like any other call
+    pub(crate) fn new(runnable: ToolRegistry, notifier: Notifier) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+            runnable,
+            notifier,
+        }
+    }
+
+    fn tasks(&self) -> std::sync::MutexGuard<'_, HashMap<String, ScheduledTask>> {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs happen on behalf of `client`, so they stay inside its roots
+    fn schedule(
+        &self,
+        tool: &str,
+        arguments: Value,
+        delay: Duration,
+        interval: Option<Duration>,
+        client: ClientHandle,
+    ) -> Result<String, String> {
+        if !self.runnable.tools.contains_key(tool) {
+            let mut names: Vec<&str> = self.runnable.tools.keys().map(String::as_str).collect();
+            names.sort();
+            return Err(format!(
+                "Tool '{}' cannot be scheduled. Schedulable tools: {}",
+                tool,
+                names.join(", ")
+            ));
+        }
+
+        let mut tasks = self.tasks();
+        if tasks.len() >= MAX_TASKS {
+            return Err(format!("Too many scheduled tasks (max: {})", MAX_TASKS));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let task_id = id.clone();
+        let task_tool = tool.to_string();
+        let task_arguments = arguments.clone();
+        let runnable = self.runnable.clone();
+        let notifier = self.notifier.clone();
+        let guard = TaskGuard {
+            tasks: self.tasks.clone(),
+            id: id.clone(),
+        };
+
+        let handle = tokio::spawn(async move {
+            let _guard = guard;
+            tokio::time::sleep(delay).await;
+            let mut interval = interval.map(|period| {
+                let mut interval = tokio::time::interval(period);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            });
+
+            let mut run = 0u64;
+            loop {
+                if let Some(interval) = interval.as_mut() {
+                    interval.tick().await;
+                }
+                run += 1;
+                let context = ToolContext {
+                    client: client.clone(),
+                    ..ToolContext::default()
+                };
+                let request = CallToolRequest {
+                    name: task_tool.clone(),
+                    arguments: Some(task_arguments.clone()),
+                };
+                let result = match runnable.call_tool_with_context(request, context).await {
+                    Ok(response) => serde_json::to_value(response).unwrap_or(Value::Null),
+                    Err(e) => json!({ "error": e.to_string() }),
+                };
+                notifier.notify(
+                    TASK_RESULT_NOTIFICATION,
+                    Some(json!({
+                        "taskId": task_id,
+                        "tool": task_tool,
+                        "run": run,
+                        "result": result,
+                    })),
+                );
+                if interval.is_none() {
+                    break;
+                }
+            }
+        });
+
+        tasks.insert(
+            id.clone(),
+            ScheduledTask {
+                tool: tool.to_string(),
+                arguments,
+                delay,
+                interval,
+                handle,
+            },
+        );
+        Ok(id)
+    }
+
+    fn list(&self) -> Vec<Value> {
+        let mut list: Vec<Value> = self
+            .tasks()
+            .iter()
+            .map(|(id, task)| {
+                json!({
+                    "id": id,
+                    "tool": task.tool,
+                    "arguments": task.arguments,
+                    "delay_seconds": task.delay.as_secs_f64(),
+                    "interval_seconds": task.interval.map(|interval| interval.as_secs_f64()),
+                })
+            })
+            .collect();
+        list.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+        list
+    }
+
+    fn cancel(&self, id: &str) -> bool {
+        let task = self.tasks().remove(id);
+        match task {
+            Some(task) => {
+                task.handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for TaskScheduler {
+    fn drop(&mut self) {
+        let tasks: Vec<ScheduledTask> = self.tasks().drain().map(|(_, task)| task).collect();
+        for task in tasks {
+            task.handle.abort();
+        }
+    }
+}
+
+/// `secs` as a duration if it lies between `min` and `MAX_SECONDS`
+fn seconds_within(secs: f64, min: f64) -> Option<Duration> {
+    if !(min..=MAX_SECONDS).contains(&secs) {
+        return None;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+// Schedule a delayed or recurring tool call
+pub(crate) struct ScheduleTaskTool {
+    scheduler: Arc<TaskScheduler>,
+}
+
+impl ScheduleTaskTool {
+    pub(crate) fn new(scheduler: Arc<TaskScheduler>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ScheduleTaskTool {
+    fn description(&self) -> String {
+        format!(
+            "Run a tool after a delay or on an interval; each result is sent as a {} notification",
+            TASK_RESULT_NOTIFICATION
+        )
+    }
+
+    fn input_schema(&self) -> Value {
+        let mut tools: Vec<&str> = self.scheduler.runnable.tools.keys().map(String::as_str).collect();
+        tools.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "tool": {
+                    "type": "string",
+                    "enum": tools,
+                    "description": "Tool to run"
+                },
+                "arguments": {
+                    "type": "object",
+                    "description": "Arguments passed to the tool on every run"
+                },
+                "delay_seconds": {
+                    "type": "number",
+                    "description": "Seconds to wait before the first run",
+                    "default": 0
+                },
+                "interval_seconds": {
+                    "type": "number",
+                    "description": "Repeat every N seconds (minimum 1); omit for a one-shot task"
+                }
+            },
+            "required": ["tool"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        self.call_with_context(args, ToolContext::default()).await
+    }
+
+    async fn call_with_context(&self, args: Value, context: ToolContext) -> Result<CallToolResponse> {
+        let tool = args.get("tool")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Tool is required"))?;
+
+        let arguments = args.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        let delay_seconds = args.get("delay_seconds")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0);
+
+        let interval_seconds = args.get("interval_seconds").and_then(|v| v.as_f64());
+
+        let Some(delay) = seconds_within(delay_seconds, 0.0) else {
+            return Ok(CallToolResponse::error(format!(
+                "delay_seconds must be between 0 and {}",
+                MAX_SECONDS
+            )));
+        };
+        let interval = match interval_seconds.map(|secs| seconds_within(secs, MIN_INTERVAL_SECONDS)) {
+            Some(None) => {
+                return Ok(CallToolResponse::error(format!(
+                    "interval_seconds must be between {} and {}",
+                    MIN_INTERVAL_SECONDS, MAX_SECONDS
+                )));
+            }
+            Some(Some(interval)) => Some(interval),
+            None => None,
+        };
+
+        match self.scheduler.schedule(tool, arguments, delay, interval, context.client) {
+            Ok(id) => Ok(CallToolResponse::text(
+                json!({ "task_id": id, "recurring": interval_seconds.is_some() }).to_string(),
+            )),
+            Err(e) => Ok(CallToolResponse::error(e)),
+        }
+    }
+}
+
+// List pending scheduled tasks
+pub(crate) struct ListTasksTool {
+    scheduler: Arc<TaskScheduler>,
+}
+
+impl ListTasksTool {
+    pub(crate) fn new(scheduler: Arc<TaskScheduler>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ListTasksTool {
+    fn description(&self) -> String {
+        "List scheduled tasks that have not finished or been cancelled".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {},
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, _args: Value) -> Result<CallToolResponse> {
+        let tasks = self.scheduler.list();
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&json!({ "tasks": tasks }))?))
+    }
+}
+
+// Cancel a scheduled task
+pub(crate) struct CancelTaskTool {
+    scheduler: Arc<TaskScheduler>,
+}
+
+impl CancelTaskTool {
+    pub(crate) fn new(scheduler: Arc<TaskScheduler>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for CancelTaskTool {
+    fn description(&self) -> String {
+        "Cancel a scheduled task".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "task_id": {
+                    "type": "string",
+                    "description": "Id returned by schedule_task"
+                }
+            },
+            "required": ["task_id"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let task_id = args.get("task_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Task id is required"))?;
+
+        if self.scheduler.cancel(task_id) {
+            Ok(CallToolResponse::text(format!("Cancelled task {}", task_id)))
+        } else {
+            Ok(CallToolResponse::error(format!("No scheduled task with id '{}'", task_id)))
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use semver::{Version, VersionReq};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+
+// Semantic version tool
+pub(crate) struct SemverTool;
+
+/// Parse a version, tolerating surrounding whitespace and a leading `v`
+fn parse_version(input: &str) -> Result<Version, String> {
+    let trimmed = input.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    Version::parse(trimmed).map_err(|e| format!("Invalid version '{}': {}", input, e))
+}
+
+fn describe(version: &Version) -> Value {
+    json!({
+        "version": version.to_string(),
+        "major": version.major,
+        "minor": version.minor,
+        "patch": version.patch,
+        "pre": (!version.pre.is_empty()).then(|| version.pre.as_str()),
+        "build": (!version.build.is_empty()).then(|| version.build.as_str()),
+        "is_prerelease": !version.pre.is_empty(),
+    })
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for SemverTool {
+    fn description(&self) -> String {
+        "Parse semantic versions, compare two versions or check a version against a range".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["parse", "compare", "satisfies"],
+                    "description": "What to do with the version",
+                    "default": "parse"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Version to parse, e.g. 1.2.3-beta.1+build.5"
+                },
+                "other": {
+                    "type": "string",
+                    "description": "Version to compare against (compare)"
+                },
+                "range": {
+                    "type": "string",
+                    "description": "Cargo-style version requirement, e.g. >=1.2, <2.0 (satisfies)"
+                }
+            },
+            "required": ["version"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let input = args.get("version")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Version is required"))?;
+
+        let operation = args.get("operation")
+            .and_then(|v| v.as_str())
+            .unwrap_or("parse");
+
+        let version = match parse_version(input) {
+            Ok(version) => version,
+            Err(e) => return Ok(CallToolResponse::error(e)),
+        };
+
+        let result = match operation {
+            "parse" => describe(&version),
+            "compare" => {
+                let other_input = args.get("other")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Other version is required for compare"))?;
+                let other = match parse_version(other_input) {
+                    Ok(other) => other,
+                    Err(e) => return Ok(CallToolResponse::error(e)),
+                };
+
+                // Precedence per the semver spec: build metadata is ignored
+                let ordering = version.cmp_precedence(&other);
+                json!({
+                    "version": version.to_string(),
+                    "other": other.to_string(),
+                    "ordering": match ordering {
+                        Ordering::Less => "less",
+                        Ordering::Equal => "equal",
+                        Ordering::Greater => "greater",
+                    },
+                    "less": ordering == Ordering::Less,
+                    "equal": ordering == Ordering::Equal,
+                    "greater": ordering == Ordering::Greater,
+                })
+            }
+            "satisfies" => {
+                let range_input = args.get("range")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Range is required for satisfies"))?;
+                let range = match VersionReq::parse(range_input.trim()) {
+                    Ok(range) => range,
+                    Err(e) => {
+                        return Ok(CallToolResponse::error(format!("Invalid range '{}': {}", range_input, e)));
+                    }
+                };
+
+                json!({
+                    "version": version.to_string(),
+                    "range": range.to_string(),
+                    "satisfies": range.matches(&version),
+                })
+            }
+            other => {
+                return Ok(CallToolResponse::error(format!(
+                    "Unknown operation '{}'. Supported operations: parse, compare, satisfies",
+                    other
+                )));
+            }
+        };
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,34 @@
+use super::ToolHandler;
+use crate::stats::Stats;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+// Request and tool call counters tool
+pub(crate) struct ServerStatsTool {
+    stats: Stats,
+}
+
+impl ServerStatsTool {
+    pub(crate) fn new(stats: Stats) -> Self {
+        Self { stats }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for ServerStatsTool {
+    fn description(&self) -> String {
+        "Report how many requests the server has handled, per method, and how often each tool was called and failed".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn call(&self, _args: Value) -> Result<CallToolResponse> {
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&self.stats.snapshot())?))
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+/// Follow-up tools worth offering after each tool, most useful first.
+/// `search_files` is not built in but is a common custom tool.
+const DEFAULT_SUGGESTIONS: &[(&str, &[&str])] = &[
+    ("search_files", &["read_file", "read_file_smart"]),
+    ("list_files", &["read_file", "read_file_smart", "recently_modified"]),
+    ("project_overview", &["list_files", "read_file", "recently_modified"]),
+    ("recently_modified", &["read_file", "read_file_smart"]),
+    ("find_duplicates", &["read_file", "cache_key"]),
+    ("find_todos", &["read_file"]),
+    ("read_file", &["chunk_text", "transform_text"]),
+    ("read_file_smart", &["chunk_text", "transform_text"]),
+    ("execute_command", &["output_page"]),
+    ("query_jsonl", &["chart"]),
+    ("parse_dockerfile", &["parse_compose"]),
+    ("parse_compose", &["parse_dockerfile"]),
+    ("parse_url", &["fetch_url", "tcp_ping", "tls_inspect"]),
+    ("fetch_url", &["chunk_text", "transform_text"]),
+    ("tcp_ping", &["tls_inspect"]),
+    ("schedule_task", &["list_tasks"]),
+    ("list_tasks", &["cancel_task"]),
+];
+
+/// The built-in suggestions, with each configured entry replacing the
+/// built-in one for the same tool
+pub(super) fn suggestions(overrides: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    let mut suggestions: HashMap<String, Vec<String>> = DEFAULT_SUGGESTIONS
+        .iter()
+        .map(|(tool, next)| (tool.to_string(), next.iter().map(|n| n.to_string()).collect()))
+        .collect();
+    suggestions.extend(overrides.iter().map(|(tool, next)| (tool.clone(), next.clone())));
+    suggestions
+}
@@ -0,0 +1,115 @@
+use super::{ToolContext, ToolHandler};
+use crate::sandbox::Sandbox;
+use crate::types::{CallToolResponse, CreateMessageRequest, Role, SamplingMessage, ToolContent};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Larger files would crowd the client model's context
+const MAX_FILE_BYTES: u64 = 200 * 1024;
+const DEFAULT_MAX_TOKENS: u64 = 500;
+const MAX_TOKENS_LIMIT: u64 = 4000;
+
+const SYSTEM_PROMPT: &str = "You summarize files for a developer. Be concise and concrete: \
+    say what the file is for, its main parts, and anything surprising. Don't repeat the file.";
+
+// Summarizes a file with the client's own model, through sampling
+pub(crate) struct SummarizeFileTool {
+    sandbox: Sandbox,
+}
+
+impl SummarizeFileTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for SummarizeFileTool {
+    fn description(&self) -> String {
+        "Summarize a text file using the client's model (requires a client that supports sampling)".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file to summarize"
+                },
+                "focus": {
+                    "type": "string",
+                    "description": "What the summary should concentrate on, e.g. 'error handling'"
+                },
+                "max_tokens": {
+                    "type": "integer",
+                    "description": "Longest summary to ask the model for, in tokens",
+                    "default": DEFAULT_MAX_TOKENS,
+                    "minimum": 1,
+                    "maximum": MAX_TOKENS_LIMIT
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        self.call_with_context(args, ToolContext::default()).await
+    }
+
+    async fn call_with_context(&self, args: Value, context: ToolContext) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+        let focus = args.get("focus").and_then(|v| v.as_str());
+        let max_tokens = args.get("max_tokens")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_TOKENS)
+            .clamp(1, MAX_TOKENS_LIMIT);
+
+        if !context.client.supports_sampling() {
+            return Ok(CallToolResponse::error(
+                "The client does not support sampling, so there is no model to summarize with",
+            ));
+        }
+
+        let resolved = match self.sandbox.resolve(path) {
+            Ok(resolved) => resolved,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        let size = match std::fs::metadata(&resolved) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => return Ok(CallToolResponse::error(format!("Error accessing file: {}", e))),
+        };
+        if size > MAX_FILE_BYTES {
+            return Ok(CallToolResponse::error(format!(
+                "File is too large to summarize ({} bytes, max: {} bytes)",
+                size, MAX_FILE_BYTES
+            )));
+        }
+        let text = match std::fs::read_to_string(&resolved) {
+            Ok(text) => text,
+            Err(e) => return Ok(CallToolResponse::error(format!("Error reading file as text: {}", e))),
+        };
+
+        let focus = focus.map(|focus| format!(", focusing on {}", focus)).unwrap_or_default();
+        let request = CreateMessageRequest {
+            messages: vec![SamplingMessage {
+                role: Role::User,
+                content: ToolContent::Text {
+                    text: format!("Summarize the file {}{}:\n\n```\n{}\n```", path, focus, text),
+                },
+            }],
+            system_prompt: Some(SYSTEM_PROMPT.to_string()),
+            max_tokens: max_tokens as u32,
+            temperature: None,
+            stop_sequences: None,
+            model_preferences: Some(json!({"intelligencePriority": 0.3, "speedPriority": 0.7})),
+        };
+        let result = context.client.create_message(request).await?;
+        match result.content {
+            ToolContent::Text { text } => Ok(CallToolResponse::text(text)),
+            _ => Ok(CallToolResponse::error(format!("Model {} did not answer with text", result.model))),
+        }
+    }
+}
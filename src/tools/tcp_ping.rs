@@ -0,0 +1,99 @@
+use super::{is_host_allowed, ToolHandler};
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+// TCP connectivity check
+pub(crate) struct TcpPingTool {
+    allowed_hosts: Vec<String>,
+}
+
+impl TcpPingTool {
+    pub(crate) fn new(allowed_hosts: Vec<String>) -> Self {
+        Self { allowed_hosts }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for TcpPingTool {
+    fn description(&self) -> String {
+        "Check whether a TCP connection can be opened to a host and port".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "host": {
+                    "type": "string",
+                    "description": "Host name or IP address to connect to"
+                },
+                "port": {
+                    "type": "integer",
+                    "description": "TCP port to connect to",
+                    "minimum": 1,
+                    "maximum": 65535
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "description": "Connect timeout in milliseconds",
+                    "default": DEFAULT_TIMEOUT_MS
+                }
+            },
+            "required": ["host", "port"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let host = args.get("host")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Host is required"))?;
+
+        let port = args.get("port")
+            .and_then(|v| v.as_u64())
+            .filter(|p| (1..=65535).contains(p))
+            .ok_or_else(|| anyhow::anyhow!("Port must be an integer between 1 and 65535"))?
+            as u16;
+
+        let timeout_ms = args.get("timeout_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_MS)
+            .min(MAX_TIMEOUT_MS);
+
+        if !is_host_allowed(&self.allowed_hosts, host) {
+            return Ok(CallToolResponse::error(format!(
+                "Host '{}' is not in the allowed host list",
+                host
+            )));
+        }
+
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            TcpStream::connect((host, port)),
+        )
+        .await;
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let (reachable, error) = match outcome {
+            Ok(Ok(_)) => (true, None),
+            Ok(Err(e)) => (false, Some(e.to_string())),
+            Err(_) => (false, Some(format!("Connection timed out after {} ms", timeout_ms))),
+        };
+
+        let result = json!({
+            "host": host,
+            "port": port,
+            "reachable": reachable,
+            "latency_ms": if reachable { Some(latency_ms) } else { None },
+            "error": error,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
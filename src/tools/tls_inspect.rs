@@ -0,0 +1,222 @@
+use super::{is_host_allowed, ToolHandler};
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider, WebPkiSupportedAlgorithms};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+const DEFAULT_PORT: u16 = 443;
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+// TLS certificate inspection tool
+pub(crate) struct TlsInspectTool {
+    allowed_hosts: Vec<String>,
+}
+
+impl TlsInspectTool {
+    pub(crate) fn new(allowed_hosts: Vec<String>) -> Self {
+        Self { allowed_hosts }
+    }
+}
+
+/// Accepts any certificate chain so it can be inspected, while still checking
+/// that the handshake signatures were made with the presented certificate.
+#[derive(Debug)]
+struct InspectOnlyVerifier {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for InspectOnlyVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+fn describe_certificate(der: &CertificateDer<'_>) -> Value {
+    let cert = match X509Certificate::from_der(der.as_ref()) {
+        Ok((_, cert)) => cert,
+        Err(e) => return json!({ "error": format!("Unparseable certificate: {}", e) }),
+    };
+
+    let san: Vec<String> = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|ext| {
+            ext.value
+                .general_names
+                .iter()
+                .map(|name| match name {
+                    GeneralName::DNSName(dns) => format!("DNS:{}", dns),
+                    GeneralName::IPAddress(bytes) => match bytes.len() {
+                        4 => format!("IP:{}", std::net::Ipv4Addr::from(<[u8; 4]>::try_from(*bytes).unwrap())),
+                        16 => format!("IP:{}", std::net::Ipv6Addr::from(<[u8; 16]>::try_from(*bytes).unwrap())),
+                        _ => "IP:<invalid>".to_string(),
+                    },
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let validity = cert.validity();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let days_until_expiry = (validity.not_after.timestamp() - now).div_euclid(86_400);
+
+    json!({
+        "subject": cert.subject().to_string(),
+        "issuer": cert.issuer().to_string(),
+        "serial": cert.raw_serial_as_string(),
+        "subject_alt_names": san,
+        "not_before": validity.not_before.to_rfc2822().unwrap_or_default(),
+        "not_after": validity.not_after.to_rfc2822().unwrap_or_default(),
+        "days_until_expiry": days_until_expiry,
+        "currently_valid": validity.is_valid(),
+        "self_signed": cert.subject() == cert.issuer(),
+    })
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for TlsInspectTool {
+    fn description(&self) -> String {
+        "Connect to a TLS server and report its certificate chain without validating it".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "host": {
+                    "type": "string",
+                    "description": "Host name or IP address to connect to"
+                },
+                "port": {
+                    "type": "integer",
+                    "description": "TLS port",
+                    "default": DEFAULT_PORT
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "description": "Connect and handshake timeout in milliseconds",
+                    "default": DEFAULT_TIMEOUT_MS
+                }
+            },
+            "required": ["host"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let host = args.get("host")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Host is required"))?;
+
+        let port = args.get("port")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_PORT as u64);
+        let Ok(port) = u16::try_from(port) else {
+            return Ok(CallToolResponse::error(format!("Invalid port {}", port)));
+        };
+
+        let timeout_ms = args.get("timeout_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_MS)
+            .min(MAX_TIMEOUT_MS);
+
+        if !is_host_allowed(&self.allowed_hosts, host) {
+            return Ok(CallToolResponse::error(format!(
+                "Host '{}' is not in the allowed host list",
+                host
+            )));
+        }
+
+        let server_name = match ServerName::try_from(host.to_string()) {
+            Ok(name) => name,
+            Err(e) => return Ok(CallToolResponse::error(format!("Invalid host name: {}", e))),
+        };
+
+        let provider = Arc::new(crypto::ring::default_provider());
+        let verifier = Arc::new(InspectOnlyVerifier {
+            algorithms: provider.signature_verification_algorithms,
+        });
+        let config = ClientConfig::builder_with_provider(provider as Arc<CryptoProvider>)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_no_client_auth();
+        let connector = TlsConnector::from(Arc::new(config));
+
+        let handshake = async {
+            let stream = TcpStream::connect((host, port)).await?;
+            connector.connect(server_name, stream).await
+        };
+        let stream = match tokio::time::timeout(Duration::from_millis(timeout_ms), handshake).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => return Ok(CallToolResponse::error(format!("TLS connection failed: {}", e))),
+            Err(_) => {
+                return Ok(CallToolResponse::error(format!(
+                    "TLS connection timed out after {} ms",
+                    timeout_ms
+                )));
+            }
+        };
+
+        let (_, session) = stream.get_ref();
+        let chain: Vec<Value> = session
+            .peer_certificates()
+            .unwrap_or_default()
+            .iter()
+            .map(describe_certificate)
+            .collect();
+        let protocol = session.protocol_version().map(|v| format!("{:?}", v));
+
+        let result = json!({
+            "host": host,
+            "port": port,
+            "protocol": protocol,
+            "certificates": chain,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
@@ -0,0 +1,180 @@
+use super::ToolHandler;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const MAX_INPUT_BYTES: usize = 1024 * 1024;
+const DEFAULT_WRAP_WIDTH: usize = 80;
+
+const OPERATIONS: &[&str] = &[
+    "upper", "lower", "title", "trim", "dedent", "wrap", "snake", "camel", "pascal", "kebab",
+];
+
+// Text transformation tool
+pub(crate) struct TransformTextTool;
+
+/// Split an identifier or phrase into lowercase words, breaking on
+/// separators and lower-to-upper case transitions (`fooBar` -> `foo`, `bar`).
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = text.chars().collect();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // Break on `aB`, and on the last capital of an acronym (`HTTPServer` -> `http`, `server`)
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn title_case(text: &str) -> String {
+    text.split(' ')
+        .map(|word| capitalize(&word.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Remove the longest common leading whitespace from all non-blank lines
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Greedy word wrap; paragraphs separated by blank lines are preserved
+fn wrap(text: &str, width: usize) -> String {
+    text.split("\n\n")
+        .map(|paragraph| {
+            let mut lines = Vec::new();
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for TransformTextTool {
+    fn description(&self) -> String {
+        "Transform text: change case, trim, dedent, wrap, or convert between snake/camel/kebab case"
+            .to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Text to transform"
+                },
+                "operation": {
+                    "type": "string",
+                    "enum": OPERATIONS,
+                    "description": "Transformation to apply"
+                },
+                "width": {
+                    "type": "integer",
+                    "description": "Line width for the wrap operation",
+                    "default": DEFAULT_WRAP_WIDTH
+                }
+            },
+            "required": ["text", "operation"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let text = args.get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Text is required"))?;
+
+        let operation = args.get("operation")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Operation is required"))?;
+
+        if text.len() > MAX_INPUT_BYTES {
+            return Ok(CallToolResponse::error(format!(
+                "Text is too large ({} bytes, max: {} bytes)",
+                text.len(),
+                MAX_INPUT_BYTES
+            )));
+        }
+
+        let result = match operation {
+            "upper" => text.to_uppercase(),
+            "lower" => text.to_lowercase(),
+            "title" => title_case(text),
+            "trim" => text.trim().to_string(),
+            "dedent" => dedent(text),
+            "wrap" => {
+                let width = args.get("width")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(DEFAULT_WRAP_WIDTH as u64)
+                    .max(1) as usize;
+                wrap(text, width)
+            }
+            "snake" => words(text).join("_"),
+            "kebab" => words(text).join("-"),
+            "camel" => {
+                let words = words(text);
+                let mut iter = words.iter();
+                let first = iter.next().cloned().unwrap_or_default();
+                first + &iter.map(|w| capitalize(w)).collect::<String>()
+            }
+            "pascal" => words(text).iter().map(|w| capitalize(w)).collect(),
+            other => {
+                return Ok(CallToolResponse::error(format!(
+                    "Unknown operation '{}'. Expected one of: {}",
+                    other,
+                    OPERATIONS.join(", ")
+                )));
+            }
+        };
+
+        Ok(CallToolResponse::text(result))
+    }
+}
@@ -0,0 +1,136 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Directories that scanning tools skip unless asked otherwise
+pub(crate) const DEFAULT_SKIP_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Limits for a directory walk
+pub(crate) struct WalkOptions<'a> {
+    pub max_depth: usize,
+    pub max_entries: usize,
+    pub skip_dirs: &'a [&'a str],
+    /// Leave out paths matched by `.gitignore` files in the root and below
+    pub respect_gitignore: bool,
+}
+
+/// A file or directory found during a walk
+pub(crate) struct WalkEntry {
+    pub path: PathBuf,
+    /// Path relative to the walk root, using `/` separators
+    pub relative: String,
+    pub metadata: Metadata,
+}
+
+pub(crate) struct WalkResult {
+    pub entries: Vec<WalkEntry>,
+    /// Set when `max_entries` stopped the walk early
+    pub truncated: bool,
+}
+
+/// The `.gitignore` files that apply in a directory, outermost first
+type IgnoreChain = Vec<Arc<Gitignore>>;
+
+/// `parent`'s rules plus those of `dir`'s own `.gitignore`, if it has one
+fn with_gitignore(dir: &Path, parent: &IgnoreChain) -> IgnoreChain {
+    let mut chain = parent.clone();
+    let file = dir.join(".gitignore");
+    if file.is_file() {
+        let mut builder = GitignoreBuilder::new(dir);
+        // Lines that fail to parse are skipped; the rest still apply
+        if let Some(e) = builder.add(&file) {
+            debug!("Problem in {}: {}", file.display(), e);
+        }
+        match builder.build() {
+            Ok(gitignore) => chain.push(Arc::new(gitignore)),
+            Err(e) => debug!("Ignoring {}: {}", file.display(), e),
+        }
+    }
+    chain
+}
+
+/// Whether the innermost `.gitignore` with a matching pattern ignores the
+/// path; a `!pattern` in a nested file re-includes what an outer one ignored
+fn is_ignored(chain: &IgnoreChain, path: &Path, is_dir: bool) -> bool {
+    for gitignore in chain.iter().rev() {
+        match gitignore.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+    }
+    false
+}
+
+/// Breadth-first walk below `root`.
+///
+/// Symlinks are reported but never followed, so links cannot lead the walk
+/// outside the root or into a cycle. Unreadable directories are skipped, as
+/// are ignored paths when `respect_gitignore` is set; an ignored directory
+/// is not descended into.
+pub(crate) fn walk(root: &Path, options: &WalkOptions) -> WalkResult {
+    let mut entries = Vec::new();
+    let mut queue = std::collections::VecDeque::from([(root.to_path_buf(), 0, IgnoreChain::new())]);
+
+    while let Some((dir, depth, parent_rules)) = queue.pop_front() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let rules = if options.respect_gitignore {
+            with_gitignore(&dir, &parent_rules)
+        } else {
+            parent_rules
+        };
+        let mut children: Vec<_> = read_dir.flatten().collect();
+        children.sort_by_key(|entry| entry.file_name());
+
+        for child in children {
+            if entries.len() >= options.max_entries {
+                return WalkResult {
+                    entries,
+                    truncated: true,
+                };
+            }
+            let Ok(metadata) = std::fs::symlink_metadata(child.path()) else {
+                continue;
+            };
+            let path = child.path();
+            let name = child.file_name().to_string_lossy().to_string();
+            if metadata.is_dir() && options.skip_dirs.contains(&name.as_str()) {
+                continue;
+            }
+            if is_ignored(&rules, &path, metadata.is_dir()) {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if metadata.is_dir() && depth + 1 < options.max_depth {
+                queue.push_back((path.clone(), depth + 1, rules.clone()));
+            }
+            entries.push(WalkEntry {
+                path,
+                relative,
+                metadata,
+            });
+        }
+    }
+
+    WalkResult {
+        entries,
+        truncated: false,
+    }
+}
+
+/// Heuristic binary check: a NUL byte in the first 8KB
+pub(crate) fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|b| *b == 0)
+}
@@ -0,0 +1,112 @@
+use super::ToolHandler;
+use crate::sandbox::{no_follow, Sandbox};
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+
+const MAX_CONTENT_BYTES: usize = 10 * 1024 * 1024;
+
+// File writing tool
+pub(crate) struct WriteFileTool {
+    sandbox: Sandbox,
+}
+
+impl WriteFileTool {
+    pub(crate) fn new(sandbox: Sandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for WriteFileTool {
+    fn description(&self) -> String {
+        "Write text to a file, replacing it, appending to it or creating it only if it doesn't exist".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File to write; missing parent directories are created"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Text to write"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["overwrite", "append", "create_new"],
+                    "description": "overwrite replaces the file, append adds to its end, create_new fails if it already exists",
+                    "default": "overwrite"
+                }
+            },
+            "required": ["path", "content"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+
+        let content = args.get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Content is required"))?;
+
+        let mode = args.get("mode")
+            .and_then(|v| v.as_str())
+            .unwrap_or("overwrite");
+
+        if content.len() > MAX_CONTENT_BYTES {
+            return Ok(CallToolResponse::error(format!(
+                "Content is too large ({} bytes, max: {} bytes)",
+                content.len(),
+                MAX_CONTENT_BYTES
+            )));
+        }
+
+        let target = match self.sandbox.resolve_new(path) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+        if target.is_dir() {
+            return Ok(CallToolResponse::error(format!("'{}' is a directory", path)));
+        }
+
+        let mut options = OpenOptions::new();
+        no_follow(&mut options);
+        match mode {
+            "append" => options.append(true).create(true),
+            "create_new" => options.write(true).create_new(true),
+            _ => options.write(true).create(true).truncate(true),
+        };
+
+        let existed = target.exists();
+        if let Some(parent) = target.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Ok(CallToolResponse::error(format!("Error creating directories for '{}': {}", path, e)));
+            }
+        }
+        let written = options.open(&target).and_then(|mut file| file.write_all(content.as_bytes()));
+        match written {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                return Ok(CallToolResponse::error(format!("File '{}' already exists", path)));
+            }
+            Err(e) => return Ok(CallToolResponse::error(format!("Error writing file: {}", e))),
+        }
+
+        let result = json!({
+            "path": path,
+            "mode": mode,
+            "bytes_written": content.len(),
+            "created": !existed,
+        });
+
+        Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+    }
+}
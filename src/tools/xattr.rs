@@ -0,0 +1,185 @@
+use super::ToolHandler;
+use crate::sandbox::Sandbox;
+use crate::types::CallToolResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const MAX_NAME_LEN: usize = 255;
+
+// Extended attribute tool
+pub(crate) struct XattrTool {
+    sandbox: Sandbox,
+    read_only: bool,
+}
+
+impl XattrTool {
+    pub(crate) fn new(sandbox: Sandbox, read_only: bool) -> Self {
+        Self { sandbox, read_only }
+    }
+}
+
+/// Attribute names must be non-empty, NUL-free and, on Linux, namespaced (e.g. `user.`)
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("Attribute name must be 1-{} bytes long", MAX_NAME_LEN));
+    }
+    if name.contains('\0') {
+        return Err("Attribute name must not contain NUL bytes".to_string());
+    }
+    if cfg!(target_os = "linux") {
+        let namespaced = ["user.", "trusted.", "security.", "system."]
+            .iter()
+            .any(|ns| name.starts_with(ns) && name.len() > ns.len());
+        if !namespaced {
+            return Err(format!(
+                "Attribute name '{}' must start with a namespace such as 'user.'",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for XattrTool {
+    fn description(&self) -> String {
+        "List, read or set extended file attributes".to_string()
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File whose attributes to inspect"
+                },
+                "operation": {
+                    "type": "string",
+                    "enum": ["list", "get", "set"],
+                    "description": "Operation to perform",
+                    "default": "list"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Attribute name (required for get and set)"
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Attribute value (required for set)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn call(&self, args: Value) -> Result<CallToolResponse> {
+        let path = args.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Path is required"))?;
+
+        let operation = args.get("operation")
+            .and_then(|v| v.as_str())
+            .unwrap_or("list");
+
+        let path = match self.sandbox.resolve(path) {
+            Ok(path) => path,
+            Err(e) => return Ok(CallToolResponse::error(e.to_string())),
+        };
+
+        let name = args.get("name").and_then(|v| v.as_str());
+        if operation != "list" {
+            let Some(name) = name else {
+                return Ok(CallToolResponse::error(format!(
+                    "Attribute name is required for '{}'",
+                    operation
+                )));
+            };
+            if let Err(e) = validate_name(name) {
+                return Ok(CallToolResponse::error(e));
+            }
+        }
+
+        if operation == "set" && self.read_only {
+            return Ok(CallToolResponse::error(
+                "Setting attributes is disabled in read-only mode",
+            ));
+        }
+
+        let value = args.get("value").and_then(|v| v.as_str());
+        apply(&path, operation, name.unwrap_or_default(), value)
+    }
+}
+
+#[cfg(unix)]
+fn apply(path: &Path, operation: &str, name: &str, value: Option<&str>) -> Result<CallToolResponse> {
+    if !xattr::SUPPORTED_PLATFORM {
+        return Ok(unsupported());
+    }
+
+    match operation {
+        "list" => match xattr::list(path) {
+            Ok(names) => {
+                let names: Vec<String> = names
+                    .map(|n| n.to_string_lossy().to_string())
+                    .collect();
+                Ok(CallToolResponse::text(serde_json::to_string_pretty(
+                    &json!({ "path": path, "attributes": names }),
+                )?))
+            }
+            Err(e) => Ok(CallToolResponse::error(format!(
+                "Error listing attributes: {}",
+                e
+            ))),
+        },
+        "get" => {
+            match xattr::get(path, name) {
+                Ok(Some(value)) => {
+                    let result = match String::from_utf8(value) {
+                        Ok(text) => json!({ "name": name, "value": text }),
+                        Err(e) => json!({ "name": name, "value_bytes": e.into_bytes() }),
+                    };
+                    Ok(CallToolResponse::text(serde_json::to_string_pretty(&result)?))
+                }
+                Ok(None) => Ok(CallToolResponse::error(format!(
+                    "Attribute '{}' is not set",
+                    name
+                ))),
+                Err(e) => Ok(CallToolResponse::error(format!(
+                    "Error reading attribute: {}",
+                    e
+                ))),
+            }
+        }
+        "set" => {
+            let Some(value) = value else {
+                return Ok(CallToolResponse::error("Value is required for 'set'"));
+            };
+            match xattr::set(path, name, value.as_bytes()) {
+                Ok(()) => Ok(CallToolResponse::text(format!(
+                    "Set attribute '{}' on {}",
+                    name,
+                    path.display()
+                ))),
+                Err(e) => Ok(CallToolResponse::error(format!(
+                    "Error setting attribute: {}",
+                    e
+                ))),
+            }
+        }
+        other => Ok(CallToolResponse::error(format!(
+            "Unknown operation '{}'. Expected list, get or set",
+            other
+        ))),
+    }
+}
+
+#[cfg(not(unix))]
+fn apply(_path: &Path, _operation: &str, _name: &str, _value: Option<&str>) -> Result<CallToolResponse> {
+    Ok(unsupported())
+}
+
+fn unsupported() -> CallToolResponse {
+    CallToolResponse::error("Extended attributes are not supported on this platform")
+}
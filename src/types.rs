@@ -21,6 +21,15 @@ pub struct JsonRpcResponse {
     pub error: Option<JsonRpcError>,
 }
 
+/// JSON-RPC 2.0 notification (a message without an id that expects no response)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+}
+
 /// JSON-RPC 2.0 error
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
@@ -55,11 +64,13 @@ pub struct ServerCapabilities {
     pub prompts: Option<PromptsCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logging: Option<LoggingCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<CompletionsCapability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolsCapability {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
@@ -67,19 +78,22 @@ pub struct ToolsCapability {
 pub struct ResourcesCapability {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subscribe: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptsCapability {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingCapability {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionsCapability {}
+
 /// Initialize request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitializeRequest {
@@ -97,11 +111,20 @@ pub struct ClientCapabilities {
     pub experimental: Option<HashMap<String, serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<SamplingCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SamplingCapability {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootsCapability {
+    /// Whether the client sends `notifications/roots/list_changed`
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
 /// Initialize response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitializeResponse {
@@ -110,6 +133,9 @@ pub struct InitializeResponse {
     pub capabilities: ServerCapabilities,
     #[serde(rename = "serverInfo")]
     pub server_info: ServerInfo,
+    /// Hints on using the server, which clients may add to the model's context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
 }
 
 /// Tool definition
@@ -137,30 +163,253 @@ pub struct CallToolResponse {
     pub is_error: Option<bool>,
 }
 
+impl CallToolResponse {
+    /// Successful response with a single text block
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: vec![ToolContent::Text { text: text.into() }],
+            is_error: None,
+        }
+    }
+    
+    /// Tool-level failure with a single text block
+    pub fn error(text: impl Into<String>) -> Self {
+        Self {
+            content: vec![ToolContent::Text { text: text.into() }],
+            is_error: Some(true),
+        }
+    }
+}
+
 /// Tool content
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ToolContent {
     #[serde(rename = "text")]
     Text { text: String },
+    /// Base64-encoded image data
     #[serde(rename = "image")]
-    Image { data: String, mime_type: String },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// An embedded resource; binary data travels as a base64 `blob`
     #[serde(rename = "resource")]
-    Resource { resource: ResourceReference },
+    Resource { resource: ResourceContents },
 }
 
-/// Resource reference
+/// Resource definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResourceReference {
+pub struct Resource {
     pub uri: String,
+    pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub text: Option<String>,
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Resource contents, either UTF-8 text or base64-encoded bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ResourceContents {
+    Text {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        text: String,
+    },
+    Blob {
+        uri: String,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        blob: String,
+    },
+}
+
+/// List resources response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResourcesResponse {
+    pub resources: Vec<Resource>,
+}
+
+/// Read resource request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceRequest {
+    pub uri: String,
+}
+
+/// Subscribe or unsubscribe request for one resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeRequest {
+    pub uri: String,
+}
+
+/// Read resource response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceResponse {
+    pub contents: Vec<ResourceContents>,
+}
+
+/// Prompt definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Vec<PromptArgument>>,
+}
+
+/// Argument accepted by a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+/// Speaker of a prompt message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// Message produced by a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: Role,
+    pub content: ToolContent,
+}
+
+/// A message in a `sampling/createMessage` request or result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: Role,
+    pub content: ToolContent,
+}
+
+/// Params of `sampling/createMessage`, which asks the client's model for
+/// a completion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageRequest {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(rename = "systemPrompt", skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(rename = "stopSequences", skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// Hints and priorities for the client's choice of model
+    #[serde(rename = "modelPreferences", skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<serde_json::Value>,
+}
+
+/// What the client's model produced for `sampling/createMessage`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMessageResult {
+    pub role: Role,
+    pub content: ToolContent,
+    /// The model the client chose
+    pub model: String,
+    #[serde(rename = "stopReason", skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// A directory or file the client lets the server work in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    /// A `file://` URI
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// The client's answer to `roots/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
+}
+
+/// List prompts response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPromptsResponse {
+    pub prompts: Vec<Prompt>,
+}
+
+/// Get prompt request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptRequest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<HashMap<String, String>>,
+}
+
+/// Get prompt response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub messages: Vec<PromptMessage>,
+}
+
+/// What a completion request completes an argument of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompletionReference {
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    /// A resource URI or URI template
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed and what has been typed so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionArgument {
+    pub name: String,
+    pub value: String,
+}
+
+/// Complete request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteRequest {
+    #[serde(rename = "ref")]
+    pub reference: CompletionReference,
+    pub argument: CompletionArgument,
+}
+
+/// Candidate values for an argument, at most 100
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Completion {
+    pub values: Vec<String>,
+    /// Number of candidates before truncation to `values`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    #[serde(rename = "hasMore", skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+}
+
+/// Complete response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResponse {
+    pub completion: Completion,
 }
 
 /// List tools response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListToolsResponse {
     pub tools: Vec<Tool>,
+    /// Pass back as `cursor` for the next page; absent on the last page
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 /// Error codes
@@ -170,6 +419,14 @@ pub mod error_codes {
     pub const METHOD_NOT_FOUND: i32 = -32601;
     pub const INVALID_PARAMS: i32 = -32602;
     pub const INTERNAL_ERROR: i32 = -32603;
+    /// MCP-specific: the requested resource URI is not served
+    pub const RESOURCE_NOT_FOUND: i32 = -32002;
+    /// A request other than `initialize` arrived before the server was initialized
+    pub const SERVER_NOT_INITIALIZED: i32 = -32003;
+    /// `tools/call` named a tool that is not registered
+    pub const TOOL_NOT_FOUND: i32 = -32004;
+    /// The client sent more requests than the server's rate limit allows
+    pub const RATE_LIMITED: i32 = -32005;
 }
 
 impl JsonRpcError {
@@ -208,4 +465,24 @@ impl JsonRpcError {
     pub fn internal_error() -> Self {
         Self::new(error_codes::INTERNAL_ERROR, "Internal error".to_string())
     }
+    
+    pub fn server_not_initialized() -> Self {
+        Self::new(error_codes::SERVER_NOT_INITIALIZED, "Server not initialized".to_string())
+    }
+    
+    pub fn rate_limited(requests_per_second: u32) -> Self {
+        Self::with_data(
+            error_codes::RATE_LIMITED,
+            "Rate limited".to_string(),
+            serde_json::json!({ "requests_per_second": requests_per_second }),
+        )
+    }
+    
+    pub fn resource_not_found(uri: &str) -> Self {
+        Self::with_data(
+            error_codes::RESOURCE_NOT_FOUND,
+            "Resource not found".to_string(),
+            serde_json::json!({ "uri": uri }),
+        )
+    }
 }
@@ -0,0 +1,3 @@
+#!/bin/sh
+# XXX remove before release
+echo done
@@ -0,0 +1,15 @@
+//! Fixture for the find_todos tool
+
+// TODO: handle empty input
+pub fn parse(input: &str) -> Vec<&str> {
+    let TODO_COUNT = 0;
+    let label = "TODO in a string is not a comment";
+    input.split(',').collect() // FIXME(alice): trims nothing
+}
+
+/*
+ * HACK - works around a borrowck limitation
+ */
+pub fn todos() -> usize {
+    0 // TODOS is not a tag
+}
@@ -1,7 +1,44 @@
+use rust_mcp_server::config::ServerConfig;
 use rust_mcp_server::mcp::McpServer;
+use rust_mcp_server::tools::ToolCallError;
 use rust_mcp_server::types::*;
 use serde_json::json;
 
+fn test_server_with_config(config: ServerConfig) -> McpServer {
+    McpServer::with_config("test-server".to_string(), "1.0.0".to_string(), config)
+}
+
+async fn call_tool(server: &McpServer, name: &str, arguments: serde_json::Value) -> CallToolResponse {
+    server
+        .tool_registry
+        .call_tool(CallToolRequest {
+            name: name.to_string(),
+            arguments: Some(arguments),
+        })
+        .await
+        .unwrap()
+}
+
+async fn call_tool_error(server: &McpServer, name: &str, arguments: serde_json::Value) -> ToolCallError {
+    server
+        .tool_registry
+        .call_tool(CallToolRequest {
+            name: name.to_string(),
+            arguments: Some(arguments),
+        })
+        .await
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+fn text_content(response: &CallToolResponse, index: usize) -> &str {
+    match &response.content[index] {
+        ToolContent::Text { text } => text,
+        other => panic!("Expected text content, got {:?}", other),
+    }
+}
+
 #[tokio::test]
 async fn test_mcp_server_initialization() {
     let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
@@ -66,6 +103,46 @@ async fn test_list_tools() {
     }
 }
 
+#[tokio::test]
+async fn test_list_tools_pagination() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tools_page_size = 2;
+
+    let list = |cursor: Option<String>| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "tools/list".to_string(),
+        params: cursor.map(|cursor| json!({"cursor": cursor})),
+    };
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let response = server.handle_request(list(cursor)).await.unwrap().unwrap();
+        let page: ListToolsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert!(!page.tools.is_empty() && page.tools.len() <= 2);
+        seen.extend(page.tools.into_iter().map(|t| t.name));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    // Every tool exactly once, in a stable order
+    let all: Vec<String> = server.tool_registry.list_tools().into_iter().map(|t| t.name).collect();
+    assert!(all.len() > 2);
+    assert_eq!(seen, all);
+
+    let response = server.handle_request(list(Some("not a cursor!".to_string()))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, rust_mcp_server::types::error_codes::INVALID_PARAMS);
+
+    // A page that fits everything has no nextCursor
+    server.tools_page_size = rust_mcp_server::mcp::DEFAULT_TOOLS_PAGE_SIZE;
+    let response = server.handle_request(list(None)).await.unwrap().unwrap();
+    assert!(response.result.unwrap().get("nextCursor").is_none());
+}
+
 #[tokio::test]
 async fn test_echo_tool() {
     let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
@@ -105,6 +182,7 @@ async fn test_echo_tool() {
 #[tokio::test]
 async fn test_method_not_found() {
     let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
 
     let request = JsonRpcRequest {
         jsonrpc: "2.0".to_string(),
@@ -128,6 +206,7 @@ async fn test_method_not_found() {
 #[tokio::test]
 async fn test_ping() {
     let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
 
     let request = JsonRpcRequest {
         jsonrpc: "2.0".to_string(),
@@ -148,3 +227,5055 @@ async fn test_ping() {
         assert_eq!(ping_response["pong"], json!(true));
     }
 }
+
+#[tokio::test]
+async fn test_requests_before_initialize_are_refused() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let request = |method: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: method.to_string(),
+        params: None,
+    };
+
+    for method in ["tools/list", "ping", "resources/list", "prompts/list"] {
+        let response = server.handle_request(request(method)).await.unwrap().unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, rust_mcp_server::types::error_codes::SERVER_NOT_INITIALIZED, "{}", method);
+        assert_eq!(error.message, "Server not initialized");
+    }
+
+    server.initialized = true;
+    let response = server.handle_request(request("tools/list")).await.unwrap().unwrap();
+    assert!(response.error.is_none());
+}
+
+#[tokio::test]
+async fn test_malformed_params_are_invalid_params() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let request = |id: i64, method: &str, params: Option<serde_json::Value>| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params,
+    };
+
+    let params = json!({"protocolVersion": "2024-11-05", "capabilities": {}});
+    let response = server.handle_request(request(1, "initialize", Some(params))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INVALID_PARAMS);
+    assert_eq!(error.message, "Invalid params");
+    assert!(error.data.unwrap()["message"].as_str().unwrap().contains("missing field `clientInfo`"));
+    assert!(!server.initialized);
+
+    server.initialized = true;
+    let params = json!({"name": "echo", "arguments": "text=hi"});
+    let response = server.handle_request(request(2, "tools/call", Some(params))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INVALID_PARAMS);
+    assert_eq!(error.data.unwrap()["message"], "arguments must be an object");
+
+    for (method, params) in [
+        ("tools/call", None),
+        ("tools/call", Some(json!({"arguments": {}}))),
+        ("prompts/get", Some(json!({"name": 7}))),
+        ("resources/read", None),
+        ("logging/setLevel", Some(json!({}))),
+    ] {
+        let response = server.handle_request(request(3, method, params)).await.unwrap().unwrap();
+        assert_eq!(response.error.unwrap().code, error_codes::INVALID_PARAMS, "{}", method);
+    }
+}
+
+#[tokio::test]
+async fn test_tcp_ping_local_listener() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = test_server_with_config(ServerConfig {
+        enable_network: true,
+        allowed_hosts: vec!["127.0.0.1".to_string()],
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "tcp_ping", json!({"host": "127.0.0.1", "port": port})).await;
+    assert!(response.is_error.is_none());
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["reachable"], json!(true));
+    assert!(result["latency_ms"].is_number());
+
+    // Hosts outside the allowlist are refused
+    let response = call_tool(&server, "tcp_ping", json!({"host": "localhost", "port": port})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+/// Serves one canned HTTP response per connection: `/large` is 4000 bytes,
+/// `/binary` is a few non-text bytes, anything else is a short text body
+async fn spawn_http_server() -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = vec![0; 4096];
+            let n = socket.read(&mut request).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&request[..n]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let (content_type, body) = match path.as_str() {
+                "/large" => ("text/plain", vec![b'a'; 4000]),
+                "/binary" => ("application/octet-stream", vec![0, 159, 146, 150, 0]),
+                _ => ("text/plain; charset=utf-8", b"hello from the mock".to_vec()),
+            };
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content_type,
+                body.len()
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_fetch_url() {
+    use rust_mcp_server::tools::FetchUrlTool;
+
+    let port = spawn_http_server().await;
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let tool = FetchUrlTool::new(vec!["127.0.0.1".to_string()]).with_allowed_ports(vec![port]);
+    server.tool_registry.register_tool("fetch_url", Box::new(tool));
+
+    let response = call_tool(&server, "fetch_url", json!({"url": format!("http://127.0.0.1:{}/hello", port)})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["status"], 200);
+    assert_eq!(result["content_type"], "text/plain; charset=utf-8");
+    assert_eq!(result["encoding"], "text");
+    assert_eq!(result["body"], "hello from the mock");
+    assert_eq!(result["truncated"], false);
+
+    let response = call_tool(&server, "fetch_url", json!({"url": format!("http://127.0.0.1:{}/binary", port)})).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["encoding"], "base64");
+    assert_eq!(result["body"], "AJ+SlgA=");
+
+    // Oversized bodies are cut at max_bytes with a note
+    let args = json!({"url": format!("http://127.0.0.1:{}/large", port), "max_bytes": 100});
+    let response = call_tool(&server, "fetch_url", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["bytes"], 100);
+    assert_eq!(result["body"].as_str().unwrap().len(), 100);
+    assert_eq!(result["truncated"], true);
+    assert!(result["note"].as_str().unwrap().contains("100 bytes"));
+
+    // Only http(s) on allowed hosts and ports
+    let response = call_tool(&server, "fetch_url", json!({"url": "file:///etc/passwd"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("Scheme 'file' is not allowed"));
+
+    let response = call_tool(&server, "fetch_url", json!({"url": format!("http://localhost:{}/", port)})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("not in the allowed host list"));
+
+    let response = call_tool(&server, "fetch_url", json!({"url": "http://127.0.0.1:22/"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains(&format!("Port 22 is not allowed. Allowed ports: {}", port)));
+
+    // Without an allowlist entry naming them, internal addresses are refused
+    let tool = FetchUrlTool::new(Vec::new()).with_allowed_ports(vec![80, port]);
+    server.tool_registry.register_tool("fetch_url", Box::new(tool));
+    for url in [
+        format!("http://127.0.0.1:{}/hello", port),
+        format!("http://localhost:{}/hello", port),
+        "http://169.254.169.254/latest/meta-data/".to_string(),
+        "http://10.0.0.1/".to_string(),
+        "http://[::1]/".to_string(),
+    ] {
+        let response = call_tool(&server, "fetch_url", json!({"url": url})).await;
+        assert_eq!(response.is_error, Some(true), "{}", url);
+        assert!(text_content(&response, 0).contains("internal address"), "{}: {}", url, text_content(&response, 0));
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_xattr_set_and_get() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("data.txt"), "hello").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+
+    let args = json!({"path": "data.txt", "operation": "set", "name": "user.mcp_test", "value": "42"});
+    let set = call_tool(&server, "xattr", args).await;
+    assert!(set.is_error.is_none(), "set failed: {:?}", set.content);
+
+    let args = json!({"path": "data.txt", "operation": "get", "name": "user.mcp_test"});
+    let get = call_tool(&server, "xattr", args).await;
+    assert!(get.is_error.is_none());
+    let result: serde_json::Value = serde_json::from_str(text_content(&get, 0)).unwrap();
+    assert_eq!(result["value"], json!("42"));
+
+    // Names without a namespace are rejected
+    let args = json!({"path": "data.txt", "operation": "get", "name": "bogus"});
+    let invalid = call_tool(&server, "xattr", args).await;
+    assert_eq!(invalid.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_execute_command_paginates_large_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("big.txt");
+    let line = "0123456789abcdefghij\n";
+    std::fs::write(&file, line.repeat(500)).unwrap();
+
+    let server = test_server_with_config(ServerConfig::default());
+    let args = json!({
+        "command": "cat",
+        "args": [file.to_string_lossy()],
+        "paginate": true,
+        "page_size": 4000
+    });
+    let response = call_tool(&server, "execute_command", args).await;
+    assert!(response.is_error.is_none());
+
+    let mut collected = text_content(&response, 0).to_string();
+    let mut pages = 1;
+    let mut next: serde_json::Value = serde_json::from_str(text_content(&response, 1)).unwrap();
+    while let Some(handle) = next.get("next").and_then(|h| h.as_str()) {
+        let page = call_tool(&server, "output_page", json!({"handle": handle, "page_size": 4000})).await;
+        assert!(page.is_error.is_none());
+        collected.push_str(text_content(&page, 0));
+        pages += 1;
+        next = match page.content.get(1) {
+            Some(_) => serde_json::from_str(text_content(&page, 1)).unwrap(),
+            None => json!({}),
+        };
+    }
+
+    assert_eq!(pages, 3);
+    assert!(collected.ends_with(&line.repeat(500)));
+
+    let expired = call_tool(&server, "output_page", json!({"handle": "missing:0"})).await;
+    assert_eq!(expired.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_transform_text_snake_to_camel() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({"text": "parse_http_response", "operation": "camel"});
+    let response = call_tool(&server, "transform_text", args).await;
+    assert!(response.is_error.is_none());
+    assert_eq!(text_content(&response, 0), "parseHttpResponse");
+
+    let args = json!({"text": "parseHTTPResponse", "operation": "snake"});
+    let response = call_tool(&server, "transform_text", args).await;
+    assert_eq!(text_content(&response, 0), "parse_http_response");
+}
+
+#[tokio::test]
+async fn test_transform_text_wrap_to_width() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({
+        "text": "the quick brown fox jumps over the lazy dog",
+        "operation": "wrap",
+        "width": 10
+    });
+    let response = call_tool(&server, "transform_text", args).await;
+    assert!(response.is_error.is_none());
+
+    let wrapped = text_content(&response, 0);
+    assert_eq!(wrapped, "the quick\nbrown fox\njumps over\nthe lazy\ndog");
+    assert!(wrapped.lines().all(|line| line.len() <= 10));
+}
+
+#[tokio::test]
+async fn test_tool_description_override() {
+    let mut tool_descriptions = std::collections::HashMap::new();
+    tool_descriptions.insert("echo".to_string(), "Repeat text verbatim".to_string());
+    tool_descriptions.insert("no_such_tool".to_string(), "Ignored".to_string());
+
+    let mut server = test_server_with_config(ServerConfig {
+        tool_descriptions,
+        ..ServerConfig::default()
+    });
+    server.initialized = true;
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "tools/list".to_string(),
+        params: None,
+    };
+    let response = server.handle_request(request).await.unwrap().unwrap();
+    let tools: ListToolsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+
+    let echo = tools.tools.iter().find(|t| t.name == "echo").unwrap();
+    assert_eq!(echo.description, "Repeat text verbatim");
+    assert!(tools.tools.iter().all(|t| t.name != "no_such_tool"));
+}
+
+#[tokio::test]
+async fn test_chunk_text_count_and_overlap() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({"text": "abcdefghij", "chunk_size": 4, "overlap": 1});
+    let response = call_tool(&server, "chunk_text", args).await;
+    assert!(response.is_error.is_none());
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let chunks = result["chunks"].as_array().unwrap();
+    let texts: Vec<&str> = chunks.iter().map(|c| c["text"].as_str().unwrap()).collect();
+    assert_eq!(texts, vec!["abcd", "defg", "ghij"]);
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[0]["end"].as_u64().unwrap() - pair[1]["start"].as_u64().unwrap(), 1);
+    }
+
+    // Token mode never splits a multi-byte character
+    let args = json!({"text": "héllo wörld ünïcode", "chunk_size": 2, "overlap": 0, "mode": "tokens"});
+    let response = call_tool(&server, "chunk_text", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let joined: String = result["chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(joined, "héllo wörld ünïcode");
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_process_info_for_own_pid() {
+    let server = test_server_with_config(ServerConfig {
+        enable_process_info: true,
+        ..ServerConfig::default()
+    });
+
+    let pid = std::process::id();
+    let response = call_tool(&server, "process_info", json!({"pid": pid})).await;
+    assert!(response.is_error.is_none());
+
+    let info: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(info["pid"], json!(pid));
+    assert!(info["open_files"].as_u64().unwrap() > 0);
+    assert!(info["status"]["State"].is_string());
+    assert_eq!(
+        info["cwd"].as_str().unwrap(),
+        std::env::current_dir().unwrap().to_string_lossy()
+    );
+
+    let response = call_tool(&server, "process_info", json!({"pid": u32::MAX})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_tls_inspect_self_signed_listener() {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+    let certs: Vec<CertificateDer> = CertificateDer::pem_file_iter(fixtures.join("cert.pem"))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(fixtures.join("key.pem")).unwrap();
+    let tls_config = tokio_rustls::rustls::ServerConfig::builder_with_provider(std::sync::Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(tls_config));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let _ = acceptor.accept(stream).await;
+    });
+
+    let server = test_server_with_config(ServerConfig {
+        enable_network: true,
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "tls_inspect", json!({"host": "localhost", "port": port})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let leaf = &result["certificates"][0];
+    assert!(leaf["subject"].as_str().unwrap().contains("CN=localhost"));
+    assert_eq!(leaf["self_signed"], json!(true));
+    assert!(leaf["subject_alt_names"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("DNS:localhost")));
+    assert!(leaf["days_until_expiry"].as_i64().unwrap() > 365);
+
+    // Nothing is listening here any more, so the handshake fails cleanly
+    let response = call_tool(&server, "tls_inspect", json!({"host": "localhost", "port": port})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_project_overview_detects_cargo() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\nanyhow = \"1\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), "fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+    std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+    std::fs::write(dir.path().join("target/debug/junk.rs"), "// build output\n").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "project_overview", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let overview: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(overview["project_type"], json!("cargo"));
+    assert!(overview["key_files"].as_array().unwrap().contains(&json!("Cargo.toml")));
+    assert_eq!(overview["dependencies"]["cargo.dependencies"], json!(2));
+    assert_eq!(overview["lines_of_code"]["by_extension"]["rs"], json!(3));
+    assert!(overview["layout"].get("target").is_none());
+}
+
+#[tokio::test]
+async fn test_schedule_one_shot_task_notifies() {
+    let server = test_server_with_config(ServerConfig::default());
+    let mut notifications = server.notifier.subscribe();
+
+    let args = json!({
+        "tool": "execute_command",
+        "arguments": {"command": "echo", "args": ["scheduled"]},
+        "delay_seconds": 0.05
+    });
+    let response = call_tool(&server, "schedule_task", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let scheduled: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let task_id = scheduled["task_id"].as_str().unwrap().to_string();
+
+    let listed = call_tool(&server, "list_tasks", json!({})).await;
+    assert!(text_content(&listed, 0).contains(&task_id));
+
+    let notification = tokio::time::timeout(std::time::Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("task notification did not arrive")
+        .unwrap();
+    assert_eq!(notification.method, rust_mcp_server::tools::TASK_RESULT_NOTIFICATION);
+    let params = notification.params.unwrap();
+    assert_eq!(params["taskId"], json!(task_id));
+    assert!(params["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("scheduled"));
+
+    // One-shot tasks are gone once they have run
+    let cancelled = call_tool(&server, "cancel_task", json!({"task_id": task_id})).await;
+    assert_eq!(cancelled.is_error, Some(true));
+
+    // Delays too long to represent are refused up front instead of leaving a dead task
+    let args = json!({"tool": "execute_command", "arguments": {"command": "echo"}, "delay_seconds": 1e300});
+    let response = call_tool(&server, "schedule_task", args).await;
+    assert_eq!(response.is_error, Some(true));
+    let listed = call_tool(&server, "list_tasks", json!({})).await;
+    let listed: serde_json::Value = serde_json::from_str(text_content(&listed, 0)).unwrap();
+    assert_eq!(listed["tasks"], json!([]));
+}
+
+#[tokio::test]
+async fn test_scheduled_runs_go_through_dispatch() {
+    let server = test_server_with_config(ServerConfig {
+        allowed_commands: vec!["sleep".to_string()],
+        tool_timeouts: std::collections::HashMap::from([("execute_command".to_string(), std::time::Duration::from_millis(200))]),
+        ..ServerConfig::default()
+    });
+    let mut notifications = server.notifier.subscribe();
+
+    // A hung run is cut off by the tool's timeout instead of holding its slot
+    let args = json!({"tool": "execute_command", "arguments": {"command": "sleep", "args": ["30"]}});
+    let response = call_tool(&server, "schedule_task", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let notification = tokio::time::timeout(std::time::Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("task notification did not arrive")
+        .unwrap();
+    let params = notification.params.unwrap();
+    assert!(params["result"]["content"][0]["text"].as_str().unwrap().contains("timed out"), "{}", params);
+
+    // Arguments are checked against the tool's schema
+    let args = json!({"tool": "read_file", "arguments": {"path": 5}});
+    let response = call_tool(&server, "schedule_task", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let notification = tokio::time::timeout(std::time::Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("task notification did not arrive")
+        .unwrap();
+    let params = notification.params.unwrap();
+    assert!(params["result"]["error"].as_str().unwrap().contains("Invalid arguments for tool"), "{}", params);
+    let listed = call_tool(&server, "list_tasks", json!({})).await;
+    let listed: serde_json::Value = serde_json::from_str(text_content(&listed, 0)).unwrap();
+    assert_eq!(listed["tasks"], json!([]));
+}
+
+#[tokio::test]
+async fn test_cache_key_tracks_file_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "alpha").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "beta").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let key = |files: serde_json::Value| {
+        let server = &server;
+        async move {
+            let args = json!({"files": files, "values": ["v1"]});
+            let response = call_tool(server, "cache_key", args).await;
+            assert!(response.is_error.is_none(), "{:?}", response.content);
+            let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+            result["key"].as_str().unwrap().to_string()
+        }
+    };
+
+    let first = key(json!(["a.txt", "b.txt"])).await;
+    assert_eq!(first, key(json!(["a.txt", "b.txt"])).await);
+    // Unordered by default
+    assert_eq!(first, key(json!(["b.txt", "a.txt"])).await);
+
+    std::fs::write(dir.path().join("b.txt"), "beta, edited").unwrap();
+    assert_ne!(first, key(json!(["a.txt", "b.txt"])).await);
+}
+
+#[tokio::test]
+async fn test_chart_sparkline() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({"values": [1, 2, 3, 4, 5, 6, 7, 8], "type": "sparkline"});
+    let response = call_tool(&server, "chart", args).await;
+    assert!(response.is_error.is_none());
+    let sparkline = text_content(&response, 0);
+    assert_eq!(sparkline.chars().count(), 8);
+    assert_eq!(sparkline, "▁▂▃▄▅▆▇█");
+
+    let response = call_tool(&server, "chart", json!({"values": [], "type": "bar"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_recently_modified_finds_touched_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    let old = std::fs::File::create(dir.path().join("old.rs")).unwrap();
+    old.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(3600)).unwrap();
+    std::fs::write(dir.path().join("src/new.rs"), "fn main() {}").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "recent").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let args = json!({"since": "5m", "glob": "*.rs"});
+    let response = call_tool(&server, "recently_modified", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let paths: Vec<&str> = result["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, vec!["src/new.rs"]);
+
+    // Cutoffs that overflow or fall before 1970 fail the call instead of panicking
+    for since in ["18446744073709551615", "100years"] {
+        let response = call_tool(&server, "recently_modified", json!({"since": since})).await;
+        assert_eq!(response.is_error, Some(true), "{}", since);
+        assert!(text_content(&response, 0).contains("out of range"), "{}", since);
+    }
+}
+
+struct PanicTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for PanicTool {
+    fn description(&self) -> String {
+        "Always panics".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, _args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        panic!("tool exploded");
+    }
+}
+
+#[tokio::test]
+async fn test_tool_panic_is_isolated() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("panic", Box::new(PanicTool));
+
+    let call = |id: i64, name: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: "tools/call".to_string(),
+        params: Some(json!({"name": name, "arguments": {"text": "still alive"}})),
+    };
+
+    let response = server.handle_request(call(1, "panic")).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INTERNAL_ERROR);
+    assert!(error.message.contains("tool exploded"));
+
+    let response = server.handle_request(call(2, "echo")).await.unwrap().unwrap();
+    assert!(response.error.is_none());
+    assert_eq!(response.id, Some(json!(2)));
+}
+
+struct FailingTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for FailingTool {
+    fn description(&self) -> String {
+        "Always returns an error".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object", "properties": {"count": {"type": "integer"}}})
+    }
+
+    async fn call(&self, _args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        Err(anyhow::anyhow!("disk on fire"))
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_error_codes() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("failing", Box::new(FailingTool));
+
+    let call = |id: i64, name: &str, arguments: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: "tools/call".to_string(),
+        params: Some(json!({"name": name, "arguments": arguments})),
+    };
+
+    let response = server.handle_request(call(1, "no_such_tool", json!({}))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::TOOL_NOT_FOUND);
+    assert_eq!(error.data.unwrap()["message"], "Tool 'no_such_tool' not found");
+
+    let response = server.handle_request(call(2, "failing", json!({"count": "two"}))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INVALID_PARAMS);
+    assert_eq!(error.data.unwrap()["tool"], "failing");
+
+    let response = server.handle_request(call(3, "failing", json!({}))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INTERNAL_ERROR);
+    assert_eq!(error.message, "Tool 'failing' failed: disk on fire");
+    assert_eq!(error.data.unwrap()["message"], "Tool 'failing' failed: disk on fire");
+
+    // Failures a tool reports itself stay in the result
+    let response = server.handle_request(call(4, "read_file", json!({"path": "no/such/file"}))).await.unwrap().unwrap();
+    assert!(response.error.is_none());
+    assert_eq!(response.result.unwrap()["isError"], true);
+}
+
+#[tokio::test]
+async fn test_parse_url_components() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({
+        "url": "HTTPS://Example.com:443/a/./b/../docs?q=rust&tag=x&tag=y#install",
+        "normalize": true
+    });
+    let response = call_tool(&server, "parse_url", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["scheme"], "https");
+    assert_eq!(result["host"], "example.com");
+    assert_eq!(result["port"], serde_json::Value::Null);
+    assert_eq!(result["effective_port"], 443);
+    assert_eq!(result["path"], "/a/docs");
+    assert_eq!(result["query_params"]["q"], "rust");
+    assert_eq!(result["query_params"]["tag"], json!(["x", "y"]));
+    assert_eq!(result["fragment"], "install");
+    assert_eq!(result["normalized"], "https://example.com/a/docs?q=rust&tag=x&tag=y#install");
+
+    let response = call_tool(&server, "parse_url", json!({"url": "not a url"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_resource_limits_reports_fd_limits() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let response = call_tool(&server, "resource_limits", json!({"path": "."})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let fds = &result["file_descriptors"];
+    assert!(fds["open"].as_u64().unwrap() > 0);
+    for field in ["soft_limit", "hard_limit"] {
+        assert!(fds[field].is_u64() || fds[field] == "unlimited", "{}: {}", field, fds[field]);
+    }
+}
+
+#[tokio::test]
+async fn test_scaffold_substitutes_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+
+    let args = json!({
+        "destination": "demo",
+        "files": {
+            "Cargo.toml": "[package]\nname = \"{{name}}\"\nversion = \"{{ version }}\"\n",
+            "src/main.rs": "fn main() { println!(\"{{name}}\"); }\n"
+        },
+        "variables": {"name": "demo", "version": "0.1.0"}
+    });
+    let response = call_tool(&server, "scaffold", args.clone()).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let project = dir.path().join("demo");
+    assert_eq!(
+        std::fs::read_to_string(project.join("Cargo.toml")).unwrap(),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(project.join("src/main.rs")).unwrap(),
+        "fn main() { println!(\"demo\"); }\n"
+    );
+
+    // Existing files are kept unless forced
+    let response = call_tool(&server, "scaffold", args).await;
+    assert_eq!(response.is_error, Some(true));
+
+    let read_only = test_server_with_config(ServerConfig {
+        read_only: true,
+        ..ServerConfig::default()
+    });
+    assert!(read_only.tool_registry.list_tools().iter().all(|t| t.name != "scaffold" && t.name != "write_file"));
+}
+
+#[tokio::test]
+async fn test_find_duplicates_groups_identical_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("copies")).unwrap();
+    std::fs::write(dir.path().join("report.txt"), "quarterly numbers").unwrap();
+    std::fs::write(dir.path().join("copies/report-final.txt"), "quarterly numbers").unwrap();
+    // Same size, different content
+    std::fs::write(dir.path().join("other.txt"), "quarterly NUMBERS").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "find_duplicates", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["total_groups"], 1);
+    assert_eq!(result["groups"][0]["paths"], json!(["copies/report-final.txt", "report.txt"]));
+    assert_eq!(result["wasted_bytes"], 17);
+}
+
+#[tokio::test]
+async fn test_find_todos_reports_comments_by_tag() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/todos");
+    let server = test_server_with_config(ServerConfig {
+        root: Some(fixture),
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "find_todos", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["total"], 4);
+    assert_eq!(result["skipped_binary"], 1);
+    assert_eq!(result["by_tag"]["TODO"], json!([
+        {"path": "src/lib.rs", "line": 3, "tag": "TODO", "text": "handle empty input"}
+    ]));
+    assert_eq!(result["by_tag"]["FIXME"], json!([
+        {"path": "src/lib.rs", "line": 7, "tag": "FIXME", "text": "trims nothing", "author": "alice"}
+    ]));
+    assert_eq!(result["by_tag"]["HACK"][0]["line"], 11);
+    assert_eq!(result["by_tag"]["XXX"][0]["path"], "notes.sh");
+
+    let response = call_tool(&server, "find_todos", json!({"glob": "**/*.rs", "tags": ["FIXME"]})).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["counts"], json!({"FIXME": 1}));
+}
+
+#[tokio::test]
+async fn test_walking_tools_respect_gitignore() {
+    // Built in a temp dir so the fixture's .gitignore doesn't hide files from this repo's git
+    let dir = tempfile::tempdir().unwrap();
+    let files = [
+        (".gitignore", "dist/\n*.log\n"),
+        ("src/main.rs", "// TODO: kept\n"),
+        ("src/.gitignore", "generated.rs\n"),
+        ("src/generated.rs", "// TODO: ignored by the nested .gitignore\n"),
+        ("dist/bundle.js", "// TODO: ignored directory\n"),
+        ("debug.log", "# TODO: ignored by pattern\n"),
+        ("logs/.gitignore", "!keep.log\n"),
+        ("logs/keep.log", "# TODO: re-included\n"),
+    ];
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+
+    let todo_paths = |response: &CallToolResponse| -> Vec<String> {
+        let result: serde_json::Value = serde_json::from_str(text_content(response, 0)).unwrap();
+        let mut paths: Vec<String> = result["by_tag"]["TODO"].as_array().unwrap()
+            .iter()
+            .map(|todo| todo["path"].as_str().unwrap().to_string())
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    let response = call_tool(&server, "find_todos", json!({})).await;
+    assert_eq!(todo_paths(&response), ["logs/keep.log", "src/main.rs"]);
+
+    let response = call_tool(&server, "find_todos", json!({"respect_gitignore": false})).await;
+    assert_eq!(todo_paths(&response).len(), 5);
+
+    let response = call_tool(&server, "recently_modified", json!({"since": "1h"})).await;
+    let text = text_content(&response, 0);
+    assert!(text.contains("src/main.rs") && !text.contains("dist/") && !text.contains("debug.log"));
+}
+
+#[tokio::test]
+async fn test_read_file_smart_transcodes_utf16() {
+    let server = test_server_with_config(ServerConfig::default());
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/encoding/utf16le.txt");
+
+    let response = call_tool(&server, "read_file_smart", json!({"path": fixture})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["encoding"], "UTF-16LE");
+    assert_eq!(result["content"], "Grüße aus Köln\r\nZweite Zeile\r\n");
+    assert_eq!(result["had_invalid_sequences"], false);
+}
+
+#[tokio::test]
+async fn test_resources_list_and_read_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.md"), "# Notes\n").unwrap();
+    std::fs::write(dir.path().join("logo.png"), [0x89, b'P', b'N', b'G', 0, 0xff]).unwrap();
+
+    let mut server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let request = |id: i64, method: &str, params: Option<serde_json::Value>| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params,
+    };
+
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    let response = server.handle_request(request(1, "initialize", Some(init))).await.unwrap().unwrap();
+    assert!(response.result.unwrap()["capabilities"]["resources"].is_object());
+
+    let response = server.handle_request(request(2, "resources/list", None)).await.unwrap().unwrap();
+    let list: ListResourcesResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    let notes = list.resources.iter().find(|r| r.name == "notes.md").unwrap();
+    assert_eq!(notes.mime_type.as_deref(), Some("text/markdown"));
+    let logo = list.resources.iter().find(|r| r.name == "logo.png").unwrap();
+
+    let params = json!({"uri": notes.uri});
+    let response = server.handle_request(request(3, "resources/read", Some(params))).await.unwrap().unwrap();
+    let read: ReadResourceResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    match &read.contents[0] {
+        ResourceContents::Text { text, .. } => assert_eq!(text, "# Notes\n"),
+        other => panic!("Expected text contents, got {:?}", other),
+    }
+
+    let params = json!({"uri": logo.uri});
+    let response = server.handle_request(request(4, "resources/read", Some(params))).await.unwrap().unwrap();
+    let read: ReadResourceResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    assert!(matches!(&read.contents[0], ResourceContents::Blob { blob, .. } if blob == "iVBORwD/"));
+
+    let params = json!({"uri": "file:///etc/passwd"});
+    let response = server.handle_request(request(5, "resources/read", Some(params))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::RESOURCE_NOT_FOUND);
+}
+
+/// Serves one fixed text resource
+struct StaticResource;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::resources::ResourceProvider for StaticResource {
+    async fn list(&self) -> anyhow::Result<Vec<Resource>> {
+        Ok(vec![Resource {
+            uri: "memo://greeting".to_string(),
+            name: "greeting".to_string(),
+            description: None,
+            mime_type: Some("text/plain".to_string()),
+        }])
+    }
+
+    async fn read(&self, uri: &str) -> anyhow::Result<Option<Vec<ResourceContents>>> {
+        Ok((uri == "memo://greeting").then(|| {
+            vec![ResourceContents::Text {
+                uri: uri.to_string(),
+                mime_type: Some("text/plain".to_string()),
+                text: "hello".to_string(),
+            }]
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_resource_list_changed_on_registration() {
+    use rust_mcp_server::resources::RESOURCE_LIST_CHANGED_NOTIFICATION;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let init = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "initialize".to_string(),
+        params: Some(json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        })),
+    };
+    let response = server.handle_request(init).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["capabilities"]["resources"]["listChanged"], true);
+
+    let mut notifications = server.notifier.subscribe();
+    server.resource_registry.register_provider(Box::new(StaticResource));
+    let notification = notifications.try_recv().expect("no list_changed notification");
+    assert_eq!(notification.method, RESOURCE_LIST_CHANGED_NOTIFICATION);
+    assert_eq!(notification.params, None);
+    let resources = server.resource_registry.list_resources().await.unwrap();
+    assert!(resources.iter().any(|resource| resource.uri == "memo://greeting"));
+
+    server.resource_registry.clear();
+    assert_eq!(notifications.try_recv().unwrap().method, RESOURCE_LIST_CHANGED_NOTIFICATION);
+    assert!(server.resource_registry.list_resources().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_resource_subscriptions_notify_on_change() {
+    use rust_mcp_server::resources::RESOURCE_UPDATED_NOTIFICATION;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().canonicalize().unwrap().join("notes.md");
+    std::fs::write(&notes, "# Notes\n").unwrap();
+    std::fs::write(dir.path().join("other.md"), "").unwrap();
+    let uri = url::Url::from_file_path(&notes).unwrap().to_string();
+
+    let mut server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let request = |id: i64, method: &str, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params: Some(params),
+    };
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    let response = server.handle_request(request(1, "initialize", init)).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["capabilities"]["resources"]["subscribe"], true);
+
+    let mut notifications = server.notifier.subscribe();
+    let response = server.handle_request(request(2, "resources/subscribe", json!({"uri": uri}))).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap(), json!({}));
+    let response = server.handle_request(request(3, "resources/subscribe", json!({"uri": uri}))).await.unwrap().unwrap();
+    assert!(response.error.is_none());
+    let response = server.handle_request(request(4, "session/info", json!({}))).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["subscriptions"], json!([uri]));
+    assert_eq!(server.subscriptions.watched_paths(), vec![notes.clone()]);
+
+    // Changes to other files in the directory are not reported
+    std::fs::write(dir.path().join("other.md"), "changed").unwrap();
+    std::fs::write(&notes, "# Notes\n\nUpdated\n").unwrap();
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("no update notification")
+        .unwrap();
+    assert_eq!(notification.method, RESOURCE_UPDATED_NOTIFICATION);
+    assert_eq!(notification.params, Some(json!({"uri": uri})));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(notifications.try_recv().is_err(), "expected exactly one update");
+
+    let response = server.handle_request(request(5, "resources/unsubscribe", json!({"uri": uri}))).await.unwrap().unwrap();
+    assert!(response.error.is_none());
+    assert!(server.subscriptions.watched_paths().is_empty());
+    std::fs::write(&notes, "# Notes\n\nUpdated again\n").unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(notifications.try_recv().is_err(), "expected no update after unsubscribing");
+
+    let missing = url::Url::from_file_path(dir.path().join("missing.md")).unwrap().to_string();
+    let response = server.handle_request(request(6, "resources/subscribe", json!({"uri": missing}))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::RESOURCE_NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_pretty_output_uses_content_length_framing() {
+    use rust_mcp_server::server::{Framing, WireFormat};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    assert!(WireFormat::new(Some(Framing::Ndjson), true).is_err());
+    assert_eq!(WireFormat::new(None, true).unwrap().framing, Framing::ContentLength);
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .args(["--quiet", "--pretty"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    })
+    .to_string();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes())
+        .await
+        .unwrap();
+    drop(stdin);
+
+    let mut output = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut output).await.unwrap();
+    child.wait().await.unwrap();
+
+    let (header, payload) = output.split_once("\r\n\r\n").unwrap();
+    assert_eq!(header, format!("Content-Length: {}", payload.len()));
+    assert!(payload.contains("\n  \"jsonrpc\": \"2.0\""), "not pretty: {}", payload);
+    let response: serde_json::Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+}
+
+#[tokio::test]
+async fn test_framing_is_detected_from_first_message() {
+    use rust_mcp_server::server::{Framing, StdioServer, WireFormat};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    assert_eq!(WireFormat::default().framing, Framing::Auto);
+    assert!(WireFormat::new(Some(Framing::Auto), true).is_err());
+
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}).to_string();
+    let content_length = format!("\r\nContent-Length: {}\r\n\r\n{}", ping.len(), ping);
+    let ndjson = format!("\n{}\n", ping);
+    for (input, expected) in [(content_length, Framing::ContentLength), (ndjson, Framing::Ndjson)] {
+        let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+        server.initialized = true;
+        let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+        let (mut client, server_end) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_end);
+        let serving = tokio::spawn(async move { stdio.serve(server_read, server_write).await });
+
+        client.write_all(input.as_bytes()).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut output = String::new();
+        client.read_to_string(&mut output).await.unwrap();
+        serving.await.unwrap().unwrap();
+
+        let payload = match expected {
+            Framing::ContentLength => {
+                let (header, payload) = output.split_once("\r\n\r\n").unwrap();
+                assert_eq!(header, format!("Content-Length: {}", payload.len()));
+                payload
+            }
+            _ => output.strip_suffix('\n').unwrap(),
+        };
+        let response: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(response["result"]["pong"], true, "{:?}", expected);
+    }
+}
+
+#[tokio::test]
+async fn test_unterminated_lines_are_bounded() {
+    use rust_mcp_server::server::{Framing, MessageSource, StreamTransport, Transport, WireFormat};
+    use tokio::io::AsyncWriteExt;
+
+    // Past the 64 MiB message limit, and never a newline
+    for framing in [Framing::Ndjson, Framing::ContentLength] {
+        let (mut client, server_end) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_end);
+        let format = WireFormat { framing, ..WireFormat::default() };
+        let (mut source, _sink) = StreamTransport::new(server_read, server_write, format).split();
+        tokio::spawn(async move {
+            let chunk = vec![b'x'; 1024 * 1024];
+            for _ in 0..65 {
+                if client.write_all(&chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let error = source.receive().await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("exceeds the 67108864 byte limit"), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn test_effective_config_reports_sources() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("server.toml");
+    std::fs::write(&config_path, "tool_timeout = 10\nread_only = true\n").unwrap();
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .args(["--quiet", "--tool-timeout", "5", "--config"])
+        .arg(&config_path)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    });
+    let effective = json!({"jsonrpc": "2.0", "id": 2, "method": "server/effective_config"});
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(format!("{}\n{}\n", initialize, effective).as_bytes()).await.unwrap();
+    drop(stdin);
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut response = serde_json::Value::Null;
+    while let Some(line) = lines.next_line().await.unwrap() {
+        let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+        if message["id"] == 2 {
+            response = message;
+        }
+    }
+    child.wait().await.unwrap();
+
+    let settings = &response["result"]["settings"];
+    // The command line wins over the config file, which wins over defaults
+    assert_eq!(settings["tool_timeout"], json!({"value": 5.0, "source": "cli"}));
+    assert_eq!(settings["read_only"], json!({"value": true, "source": "config"}));
+    assert_eq!(settings["enable_network"], json!({"value": false, "source": "default"}));
+    assert_eq!(settings["allowed_commands"]["source"], "default");
+    assert!(settings["allowed_commands"]["value"].as_array().unwrap().contains(&json!("echo")));
+    // Read-only mode is why write_file is missing
+    let tools = response["result"]["tools"].as_array().unwrap();
+    assert!(tools.contains(&json!("read_file")) && !tools.contains(&json!("write_file")));
+}
+
+#[tokio::test]
+async fn test_working_dir_resolves_relative_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = dir.path().canonicalize().unwrap();
+    std::fs::create_dir_all(base.join("work/sub")).unwrap();
+    std::fs::write(base.join("work/hello.txt"), "hello from work").unwrap();
+    std::fs::write(base.join("outside.txt"), "outside").unwrap();
+    assert!(!std::path::Path::new("hello.txt").exists());
+
+    let server = test_server_with_config(ServerConfig {
+        working_dir: Some(base.join("work")),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "read_file", json!({"path": "hello.txt"})).await;
+    assert!(text_content(&response, 0).ends_with("hello from work"));
+    // Absolute paths are still honored
+    let outside = base.join("outside.txt");
+    let response = call_tool(&server, "read_file", json!({"path": outside.to_str().unwrap()})).await;
+    assert!(text_content(&response, 0).ends_with("outside"));
+
+    let response = call_tool(&server, "list_files", json!({})).await;
+    let listing: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let paths: Vec<&str> = listing["entries"].as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["hello.txt", "sub"]);
+
+    let response = call_tool(&server, "execute_command", json!({"command": "pwd"})).await;
+    assert!(text_content(&response, 0).contains(&format!("{}\n", base.join("work").display())));
+    let response = call_tool(&server, "execute_command", json!({"command": "cat", "args": ["hello.txt"]})).await;
+    assert!(text_content(&response, 0).contains("hello from work"));
+
+    // Under a root, relative paths start from the working dir but may not leave the root
+    let server = test_server_with_config(ServerConfig {
+        root: Some(base.join("work")),
+        working_dir: Some(base.join("work/sub")),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "read_file", json!({"path": "../hello.txt"})).await;
+    assert!(text_content(&response, 0).ends_with("hello from work"));
+    let response = call_tool(&server, "read_file", json!({"path": "../../outside.txt"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_config_file_disables_tools() {
+    use rust_mcp_server::config::{ConfigLayer, ConfigSource};
+
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("server.json");
+    let config_json = json!({
+        "root": dir.path(),
+        "allowed_commands": ["printf"],
+        "tool_timeout": 3,
+        "disabled_tools": ["execute_command"]
+    });
+    std::fs::write(&config_path, config_json.to_string()).unwrap();
+
+    let mut config = ServerConfig::default();
+    config.merge(ConfigLayer::load(&config_path).unwrap(), ConfigSource::Config);
+    assert_eq!(config.root.as_deref(), Some(dir.path()));
+    assert_eq!(config.tool_timeout, Some(std::time::Duration::from_secs(3)));
+    assert_eq!(config.source("disabled_tools"), ConfigSource::Config);
+
+    let mut server = test_server_with_config(config);
+    server.initialized = true;
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "tools/list".to_string(),
+        params: None,
+    };
+    let response = server.handle_request(request).await.unwrap().unwrap();
+    let list: ListToolsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    let names: Vec<&str> = list.tools.iter().map(|tool| tool.name.as_str()).collect();
+    assert!(!names.contains(&"execute_command"));
+    assert!(names.contains(&"execute_command_limited"));
+
+    let args = json!({"tool": "execute_command", "arguments": {"command": "printf", "args": ["x"]}, "delay_seconds": 1});
+    let error = call_tool_error(&server, "schedule_task", args).await;
+    assert!(matches!(error, ToolCallError::InvalidArguments { .. }));
+
+    // Unknown settings are rejected rather than silently ignored
+    std::fs::write(&config_path, r#"{"disable_tools": ["echo"]}"#).unwrap();
+    assert!(ConfigLayer::load(&config_path).is_err());
+}
+
+#[tokio::test]
+async fn test_registry_with_selected_tools_and_unregister() {
+    use rust_mcp_server::tools::ToolRegistry;
+
+    let registry = ToolRegistry::with_tools(&["echo", "read_file", "list_files", "no_such_tool"]);
+    let names: Vec<String> = registry.list_tools().into_iter().map(|tool| tool.name).collect();
+    assert_eq!(names, vec!["echo", "list_files", "read_file"]);
+
+    // Installed on a server, the registry notifies that server's clients
+    let registry = ToolRegistry::with_tools(&["read_file", "schedule_task"]);
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string()).with_tool_registry(registry);
+    let mut notifications = server.notifier.subscribe();
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), "scheduled").unwrap();
+    let args = json!({"tool": "read_file", "arguments": {"path": file.path()}});
+    let response = call_tool(&server, "schedule_task", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let notification = tokio::time::timeout(std::time::Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("task notification did not arrive")
+        .unwrap();
+    assert_eq!(notification.method, rust_mcp_server::tools::TASK_RESULT_NOTIFICATION);
+    assert!(notification.params.unwrap()["result"]["content"][0]["text"].as_str().unwrap().contains("scheduled"));
+    assert!(server.tool_registry.unregister_tool("read_file"));
+    let notification = notifications.recv().await.unwrap();
+    assert_eq!(notification.method, rust_mcp_server::tools::TOOL_LIST_CHANGED_NOTIFICATION);
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.tool_registry.register_tool("failing", Box::new(FailingTool));
+    server.tool_registry.set_timeout("failing", std::time::Duration::from_secs(1));
+    assert!(server.tool_registry.unregister_tool("failing"));
+    assert!(server.tool_registry.unregister_tool("execute_command"));
+    assert!(!server.tool_registry.unregister_tool("failing"));
+
+    let names: Vec<String> = server.tool_registry.list_tools().into_iter().map(|tool| tool.name).collect();
+    assert!(!names.contains(&"failing".to_string()) && !names.contains(&"execute_command".to_string()));
+    let error = call_tool_error(&server, "execute_command", json!({"command": "ls"})).await;
+    assert!(matches!(error, ToolCallError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_tool_list_changed_at_runtime() {
+    use rust_mcp_server::server::StdioServer;
+    use rust_mcp_server::tools::TOOL_LIST_CHANGED_NOTIFICATION;
+    use std::time::Duration;
+
+    let session = std::sync::Arc::new(tokio::sync::Mutex::new(McpServer::new("test-server".to_string(), "1.0.0".to_string())));
+    let mut client = StdioServer::from_session(session.clone(), true).connect_in_process();
+    let response = client.initialize(ClientInfo { name: "test-client".to_string(), version: "1.0.0".to_string() }).await.unwrap();
+    assert_eq!(response.result.unwrap()["capabilities"]["tools"]["listChanged"], true);
+
+    session.lock().await.tool_registry.register_tool_fn("greet", "Say hello", json!({"type": "object"}), |_| async {
+        CallToolResponse::text("hello")
+    });
+    let notification = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(notification["method"], TOOL_LIST_CHANGED_NOTIFICATION);
+    let response = client.request("tools/call", Some(json!({"name": "greet"}))).await.unwrap();
+    assert_eq!(response.result.unwrap()["content"][0]["text"], "hello");
+
+    // Removing a tool that isn't there changes nothing
+    let mut notifications = session.lock().await.notifier.subscribe();
+    assert!(!session.lock().await.tool_registry.unregister_tool("missing"));
+    assert!(notifications.try_recv().is_err());
+
+    assert!(session.lock().await.tool_registry.unregister_tool("greet"));
+    let notification = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(notification["method"], TOOL_LIST_CHANGED_NOTIFICATION);
+    let response = client.request("tools/call", Some(json!({"name": "greet"}))).await.unwrap();
+    assert!(response.error.is_some());
+}
+
+#[tokio::test]
+async fn test_register_tool_fn() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let greeting = "Hello".to_string();
+    server.tool_registry.register_tool_fn(
+        "greet",
+        "Greet someone by name",
+        json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "required": ["name"]
+        }),
+        move |args| {
+            let greeting = greeting.clone();
+            async move {
+                match args.get("name").and_then(|v| v.as_str()) {
+                    Some("") => CallToolResponse::error("Name is empty"),
+                    Some(name) => CallToolResponse::text(format!("{}, {}!", greeting, name)),
+                    None => CallToolResponse::error("Name is required"),
+                }
+            }
+        },
+    );
+
+    let tool = server.tool_registry.list_tools().into_iter().find(|tool| tool.name == "greet").unwrap();
+    assert_eq!(tool.description, "Greet someone by name");
+    assert_eq!(tool.input_schema["required"], json!(["name"]));
+
+    let response = call_tool(&server, "greet", json!({"name": "Ada"})).await;
+    assert_eq!(response.is_error, None);
+    assert_eq!(text_content(&response, 0), "Hello, Ada!");
+    let response = call_tool(&server, "greet", json!({"name": ""})).await;
+    assert_eq!(response.is_error, Some(true));
+
+    let error = call_tool_error(&server, "greet", json!({})).await;
+    assert!(matches!(error, ToolCallError::InvalidArguments { .. }));
+}
+
+#[tokio::test]
+async fn test_server_stats_counts_requests_and_tool_errors() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let request = |id: i64, method: &str, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params: Some(params),
+    };
+
+    let params = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    server.handle_request(request(1, "initialize", params)).await.unwrap();
+    server.handle_request(JsonRpcRequest { id: None, ..request(0, "notifications/initialized", json!({})) }).await.unwrap();
+    server.handle_request(request(2, "ping", json!({}))).await.unwrap();
+    server.handle_request(request(3, "tools/list", json!({}))).await.unwrap();
+    let call = |id: i64, name: &str, arguments: serde_json::Value| request(id, "tools/call", json!({"name": name, "arguments": arguments}));
+    server.handle_request(call(4, "echo", json!({"text": "one"}))).await.unwrap();
+    server.handle_request(call(5, "echo", json!({"text": "two"}))).await.unwrap();
+    server.handle_request(call(6, "echo", json!({}))).await.unwrap();
+    server.handle_request(call(7, "read_file", json!({"path": "/no/such/file"}))).await.unwrap();
+    server.handle_request(call(8, "no_such_tool", json!({}))).await.unwrap();
+
+    let response = server.handle_request(call(9, "get_server_stats", json!({}))).await.unwrap().unwrap();
+    let result: CallToolResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    let stats: serde_json::Value = serde_json::from_str(text_content(&result, 0)).unwrap();
+
+    assert_eq!(stats["requests"], 10);
+    assert_eq!(stats["requests_by_method"]["initialize"], 1);
+    assert_eq!(stats["requests_by_method"]["notifications/initialized"], 1);
+    assert_eq!(stats["requests_by_method"]["ping"], 1);
+    assert_eq!(stats["requests_by_method"]["tools/call"], 6);
+    // The stats call itself is counted once it has finished
+    assert_eq!(stats["tool_calls"], 5);
+    assert_eq!(stats["tool_errors"], 3);
+    assert_eq!(stats["tools"]["echo"], json!({"calls": 3, "errors": 1}));
+    assert_eq!(stats["tools"]["read_file"], json!({"calls": 1, "errors": 1}));
+    assert_eq!(stats["tools"]["no_such_tool"], json!({"calls": 1, "errors": 1}));
+
+    // Clones of the server share the counters
+    let snapshot = server.clone();
+    call_tool(&snapshot, "echo", json!({"text": "three"})).await;
+    assert_eq!(server.stats().snapshot().tool_calls, 7);
+    assert_eq!(server.stats().snapshot().tools["get_server_stats"].calls, 1);
+}
+
+#[tokio::test]
+async fn test_prompt_templates_from_directory() {
+    use rust_mcp_server::prompts::PROMPT_LIST_CHANGED_NOTIFICATION;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("commit_message.md"),
+        "---\ndescription: Write a commit message\narguments:\n  - name: diff\n    required: true\n  - name: style\n    default: imperative mood\n    completions: [conventional commits, imperative mood]\n---\nWrite a commit message in {{style}} for:\n\n{{diff}}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("greet.yaml"),
+        "description: Greet someone\narguments:\n  - name: who\n    required: true\ntemplate: Say hello to {{who}}.\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("broken.yaml"), "description: [unclosed\n").unwrap();
+
+    let mut server = test_server_with_config(ServerConfig {
+        prompts_dir: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let request = |id: i64, method: &str, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params: Some(params),
+    };
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    let response = server.handle_request(request(1, "initialize", init)).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["capabilities"]["prompts"]["listChanged"], true);
+
+    let response = server.handle_request(request(2, "prompts/list", json!({}))).await.unwrap().unwrap();
+    let list: ListPromptsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    let names: Vec<&str> = list.prompts.iter().map(|prompt| prompt.name.as_str()).collect();
+    assert_eq!(names, ["code_review", "commit_message", "greet", "summarize_text"]);
+
+    let params = json!({"name": "commit_message", "arguments": {"diff": "+fn main() {}"}});
+    let response = server.handle_request(request(3, "prompts/get", params)).await.unwrap().unwrap();
+    let prompt: GetPromptResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    assert_eq!(prompt.description.as_deref(), Some("Write a commit message"));
+    match &prompt.messages[0].content {
+        ToolContent::Text { text } => assert_eq!(text, "Write a commit message in imperative mood for:\n\n+fn main() {}"),
+        other => panic!("Expected text content, got {:?}", other),
+    }
+
+    let params = json!({"ref": {"type": "ref/prompt", "name": "commit_message"}, "argument": {"name": "style", "value": "con"}});
+    let response = server.handle_request(request(4, "completion/complete", params)).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["completion"]["values"], json!(["conventional commits"]));
+
+    // Adding a template reloads the directory and tells the client
+    let mut notifications = server.notifier.subscribe();
+    std::fs::write(dir.path().join("farewell.md"), "Say goodbye to {{who}}.").unwrap();
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("no list_changed notification")
+        .unwrap();
+    assert_eq!(notification.method, PROMPT_LIST_CHANGED_NOTIFICATION);
+    let response = server.handle_request(request(5, "prompts/list", json!({}))).await.unwrap().unwrap();
+    assert!(response.result.unwrap()["prompts"].as_array().unwrap().iter().any(|prompt| prompt["name"] == "farewell"));
+}
+
+#[tokio::test]
+async fn test_completion_for_prompt_and_resource_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+    std::fs::write(dir.path().join("src/mcp.rs"), "").unwrap();
+    std::fs::write(dir.path().join("README.md"), "").unwrap();
+
+    let mut server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    server.initialized = true;
+    let complete = |id: i64, reference: serde_json::Value, argument: &str, value: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: "completion/complete".to_string(),
+        params: Some(json!({"ref": reference, "argument": {"name": argument, "value": value}})),
+    };
+    let review = json!({"type": "ref/prompt", "name": "code_review"});
+
+    let response = server.handle_request(complete(1, review.clone(), "focus", "Re")).await.unwrap().unwrap();
+    let completion = &response.result.unwrap()["completion"];
+    assert_eq!(completion["values"], json!(["readability"]));
+    assert_eq!(completion["total"], 1);
+    assert_eq!(completion["hasMore"], false);
+
+    // Arguments without suggestions complete to nothing rather than failing
+    let response = server.handle_request(complete(2, review, "code", "fn")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["completion"]["values"], json!([]));
+
+    let files = json!({"type": "ref/resource", "uri": "file:///{path}"});
+    let response = server.handle_request(complete(3, files.clone(), "path", "")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["completion"]["values"], json!(["README.md", "src/"]));
+
+    let response = server.handle_request(complete(4, files.clone(), "path", "src/m")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["completion"]["values"], json!(["src/main.rs", "src/mcp.rs"]));
+
+    let response = server.handle_request(complete(5, files, "path", "../")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["completion"]["values"], json!([]));
+
+    let missing = json!({"type": "ref/prompt", "name": "no_such_prompt"});
+    let response = server.handle_request(complete(6, missing, "focus", "")).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn test_prompts_list_and_get() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    let request = |id: i64, method: &str, params: Option<serde_json::Value>| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params,
+    };
+
+    let response = server.handle_request(request(1, "prompts/list", None)).await.unwrap().unwrap();
+    let list: ListPromptsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    let review = list.prompts.iter().find(|p| p.name == "code_review").unwrap();
+    let code_arg = review.arguments.as_ref().unwrap().iter().find(|a| a.name == "code").unwrap();
+    assert_eq!(code_arg.required, Some(true));
+
+    let params = json!({"name": "code_review", "arguments": {"code": "fn main() {}", "focus": "naming"}});
+    let response = server.handle_request(request(2, "prompts/get", Some(params))).await.unwrap().unwrap();
+    let prompt: GetPromptResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    assert_eq!(prompt.messages[0].role, Role::User);
+    match &prompt.messages[0].content {
+        ToolContent::Text { text } => {
+            assert!(text.contains("focusing on naming"));
+            assert!(text.contains("fn main() {}"));
+        }
+        other => panic!("Expected text content, got {:?}", other),
+    }
+
+    let params = json!({"name": "code_review", "arguments": {}});
+    let response = server.handle_request(request(3, "prompts/get", Some(params))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::INVALID_PARAMS);
+
+    let params = json!({"name": "no_such_prompt"});
+    let response = server.handle_request(request(4, "prompts/get", Some(params))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn test_session_info_reports_client() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let request = |id: i64, method: &str, params: Option<serde_json::Value>| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params,
+    };
+
+    let response = server.handle_request(request(1, "session/info", None)).await.unwrap().unwrap();
+    assert!(response.error.is_some());
+
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {"sampling": {}},
+        "clientInfo": {"name": "inspector", "version": "2.3.4"}
+    });
+    server.handle_request(request(2, "initialize", Some(init))).await.unwrap().unwrap();
+
+    let response = server.handle_request(request(3, "session/info", None)).await.unwrap().unwrap();
+    let info = response.result.unwrap();
+    assert_eq!(info["protocolVersion"], "2024-11-05");
+    assert_eq!(info["clientInfo"], json!({"name": "inspector", "version": "2.3.4"}));
+    assert_eq!(info["clientCapabilities"], json!({"sampling": {}}));
+    assert_eq!(info["subscriptions"], json!([]));
+}
+
+#[tokio::test]
+async fn test_clone_keeps_custom_tools() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.tool_registry.register_tool("panic", Box::new(PanicTool));
+
+    // StdioServer::new clones the server out of the Arc it is given
+    let shared = std::sync::Arc::new(server);
+    let mut cloned = (*shared).clone();
+    cloned.initialized = true;
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "tools/list".to_string(),
+        params: None,
+    };
+    let response = cloned.handle_request(request).await.unwrap().unwrap();
+    let tools: ListToolsResponse = serde_json::from_value(response.result.unwrap()).unwrap();
+    assert!(tools.tools.iter().any(|t| t.name == "panic"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_limited_command_memory_cap() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    // /dev/zero has no newlines, so tail buffers it until allocation fails
+    let args = json!({"command": "tail", "args": ["/dev/zero"], "max_memory_mb": 64, "max_cpu_seconds": 30});
+    let response = call_tool(&server, "execute_command_limited", args).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("memory limit"), "{}", text_content(&response, 0));
+
+    let args = json!({"command": "echo", "args": ["within limits"]});
+    let response = call_tool(&server, "execute_command_limited", args).await;
+    assert!(response.is_error.is_none());
+    assert!(text_content(&response, 0).contains("within limits"));
+
+    // Caps beyond the server's maximums are clamped rather than overflowing
+    let args = json!({
+        "command": "cat",
+        "args": ["/dev/zero"],
+        "max_memory_mb": u64::MAX,
+        "max_output_bytes": u64::MAX
+    });
+    let response = call_tool(&server, "execute_command_limited", args).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("output limit (16777216 bytes)"));
+}
+
+#[tokio::test]
+async fn test_batch_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .arg("--quiet")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "batched"}}},
+        {"jsonrpc": "2.0", "id": 3, "method": "ping"},
+        {"jsonrpc": "2.0", "method": "notifications/initialized"}
+    ]);
+    let notifications_only = json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}]);
+    let input = format!("{}\n{}\n{}\n[]\n", initialize, batch, notifications_only);
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input.as_bytes()).await.unwrap();
+    drop(stdin);
+
+    let mut output = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut output).await.unwrap();
+    child.wait().await.unwrap();
+
+    // The notification-only batch writes nothing, so three lines come back.
+    // Messages are handled concurrently, so replies may arrive in any order.
+    let lines: Vec<serde_json::Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 3, "{}", output);
+    let find = |predicate: &dyn Fn(&serde_json::Value) -> bool| lines.iter().find(|l| predicate(l)).unwrap();
+
+    assert!(find(&|l| l["id"] == 1)["result"]["serverInfo"].is_object());
+    let responses = find(&|l| l.is_array()).as_array().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 2);
+    assert!(responses[0]["result"]["content"][0]["text"].as_str().unwrap().contains("batched"));
+    assert_eq!(responses[1]["id"], 3);
+    assert_eq!(responses[1]["result"]["pong"], true);
+
+    assert_eq!(find(&|l| l.is_object() && l["id"].is_null())["error"]["code"], error_codes::INVALID_REQUEST);
+}
+
+#[tokio::test]
+async fn test_semver_parse_and_satisfies() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let args = json!({"version": "v2.0.0-rc.1+build.7"});
+    let response = call_tool(&server, "semver", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["major"], 2);
+    assert_eq!(result["minor"], 0);
+    assert_eq!(result["patch"], 0);
+    assert_eq!(result["pre"], "rc.1");
+    assert_eq!(result["build"], "build.7");
+    assert_eq!(result["is_prerelease"], true);
+
+    let args = json!({"operation": "satisfies", "version": "1.4.2", "range": ">=1.2, <2.0"});
+    let response = call_tool(&server, "semver", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["satisfies"], true);
+
+    let args = json!({"operation": "satisfies", "version": "2.0.0", "range": "^1.4"});
+    let response = call_tool(&server, "semver", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["satisfies"], false);
+
+    let args = json!({"operation": "compare", "version": "1.0.0-alpha", "other": "1.0.0"});
+    let response = call_tool(&server, "semver", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["ordering"], "less");
+
+    let response = call_tool(&server, "semver", json!({"version": "1.2"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_notifications_get_no_reply() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notification = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: None,
+        method: "tools/list".to_string(),
+        params: None,
+    };
+    assert!(server.handle_request(notification).await.unwrap().is_none());
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .arg("--quiet")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"initialized\"}\n").await.unwrap();
+    drop(stdin);
+
+    let mut output = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut output).await.unwrap();
+    child.wait().await.unwrap();
+    assert!(output.is_empty(), "unexpected output: {}", output);
+}
+
+#[tokio::test]
+async fn test_custom_command_allowlist() {
+    use rust_mcp_server::tools::ExecuteCommandTool;
+
+    let config = ServerConfig {
+        allowed_commands: vec!["printf".to_string()],
+        ..ServerConfig::default()
+    };
+    let server = test_server_with_config(config);
+
+    let response = call_tool(&server, "execute_command", json!({"command": "printf", "args": ["allowed"]})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert!(text_content(&response, 0).contains("allowed"));
+
+    let response = call_tool(&server, "execute_command", json!({"command": "echo", "args": ["hi"]})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("Command 'echo' is not allowed. Allowed commands: printf"));
+
+    // Embedders can register their own instance
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.tool_registry.register_tool(
+        "execute_command",
+        Box::new(ExecuteCommandTool::with_allowed(vec!["uname".to_string()])),
+    );
+    let response = call_tool(&server, "execute_command", json!({"command": "uname"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let response = call_tool(&server, "execute_command", json!({"command": "date"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_list_cron_parses_crontab() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("crontab"),
+        "# m h dom mon dow command\n\
+         MAILTO=ops@example.com\n\
+         */15 * * * * /usr/local/bin/sync --quiet > /dev/null 2>&1\n\
+         0 3 * * mon-fri backup.sh\n\
+         @reboot start-agent\n\
+         61 * * *\n\
+         @sometimes task\n",
+    )
+    .unwrap();
+
+    let server = test_server_with_config(ServerConfig::default());
+    assert!(!server.tool_registry.list_tools().iter().any(|t| t.name == "list_cron"));
+
+    let server = test_server_with_config(ServerConfig {
+        enable_cron: true,
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "list_cron", json!({"path": "crontab"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let entries = result["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["schedule"], "*/15 * * * *");
+    assert_eq!(entries[0]["command"], "/usr/local/bin/sync --quiet > /dev/null 2>&1");
+    assert_eq!(entries[0]["line"], 3);
+    assert_eq!(entries[1]["schedule"], "0 3 * * mon-fri");
+    assert_eq!(entries[2]["schedule"], "@reboot");
+    assert_eq!(entries[2]["command"], "start-agent");
+    assert_eq!(result["variables"], json!(["MAILTO=ops@example.com"]));
+
+    let errors = result["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0]["line"], 6);
+    assert!(errors[1]["error"].as_str().unwrap().contains("@sometimes"));
+
+    let system = "0 4 * * * root /usr/sbin/logrotate /etc/logrotate.conf\n";
+    std::fs::write(dir.path().join("system"), system).unwrap();
+    let args = json!({"path": "system", "system_format": true});
+    let response = call_tool(&server, "list_cron", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["entries"][0]["user"], "root");
+    assert_eq!(result["entries"][0]["command"], "/usr/sbin/logrotate /etc/logrotate.conf");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ownership_audit_flags_world_writable() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("private.txt"), "ok").unwrap();
+    std::fs::set_permissions(dir.path().join("private.txt"), std::fs::Permissions::from_mode(0o600)).unwrap();
+    std::fs::write(dir.path().join("shared.txt"), "anyone").unwrap();
+    std::fs::set_permissions(dir.path().join("shared.txt"), std::fs::Permissions::from_mode(0o666)).unwrap();
+    std::os::unix::fs::symlink("/etc/passwd", dir.path().join("link")).unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "ownership_audit", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let findings = result["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 1, "{}", result);
+    assert_eq!(findings[0]["path"], "shared.txt");
+    assert_eq!(findings[0]["mode"], "0666");
+    assert_eq!(findings[0]["issues"], json!(["world_writable"]));
+    assert_eq!(result["symlinks_skipped"], 1);
+
+    let uid = std::fs::metadata(dir.path()).unwrap().uid();
+    assert_eq!(result["owners"][0]["uid"], uid);
+    assert_eq!(result["owners"][0]["files"], 2);
+}
+
+#[tokio::test]
+async fn test_file_tools_confined_to_root() {
+    let outer = tempfile::tempdir().unwrap();
+    let root = outer.path().join("root");
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    std::fs::write(root.join("docs/inside.txt"), "inside the root").unwrap();
+    std::fs::write(outer.path().join("secret.txt"), "outside the root").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(root.clone()),
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "read_file", json!({"path": "docs/inside.txt"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert!(text_content(&response, 0).contains("inside the root"));
+
+    let response = call_tool(&server, "list_files", json!({"path": "docs"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert!(text_content(&response, 0).contains("inside.txt"));
+
+    let response = call_tool(&server, "read_file", json!({"path": "docs/../../secret.txt"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("outside the allowed root"));
+
+    let absolute = outer.path().join("secret.txt");
+    let response = call_tool(&server, "read_file", json!({"path": absolute.to_str().unwrap()})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("outside the allowed root"));
+
+    let response = call_tool(&server, "list_files", json!({"path": "../.."})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("outside the allowed root"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sandbox_refuses_dangling_symlink() {
+    use rust_mcp_server::sandbox::Sandbox;
+
+    let outer = tempfile::tempdir().unwrap();
+    let root = outer.path().join("root");
+    std::fs::create_dir(&root).unwrap();
+    let outside = outer.path().join("outside_target");
+    std::os::unix::fs::symlink(&outside, root.join("evil")).unwrap();
+
+    let sandbox = Sandbox::new(Some(root.clone()));
+    assert!(sandbox.resolve_new("evil").is_err());
+    assert!(sandbox.resolve_new("evil/child.txt").is_err());
+    assert!(sandbox.resolve_new("fresh.txt").unwrap().starts_with(root.canonicalize().unwrap()));
+}
+
+#[tokio::test]
+async fn test_list_files_recursive_with_depth_and_pattern() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src/deep/deeper")).unwrap();
+    std::fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+    std::fs::write(dir.path().join("README.md"), "# readme").unwrap();
+    std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+    std::fs::write(dir.path().join("src/deep/mod.rs"), "").unwrap();
+    std::fs::write(dir.path().join("src/deep/deeper/leaf.rs"), "").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let list = |args: serde_json::Value| {
+        let server = &server;
+        async move {
+            let response = call_tool(server, "list_files", args).await;
+            assert!(response.is_error.is_none(), "{:?}", response.content);
+            serde_json::from_str::<serde_json::Value>(text_content(&response, 0)).unwrap()
+        }
+    };
+    let paths = |result: &serde_json::Value| -> Vec<String> {
+        result["entries"].as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap().to_string()).collect()
+    };
+
+    // Without recursive only the directory itself is listed
+    let result = list(json!({})).await;
+    assert_eq!(paths(&result), vec!["README.md", "main.rs", "src"]);
+    assert_eq!(result["entries"][1], json!({"path": "main.rs", "type": "file", "size": 12}));
+    assert_eq!(result["entries"][2], json!({"path": "src", "type": "directory"}));
+
+    let result = list(json!({"recursive": true, "max_depth": 2})).await;
+    assert_eq!(paths(&result), vec!["README.md", "main.rs", "src", "src/deep", "src/lib.rs"]);
+
+    let result = list(json!({"recursive": true, "pattern": "*.rs"})).await;
+    assert_eq!(paths(&result), vec!["main.rs", "src/lib.rs", "src/deep/mod.rs", "src/deep/deeper/leaf.rs"]);
+    assert_eq!(result["total"], 4);
+
+    let result = list(json!({"path": "src", "recursive": true, "pattern": "deep/*"})).await;
+    assert_eq!(paths(&result), vec!["deep/deeper", "deep/mod.rs", "deep/deeper/leaf.rs"]);
+
+    let response = call_tool(&server, "list_files", json!({"pattern": "[oops"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("Invalid glob"));
+}
+
+#[tokio::test]
+async fn test_read_file_slices() {
+    let dir = tempfile::tempdir().unwrap();
+    let lines: String = (0..1000).map(|i| format!("line {:04}\n", i)).collect();
+    std::fs::write(dir.path().join("big.log"), &lines).unwrap();
+    std::fs::write(dir.path().join("accents.txt"), "café au lait").unwrap();
+
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let metadata = |response: &CallToolResponse| -> serde_json::Value {
+        serde_json::from_str(text_content(response, 0)).unwrap()
+    };
+
+    // The whole file is over max_size, but a slice of it can be read
+    let response = call_tool(&server, "read_file", json!({"path": "big.log", "max_size": 1000})).await;
+    assert_eq!(response.is_error, Some(true));
+
+    let args = json!({"path": "big.log", "max_size": 1000, "offset": 5000, "length": 20});
+    let response = call_tool(&server, "read_file", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert_eq!(text_content(&response, 1), "line 0500\nline 0501\n");
+    let meta = metadata(&response);
+    assert_eq!(meta["offset"], 5000);
+    assert_eq!(meta["length"], 20);
+    assert_eq!(meta["total_size"], 10_000);
+    assert_eq!(meta["next_offset"], 5020);
+    assert_eq!(meta["has_more"], true);
+
+    // Length is capped at max_size
+    let args = json!({"path": "big.log", "max_size": 100, "offset": 9950, "length": 500});
+    let response = call_tool(&server, "read_file", args).await;
+    assert_eq!(text_content(&response, 1).len(), 50);
+    assert_eq!(metadata(&response)["has_more"], false);
+
+    let args = json!({"path": "big.log", "offset": 20_000});
+    let response = call_tool(&server, "read_file", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert_eq!(text_content(&response, 1), "");
+    let meta = metadata(&response);
+    assert_eq!(meta["length"], 0);
+    assert_eq!(meta["total_size"], 10_000);
+    assert_eq!(meta["has_more"], false);
+
+    // A character split by the slice is left to the neighbouring read
+    let response = call_tool(&server, "read_file", json!({"path": "accents.txt", "length": 4})).await;
+    assert_eq!(text_content(&response, 1), "caf");
+    assert_eq!(metadata(&response)["next_offset"], 3);
+    let response = call_tool(&server, "read_file", json!({"path": "accents.txt", "offset": 3})).await;
+    assert_eq!(text_content(&response, 1), "é au lait");
+}
+
+#[tokio::test]
+async fn test_read_file_returns_binary_as_base64() {
+    use base64::Engine;
+
+    // The 8-byte PNG signature followed by the start of an IHDR chunk
+    let png = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0, 0, 0, 13, b'I', b'H', b'D', b'R'];
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "plain UTF-8 text ✓").unwrap();
+    std::fs::write(dir.path().join("pixel.png"), png).unwrap();
+    std::fs::write(dir.path().join("data.bin"), [0xff, 0xfe, 0x00, 0x01]).unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "read_file", json!({"path": "notes.txt"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert_eq!(text_content(&response, 0), "Contents of notes.txt:\nplain UTF-8 text ✓");
+
+    let response = call_tool(&server, "read_file", json!({"path": "pixel.png"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let image = serde_json::to_value(&response.content[1]).unwrap();
+    assert_eq!(image["type"], "image");
+    assert_eq!(image["mimeType"], "image/png");
+    let decoded = base64::engine::general_purpose::STANDARD.decode(image["data"].as_str().unwrap()).unwrap();
+    assert_eq!(decoded, png);
+
+    let response = call_tool(&server, "read_file", json!({"path": "data.bin"})).await;
+    let resource = serde_json::to_value(&response.content[1]).unwrap();
+    assert_eq!(resource["type"], "resource");
+    assert_eq!(resource["resource"]["mimeType"], "application/octet-stream");
+    assert_eq!(resource["resource"]["blob"], "//4AAQ==");
+    assert!(resource["resource"]["uri"].as_str().unwrap().starts_with("file://"));
+
+    // base64 can be requested for text too
+    let response = call_tool(&server, "read_file", json!({"path": "notes.txt", "encoding": "base64"})).await;
+    let resource = serde_json::to_value(&response.content[1]).unwrap();
+    assert_eq!(resource["resource"]["mimeType"], "text/plain");
+
+    let response = call_tool(&server, "read_file", json!({"path": "data.bin", "encoding": "text"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_query_jsonl_filters_by_field() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/logs");
+    let server = test_server_with_config(ServerConfig {
+        root: Some(fixtures),
+        ..ServerConfig::default()
+    });
+
+    let args = json!({"path": "app.jsonl", "filter": "level == \"error\""});
+    let response = call_tool(&server, "query_jsonl", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let messages: Vec<&str> = result["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["msg"].as_str().unwrap())
+        .collect();
+    assert_eq!(messages, ["upstream timeout", "disk full", "bad request body"]);
+    assert_eq!(result["malformed_lines"], 1);
+    assert_eq!(result["lines_scanned"], 7);
+    assert_eq!(result["truncated"], false);
+
+    // Nested fields, numeric comparison and tail mode
+    let args = json!({"path": "app.jsonl", "filter": "level == error && http.status >= 500", "tail": true, "limit": 1});
+    let response = call_tool(&server, "query_jsonl", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["matches"][0]["msg"], "disk full");
+    assert_eq!(result["matched"], 2);
+    assert_eq!(result["truncated"], true);
+
+    let args = json!({"path": "app.jsonl", "filter": "level ="});
+    let response = call_tool(&server, "query_jsonl", args).await;
+    assert_eq!(response.is_error, Some(true));
+
+    let response = call_tool(&server, "query_jsonl", json!({"path": "../encoding/utf16le.txt"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_arguments_validated_against_schema() {
+    let server = test_server_with_config(ServerConfig::default());
+
+    let error = call_tool_error(&server, "echo", json!({})).await;
+    assert_eq!(
+        error.to_string(),
+        "Invalid arguments for tool 'echo': Missing required field 'text'"
+    );
+
+    let error = call_tool_error(&server, "echo", json!({"text": 42})).await;
+    assert_eq!(
+        error.to_string(),
+        "Invalid arguments for tool 'echo': 'text' must be a string, got a number"
+    );
+
+    let args = json!({"command": "echo", "args": ["ok", 7]});
+    let error = call_tool_error(&server, "execute_command", args).await;
+    assert!(error.to_string().contains("'args[1]' must be a string"));
+
+    let error = call_tool_error(&server, "semver", json!({"version": "1.0.0", "operation": "bump"})).await;
+    assert!(error.to_string().contains("'operation' must be one of"));
+}
+
+#[tokio::test]
+async fn test_export_openai_functions() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "tools/export_openai".to_string(),
+        params: None,
+    };
+    let response = server.handle_request(request).await.unwrap().unwrap();
+    let result = response.result.unwrap();
+
+    let tools = result["tools"].as_array().unwrap();
+    let read_file = tools.iter().find(|t| t["function"]["name"] == "read_file").unwrap();
+    assert_eq!(read_file["type"], "function");
+    assert_eq!(read_file["function"]["description"], "Read the contents of a file");
+
+    let parameters = &read_file["function"]["parameters"];
+    assert_eq!(parameters["type"], "object");
+    assert_eq!(parameters["required"], json!(["path"]));
+    assert_eq!(parameters["properties"]["path"]["type"], "string");
+    assert_eq!(parameters["properties"]["max_size"]["type"], "integer");
+    assert!(parameters["properties"]["max_size"].get("default").is_none());
+    assert!(parameters["properties"]["max_size"]["description"]
+        .as_str()
+        .unwrap()
+        .ends_with("(default: 1048576)"));
+
+    // Bounds like chunk_text's `minimum` are stripped with a warning
+    let warnings = result["warnings"].as_array().unwrap();
+    assert!(warnings.iter().any(|w| w.as_str().unwrap().starts_with("chunk_text.")));
+}
+
+/// Minimal HTTP/1.1 exchange; returns the status code and body
+async fn http_request(addr: std::net::SocketAddr, request: String) -> (u16, String) {
+    let (status, _, body) = http_exchange(addr, request).await;
+    (status, body)
+}
+
+/// `http_request`, also returning the response headers
+async fn http_exchange(addr: std::net::SocketAddr, request: String) -> (u16, String, String) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, head.to_string(), body.to_string())
+}
+
+/// POST to the streamable HTTP endpoint with extra header lines, each
+/// ending in `\r\n`; returns the status code, headers and body
+async fn http_post(addr: std::net::SocketAddr, headers: &str, body: &str) -> (u16, String, String) {
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        headers,
+        body.len(),
+        body
+    );
+    http_exchange(addr, request).await
+}
+
+/// The `Mcp-Session-Id` response header
+fn session_header(head: &str) -> Option<String> {
+    head.lines()
+        .find_map(|line| line.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case("mcp-session-id")))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+#[tokio::test]
+async fn test_http_transport() {
+    use rust_mcp_server::server::HttpServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notifier = server.notifier.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let (status, _, body) = http_post(addr, "", "{not json").await;
+    assert_eq!(status, 400);
+    let response: JsonRpcResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::PARSE_ERROR);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (status, head, body) = http_post(addr, "", &initialize.to_string()).await;
+    assert_eq!(status, 200);
+    let response: JsonRpcResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).expect("no session id"));
+
+    // Same routing as stdio: tools need the initialize above
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "over http"}}});
+    let (status, _, body) = http_post(addr, &session, &call.to_string()).await;
+    assert_eq!(status, 200);
+    assert!(body.contains("over http"));
+
+    let (status, _, body) = http_post(addr, &session, r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).await;
+    assert_eq!(status, 202);
+    assert!(body.is_empty());
+
+    // Notifications reach SSE subscribers
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /sse HTTP/1.1\r\nHost: localhost\r\nAccept: text/event-stream\r\n\r\n")
+        .await
+        .unwrap();
+    let mut reader = tokio::io::BufReader::new(stream);
+    let mut line = String::new();
+    let mut saw_event_stream = false;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        saw_event_stream |= line.to_ascii_lowercase().starts_with("content-type: text/event-stream");
+        if line == "\r\n" {
+            break;
+        }
+    }
+    assert!(saw_event_stream);
+
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "hello"})));
+    let data = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            if let Some(data) = line.strip_prefix("data: ") {
+                return data.trim().to_string();
+            }
+        }
+    })
+    .await
+    .unwrap();
+    let notification: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(notification["method"], "notifications/message");
+}
+
+#[tokio::test]
+async fn test_streamable_http_sessions() {
+    use rust_mcp_server::server::HttpServer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}).to_string();
+    let (status, _, body) = http_post(addr, "", &list).await;
+    assert_eq!(status, 400);
+    let response: JsonRpcResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::INVALID_REQUEST);
+    let (status, _, _) = http_post(addr, "Mcp-Session-Id: nope\r\n", &list).await;
+    assert_eq!(status, 404);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    })
+    .to_string();
+    let (_, head, _) = http_post(addr, "", &initialize).await;
+    let first = session_header(&head).unwrap();
+    let (_, head, _) = http_post(addr, "", &initialize).await;
+    let second = session_header(&head).unwrap();
+    assert_ne!(first, second);
+    let session = format!("Mcp-Session-Id: {}\r\n", first);
+
+    // Clients accepting event streams get tool call results as one
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "streamed"}}});
+    let headers = format!("{}Accept: application/json, text/event-stream\r\n", session);
+    let (status, head, body) = http_post(addr, &headers, &call.to_string()).await;
+    assert_eq!(status, 200);
+    assert!(head.to_ascii_lowercase().contains("content-type: text/event-stream"));
+    let data = body.lines().find_map(|line| line.strip_prefix("data: ")).expect("no message event");
+    let response: JsonRpcResponse = serde_json::from_str(data).unwrap();
+    assert_eq!(response.id, Some(json!(2)));
+    assert!(data.contains("streamed"));
+
+    // Other requests, and clients accepting only JSON, get plain JSON
+    let (status, head, _) = http_post(addr, &session, &list).await;
+    assert_eq!(status, 200);
+    assert!(head.to_ascii_lowercase().contains("content-type: application/json"));
+
+    let delete = format!("DELETE /mcp HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n", session);
+    let (status, _) = http_request(addr, delete).await;
+    assert_eq!(status, 204);
+    let (status, _, _) = http_post(addr, &session, &list).await;
+    assert_eq!(status, 404);
+
+    // Ending one session leaves the others alone
+    let (status, _, _) = http_post(addr, &format!("Mcp-Session-Id: {}\r\n", second), &list).await;
+    assert_eq!(status, 200);
+}
+
+/// Reports progress on its call while announcing other notifications to
+/// every session
+struct NoisyProgressTool {
+    notifier: rust_mcp_server::notifications::Notifier,
+}
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for NoisyProgressTool {
+    fn description(&self) -> String {
+        "Reports progress amid other notifications".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        self.call_with_progress(args, rust_mcp_server::notifications::Progress::none()).await
+    }
+
+    async fn call_with_progress(
+        &self,
+        _args: serde_json::Value,
+        progress: rust_mcp_server::notifications::Progress,
+    ) -> anyhow::Result<CallToolResponse> {
+        self.notifier.notify("notifications/tools/list_changed", None);
+        self.notifier.notify("notifications/progress", Some(json!({"progressToken": "other", "progress": 1})));
+        progress.report(50.0, Some(100.0), None);
+        Ok(CallToolResponse::text("done".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_http_tool_call_stream_carries_only_its_progress() {
+    use rust_mcp_server::server::HttpServer;
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notifier = server.notifier.clone();
+    server.tool_registry.register_tool("noisy", Box::new(NoisyProgressTool { notifier }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "noisy", "arguments": {}, "_meta": {"progressToken": "mine"}}
+    });
+    let headers = format!("{}Accept: application/json, text/event-stream\r\n", session);
+    let (status, _, body) = http_post(addr, &headers, &call.to_string()).await;
+    assert_eq!(status, 200);
+    let messages: Vec<serde_json::Value> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert_eq!(messages[0]["method"], "notifications/progress");
+    assert_eq!(messages[0]["params"]["progressToken"], "mine");
+    assert_eq!(messages[1]["id"], 2);
+    assert_eq!(messages[1]["result"]["content"][0]["text"], "done");
+}
+
+/// Open the streamable HTTP event stream with extra header lines and skip
+/// past the response headers
+async fn http_get_stream(addr: std::net::SocketAddr, headers: &str) -> tokio::io::BufReader<tokio::net::TcpStream> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET /mcp HTTP/1.1\r\nHost: localhost\r\nAccept: text/event-stream\r\n{}\r\n", headers);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut reader = tokio::io::BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        if line == "\r\n" {
+            return reader;
+        }
+    }
+}
+
+/// The id and data of the next event on an SSE stream
+async fn sse_next_event_id(reader: &mut tokio::io::BufReader<tokio::net::TcpStream>) -> (u64, String) {
+    use tokio::io::AsyncBufReadExt;
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut id = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            if let Some(value) = line.strip_prefix("id: ") {
+                id = value.trim().parse().unwrap();
+            } else if let Some(data) = line.strip_prefix("data: ") {
+                return (id, data.trim().to_string());
+            }
+        }
+    })
+    .await
+    .expect("no SSE event")
+}
+
+#[tokio::test]
+async fn test_streamable_http_resumption() {
+    use rust_mcp_server::server::HttpServer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notifier = server.notifier.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    let mut stream = http_get_stream(addr, &session).await;
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "first"})));
+    let (first, data) = sse_next_event_id(&mut stream).await;
+    assert!(data.contains("first"));
+    drop(stream);
+
+    // Sent while no client is connected, so only the buffer has them
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "second"})));
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "third"})));
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let mut stream = http_get_stream(addr, &format!("{}Last-Event-ID: {}\r\n", session, first)).await;
+    let (second, data) = sse_next_event_id(&mut stream).await;
+    assert!(second > first);
+    assert!(data.contains("second"));
+    let (_, data) = sse_next_event_id(&mut stream).await;
+    assert!(data.contains("third"));
+
+    // A tool call's stream ends with its reply, so resuming it after the
+    // reply gets an empty stream rather than one that never ends
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "resumed"}}});
+    let headers = format!("{}Accept: application/json, text/event-stream\r\n", session);
+    let (_, _, body) = http_post(addr, &headers, &call.to_string()).await;
+    assert!(body.contains("resumed"));
+    let reply_id = body.lines().find_map(|line| line.strip_prefix("id: ")).expect("no event id").trim();
+    let request = format!(
+        "GET /mcp HTTP/1.1\r\nHost: localhost\r\nAccept: text/event-stream\r\n{}Last-Event-ID: {}\r\nConnection: close\r\n\r\n",
+        session, reply_id
+    );
+    let (status, _, body) = tokio::time::timeout(std::time::Duration::from_secs(5), http_exchange(addr, request))
+        .await
+        .expect("resumed stream did not end");
+    assert_eq!(status, 200);
+    assert!(!body.contains("data:"));
+}
+
+/// Open an SSE stream and skip past the response headers
+async fn sse_connect(addr: std::net::SocketAddr) -> tokio::io::BufReader<tokio::net::TcpStream> {
+    sse_connect_at(addr, "/sse").await
+}
+
+/// `sse_connect` for a stream at another path
+async fn sse_connect_at(addr: std::net::SocketAddr, path: &str) -> tokio::io::BufReader<tokio::net::TcpStream> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nAccept: text/event-stream\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut reader = tokio::io::BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        if line == "\r\n" {
+            return reader;
+        }
+    }
+}
+
+/// The name and data of the next event on an SSE stream
+async fn sse_next_event(reader: &mut tokio::io::BufReader<tokio::net::TcpStream>) -> (String, String) {
+    use tokio::io::AsyncBufReadExt;
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut event = String::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            if let Some(name) = line.strip_prefix("event: ") {
+                event = name.trim().to_string();
+            } else if let Some(data) = line.strip_prefix("data: ") {
+                return (event, data.trim().to_string());
+            }
+        }
+    })
+    .await
+    .expect("no SSE event")
+}
+
+async fn sse_post(addr: std::net::SocketAddr, endpoint: &str, body: &str) -> (u16, String) {
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        endpoint,
+        body.len(),
+        body
+    );
+    http_request(addr, request).await
+}
+
+#[tokio::test]
+async fn test_sse_transport() {
+    use rust_mcp_server::server::HttpSseServer;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let sse = HttpSseServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { sse.serve(listener).await });
+
+    let mut events = sse_connect(addr).await;
+    let (event, endpoint) = sse_next_event(&mut events).await;
+    assert_eq!(event, "endpoint");
+    assert!(endpoint.starts_with("/messages?sessionId="));
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (status, body) = sse_post(addr, &endpoint, &initialize.to_string()).await;
+    assert_eq!(status, 202);
+    assert!(body.is_empty());
+    let (event, data) = sse_next_event(&mut events).await;
+    assert_eq!(event, "message");
+    let response: JsonRpcResponse = serde_json::from_str(&data).unwrap();
+    assert_eq!(response.id, Some(json!(1)));
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "over sse"}}});
+    assert_eq!(sse_post(addr, &endpoint, &call.to_string()).await.0, 202);
+    let (_, data) = sse_next_event(&mut events).await;
+    let response: JsonRpcResponse = serde_json::from_str(&data).unwrap();
+    assert_eq!(response.id, Some(json!(2)));
+    assert!(data.contains("over sse"));
+
+    // Every stream is its own session and must initialize separately
+    let mut other = sse_connect(addr).await;
+    let (_, other_endpoint) = sse_next_event(&mut other).await;
+    assert_ne!(other_endpoint, endpoint);
+    assert_eq!(sse_post(addr, &other_endpoint, &call.to_string()).await.0, 202);
+    let (_, data) = sse_next_event(&mut other).await;
+    let response: JsonRpcResponse = serde_json::from_str(&data).unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::SERVER_NOT_INITIALIZED);
+
+    let (status, _) = sse_post(addr, &endpoint, "{not json").await;
+    assert_eq!(status, 400);
+    let (status, _) = sse_post(addr, "/messages?sessionId=nope", &call.to_string()).await;
+    assert_eq!(status, 404);
+
+    // Closing the stream ends the session
+    drop(other);
+    let closed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while sse_post(addr, &other_endpoint, &call.to_string()).await.0 != 404 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "session outlived its stream");
+}
+
+#[tokio::test]
+async fn test_base_path() {
+    use rust_mcp_server::server::{HttpServer, HttpSseServer};
+
+    let server = std::sync::Arc::new(McpServer::new("test-server".to_string(), "1.0.0".to_string()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let sse = HttpSseServer::new(server.clone(), true).with_base_path("ai/mcp/");
+    tokio::spawn(async move { sse.serve(listener).await });
+
+    // The advertised endpoint goes through the proxy's prefix
+    let mut events = sse_connect_at(addr, "/ai/mcp/sse").await;
+    let (event, endpoint) = sse_next_event(&mut events).await;
+    assert_eq!(event, "endpoint");
+    assert!(endpoint.starts_with("/ai/mcp/messages?sessionId="), "{}", endpoint);
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}).to_string();
+    assert_eq!(sse_post(addr, &endpoint, &ping).await.0, 202);
+    let (_, data) = sse_next_event(&mut events).await;
+    assert!(data.contains("\"id\":1"));
+
+    // A proxy that strips the prefix still reaches the routes
+    let stripped = endpoint.strip_prefix("/ai/mcp").unwrap();
+    assert_eq!(sse_post(addr, stripped, &ping).await.0, 202);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(server, true).with_base_path("/ai/mcp");
+    tokio::spawn(async move { http.serve(listener).await });
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    })
+    .to_string();
+    let request = format!(
+        "POST /ai/mcp/mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        initialize.len(),
+        initialize
+    );
+    let (status, head, _) = http_exchange(addr, request).await;
+    assert_eq!(status, 200);
+    assert!(session_header(&head).is_some());
+    let (status, _, _) = http_post(addr, "", &initialize).await;
+    assert_eq!(status, 200);
+}
+
+/// Replies after a short delay, long enough to still be running when a
+/// test shuts the server down
+struct DelayedTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for DelayedTool {
+    fn description(&self) -> String {
+        "Replies after 300ms".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, _args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        Ok(CallToolResponse::text("delayed reply"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_graceful_shutdown() {
+    use rust_mcp_server::server::HttpServer;
+    use tokio::io::AsyncBufReadExt;
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.tool_registry.register_tool("delayed", Box::new(DelayedTool));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true).with_shutdown_timeout(std::time::Duration::from_secs(30));
+    let (signal, shutdown) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(async move { http.serve_until(listener, async { let _ = shutdown.await; }).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+    let mut notifications = http_get_stream(addr, &session).await;
+
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "delayed", "arguments": {}}});
+    let calling = tokio::spawn(async move { http_post(addr, &session, &call.to_string()).await });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    signal.send(()).unwrap();
+
+    // The call in flight is answered, and the notification stream ends
+    // rather than holding shutdown up for the whole timeout
+    let (status, _, body) = calling.await.unwrap();
+    assert_eq!(status, 200);
+    assert!(body.contains("delayed reply"));
+    let finished = tokio::time::timeout(std::time::Duration::from_secs(5), serving).await;
+    finished.expect("server did not shut down").unwrap().unwrap();
+    let mut rest = String::new();
+    while notifications.read_line(&mut rest).await.unwrap_or(0) > 0 {}
+    assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+}
+
+/// Send one line and read one line back
+async fn tcp_exchange(
+    reader: &mut tokio::io::BufReader<tokio::net::tcp::OwnedReadHalf>,
+    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+    message: serde_json::Value,
+) -> JsonRpcResponse {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    writer.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+    let mut line = String::new();
+    tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+        .await
+        .expect("no TCP response")
+        .unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+#[tokio::test]
+async fn test_tcp_transport() {
+    use rust_mcp_server::server::TcpServer;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    let (first_read, mut first) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut first_read = tokio::io::BufReader::new(first_read);
+    let (second_read, mut second) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut second_read = tokio::io::BufReader::new(second_read);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let response = tcp_exchange(&mut first_read, &mut first, initialize).await;
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "over tcp"}}});
+    let response = tcp_exchange(&mut first_read, &mut first, call.clone()).await;
+    assert!(serde_json::to_string(&response.result.unwrap()).unwrap().contains("over tcp"));
+
+    // Each connection is its own session
+    let response = tcp_exchange(&mut second_read, &mut second, call).await;
+    assert_eq!(response.error.unwrap().code, error_codes::SERVER_NOT_INITIALIZED);
+}
+
+#[tokio::test]
+async fn test_tcp_sessions_keep_notifications_apart() {
+    use rust_mcp_server::server::TcpServer;
+    use tokio::io::AsyncBufReadExt;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    let (first_read, mut first) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut first_read = tokio::io::BufReader::new(first_read);
+    let (second_read, mut second) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut second_read = tokio::io::BufReader::new(second_read);
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    tcp_exchange(&mut first_read, &mut first, initialize.clone()).await;
+    tcp_exchange(&mut second_read, &mut second, initialize).await;
+
+    // The first client's log level, task and its result are its own
+    let set_level = json!({"jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": {"level": "debug"}});
+    tcp_exchange(&mut first_read, &mut first, set_level).await;
+    let schedule = json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "tools/call",
+        "params": {
+            "name": "schedule_task",
+            "arguments": {"tool": "execute_command", "arguments": {"command": "echo", "args": ["first only"]}, "delay_seconds": 0.05}
+        }
+    });
+    let response = tcp_exchange(&mut first_read, &mut first, schedule).await;
+    assert!(response.result.unwrap()["isError"].is_null());
+    let mut line = String::new();
+    loop {
+        line.clear();
+        tokio::time::timeout(std::time::Duration::from_secs(5), first_read.read_line(&mut line))
+            .await
+            .expect("task result did not arrive")
+            .unwrap();
+        let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+        if message["method"] == rust_mcp_server::tools::TASK_RESULT_NOTIFICATION {
+            break;
+        }
+    }
+
+    // Anything sent to the second client would be read ahead of the reply
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "list_tasks", "arguments": {}}});
+    let response = tcp_exchange(&mut second_read, &mut second, list).await;
+    assert_eq!(response.id, Some(json!(2)));
+    let listed: serde_json::Value = serde_json::from_str(response.result.unwrap()["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(listed["tasks"], json!([]));
+}
+
+/// A client connector trusting only the fixture CA, which signed `server.pem`,
+/// and presenting the CA-signed `client.pem` if `with_client_cert`
+fn fixture_tls_connector(with_client_cert: bool) -> tokio_rustls::TlsConnector {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+    let mut roots = tokio_rustls::rustls::RootCertStore::empty();
+    roots.add(CertificateDer::from_pem_file(fixtures.join("cert.pem")).unwrap()).unwrap();
+    let config = tokio_rustls::rustls::ClientConfig::builder_with_provider(std::sync::Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots);
+    let config = if with_client_cert {
+        let cert = CertificateDer::from_pem_file(fixtures.join("client.pem")).unwrap();
+        let key = PrivateKeyDer::from_pem_file(fixtures.join("client-key.pem")).unwrap();
+        config.with_client_auth_cert(vec![cert], key).unwrap()
+    } else {
+        config.with_no_client_auth()
+    };
+    tokio_rustls::TlsConnector::from(std::sync::Arc::new(config))
+}
+
+#[tokio::test]
+async fn test_tls_transports() {
+    use rust_mcp_server::server::{load_tls_acceptor, HttpServer, TcpServer};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::ServerName;
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+    let acceptor = load_tls_acceptor(&fixtures.join("server.pem"), &fixtures.join("server-key.pem"), None).unwrap();
+    assert!(load_tls_acceptor(&fixtures.join("server.pem"), &fixtures.join("missing.pem"), None).is_err());
+    assert!(load_tls_acceptor(&fixtures.join("server.pem"), &fixtures.join("key.pem"), None).is_err());
+    let connector = fixture_tls_connector(false);
+    let localhost = ServerName::try_from("localhost").unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true).with_tls(acceptor.clone());
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    // A plaintext client gets nothing back but a closed connection
+    let mut plain = tokio::net::TcpStream::connect(addr).await.unwrap();
+    plain.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n").await.unwrap();
+    let mut received = Vec::new();
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(5), plain.read_to_end(&mut received))
+        .await
+        .expect("plaintext connection left open");
+    assert!(!String::from_utf8_lossy(&received).contains("jsonrpc"));
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let stream = connector.connect(localhost.clone(), stream).await.unwrap();
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = tokio::io::BufReader::new(reader);
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    writer.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    let mut line = String::new();
+    tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+        .await
+        .expect("no TLS response")
+        .unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let http = HttpServer::new(std::sync::Arc::new(server), true).with_tls(acceptor);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    // A client that stalls mid-handshake holds up no one else
+    let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut stream = connector.connect(localhost, stream).await.unwrap();
+    let body = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}).to_string();
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    tokio::time::timeout(std::time::Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("no HTTPS response")
+        .unwrap();
+    // Routed like plain HTTP: no session yet
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+}
+
+#[tokio::test]
+async fn test_tls_client_certificates() {
+    use rust_mcp_server::server::{load_tls_acceptor, HttpServer, TcpServer};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::ServerName;
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+    let acceptor = load_tls_acceptor(
+        &fixtures.join("server.pem"),
+        &fixtures.join("server-key.pem"),
+        Some(&fixtures.join("cert.pem")),
+    )
+    .unwrap();
+    let localhost = ServerName::try_from("localhost").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    std::fs::create_dir(root.join("public")).unwrap();
+    std::fs::write(root.join("public/notes.txt"), "shared").unwrap();
+    std::fs::write(root.join("secret.txt"), "private").unwrap();
+    let mut config = ServerConfig {
+        root: Some(root.clone()),
+        ..ServerConfig::default()
+    };
+    config.client_profiles.insert("agent-01".to_string(), "operator".to_string());
+    config.profile_tools.insert("operator".to_string(), vec!["echo".to_string(), "schedule_task".to_string()]);
+    config.profile_resources.insert("operator".to_string(), vec![format!("file://{}/public/", root.display())]);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::with_config("test-server".to_string(), "1.0.0".to_string(), config.clone());
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true).with_tls(acceptor.clone());
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    // Without a certificate the connection ends before any JSON-RPC is read
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let refused = async {
+        let mut stream = fixture_tls_connector(false).connect(localhost.clone(), stream).await?;
+        stream.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n").await?;
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await?;
+        Ok::<_, std::io::Error>(received)
+    };
+    let refused = tokio::time::timeout(std::time::Duration::from_secs(5), refused).await.unwrap();
+    assert!(refused.is_err() || refused.unwrap().is_empty());
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let stream = fixture_tls_connector(true).connect(localhost.clone(), stream).await.unwrap();
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = tokio::io::BufReader::new(reader);
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let info = json!({"jsonrpc": "2.0", "id": 2, "method": "session/info"});
+    writer.write_all(format!("{}\n{}\n", initialize, info).as_bytes()).await.unwrap();
+    let mut line = String::new();
+    let response = loop {
+        line.clear();
+        tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .expect("no TLS response")
+            .unwrap();
+        let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+        if response.id == Some(json!(2)) {
+            break response;
+        }
+    };
+    // The certificate's common name maps to a profile, kept through initialize
+    assert_eq!(response.result.unwrap()["scopes"], json!(["operator"]));
+
+    // The profile's tools are the only ones the session lists, calls or schedules
+    let list = json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"});
+    let call = json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "read_file", "arguments": {"path": "Cargo.toml"}}});
+    let schedule = json!({
+        "jsonrpc": "2.0",
+        "id": 5,
+        "method": "tools/call",
+        "params": {"name": "schedule_task", "arguments": {"tool": "read_file", "arguments": {"path": "Cargo.toml"}}}
+    });
+    writer.write_all(format!("{}\n{}\n{}\n", list, call, schedule).as_bytes()).await.unwrap();
+    let mut responses = std::collections::HashMap::new();
+    while responses.len() < 3 {
+        line.clear();
+        tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .expect("no TLS response")
+            .unwrap();
+        let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+        responses.insert(response.id.clone().unwrap().as_u64().unwrap(), response);
+    }
+    let listed = responses[&3].result.as_ref().unwrap();
+    let names: Vec<&str> = listed["tools"].as_array().unwrap().iter().map(|tool| tool["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["echo", "schedule_task"]);
+    assert!(responses[&4].error.is_some());
+    assert!(responses[&5].error.is_some());
+
+    // Its resources likewise, with no way around the prefix
+    let public = format!("file://{}/public/notes.txt", root.display());
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 6, "method": "resources/list"}),
+        json!({"jsonrpc": "2.0", "id": 7, "method": "resources/read", "params": {"uri": public}}),
+        json!({"jsonrpc": "2.0", "id": 8, "method": "resources/read", "params": {"uri": format!("file://{}/secret.txt", root.display())}}),
+        json!({"jsonrpc": "2.0", "id": 9, "method": "resources/read", "params": {"uri": format!("file://{}/public/../secret.txt", root.display())}}),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 10,
+            "method": "completion/complete",
+            "params": {"ref": {"type": "ref/resource", "uri": "file:///{path}"}, "argument": {"name": "path", "value": "s"}}
+        }),
+    ];
+    for message in &messages {
+        writer.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+    }
+    while responses.len() < 8 {
+        line.clear();
+        tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .expect("no TLS response")
+            .unwrap();
+        let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+        responses.insert(response.id.clone().unwrap().as_u64().unwrap(), response);
+    }
+    let listed = responses[&6].result.as_ref().unwrap();
+    let uris: Vec<&str> = listed["resources"].as_array().unwrap().iter().map(|resource| resource["uri"].as_str().unwrap()).collect();
+    assert_eq!(uris, vec![public.as_str()]);
+    assert_eq!(responses[&7].result.as_ref().unwrap()["contents"][0]["text"], "shared");
+    assert!(responses[&8].error.is_some());
+    assert!(responses[&9].error.is_some());
+    assert_eq!(responses[&10].result.as_ref().unwrap()["completion"]["values"], json!([]));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::with_config("test-server".to_string(), "1.0.0".to_string(), config);
+    let http = HttpServer::new(std::sync::Arc::new(server), true).with_tls(acceptor);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut stream = fixture_tls_connector(true).connect(localhost, stream).await.unwrap();
+    let body = initialize.to_string();
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut reader = tokio::io::BufReader::new(stream);
+    let mut head = String::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        if line == "\r\n" {
+            break;
+        }
+        head.push_str(&line);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.unwrap();
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    let session = session_header(&head).expect("no session id");
+
+    let body = info.to_string();
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nMcp-Session-Id: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        session,
+        body.len(),
+        body
+    );
+    reader.get_mut().write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    reader.read_to_string(&mut response).await.unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(body).unwrap();
+    assert_eq!(response.result.unwrap()["scopes"], json!(["operator"]));
+}
+
+#[tokio::test]
+async fn test_session_manager_tracks_tcp_connections() {
+    use rust_mcp_server::server::TcpServer;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true);
+    let sessions = tcp.sessions().clone();
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    let (first_read, mut first) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut first_read = tokio::io::BufReader::new(first_read);
+    let (second_read, mut second) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut second_read = tokio::io::BufReader::new(second_read);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "first-client", "version": "1.0.0"}
+        }
+    });
+    tcp_exchange(&mut first_read, &mut first, initialize).await;
+    let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+    let response = tcp_exchange(&mut second_read, &mut second, ping).await;
+    assert_eq!(response.error.unwrap().code, error_codes::SERVER_NOT_INITIALIZED);
+
+    // One session per connection, each with its own negotiated state
+    assert_eq!(sessions.len(), 2);
+    let mut clients = Vec::new();
+    for id in sessions.ids() {
+        let session = sessions.get(&id).unwrap();
+        let session = session.lock().await;
+        clients.push(session.session.client_info.as_ref().map(|info| info.name.clone()));
+    }
+    clients.sort();
+    assert_eq!(clients, vec![None, Some("first-client".to_string())]);
+
+    // A closed connection's session goes away
+    drop(second);
+    drop(second_read);
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while sessions.len() != 1 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("session not closed");
+    assert!(sessions.template().tool_registry.list_tools().iter().any(|tool| tool.name == "echo"));
+}
+
+#[tokio::test]
+async fn test_tcp_keepalive() {
+    use rust_mcp_server::server::{Keepalive, TcpServer};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let keepalive = Keepalive {
+        interval: std::time::Duration::from_millis(50),
+        idle_timeout: std::time::Duration::from_millis(200),
+    };
+    let tcp = TcpServer::new(std::sync::Arc::new(server), true).with_keepalive(keepalive);
+    let sessions = tcp.sessions().clone();
+    tokio::spawn(async move { tcp.serve(listener).await });
+
+    let (reader, mut writer) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut line = String::new();
+
+    // Answering pings keeps the session open well past the idle timeout
+    for _ in 0..8 {
+        line.clear();
+        tokio::time::timeout(std::time::Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .expect("no ping")
+            .unwrap();
+        let ping: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(ping["method"], "ping");
+        let pong = json!({"jsonrpc": "2.0", "id": ping["id"], "result": {}});
+        writer.write_all(format!("{}\n", pong).as_bytes()).await.unwrap();
+    }
+    assert_eq!(sessions.len(), 1);
+
+    // A client that stops answering is disconnected
+    let closed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            // Pings only: a response to the client's answers would be an error
+            let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(message["method"], "ping");
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "idle connection not closed");
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(sessions.is_empty());
+}
+
+#[tokio::test]
+async fn test_http_keepalive() {
+    use rust_mcp_server::server::{HttpServer, Keepalive};
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let keepalive = Keepalive {
+        interval: std::time::Duration::from_millis(50),
+        idle_timeout: std::time::Duration::from_millis(300),
+    };
+    let http = HttpServer::new(std::sync::Arc::new(server), true).with_keepalive(keepalive);
+    let sessions = http.sessions().clone();
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    // Pings arrive on the session's stream, and answering them is a request
+    let mut stream = http_get_stream(addr, &session).await;
+    let (_, data) = sse_next_event_id(&mut stream).await;
+    let ping: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(ping["method"], "ping");
+    let pong = json!({"jsonrpc": "2.0", "id": ping["id"], "result": {}});
+    let (status, _, body) = http_post(addr, &session, &pong.to_string()).await;
+    assert_eq!(status, 202);
+    assert!(body.is_empty());
+
+    // Left unanswered, the session is ended
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !sessions.is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("idle session not closed");
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+    let (status, _, _) = http_post(addr, &session, &list.to_string()).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn test_http_max_sessions() {
+    use rust_mcp_server::server::HttpServer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true).with_max_sessions(1);
+    let sessions = http.sessions().clone();
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (status, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    assert_eq!(status, 200);
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    // A second client is refused while the first session is open
+    let (status, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    assert_eq!(status, 503);
+    assert!(session_header(&head).is_none());
+    assert_eq!(sessions.len(), 1);
+
+    // Closing the first makes room
+    let delete = format!("DELETE /mcp HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n", session);
+    let (status, _) = http_request(addr, delete).await;
+    assert_eq!(status, 204);
+    let (status, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    assert_eq!(status, 200);
+    assert!(session_header(&head).is_some());
+}
+
+#[tokio::test]
+async fn test_http_sampling() {
+    use rust_mcp_server::server::HttpServer;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.md"), "# Notes\n").unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {"sampling": {}},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+    let mut stream = http_get_stream(addr, &session).await;
+
+    // The sampling request goes out on the session's stream while the call waits
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "summarize_file", "arguments": {"path": "notes.md"}}});
+    let calling = {
+        let session = session.clone();
+        tokio::spawn(async move { http_post(addr, &session, &call.to_string()).await })
+    };
+    let (_, data) = sse_next_event_id(&mut stream).await;
+    let request: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(request["method"], "sampling/createMessage");
+
+    let answer = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": {"role": "assistant", "content": {"type": "text", "text": "Notes."}, "model": "test-model"}
+    });
+    let (status, _, _) = http_post(addr, &session, &answer.to_string()).await;
+    assert_eq!(status, 202);
+    let (status, _, body) = tokio::time::timeout(std::time::Duration::from_secs(5), calling).await.unwrap().unwrap();
+    assert_eq!(status, 200);
+    let response: JsonRpcResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(response.result.unwrap()["content"][0]["text"], "Notes.");
+}
+
+#[tokio::test]
+async fn test_http_cancellation() {
+    use rust_mcp_server::server::HttpServer;
+    use std::time::{Duration, Instant};
+
+    let server = test_server_with_config(ServerConfig {
+        allowed_commands: vec!["sleep".to_string()],
+        ..ServerConfig::default()
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "", &initialize.to_string()).await;
+    let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    let started = Instant::now();
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "execute_command", "arguments": {"command": "sleep", "args": ["30"]}}});
+    let calling = {
+        let session = session.clone();
+        tokio::spawn(async move { http_post(addr, &session, &call.to_string()).await })
+    };
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let cancel = json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 2, "reason": "taking too long"}});
+    let (status, _, _) = http_post(addr, &session, &cancel.to_string()).await;
+    assert_eq!(status, 202);
+    // The cancelled request is answered with no reply
+    let (status, _, body) = tokio::time::timeout(Duration::from_secs(5), calling).await.unwrap().unwrap();
+    assert_eq!(status, 202);
+    assert!(body.is_empty(), "{}", body);
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    // The session carries on
+    let ping = json!({"jsonrpc": "2.0", "id": 3, "method": "ping"});
+    let (status, _, _) = http_post(addr, &session, &ping.to_string()).await;
+    assert_eq!(status, 200);
+}
+
+/// The value of a response header, matched case-insensitively
+fn response_header(head: &str, name: &str) -> Option<String> {
+    head.lines()
+        .find_map(|line| line.split_once(':').filter(|(key, _)| key.eq_ignore_ascii_case(name)))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// Send a CORS preflight for a POST from `origin`; returns the response headers
+async fn cors_preflight(addr: std::net::SocketAddr, path: &str, origin: &str) -> String {
+    let request = format!(
+        "OPTIONS {} HTTP/1.1\r\nHost: localhost\r\nOrigin: {}\r\nAccess-Control-Request-Method: POST\r\nAccess-Control-Request-Headers: content-type, mcp-session-id\r\nConnection: close\r\n\r\n",
+        path, origin
+    );
+    let (status, head, _) = http_exchange(addr, request).await;
+    assert_eq!(status, 200);
+    head
+}
+
+#[tokio::test]
+async fn test_http_cors() {
+    use rust_mcp_server::server::{CorsPolicy, HttpServer, HttpSseServer};
+
+    let server = std::sync::Arc::new(McpServer::new("test-server".to_string(), "1.0.0".to_string()));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let default_addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(server.clone(), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    // By default only pages served from this machine are allowed
+    let head = cors_preflight(default_addr, "/mcp", "http://localhost:5173").await;
+    assert_eq!(response_header(&head, "access-control-allow-origin").as_deref(), Some("http://localhost:5173"));
+    let allowed_headers = response_header(&head, "access-control-allow-headers").unwrap();
+    assert!(allowed_headers.contains("mcp-session-id"));
+    let head = cors_preflight(default_addr, "/mcp", "https://evil.example.com").await;
+    assert_eq!(response_header(&head, "access-control-allow-origin"), None);
+
+    // Requests from other pages are refused outright, since a page whose
+    // host name was rebound to this machine needs no preflight
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}).to_string();
+    let (status, _, _) = http_post(default_addr, "Origin: https://evil.example.com\r\n", &ping).await;
+    assert_eq!(status, 403);
+    let (status, _, _) = http_post(default_addr, "Origin: http://localhost:5173\r\n", &ping).await;
+    assert_ne!(status, 403);
+    let (status, _, _) = http_post(default_addr, "", &ping).await;
+    assert_ne!(status, 403);
+
+    let policy = CorsPolicy::new(&["https://app.example.com".to_string()], &["x-trace-id".to_string()], &[]).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(server.clone(), true).with_cors(policy.clone());
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let head = cors_preflight(addr, "/mcp", "https://app.example.com").await;
+    assert_eq!(response_header(&head, "access-control-allow-origin").as_deref(), Some("https://app.example.com"));
+    assert!(response_header(&head, "access-control-allow-headers").unwrap().contains("x-trace-id"));
+    let head = cors_preflight(addr, "/mcp", "http://localhost:5173").await;
+    assert_eq!(response_header(&head, "access-control-allow-origin"), None);
+
+    // Scripts can read the session id a browser's initialize gets back
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    let (_, head, _) = http_post(addr, "Origin: https://app.example.com\r\n", &initialize.to_string()).await;
+    assert!(session_header(&head).is_some());
+    let exposed = response_header(&head, "access-control-expose-headers").unwrap();
+    assert!(exposed.contains("mcp-session-id"));
+
+    // The SSE transport takes the same policy
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let sse_addr = listener.local_addr().unwrap();
+    let sse = HttpSseServer::new(server, true).with_cors(policy);
+    tokio::spawn(async move { sse.serve(listener).await });
+    let head = cors_preflight(sse_addr, "/messages", "https://app.example.com").await;
+    assert_eq!(response_header(&head, "access-control-allow-origin").as_deref(), Some("https://app.example.com"));
+    let request = "GET /sse HTTP/1.1\r\nHost: localhost\r\nOrigin: https://evil.example.com\r\nConnection: close\r\n\r\n";
+    let (status, _, _) = http_exchange(sse_addr, request.to_string()).await;
+    assert_eq!(status, 403);
+
+    assert!(CorsPolicy::new(&[], &["bad header".to_string()], &[]).is_err());
+}
+
+/// POST to the streamable HTTP endpoint and return the raw response, which
+/// may not be text once compressed
+async fn http_post_raw(addr: std::net::SocketAddr, headers: &str, body: &str) -> Vec<u8> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let request = format!(
+        "POST /mcp HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        headers,
+        body.len(),
+        body
+    );
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_http_compression() {
+    use rust_mcp_server::server::HttpServer;
+
+    let server = std::sync::Arc::new(McpServer::new("test-server".to_string(), "1.0.0".to_string()));
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    })
+    .to_string();
+    let text = "compressible ".repeat(2000);
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": text}}})
+        .to_string();
+
+    for compression in [true, false] {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let http = HttpServer::new(server.clone(), true).with_compression(compression);
+        tokio::spawn(async move { http.serve(listener).await });
+
+        let (_, head, _) = http_post(addr, "", &initialize).await;
+        let session = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+        let response = http_post_raw(addr, &format!("{}Accept-Encoding: gzip\r\n", session), &call).await;
+        let end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..end]).to_string();
+        let encoding = response_header(&head, "content-encoding");
+        if compression {
+            assert_eq!(encoding.as_deref(), Some("gzip"));
+            assert!(response.len() < text.len() / 10);
+        } else {
+            assert_eq!(encoding, None);
+            assert!(response.len() > text.len());
+        }
+
+        // Clients that don't ask for it get plain JSON either way
+        let (status, head, body) = http_post(addr, &session, &call).await;
+        assert_eq!(status, 200);
+        assert_eq!(response_header(&head, "content-encoding"), None);
+        assert!(body.contains("compressible"));
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_systemd_socket_activation() {
+    use std::os::fd::AsRawFd;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    // Bound here, as systemd would bind the unit's socket
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let fd = listener.as_raw_fd();
+
+    // The shell learns its pid before exec'ing the server, which keeps it
+    let script = format!("LISTEN_PID=$$ LISTEN_FDS=1 exec '{}' --quiet --transport tcp --port 1", env!("CARGO_BIN_EXE_rust-mcp-server"));
+    let mut command = tokio::process::Command::new("sh");
+    command.args(["-c", &script]).kill_on_drop(true);
+    // Safety: dup2 is async-signal-safe
+    unsafe {
+        command.pre_exec(move || match libc::dup2(fd, 3) {
+            -1 => Err(std::io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+    let mut child = command.spawn().unwrap();
+    drop(listener);
+
+    let (reader, mut writer) = tokio::net::TcpStream::connect(addr).await.unwrap().into_split();
+    let mut reader = tokio::io::BufReader::new(reader);
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    });
+    writer.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    let mut line = String::new();
+    tokio::time::timeout(std::time::Duration::from_secs(10), reader.read_line(&mut line))
+        .await
+        .expect("no response on the activated socket")
+        .unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+    assert_eq!(response.id, Some(json!(1)));
+    child.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_run_with_streams() {
+    use rust_mcp_server::server::StdioServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let server = McpServer::new("embedded-server".to_string(), "1.0.0".to_string());
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+
+    let (client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let running = tokio::spawn(async move { stdio.run_with_streams(server_read, server_write).await });
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let mut lines = BufReader::new(client_read).lines();
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test-client", "version": "1.0.0"}}
+    });
+    client_write.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "embedded-server");
+
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+    client_write.write_all(format!("{}\n", list).as_bytes()).await.unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert!(response.result.unwrap()["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+
+    // Closing the input stream ends the run, as EOF on stdin does
+    client_write.shutdown().await.unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), running)
+        .await
+        .expect("server still running after EOF")
+        .unwrap()
+        .unwrap();
+}
+
+/// A transport over in-memory channels, as an embedder might write one
+struct ChannelTransport {
+    incoming: tokio::sync::mpsc::Receiver<String>,
+    outgoing: tokio::sync::mpsc::Sender<serde_json::Value>,
+}
+
+struct ChannelSource(tokio::sync::mpsc::Receiver<String>);
+
+struct ChannelSink(tokio::sync::mpsc::Sender<serde_json::Value>);
+
+impl rust_mcp_server::server::Transport for ChannelTransport {
+    type Source = ChannelSource;
+    type Sink = ChannelSink;
+
+    fn split(self) -> (ChannelSource, ChannelSink) {
+        (ChannelSource(self.incoming), ChannelSink(self.outgoing))
+    }
+}
+
+#[async_trait::async_trait]
+impl rust_mcp_server::server::MessageSource for ChannelSource {
+    async fn receive(&mut self) -> std::io::Result<Option<String>> {
+        Ok(self.0.recv().await)
+    }
+}
+
+#[async_trait::async_trait]
+impl rust_mcp_server::server::MessageSink for ChannelSink {
+    async fn send(&mut self, message: serde_json::Value) -> anyhow::Result<()> {
+        self.0.send(message).await?;
+        Ok(())
+    }
+}
+
+async fn next_message(receiver: &mut tokio::sync::mpsc::Receiver<serde_json::Value>) -> serde_json::Value {
+    tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("no message from the server")
+        .expect("transport closed")
+}
+
+#[tokio::test]
+async fn test_custom_transport() {
+    use rust_mcp_server::server::StdioServer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notifier = server.notifier.clone();
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+    let (client_tx, incoming) = tokio::sync::mpsc::channel(16);
+    let (outgoing, mut client_rx) = tokio::sync::mpsc::channel(16);
+    let serving = tokio::spawn(async move {
+        stdio.serve_transport(ChannelTransport { incoming, outgoing }, std::future::pending()).await
+    });
+
+    client_tx.send("{not json".to_string()).await.unwrap();
+    assert_eq!(next_message(&mut client_rx).await["error"]["code"], error_codes::PARSE_ERROR);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    client_tx.send(initialize.to_string()).await.unwrap();
+    assert_eq!(next_message(&mut client_rx).await["result"]["serverInfo"]["name"], "test-server");
+
+    // Notifications go out through the same sink
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "hello"})));
+    assert_eq!(next_message(&mut client_rx).await["method"], "notifications/message");
+
+    // Closing the source ends the loop once replies are out
+    let call = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "over channels"}}});
+    client_tx.send(call.to_string()).await.unwrap();
+    drop(client_tx);
+    assert!(next_message(&mut client_rx).await["result"].to_string().contains("over channels"));
+    serving.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_in_process_client() {
+    use rust_mcp_server::server::StdioServer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let notifier = server.notifier.clone();
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+
+    let response = client
+        .initialize(ClientInfo { name: "test-client".to_string(), version: "1.0.0".to_string() })
+        .await
+        .unwrap();
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+
+    let response = client
+        .request("tools/call", Some(json!({"name": "echo", "arguments": {"text": "in process"}})))
+        .await
+        .unwrap();
+    assert!(response.result.unwrap().to_string().contains("in process"));
+
+    // Raw text reaches the parser as it would over stdio
+    client.send_raw("{bad").await.unwrap();
+    let reply = tokio::time::timeout(std::time::Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(reply["error"]["code"], error_codes::PARSE_ERROR);
+
+    notifier.notify("notifications/message", Some(json!({"level": "info", "data": "hello"})));
+    let notification = tokio::time::timeout(std::time::Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(notification["method"], "notifications/message");
+    assert_eq!(notification["params"]["data"], "hello");
+}
+
+#[tokio::test]
+async fn test_sampling_round_trip() {
+    use rust_mcp_server::server::StdioServer;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.md"), "# Notes\n\nShip on Friday.\n").unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {"sampling": {}},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    assert!(client.request("initialize", Some(init)).await.unwrap().error.is_none());
+    client.notify("notifications/initialized", None).await.unwrap();
+
+    let call = json!({"jsonrpc": "2.0", "id": "call", "method": "tools/call", "params": {"name": "summarize_file", "arguments": {"path": "notes.md"}}});
+    client.send(&call).await.unwrap();
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "sampling/createMessage");
+    assert!(request["params"]["messages"][0]["content"]["text"].as_str().unwrap().contains("Ship on Friday."));
+    assert_eq!(request["params"]["maxTokens"], 500);
+
+    let answer = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": {"role": "assistant", "content": {"type": "text", "text": "A release note."}, "model": "test-model"}
+    });
+    client.send(&answer).await.unwrap();
+    let reply = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(reply["id"], "call");
+    assert_eq!(reply["result"]["content"][0]["text"], "A release note.");
+
+    // A refusal from the client fails the tool call
+    client.send(&json!({"jsonrpc": "2.0", "id": "again", "method": "tools/call", "params": {"name": "summarize_file", "arguments": {"path": "notes.md"}}})).await.unwrap();
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    let refusal = json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -1, "message": "User rejected sampling request"}});
+    client.send(&refusal).await.unwrap();
+    let reply = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert!(reply["error"]["message"].as_str().unwrap().contains("User rejected sampling request"));
+}
+
+#[tokio::test]
+async fn test_sampling_needs_client_capability() {
+    use rust_mcp_server::server::StdioServer;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.md"), "# Notes\n").unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+    client.initialize(ClientInfo { name: "test-client".to_string(), version: "1.0.0".to_string() }).await.unwrap();
+
+    let params = json!({"name": "summarize_file", "arguments": {"path": "notes.md"}});
+    let response = client.request("tools/call", Some(params)).await.unwrap();
+    let result = response.result.unwrap();
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("does not support sampling"));
+}
+
+#[tokio::test]
+async fn test_file_tools_stay_inside_client_roots() {
+    use rust_mcp_server::server::StdioServer;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let allowed = dir.path().join("allowed");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::write(allowed.join("inside.txt"), "inside").unwrap();
+    std::fs::write(dir.path().join("outside.txt"), "outside").unwrap();
+    let server = test_server_with_config(ServerConfig {
+        read_only: false,
+        ..ServerConfig::default()
+    });
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {"roots": {"listChanged": true}},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    assert!(client.request("initialize", Some(init)).await.unwrap().error.is_none());
+    client.notify("notifications/initialized", None).await.unwrap();
+
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "roots/list");
+    let uri = url::Url::from_directory_path(&allowed).unwrap().to_string();
+    let answer = json!({"jsonrpc": "2.0", "id": request["id"], "result": {"roots": [{"uri": uri, "name": "allowed"}]}});
+    client.send(&answer).await.unwrap();
+
+    // The roots are cached once the answer has been handled
+    let mut roots = json!([]);
+    for _ in 0..50 {
+        roots = client.request("session/info", None).await.unwrap().result.unwrap()["roots"].clone();
+        if roots.as_array().is_some_and(|roots| !roots.is_empty()) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(roots[0]["name"], "allowed");
+
+    let call = |name: &str, arguments: serde_json::Value| json!({"name": name, "arguments": arguments});
+    let inside = allowed.join("inside.txt").display().to_string();
+    let result = client.request("tools/call", Some(call("read_file", json!({"path": inside})))).await.unwrap().result.unwrap();
+    assert!(result["content"][0]["text"].as_str().unwrap().ends_with("inside"));
+
+    let outside = dir.path().join("outside.txt").display().to_string();
+    let result = client.request("tools/call", Some(call("read_file", json!({"path": outside})))).await.unwrap().result.unwrap();
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("outside the client's roots"));
+
+    // `..` doesn't get around the check
+    let escaped = allowed.join("..").display().to_string();
+    let result = client.request("tools/call", Some(call("list_files", json!({"path": escaped})))).await.unwrap().result.unwrap();
+    assert_eq!(result["isError"], true);
+
+    // Nor does writing a file that doesn't exist yet
+    let created = dir.path().join("new.txt").display().to_string();
+    let result = client
+        .request("tools/call", Some(call("write_file", json!({"path": created, "content": "x"}))))
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(result["isError"], true);
+    assert!(!dir.path().join("new.txt").exists());
+
+    let created = allowed.join("new.txt").display().to_string();
+    let result = client
+        .request("tools/call", Some(call("write_file", json!({"path": created, "content": "x"}))))
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert!(result.get("isError").is_none_or(|e| e == false), "{}", result);
+
+    // Every tool resolving paths through the sandbox is held to the roots
+    let result = client.request("tools/call", Some(call("read_file_smart", json!({"path": outside})))).await.unwrap().result.unwrap();
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("outside the client's roots"));
+
+    // So are scheduled runs, on behalf of the client that scheduled them
+    let schedule = call("schedule_task", json!({"tool": "read_file", "arguments": {"path": outside}}));
+    let result = client.request("tools/call", Some(schedule)).await.unwrap().result.unwrap();
+    assert!(result.get("isError").is_none_or(|e| e == false), "{}", result);
+    let notification = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(notification["method"], rust_mcp_server::tools::TASK_RESULT_NOTIFICATION);
+    assert_eq!(notification["params"]["result"]["isError"], true);
+    assert!(notification["params"]["result"]["content"][0]["text"].as_str().unwrap().contains("outside the client's roots"));
+
+    // A changed list is fetched again
+    client.notify("notifications/roots/list_changed", None).await.unwrap();
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "roots/list");
+}
+
+#[tokio::test]
+async fn test_client_roots_fail_closed() {
+    use rust_mcp_server::server::StdioServer;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let allowed = root.join("allowed");
+    std::fs::create_dir(&allowed).unwrap();
+    std::fs::write(allowed.join("inside.txt"), "inside").unwrap();
+    std::fs::write(root.join("outside.txt"), "outside").unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(root.clone()),
+        ..ServerConfig::default()
+    });
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+    let init = json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {"roots": {"listChanged": true}},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    });
+    assert!(client.request("initialize", Some(init)).await.unwrap().error.is_none());
+    client.notify("notifications/initialized", None).await.unwrap();
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "roots/list");
+
+    // A call made before the client answers waits for its roots
+    let inside = allowed.join("inside.txt").display().to_string();
+    let early = json!({
+        "jsonrpc": "2.0",
+        "id": "early",
+        "method": "tools/call",
+        "params": {"name": "read_file", "arguments": {"path": inside}}
+    });
+    client.send(&early).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let uri = url::Url::from_directory_path(&allowed).unwrap().to_string();
+    let answer = json!({"jsonrpc": "2.0", "id": request["id"], "result": {"roots": [{"uri": uri, "name": "allowed"}]}});
+    client.send(&answer).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(response["id"], "early");
+    assert!(response["result"]["content"][0]["text"].as_str().unwrap().ends_with("inside"), "{}", response);
+
+    // Resources are held to the roots too
+    let listed = client.request("resources/list", None).await.unwrap().result.unwrap();
+    let uris: Vec<&str> = listed["resources"].as_array().unwrap().iter().map(|resource| resource["uri"].as_str().unwrap()).collect();
+    assert_eq!(uris, vec![url::Url::from_file_path(allowed.join("inside.txt")).unwrap().to_string()]);
+    let outside = url::Url::from_file_path(root.join("outside.txt")).unwrap().to_string();
+    let read = client.request("resources/read", Some(json!({"uri": outside}))).await.unwrap();
+    assert!(read.error.is_some());
+
+    // Once a roots/list fails, every path is refused
+    client.notify("notifications/roots/list_changed", None).await.unwrap();
+    let request = tokio::time::timeout(Duration::from_secs(5), client.receive()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "roots/list");
+    let refusal = json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -32603, "message": "unavailable"}});
+    client.send(&refusal).await.unwrap();
+    let call = json!({"name": "read_file", "arguments": {"path": inside}});
+    let mut result = json!({});
+    for _ in 0..50 {
+        result = client.request("tools/call", Some(call.clone())).await.unwrap().result.unwrap();
+        if result["isError"] == true {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("has not listed"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_self_benchmark_echo() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "self_benchmark".to_string(),
+        params: Some(json!({"iterations": 50, "concurrency": 4})),
+    };
+    let response = server.handle_request(request).await.unwrap().unwrap();
+    let report = response.result.unwrap();
+    assert_eq!(report["tool"], "echo");
+    assert_eq!(report["iterations"], 50);
+    assert_eq!(report["concurrency"], 4);
+    assert_eq!(report["errors"], 0);
+    assert!(report["throughput_per_sec"].as_f64().unwrap() > 0.0);
+    assert!(report["p50_ms"].as_f64().unwrap() <= report["p99_ms"].as_f64().unwrap());
+
+    let report = server.tool_registry.benchmark("missing", json!({}), Some(1), None).await;
+    assert!(report.is_err());
+
+    // Tools with side effects can't be run thousands of times unless configured
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(2)),
+        method: "self_benchmark".to_string(),
+        params: Some(json!({"tool": "write_file", "arguments": {"path": "x", "content": "x"}})),
+    };
+    let response = server.handle_request(request.clone()).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, error_codes::INVALID_PARAMS);
+
+    let mut server = test_server_with_config(ServerConfig {
+        benchmark_tools: vec!["get_system_info".to_string()],
+        ..ServerConfig::default()
+    });
+    server.initialized = true;
+    let request = JsonRpcRequest {
+        params: Some(json!({"tool": "get_system_info", "arguments": {}, "iterations": 2})),
+        ..request
+    };
+    let response = server.handle_request(request).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["errors"], 0);
+}
+
+struct SlowTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for SlowTool {
+    fn description(&self) -> String {
+        "Sleeps far longer than any test timeout".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, _args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        Ok(CallToolResponse::text("finished".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_tool_timeout() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("slow", Box::new(SlowTool));
+    server.tool_registry.set_timeout("slow", std::time::Duration::from_millis(100));
+
+    let started = std::time::Instant::now();
+    let response = call_tool(&server, "slow", json!({})).await;
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("timed out after 0.1 seconds"));
+
+    // The override is per tool; others keep the default
+    let response = call_tool(&server, "echo", json!({"text": "still fast"})).await;
+    assert_ne!(response.is_error, Some(true));
+
+    server.tool_registry.set_default_timeout(std::time::Duration::from_millis(50));
+    let response = call_tool(&server, "execute_command", json!({"command": "tail", "args": ["-f", "/dev/null"]})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_parse_dockerfile_and_compose() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/docker");
+    let server = test_server_with_config(ServerConfig {
+        root: Some(fixtures),
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "parse_dockerfile", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["directives"]["syntax"], "docker/dockerfile:1");
+    assert_eq!(result["stages"][0]["name"], "build");
+    assert_eq!(result["stages"][0]["base"], "rust:${RUST_VERSION}-slim");
+    assert_eq!(result["stages"][1]["base"], "debian:bookworm-slim");
+    let from = &result["instructions"][7];
+    assert_eq!(from["instruction"], "FROM");
+    assert_eq!(from["image"], "debian");
+    assert_eq!(from["tag"], "bookworm-slim");
+    let run = &result["instructions"][4];
+    assert_eq!(run["flags"]["mount"], "type=cache,target=/usr/local/cargo/registry");
+    assert_eq!(run["command"], "cargo fetch --locked");
+    assert_eq!(result["instructions"].as_array().unwrap().last().unwrap()["command"], json!(["app", "--port", "8080"]));
+    let rules: Vec<&str> = result["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| w["rule"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules, ["runs_as_root"]);
+
+    let response = call_tool(&server, "parse_dockerfile", json!({"path": "Dockerfile.broken"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("Dockerfile.broken:3: Unknown instruction 'FORM'"));
+
+    let response = call_tool(&server, "parse_compose", json!({})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let services = result["services"].as_array().unwrap();
+    let web = services.iter().find(|s| s["name"] == "web").unwrap();
+    assert_eq!(web["build"]["target"], "runtime");
+    assert_eq!(web["depends_on"], json!(["db"]));
+    assert_eq!(web["environment"], json!(["DATABASE_URL"]));
+    assert_eq!(web["restart"], "unless-stopped");
+    assert_eq!(result["volumes"], json!(["data"]));
+    assert_eq!(result["warnings"][0]["rule"], "unpinned_image");
+
+    let response = call_tool(&server, "parse_compose", json!({"path": "Dockerfile"})).await;
+    assert_eq!(response.is_error, Some(true));
+}
+
+#[tokio::test]
+async fn test_ping_not_blocked_by_slow_tool_call() {
+    use rust_mcp_server::server::StdioServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("slow", Box::new(SlowTool));
+    server.tool_registry.set_timeout("slow", std::time::Duration::from_millis(1500));
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+
+    let (mut client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let serving = tokio::spawn(async move { stdio.serve(server_read, server_write).await });
+
+    let slow = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "slow", "arguments": {}}});
+    let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+    let started = std::time::Instant::now();
+    client.write_all(format!("{}\n{}\n", slow, ping).as_bytes()).await.unwrap();
+
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let mut lines = BufReader::new(client_read).lines();
+    let first: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(first["id"], 2);
+    assert_eq!(first["result"]["pong"], true);
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+
+    let second: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(second["id"], 1);
+    assert_eq!(second["result"]["isError"], true);
+
+    // Replies still in flight at end of input are written before serve returns
+    client_write.write_all(format!("{}\n", slow).as_bytes()).await.unwrap();
+    client_write.shutdown().await.unwrap();
+    let last: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(last["id"], 1);
+    serving.await.unwrap().unwrap();
+}
+
+#[test]
+fn test_rate_limiter_refills_with_elapsed_time() {
+    use rust_mcp_server::server::RateLimiter;
+    use std::time::Duration;
+
+    let mut limiter = RateLimiter::new(10);
+    let start = tokio::time::Instant::now();
+    // A full second's burst passes, then the bucket is empty
+    assert!((0..10).all(|_| limiter.try_acquire_at(start)));
+    assert!(!limiter.try_acquire_at(start));
+
+    // Steady traffic at the limit keeps passing
+    for tick in 1..=50 {
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(100 * tick)), "tick {}", tick);
+    }
+    // Faster traffic is refused until enough time has passed
+    let now = start + Duration::from_millis(5000);
+    assert!(!limiter.try_acquire_at(now + Duration::from_millis(50)));
+    assert!(limiter.try_acquire_at(now + Duration::from_millis(100)));
+
+    // An idle client only gets back one burst
+    let later = now + Duration::from_secs(60);
+    assert!((0..10).all(|_| limiter.try_acquire_at(later)));
+    assert!(!limiter.try_acquire_at(later));
+}
+
+#[tokio::test]
+async fn test_stdio_rate_limit_rejects_bursts() {
+    use rust_mcp_server::server::StdioServer;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true).with_rate_limit(3);
+
+    let (client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let serving = tokio::spawn(async move { stdio.serve(server_read, server_write).await });
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let mut lines = BufReader::new(client_read).lines();
+
+    let mut input = String::new();
+    for id in 1..=5 {
+        let call = json!({"jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "hi"}}});
+        input.push_str(&format!("{}\n", call));
+    }
+    input.push_str(&format!("{}\n", json!({"jsonrpc": "2.0", "id": 6, "method": "ping"})));
+    let batch = json!([
+        {"jsonrpc": "2.0", "id": 7, "method": "tools/list"},
+        {"jsonrpc": "2.0", "method": "notifications/progress", "params": {}}
+    ]);
+    input.push_str(&format!("{}\n", batch));
+    client_write.write_all(input.as_bytes()).await.unwrap();
+    client_write.shutdown().await.unwrap();
+
+    let mut replies = HashMap::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        let reply: serde_json::Value = serde_json::from_str(&line).unwrap();
+        match reply {
+            serde_json::Value::Array(batch) => {
+                assert_eq!(batch.len(), 1);
+                replies.insert(batch[0]["id"].as_i64().unwrap(), batch[0].clone());
+            }
+            reply => {
+                replies.insert(reply["id"].as_i64().unwrap(), reply);
+            }
+        }
+    }
+    serving.await.unwrap().unwrap();
+
+    let rate_limited: Vec<i64> = (1..=7)
+        .filter(|id| replies[id].get("error").is_some())
+        .collect();
+    assert_eq!(rate_limited, vec![4, 5, 7]);
+    assert_eq!(replies[&4]["error"]["code"], error_codes::RATE_LIMITED);
+    assert_eq!(replies[&4]["error"]["message"], "Rate limited");
+    assert_eq!(replies[&4]["error"]["data"]["requests_per_second"], 3);
+    assert_eq!(replies[&1]["result"]["content"][0]["text"], "Echo: hi");
+    assert_eq!(replies[&6]["result"]["pong"], true);
+}
+
+/// Sleeps like `SlowTool`, reporting when it starts and when its call is dropped
+struct CancellableTool {
+    started: tokio::sync::mpsc::Sender<()>,
+    stopped: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+struct SetOnDrop(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for CancellableTool {
+    fn description(&self) -> String {
+        "Sleeps until cancelled".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, _args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        let _stopped = SetOnDrop(self.stopped.clone());
+        let _ = self.started.send(()).await;
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        Ok(CallToolResponse::text("finished".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_request_is_aborted() {
+    use rust_mcp_server::server::StdioServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (started_tx, mut started) = tokio::sync::mpsc::channel(1);
+    let stopped = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("hang", Box::new(CancellableTool {
+        started: started_tx,
+        stopped: stopped.clone(),
+    }));
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+
+    let (client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let serving = tokio::spawn(async move { stdio.serve(server_read, server_write).await });
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let mut lines = BufReader::new(client_read).lines();
+
+    let hang = json!({"jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": {"name": "hang", "arguments": {}}});
+    client_write.write_all(format!("{}\n", hang).as_bytes()).await.unwrap();
+    started.recv().await.unwrap();
+
+    // Unknown ids are ignored, and "7" is not the same id as 7
+    for request_id in [json!(99), json!("7"), json!(7)] {
+        let cancel = json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": request_id, "reason": "no longer needed"}});
+        client_write.write_all(format!("{}\n", cancel).as_bytes()).await.unwrap();
+    }
+    let ping = json!({"jsonrpc": "2.0", "id": 8, "method": "ping"});
+    client_write.write_all(format!("{}\n", ping).as_bytes()).await.unwrap();
+
+    let reply: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(reply["id"], 8);
+
+    // Serving ends promptly at end of input with no reply for the cancelled call
+    client_write.shutdown().await.unwrap();
+    let finished = tokio::time::timeout(std::time::Duration::from_secs(5), serving).await;
+    finished.expect("cancelled call still running").unwrap().unwrap();
+    assert!(lines.next_line().await.unwrap().is_none());
+    assert!(stopped.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_serving_after_timeout() {
+    use rust_mcp_server::server::StdioServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (started_tx, mut started) = tokio::sync::mpsc::channel(1);
+    let stopped = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("hang", Box::new(CancellableTool {
+        started: started_tx,
+        stopped: stopped.clone(),
+    }));
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true)
+        .with_shutdown_timeout(std::time::Duration::from_millis(100));
+
+    let (client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let (signal, shutdown) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(async move {
+        stdio.serve_until(server_read, server_write, async { let _ = shutdown.await; }).await
+    });
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let mut lines = BufReader::new(client_read).lines();
+
+    let hang = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": {"name": "hang", "arguments": {}}});
+    client_write.write_all(format!("{}\n", hang).as_bytes()).await.unwrap();
+    started.recv().await.unwrap();
+
+    // Serving ends with input still open once the hung call runs out of time
+    signal.send(()).unwrap();
+    let finished = tokio::time::timeout(std::time::Duration::from_secs(5), serving).await;
+    finished.expect("server did not shut down").unwrap().unwrap();
+    assert!(lines.next_line().await.unwrap().is_none());
+    assert!(stopped.load(std::sync::atomic::Ordering::SeqCst));
+    drop(client_write);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sigterm_exits_stdio_server() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .arg("--quiet")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+
+    // Wait for a reply so the signal handler is in place
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n").await.unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    assert!(lines.next_line().await.unwrap().is_some());
+
+    let pid = child.id().unwrap().to_string();
+    let killed = std::process::Command::new("kill").args(["-TERM", &pid]).status().unwrap();
+    assert!(killed.success());
+
+    // stdin stays open, so only the signal can end the process
+    let status = tokio::time::timeout(std::time::Duration::from_secs(5), child.wait()).await;
+    assert!(status.expect("server ignored SIGTERM").unwrap().success());
+    drop(stdin);
+}
+
+#[tokio::test]
+async fn test_transport_failure_exits_nonzero() {
+    // The address is taken, so the TCP transport can't listen on it
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = taken.local_addr().unwrap().to_string();
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_rust-mcp-server"))
+        .args(["--quiet", "--transport", "tcp", "--listen", &addr])
+        .stdin(std::process::Stdio::null())
+        .output();
+    let output = tokio::time::timeout(std::time::Duration::from_secs(10), output).await.unwrap().unwrap();
+    assert!(!output.status.success());
+    // Reported despite --quiet
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error:"), "{}", stderr);
+}
+
+#[tokio::test]
+async fn test_suggest_next_tools() {
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+
+    let suggest = |id: i64, tool: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: "tools/suggest_next".to_string(),
+        params: Some(json!({"tool": tool})),
+    };
+
+    let response = server.handle_request(suggest(1, "search_files")).await.unwrap().unwrap();
+    let suggestions = response.result.unwrap()["suggestions"].clone();
+    assert_eq!(suggestions[0], "read_file");
+
+    let response = server.handle_request(suggest(2, "no_such_tool")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["suggestions"], json!([]));
+
+    // Configured entries replace the defaults for their tool
+    let mut server = test_server_with_config(ServerConfig {
+        tool_suggestions: [("search_files".to_string(), vec!["list_files".to_string(), "missing".to_string()])].into(),
+        ..ServerConfig::default()
+    });
+    server.initialized = true;
+    let response = server.handle_request(suggest(3, "search_files")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["suggestions"], json!(["list_files"]));
+}
+
+#[tokio::test]
+async fn test_server_help_topics() {
+    let mut server = test_server_with_config(ServerConfig {
+        help_topics: [("examples".to_string(), "# Examples\n\nRead a file with `read_file`.\n".to_string())].into(),
+        ..ServerConfig::default()
+    });
+    server.initialized = true;
+
+    let help = |id: i64, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: "server/help".to_string(),
+        params: Some(params),
+    };
+
+    // The overview is the default topic
+    let response = server.handle_request(help(1, json!({}))).await.unwrap().unwrap();
+    let result = response.result.unwrap();
+    assert_eq!(result["topic"], "overview");
+    let overview = result["content"].as_str().unwrap();
+    assert!(overview.contains("- `read_file`: "));
+    assert!(overview.contains("- `examples`"));
+    let topics = result["topics"].as_array().unwrap();
+    assert_eq!(topics[0], "overview");
+    assert!(topics.contains(&json!("tools/read_file")) && topics.contains(&json!("examples")));
+
+    let response = server.handle_request(help(2, json!({"topic": "tools/list_files"}))).await.unwrap().unwrap();
+    let usage = response.result.unwrap()["content"].as_str().unwrap().to_string();
+    assert!(usage.starts_with("# list_files\n"));
+    assert!(usage.contains("- `path` (string, optional): "));
+    assert!(usage.contains("Often followed by `read_file`"));
+
+    let response = server.handle_request(help(3, json!({"topic": "examples"}))).await.unwrap().unwrap();
+    assert!(response.result.unwrap()["content"].as_str().unwrap().starts_with("# Examples"));
+
+    let response = server.handle_request(help(4, json!({"topic": "tools/nope"}))).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, rust_mcp_server::types::error_codes::INVALID_PARAMS);
+    assert!(error.data.unwrap()["topics"].as_array().unwrap().contains(&json!("overview")));
+}
+
+#[tokio::test]
+async fn test_protocol_version_negotiation() {
+    let initialize = |version: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "initialize".to_string(),
+        params: Some(json!({
+            "protocolVersion": version,
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        })),
+    };
+
+    // A supported version is accepted as requested
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let response = server.handle_request(initialize("2024-11-05")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["protocolVersion"], "2024-11-05");
+    assert_eq!(server.protocol_version, "2024-11-05");
+
+    // A newer or unknown version falls back to the newest supported one before it
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let response = server.handle_request(initialize("2099-01-01")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["protocolVersion"], server.supported_protocol_versions[0]);
+    let response = server.handle_request(initialize("2025-01-01")).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["protocolVersion"], "2024-11-05");
+
+    // The session reports the version agreed on, not the one requested
+    let info = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(2)),
+        method: "session/info".to_string(),
+        params: None,
+    };
+    let response = server.handle_request(info).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["protocolVersion"], "2024-11-05");
+
+    // Nothing old enough to match
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let response = server.handle_request(initialize("2024-10-07")).await.unwrap().unwrap();
+    let error = response.error.unwrap();
+    assert_eq!(error.code, error_codes::INVALID_PARAMS);
+    assert!(error.message.contains("Unsupported protocol version '2024-10-07'"));
+    assert_eq!(error.data.unwrap()["supported"], json!(server.supported_protocol_versions));
+    assert!(!server.initialized);
+}
+
+#[tokio::test]
+async fn test_extract_strings_from_binary() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/binary");
+    let server = test_server_with_config(ServerConfig {
+        root: Some(fixtures),
+        ..ServerConfig::default()
+    });
+
+    let response = call_tool(&server, "extract_strings", json!({"path": "sample.bin"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    let texts: Vec<&str> = result["strings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(texts, ["MCP_FIXTURE_MARKER", "tail!"]);
+    assert_eq!(result["strings"][0]["offset"], 24);
+    assert_eq!(result["truncated"], false);
+
+    let args = json!({"path": "sample.bin", "encoding": "utf-16le", "min_length": 6});
+    let response = call_tool(&server, "extract_strings", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert!(result["strings"].as_array().unwrap().iter().any(|s| s["text"].as_str().unwrap().contains("WideMarker")));
+
+    let response = call_tool(&server, "extract_strings", json!({"path": "sample.bin", "max_strings": 1})).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["returned"], 1);
+    assert_eq!(result["truncated"], true);
+}
+
+#[tokio::test]
+async fn test_write_file_modes() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        ..ServerConfig::default()
+    });
+    let target = dir.path().join("notes/todo.txt");
+
+    // Overwrite is the default and creates missing directories
+    let response = call_tool(&server, "write_file", json!({"path": "notes/todo.txt", "content": "first\n"})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["created"], true);
+    assert_eq!(result["bytes_written"], 6);
+    let args = json!({"path": "notes/todo.txt", "content": "replaced\n", "mode": "overwrite"});
+    call_tool(&server, "write_file", args).await;
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "replaced\n");
+
+    let args = json!({"path": "notes/todo.txt", "content": "more\n", "mode": "append"});
+    let response = call_tool(&server, "write_file", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "replaced\nmore\n");
+
+    let args = json!({"path": "notes/new.txt", "content": "fresh", "mode": "create_new"});
+    let response = call_tool(&server, "write_file", args).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    assert_eq!(std::fs::read_to_string(dir.path().join("notes/new.txt")).unwrap(), "fresh");
+
+    // create_new leaves an existing file alone
+    let args = json!({"path": "notes/todo.txt", "content": "clobbered", "mode": "create_new"});
+    let response = call_tool(&server, "write_file", args).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(text_content(&response, 0).contains("already exists"));
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "replaced\nmore\n");
+
+    let response = call_tool(&server, "write_file", json!({"path": "../escape.txt", "content": "x"})).await;
+    assert_eq!(response.is_error, Some(true));
+
+    let args = json!({"path": "notes/todo.txt", "content": "x", "mode": "truncate"});
+    let error = call_tool_error(&server, "write_file", args).await;
+    assert!(matches!(error, ToolCallError::InvalidArguments { .. }));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_write_file_refuses_symlinks() {
+    let outer = tempfile::tempdir().unwrap();
+    let root = outer.path().join("sbx");
+    std::fs::create_dir(&root).unwrap();
+    let outside = outer.path().join("outside_target");
+    std::os::unix::fs::symlink(&outside, root.join("evil")).unwrap();
+
+    // A dangling link inside the root can't be used to create a file outside it
+    let server = test_server_with_config(ServerConfig {
+        root: Some(root.clone()),
+        ..ServerConfig::default()
+    });
+    let response = call_tool(&server, "write_file", json!({"path": "evil", "content": "escaped"})).await;
+    assert_eq!(response.is_error, Some(true));
+    let args = json!({"destination": ".", "files": {"evil": "escaped"}, "force": true});
+    let response = call_tool(&server, "scaffold", args).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(!outside.exists());
+
+    // Nor is a link followed when it is the file being written
+    let server = test_server_with_config(ServerConfig::default());
+    let link = root.join("evil");
+    let response = call_tool(&server, "write_file", json!({"path": link.to_str().unwrap(), "content": "escaped"})).await;
+    assert_eq!(response.is_error, Some(true));
+    assert!(!outside.exists());
+}
+
+#[tokio::test]
+async fn test_humanize_time_past_and_future() {
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+
+    let hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    let timestamp = humantime::format_rfc3339_seconds(hour_ago).to_string();
+    let response = call_tool(&server, "humanize_time", json!({"timestamp": timestamp})).await;
+    assert!(response.is_error.is_none(), "{:?}", response.content);
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["relative"], "1 hour ago");
+    assert_eq!(result["absolute"], timestamp);
+
+    let response = call_tool(&server, "humanize_time", json!({"seconds": 2 * 86400 + 60})).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["relative"], "in 2 days");
+
+    let args = json!({"timestamp": 1_700_000_000, "relative_to": "2023-11-14T22:13:50Z"});
+    let response = call_tool(&server, "humanize_time", args).await;
+    let result: serde_json::Value = serde_json::from_str(text_content(&response, 0)).unwrap();
+    assert_eq!(result["relative"], "30 seconds ago");
+    assert_eq!(result["delta_seconds"], -30);
+
+    for args in [json!({"timestamp": "yesterday"}), json!({}), json!({"timestamp": "2024-01-01T00:00:00Z", "seconds": 5})] {
+        let response = call_tool(&server, "humanize_time", args).await;
+        assert_eq!(response.is_error, Some(true));
+    }
+}
+
+struct ProgressTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for ProgressTool {
+    fn description(&self) -> String {
+        "Reports progress in three steps".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        self.call_with_progress(args, rust_mcp_server::notifications::Progress::none()).await
+    }
+
+    async fn call_with_progress(
+        &self,
+        _args: serde_json::Value,
+        progress: rust_mcp_server::notifications::Progress,
+    ) -> anyhow::Result<CallToolResponse> {
+        for percent in [0.0, 50.0, 100.0] {
+            progress.report(percent, Some(100.0), None);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        Ok(CallToolResponse::text("done".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_progress_notifications_precede_result() {
+    use rust_mcp_server::server::StdioServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    server.tool_registry.register_tool("progress", Box::new(ProgressTool));
+    let stdio = StdioServer::new(std::sync::Arc::new(server), true);
+
+    let (client, server_end) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_end);
+    let serving = tokio::spawn(async move { stdio.serve(server_read, server_write).await });
+
+    let (client_read, mut client_write) = tokio::io::split(client);
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": "tools/call",
+        "params": {"name": "progress", "arguments": {}, "_meta": {"progressToken": "task-1"}}
+    });
+    // Without a token the tool runs the same but reports nothing
+    let quiet_call = json!({"jsonrpc": "2.0", "id": 8, "method": "tools/call", "params": {"name": "progress"}});
+    client_write.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    let mut lines = BufReader::new(client_read).lines();
+    let mut messages = Vec::new();
+    for _ in 0..4 {
+        let line = lines.next_line().await.unwrap().unwrap();
+        messages.push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+    }
+    for (message, percent) in messages.iter().zip([0.0, 50.0, 100.0]) {
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(message["params"]["progressToken"], "task-1");
+        assert_eq!(message["params"]["progress"], percent);
+        assert_eq!(message["params"]["total"], 100.0);
+    }
+    assert_eq!(messages[3]["id"], 7);
+    assert_eq!(messages[3]["result"]["content"][0]["text"], "done");
+
+    client_write.write_all(format!("{}\n", quiet_call).as_bytes()).await.unwrap();
+    client_write.shutdown().await.unwrap();
+    let mut rest = Vec::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        rest.push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+    }
+    serving.await.unwrap().unwrap();
+    assert_eq!(rest.len(), 1, "{:?}", rest);
+    assert_eq!(rest[0]["id"], 8);
+}
+
+struct LoggingTool;
+
+#[async_trait::async_trait]
+impl rust_mcp_server::tools::ToolHandler for LoggingTool {
+    fn description(&self) -> String {
+        "Logs one entry at each of three levels".to_string()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({"type": "object"})
+    }
+
+    async fn call(&self, args: serde_json::Value) -> anyhow::Result<CallToolResponse> {
+        self.call_with_context(args, rust_mcp_server::tools::ToolContext::default()).await
+    }
+
+    async fn call_with_context(
+        &self,
+        _args: serde_json::Value,
+        context: rust_mcp_server::tools::ToolContext,
+    ) -> anyhow::Result<CallToolResponse> {
+        use rust_mcp_server::notifications::LogLevel;
+        context.logger.log(LogLevel::Debug, "too verbose");
+        context.logger.log(LogLevel::Info, "starting");
+        context.logger.log(LogLevel::Error, json!({"error": "disk full"}));
+        Ok(CallToolResponse::text("done".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_builtin_tools_report_progress() {
+    use rust_mcp_server::server::StdioServer;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("large.txt"), "a".repeat(600 * 1024)).unwrap();
+    let server = test_server_with_config(ServerConfig {
+        root: Some(dir.path().to_path_buf()),
+        allowed_commands: vec!["sleep".to_string()],
+        ..ServerConfig::default()
+    });
+    let mut client = StdioServer::new(std::sync::Arc::new(server), true).connect_in_process();
+    client.initialize(ClientInfo { name: "test-client".to_string(), version: "1.0.0".to_string() }).await.unwrap();
+
+    // Large reads report every 256 KiB
+    let params = json!({"name": "read_file", "arguments": {"path": "large.txt"}, "_meta": {"progressToken": "read"}});
+    let response = client.request("tools/call", Some(params)).await.unwrap();
+    assert!(response.result.unwrap()["isError"].is_null());
+    let mut reported = Vec::new();
+    for _ in 0..3 {
+        let message = client.receive().await.unwrap();
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(message["params"]["progressToken"], "read");
+        assert_eq!(message["params"]["total"], 614400.0);
+        reported.push(message["params"]["progress"].as_f64().unwrap());
+    }
+    assert_eq!(reported, [262144.0, 524288.0, 614400.0]);
+
+    // Commands report how long they have been running
+    let params = json!({"name": "execute_command", "arguments": {"command": "sleep", "args": ["1.5"]}, "_meta": {"progressToken": "run"}});
+    let response = client.request("tools/call", Some(params)).await.unwrap();
+    assert!(response.result.unwrap()["isError"].is_null());
+    let message = client.receive().await.unwrap();
+    assert_eq!(message["params"]["progressToken"], "run");
+    assert_eq!(message["params"]["progress"], 1.0);
+    assert_eq!(message["params"]["message"], "sleep running for 1s");
+}
+
+#[tokio::test]
+async fn test_logging_set_level_filters_messages() {
+    use rust_mcp_server::notifications::LogLevel;
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.tool_registry.register_tool("logging", Box::new(LoggingTool));
+    let mut notifications = server.notifier.subscribe();
+    let request = |id: i64, method: &str, params: serde_json::Value| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(id)),
+        method: method.to_string(),
+        params: Some(params),
+    };
+
+    let response = server.handle_request(request(1, "initialize", json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {"name": "test-client", "version": "1.0.0"}
+    }))).await.unwrap().unwrap();
+    assert_eq!(response.result.unwrap()["capabilities"]["logging"], json!({}));
+    assert_eq!(server.logger.level(), LogLevel::Info);
+
+    let response = server.handle_request(request(2, "logging/setLevel", json!({"level": "warning"}))).await.unwrap().unwrap();
+    assert_eq!(response.result, Some(json!({})));
+    assert_eq!(server.logger.level(), LogLevel::Warning);
+
+    server.handle_request(request(3, "tools/call", json!({"name": "logging"}))).await.unwrap().unwrap();
+    let message = notifications.try_recv().unwrap();
+    assert_eq!(message.method, "notifications/message");
+    assert_eq!(message.params, Some(json!({"level": "error", "logger": "logging", "data": {"error": "disk full"}})));
+    // Debug and info fell below the threshold
+    assert!(notifications.try_recv().is_err());
+
+    let response = server.handle_request(request(4, "logging/setLevel", json!({"level": "verbose"}))).await.unwrap().unwrap();
+    assert_eq!(response.error.unwrap().code, rust_mcp_server::types::error_codes::INVALID_PARAMS);
+    assert_eq!(server.logger.level(), LogLevel::Warning);
+}
+
+#[tokio::test]
+async fn test_tracing_events_forwarded_to_client() {
+    use rust_mcp_server::notifications::ClientLogLayer;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+
+    let mut server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    server.initialized = true;
+    let mut notifications = server.notifier.subscribe();
+    let layer = ClientLogLayer::new();
+    let forwarded = layer.clone().with_filter(tracing_subscriber::filter::filter_fn(ClientLogLayer::forwards));
+    let _tracing = tracing::subscriber::set_default(tracing_subscriber::registry().with(forwarded));
+    layer.attach(server.logger.clone());
+    let set_level = |level: &str| JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        id: Some(json!(1)),
+        method: "logging/setLevel".to_string(),
+        params: Some(json!({"level": level})),
+    };
+
+    // Nothing is forwarded until the client sets a level
+    tracing::error!(target: "rust_mcp_server::tools", "too early");
+    server.handle_request(set_level("info")).await.unwrap();
+    assert!(notifications.try_recv().is_err());
+
+    // Logged at info before the new level applies
+    server.handle_request(set_level("warning")).await.unwrap();
+    let message = notifications.try_recv().unwrap();
+    assert_eq!(message.method, "notifications/message");
+    assert_eq!(message.params, Some(json!({"level": "info", "logger": "rust_mcp_server::mcp", "data": "Client set log level to Warning"})));
+
+    tracing::info!(target: "rust_mcp_server::tools", "below the level");
+    tracing::error!(target: "hyper::proto", "not ours");
+    tracing::error!(target: "rust_mcp_server::tools", path = "/tmp/full", "disk full");
+    let message = notifications.try_recv().unwrap();
+    assert_eq!(message.params, Some(json!({"level": "error", "logger": "rust_mcp_server::tools", "data": "disk full path=\"/tmp/full\""})));
+    assert!(notifications.try_recv().is_err());
+
+    // Transports log every message they send at debug, which would loop
+    server.logger.set_level(rust_mcp_server::notifications::LogLevel::Debug);
+    tracing::debug!(target: "rust_mcp_server::server", "Sending: {{}}");
+    assert!(notifications.try_recv().is_err());
+    tracing::debug!(target: "rust_mcp_server::tools", "walked 3 files");
+    assert_eq!(notifications.try_recv().unwrap().params.unwrap()["data"], "walked 3 files");
+}
+
+#[tokio::test]
+async fn test_tracing_events_forwarded_to_http_sessions() {
+    use rust_mcp_server::notifications::ClientLogLayer;
+    use rust_mcp_server::server::HttpServer;
+    use tokio::io::AsyncBufReadExt;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let layer = ClientLogLayer::new();
+    let forwarded = layer.clone().with_filter(tracing_subscriber::filter::filter_fn(ClientLogLayer::forwards));
+    let _tracing = tracing::subscriber::set_default(tracing_subscriber::registry().with(forwarded));
+    layer.attach(server.logger.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let http = HttpServer::new(std::sync::Arc::new(server), true);
+    tokio::spawn(async move { http.serve(listener).await });
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    })
+    .to_string();
+    let (_, head, _) = http_post(addr, "", &initialize).await;
+    let first = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+    let (_, head, _) = http_post(addr, "", &initialize).await;
+    let second = format!("Mcp-Session-Id: {}\r\n", session_header(&head).unwrap());
+
+    // Only the first client chooses a level
+    let set_level = json!({"jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": {"level": "error"}});
+    let (status, _, _) = http_post(addr, &first, &set_level.to_string()).await;
+    assert_eq!(status, 200);
+    let mut first_stream = http_get_stream(addr, &first).await;
+    let mut second_stream = http_get_stream(addr, &second).await;
+
+    tracing::error!(target: "rust_mcp_server::tools", "disk full");
+    let (_, data) = sse_next_event_id(&mut first_stream).await;
+    let message: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(message["method"], "notifications/message");
+    assert_eq!(message["params"], json!({"level": "error", "logger": "rust_mcp_server::tools", "data": "disk full"}));
+
+    let mut line = String::new();
+    let received = tokio::time::timeout(std::time::Duration::from_millis(200), async {
+        loop {
+            line.clear();
+            second_stream.read_line(&mut line).await.unwrap();
+            if line.starts_with("data: ") {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(received.is_err(), "{}", line);
+}
+
+#[cfg(windows)]
+#[tokio::test]
+async fn test_pipe_transport() {
+    use rust_mcp_server::server::PipeServer;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let name = format!(r"\\.\pipe\rust-mcp-server-test-{}", std::process::id());
+    let server = McpServer::new("test-server".to_string(), "1.0.0".to_string());
+    let pipe = PipeServer::new(std::sync::Arc::new(server), true);
+    let serve_name = name.clone();
+    tokio::spawn(async move { pipe.serve_until(&serve_name, std::future::pending()).await });
+
+    let client = loop {
+        match ClientOptions::new().open(&name) {
+            Ok(client) => break client,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+        }
+    };
+    let (reader, mut writer) = tokio::io::split(client);
+    let mut reader = tokio::io::BufReader::new(reader);
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "test-client", "version": "1.0.0"}
+        }
+    });
+    writer.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).await.unwrap();
+    let response: JsonRpcResponse = serde_json::from_str(&line).unwrap();
+    assert_eq!(response.result.unwrap()["serverInfo"]["name"], "test-server");
+}